@@ -0,0 +1,5 @@
+fn main() {
+    // Node.js addons need platform specific linker flags.
+    #[cfg(feature = "node")]
+    napi_build::setup();
+}
@@ -0,0 +1,62 @@
+/*
+ * C API of giffy, available when the crate is built with the `ffi` feature:
+ *
+ *     cargo rustc --release --lib --crate-type cdylib --features ffi
+ *
+ * then link against target/release/libgiffy.so (or .dylib, or giffy.dll).
+ */
+
+#ifndef GIFFY_H
+#define GIFFY_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A decoded GIF. Every frame is composited onto the full canvas. */
+typedef struct GiffyGif GiffyGif;
+
+/*
+ * Decode the GIF in the `len` bytes at `data`. Returns NULL on failure, in which case
+ * giffy_last_error() describes what went wrong. Free the result with giffy_free().
+ */
+GiffyGif *giffy_load_from_bytes(const uint8_t *data, size_t len);
+
+/*
+ * The message of the last error on this thread, or NULL if there was none. The string is
+ * valid until the next failing call on the same thread.
+ */
+const char *giffy_last_error(void);
+
+/* The width of the GIF in pixels. */
+uint32_t giffy_width(const GiffyGif *gif);
+
+/* The height of the GIF in pixels. */
+uint32_t giffy_height(const GiffyGif *gif);
+
+/* The number of times the animation should loop, 0 for forever or -1 if it does not loop. */
+int32_t giffy_loop_count(const GiffyGif *gif);
+
+/* The number of frames. */
+size_t giffy_frame_count(const GiffyGif *gif);
+
+/* The delay of frame `index` in hundredths of a second, or 0 if there is no such frame. */
+uint16_t giffy_frame_delay(const GiffyGif *gif, size_t index);
+
+/*
+ * The pixels of frame `index` as width * height * 4 bytes of RGBA, row by row, or NULL if
+ * there is no such frame. The pixels are owned by `gif`.
+ */
+const uint8_t *giffy_frame_pixels(const GiffyGif *gif, size_t index);
+
+/* Free a GIF returned by giffy_load_from_bytes(). Does nothing if `gif` is NULL. */
+void giffy_free(GiffyGif *gif);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GIFFY_H */
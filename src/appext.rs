@@ -0,0 +1,222 @@
+use crate::observer::NoObserver;
+use crate::parser::{ApplicationExtension, DataType};
+use crate::{DecodeOptions, Gif};
+
+use std::io::Read;
+
+// Called with the data sub-blocks of an application extension.
+type Handler<'a> = Box<dyn FnMut(&[&[u8]]) -> Result<(), String> + 'a>;
+
+/// Handlers for application extensions, keyed by application identifier and authentication
+/// code, as called by [`load_with_app_extensions`]. This lets proprietary extensions be
+/// decoded without changes to the parser.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::{AppExtensionHandlers, DecodeOptions};
+/// use std::fs::File;
+///
+/// let mut xmp = None;
+/// let mut frame_tags = vec![];
+/// let mut handlers = AppExtensionHandlers::new();
+/// handlers.on_xmp(|packet| xmp = Some(packet.to_string()));
+/// handlers.register("ACMETAGS", "1.0", |sub_blocks| {
+///     frame_tags.push(sub_blocks.concat());
+///     Ok(())
+/// });
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// giffy::load_with_app_extensions(&mut src, &DecodeOptions::default(), &mut handlers)
+///     .expect("Error loading GIF");
+/// drop(handlers);
+/// println!("{:?} {:?}", xmp, frame_tags);
+/// ```
+#[derive(Default)]
+pub struct AppExtensionHandlers<'a> {
+    handlers: Vec<(String, String, Handler<'a>)>,
+}
+
+impl<'a> AppExtensionHandlers<'a> {
+    /// No handlers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `handler` with the data sub-blocks of every application extension with the
+    /// identifier `id` and the authentication code `auth_code`, in file order, replacing any
+    /// handler registered for them before. The sub-blocks are as they were stored, without
+    /// their size bytes. An error returned by the handler stops the decode.
+    pub fn register<F>(&mut self, id: &str, auth_code: &str, handler: F) -> &mut Self
+    where
+        F: FnMut(&[&[u8]]) -> Result<(), String> + 'a,
+    {
+        self.handlers
+            .retain(|(i, a, _)| (i.as_str(), a.as_str()) != (id, auth_code));
+        self.handlers
+            .push((id.into(), auth_code.into(), Box::new(handler)));
+        self
+    }
+
+    /// Call `handler` with the loop count of the NETSCAPE2.0 extension, where 0 means loop
+    /// forever.
+    pub fn on_loop_count<F>(&mut self, mut handler: F) -> &mut Self
+    where
+        F: FnMut(u16) + 'a,
+    {
+        self.register("NETSCAPE", "2.0", move |sub_blocks| {
+            let ext = ApplicationExtension {
+                id: "NETSCAPE".into(),
+                auth_code: "2.0".into(),
+                data_sub_blocks: sub_blocks.concat(),
+                sub_block_sizes: vec![],
+            };
+            if let Some(loop_count) = ext.loop_count() {
+                handler(loop_count);
+            }
+            Ok(())
+        })
+    }
+
+    /// Call `handler` with the XMP packet of the "XMP DataXMP" extension. The packet is
+    /// stored as is, with its own bytes read as sub-block sizes, and is put back together
+    /// from the sub-blocks, without the "magic trailer" that follows it.
+    pub fn on_xmp<F>(&mut self, mut handler: F) -> &mut Self
+    where
+        F: FnMut(&str) + 'a,
+    {
+        self.register("XMP Data", "XMP", move |sub_blocks| {
+            let packet = xmp_packet(sub_blocks);
+            let packet = std::str::from_utf8(&packet).map_err(|e| format!("Error: {}", e))?;
+            handler(packet);
+            Ok(())
+        })
+    }
+
+    // Calls the handler registered for `ext`, if any.
+    fn handle(&mut self, ext: &ApplicationExtension) -> Result<(), String> {
+        match self
+            .handlers
+            .iter_mut()
+            .find(|(id, auth_code, _)| *id == ext.id && *auth_code == ext.auth_code)
+        {
+            Some((_, _, handler)) => handler(&ext.sub_blocks()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Debug for AppExtensionHandlers<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(
+                self.handlers
+                    .iter()
+                    .map(|(id, auth_code, _)| (id, auth_code)),
+            )
+            .finish()
+    }
+}
+
+// The bytes of an XMP packet, sizes of its sub-blocks included, without the 257 bytes of the
+// magic trailer, 0x01 and then 0xff down to 0x00, before the block terminator.
+fn xmp_packet(sub_blocks: &[&[u8]]) -> Vec<u8> {
+    let mut packet = sub_blocks
+        .iter()
+        .flat_map(|block| std::iter::once(block.len() as u8).chain(block.iter().copied()))
+        .collect::<Vec<_>>();
+    let trailer = std::iter::once(1).chain((0..=255).rev());
+    if packet.len() >= 257 && packet[packet.len() - 257..].iter().copied().eq(trailer) {
+        packet.truncate(packet.len() - 257);
+    }
+    packet
+}
+
+/// Attempt to load a GIF from a given `src` like [`load_with_options`](crate::load_with_options),
+/// calling the handler registered in `handlers` for every application extension, in file
+/// order, before any frame is decoded.
+///
+/// # Errors
+///
+/// This function will return an error in the same cases as
+/// [`load_with_options`](crate::load_with_options), or if a handler returns an error.
+pub fn load_with_app_extensions<R>(
+    src: &mut R,
+    options: &DecodeOptions,
+    handlers: &mut AppExtensionHandlers,
+) -> Result<Gif, String>
+where
+    R: Read,
+{
+    let result = crate::parse_observed(src, options, &mut NoObserver)?;
+    for block in result.data_blocks.iter() {
+        if let DataType::ApplicationExtensionType(ext) = block {
+            handlers.handle(ext)?;
+        }
+    }
+    crate::decode_with_options(&result, *options, &mut NoObserver, None).map(|(gif, _)| gif)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, Color};
+
+    // A GIF that loops 3 times, with the given application extensions, each as its
+    // identifier and the bytes stored after it, before the trailer.
+    fn encoded(extensions: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut gif = testing::gif(1, 1, [vec![Color(1, 2, 3)]]);
+        gif.loop_count = Some(3);
+        let (_, mut data) = testing::encoded(gif);
+        let trailer = data.pop();
+        for (id, stored) in extensions {
+            data.extend([0x21, 0xff, 11]);
+            data.extend(id.as_bytes());
+            data.extend(*stored);
+            data.push(0);
+        }
+        data.extend(trailer);
+        data
+    }
+
+    #[test]
+    fn test_load_with_app_extensions() {
+        let xmp = "<x:xmpmeta>hello</x:xmpmeta>";
+        let mut stored = xmp.as_bytes().to_vec();
+        stored.push(1);
+        stored.extend((0..=255).rev());
+        let data = encoded(&[("XMP DataXMP", &stored), ("ACMETAGS1.0", &[2, 1, 2, 1, 3])]);
+
+        let (mut loop_count, mut packet, mut custom) = (None, None, vec![]);
+        let mut handlers = AppExtensionHandlers::new();
+        handlers
+            .on_loop_count(|n| loop_count = Some(n))
+            .on_xmp(|p| packet = Some(p.to_string()))
+            .register("ACMETAGS", "1.0", |sub_blocks| {
+                custom.extend(sub_blocks.iter().map(|b| b.to_vec()));
+                Ok(())
+            });
+        let gif = load_with_app_extensions(
+            &mut data.as_slice(),
+            &DecodeOptions::default(),
+            &mut handlers,
+        )
+        .unwrap();
+        drop(handlers);
+
+        assert_eq!(1, gif.image_frames.len());
+        assert_eq!(Some(3), loop_count);
+        assert_eq!(Some(xmp.to_string()), packet);
+        // The sub-blocks are handed over as they were split.
+        assert_eq!(vec![vec![1, 2], vec![3]], custom);
+
+        let mut handlers = AppExtensionHandlers::new();
+        handlers.register("ACMETAGS", "1.0", |_| Err("Error: bad tags".into()));
+        assert!(load_with_app_extensions(
+            &mut data.as_slice(),
+            &DecodeOptions::default(),
+            &mut handlers
+        )
+        .is_err());
+    }
+}
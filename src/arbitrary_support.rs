@@ -0,0 +1,125 @@
+use crate::{Color, Gif, ImageFrame};
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+// Keeps generated GIFs small, so that fuzzers spend their time on many of them.
+const MAX_SIZE: u32 = 32;
+const MAX_FRAMES: usize = 8;
+const MAX_COLORS: usize = 16;
+const MAX_COMMENTS: usize = 2;
+
+impl<'a> Arbitrary<'a> for Color {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Color(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?))
+    }
+}
+
+/// Generates a frame of at most 32 by 32 pixels. It is never a placeholder.
+///
+/// This is only available with the `arbitrary` feature.
+impl<'a> Arbitrary<'a> for ImageFrame {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let width = u.int_in_range(0..=MAX_SIZE)?;
+        let height = u.int_in_range(0..=MAX_SIZE)?;
+        Ok(ImageFrame {
+            width,
+            height,
+            colors: (0..width * height)
+                .map(|_| u.arbitrary())
+                .collect::<Result<Box<[Color]>>>()?,
+            delay_time: u.arbitrary()?,
+            placeholder: false,
+        })
+    }
+}
+
+/// Generates a GIF that can be encoded, and that decodes back to the same frames: every frame
+/// has `width * height` pixels, and all of them use the same palette of at most 16 colors. It
+/// is at most 32 by 32 pixels, with 1 to 8 frames.
+///
+/// This is only available with the `arbitrary` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use giffy::Gif;
+///
+/// # let fuzz_input: &[u8] = &[];
+/// let mut u = Unstructured::new(fuzz_input);
+/// let gif = Gif::arbitrary(&mut u).expect("Not enough data");
+/// let data = gif.to_bytes().expect("Error encoding GIF");
+/// let decoded = giffy::load(&mut data.as_slice()).expect("Error loading GIF");
+/// assert_eq!(gif.image_frames.len(), decoded.image_frames.len());
+/// ```
+impl<'a> Arbitrary<'a> for Gif {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let width = u.int_in_range(1..=MAX_SIZE)?;
+        let height = u.int_in_range(1..=MAX_SIZE)?;
+        let palette = (0..u.int_in_range(1..=MAX_COLORS)?)
+            .map(|_| u.arbitrary())
+            .collect::<Result<Vec<Color>>>()?;
+
+        let image_frames = (0..u.int_in_range(1..=MAX_FRAMES)?)
+            .map(|_| {
+                let colors = (0..width * height)
+                    .map(|_| u.choose(&palette).copied())
+                    .collect::<Result<Box<[Color]>>>()?;
+                Ok(ImageFrame {
+                    width,
+                    height,
+                    colors,
+                    delay_time: u.arbitrary()?,
+                    placeholder: false,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let comments = (0..u.int_in_range(0..=MAX_COMMENTS)?)
+            .map(|_| u.arbitrary())
+            .collect::<Result<Vec<String>>>()?;
+
+        Ok(Gif {
+            width,
+            height,
+            image_frames,
+            loop_count: u.arbitrary()?,
+            comments,
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arbitrary_gif_roundtrip() {
+        // Deterministic noise, so that every run checks the same GIFs.
+        let mut state = 0x2545_f491_u32;
+        let data = (0..64 * 1024)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+
+        let mut u = Unstructured::new(&data);
+        for _ in 0..20 {
+            let gif = Gif::arbitrary(&mut u).unwrap();
+            let decoded = crate::load(&mut gif.to_bytes().unwrap().as_slice()).unwrap();
+
+            assert_eq!((gif.width, gif.height), (decoded.width, decoded.height));
+            assert_eq!(gif.loop_count, decoded.loop_count);
+            assert_eq!(gif.comments, decoded.comments);
+            assert_eq!(gif.image_frames.len(), decoded.image_frames.len());
+            for (expected, actual) in gif.image_frames.iter().zip(&decoded.image_frames) {
+                assert_eq!(expected.colors, actual.colors);
+                assert_eq!(expected.delay_time, actual.delay_time);
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{Gif, ImageFrame, StreamDecoder};
+
+use std::collections::VecDeque;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// How many bytes are read from the source at a time.
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Decodes a GIF from a [`tokio::io::AsyncRead`] `src`, handing out every frame as soon as the
+/// data for it has arrived.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run(src: impl tokio::io::AsyncRead + Unpin) -> Result<(), String> {
+/// use giffy::AsyncDecoder;
+///
+/// let mut decoder = AsyncDecoder::new(src);
+/// while let Some(frame) = decoder.next_frame().await? {
+///     println!("frame ready, delay {}", frame.delay_time);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncDecoder<R: AsyncRead + Unpin> {
+    src: R,
+    decoder: StreamDecoder,
+    buffer: Vec<u8>,
+    // The frames taken from the decoder that have not been handed out yet.
+    frames: VecDeque<ImageFrame>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDecoder<R> {
+    /// Create a decoder reading from `src`.
+    pub fn new(src: R) -> Self {
+        Self {
+            src,
+            decoder: StreamDecoder::new(),
+            buffer: vec![0; CHUNK_SIZE],
+            frames: VecDeque::new(),
+        }
+    }
+
+    /// See [`StreamDecoder::width`].
+    pub fn width(&self) -> Option<u32> {
+        self.decoder.width()
+    }
+
+    /// See [`StreamDecoder::height`].
+    pub fn height(&self) -> Option<u32> {
+        self.decoder.height()
+    }
+
+    /// See [`StreamDecoder::loop_count`].
+    pub fn loop_count(&self) -> Option<u16> {
+        self.decoder.loop_count()
+    }
+
+    /// Read from `src` until the next frame is complete and return it, or `None` once the
+    /// trailer has been read.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if reading from `src` fails, if `src` ends before the
+    /// trailer or if the GIF is not in a valid GIF format.
+    pub async fn next_frame(&mut self) -> Result<Option<ImageFrame>, String> {
+        loop {
+            if let Some(frame) = self.frames.pop_front() {
+                return Ok(Some(frame));
+            }
+            if self.decoder.is_finished() {
+                return Ok(None);
+            }
+            self.read_chunk().await?;
+            self.frames.extend(self.decoder.take_frames());
+        }
+    }
+
+    // Push the next chunk of `src` to the decoder.
+    async fn read_chunk(&mut self) -> Result<(), String> {
+        let len = self
+            .src
+            .read(&mut self.buffer)
+            .await
+            .map_err(|e| format!("Error: {}", e))?;
+        if len == 0 {
+            return Err("Error: unexpected end of file".into());
+        }
+        self.decoder.push(&self.buffer[..len])?;
+        Ok(())
+    }
+}
+
+/// Load a GIF from a [`tokio::io::AsyncRead`] `src`, reading it in chunks and decoding every
+/// frame as soon as it has arrived.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run(mut src: impl tokio::io::AsyncRead + Unpin) -> Result<(), String> {
+/// let gif = giffy::load_async(&mut src).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if reading from `src` fails or if the GIF is not in a
+/// valid GIF format.
+pub async fn load_async<R>(src: &mut R) -> Result<Gif, String>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut decoder = AsyncDecoder::new(src);
+    while !decoder.decoder.is_finished() {
+        decoder.read_chunk().await?;
+    }
+    decoder.decoder.into_gif()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, Color};
+
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    // Reading from a slice never has to wait, so the future completes on the first poll.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is pending"),
+        }
+    }
+
+    fn encoded() -> Vec<u8> {
+        let frames = [Color(255, 0, 0), Color(0, 255, 0)].map(|c| vec![c; 64]);
+        testing::encoded(testing::gif(8, 8, frames)).1
+    }
+
+    #[test]
+    fn test_load_async() {
+        let data = encoded();
+        let expected = crate::load(&mut data.as_slice()).unwrap();
+        let actual = block_on(load_async(&mut data.as_slice())).unwrap();
+        assert_eq!(
+            (expected.width, expected.height),
+            (actual.width, actual.height)
+        );
+        assert_eq!(expected.loop_count, actual.loop_count);
+        assert_eq!(expected.comments, actual.comments);
+        for (a, b) in expected.image_frames.iter().zip(actual.image_frames.iter()) {
+            assert_eq!(a.colors, b.colors);
+        }
+
+        assert!(block_on(load_async(&mut &data[..data.len() - 1])).is_err());
+    }
+
+    #[test]
+    fn test_next_frame() {
+        let data = encoded();
+        let mut decoder = AsyncDecoder::new(data.as_slice());
+        let first = block_on(decoder.next_frame()).unwrap().unwrap();
+        assert_eq!(Color(255, 0, 0), first.colors[0]);
+        assert!(decoder.decoder.frames().is_empty());
+        assert_eq!(Some(8), decoder.width());
+        let second = block_on(decoder.next_frame()).unwrap().unwrap();
+        assert_eq!(Color(0, 255, 0), second.colors[0]);
+        assert!(block_on(decoder.next_frame()).unwrap().is_none());
+    }
+}
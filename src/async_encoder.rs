@@ -0,0 +1,159 @@
+use crate::encoder::{Encoder, PaletteMode};
+use crate::{Color, ImageFrame};
+
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// An [`Encoder`] that writes to a [`tokio::io::AsyncWrite`] `dst`.
+///
+/// Each frame is encoded into memory and then written out before the call returns, so the
+/// GIF can be streamed to `dst` while the frames are being produced.
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run(frames: Vec<giffy::ImageFrame>) -> Result<(), String> {
+/// use giffy::AsyncEncoder;
+///
+/// let mut dst = tokio::io::sink();
+/// let mut encoder = AsyncEncoder::new(&mut dst, 320, 240);
+/// encoder.set_loop_count(Some(0));
+/// for frame in frames.iter() {
+///     encoder.write_frame(frame).await?;
+/// }
+/// encoder.finish().await
+/// # }
+/// ```
+pub struct AsyncEncoder<W: AsyncWrite + Unpin> {
+    dst: W,
+    encoder: Encoder<Vec<u8>>,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncEncoder<W> {
+    /// Create an encoder for a GIF of the given `width` and `height`.
+    pub fn new(dst: W, width: u16, height: u16) -> Self {
+        Self {
+            dst,
+            encoder: Encoder::new(vec![], width, height),
+        }
+    }
+
+    /// See [`Encoder::set_loop_count`].
+    pub fn set_loop_count(&mut self, loop_count: Option<u16>) {
+        self.encoder.set_loop_count(loop_count);
+    }
+
+    /// See [`Encoder::add_comment`].
+    pub fn add_comment<S>(&mut self, text: S)
+    where
+        S: Into<String>,
+    {
+        self.encoder.add_comment(text);
+    }
+
+    /// See [`Encoder::set_pixel_aspect_ratio`].
+    pub fn set_pixel_aspect_ratio(&mut self, pixel_aspect_ratio: Option<f32>) {
+        self.encoder.set_pixel_aspect_ratio(pixel_aspect_ratio);
+    }
+
+    /// See [`Encoder::set_palette_mode`].
+    pub fn set_palette_mode(&mut self, palette_mode: PaletteMode) {
+        self.encoder.set_palette_mode(palette_mode);
+    }
+
+    /// See [`Encoder::set_delta_optimization`].
+    pub fn set_delta_optimization(&mut self, delta_optimization: bool) {
+        self.encoder.set_delta_optimization(delta_optimization);
+    }
+
+    /// See [`Encoder::set_sort_palette`].
+    pub fn set_sort_palette(&mut self, sort_palette: bool) {
+        self.encoder.set_sort_palette(sort_palette);
+    }
+
+    /// See [`Encoder::set_transparent_color`].
+    pub fn set_transparent_color(&mut self, transparent_color: Option<Color>) {
+        self.encoder.set_transparent_color(transparent_color);
+    }
+
+    async fn flush_encoded(&mut self) -> Result<(), String> {
+        let buffer = self.encoder.get_mut();
+        self.dst
+            .write_all(buffer)
+            .await
+            .map_err(|e| format!("Error: {}", e))?;
+        buffer.clear();
+        Ok(())
+    }
+
+    /// See [`Encoder::write_frame`].
+    pub async fn write_frame(&mut self, frame: &ImageFrame) -> Result<(), String> {
+        self.encoder.write_frame(frame)?;
+        self.flush_encoded().await
+    }
+
+    /// See [`Encoder::write_frames`].
+    pub async fn write_frames(&mut self, frames: &[ImageFrame]) -> Result<(), String> {
+        self.encoder.write_frames(frames)?;
+        self.flush_encoded().await
+    }
+
+    /// Write the GIF trailer and flush `dst`. No more frames can be written afterwards.
+    pub async fn finish(mut self) -> Result<(), String> {
+        self.encoder.write_trailer()?;
+        self.flush_encoded().await?;
+        self.dst.flush().await.map_err(|e| format!("Error: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    // Writing into a `Vec` never has to wait, so the future completes on the first poll.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is pending"),
+        }
+    }
+
+    #[test]
+    fn test_async_encoder_matches_encoder() {
+        let frames = [Color(255, 0, 0), Color(0, 255, 0)]
+            .iter()
+            .map(|c| ImageFrame {
+                width: 8,
+                height: 8,
+                colors: vec![*c; 64].into_boxed_slice(),
+                delay_time: 5,
+                placeholder: false,
+            })
+            .collect::<Vec<_>>();
+
+        let mut expected = vec![];
+        let mut encoder = Encoder::new(&mut expected, 8, 8);
+        encoder.set_loop_count(Some(0));
+        encoder.set_pixel_aspect_ratio(Some(2.0));
+        encoder.set_transparent_color(Some(Color(0, 255, 0)));
+        for frame in frames.iter() {
+            encoder.write_frame(frame).unwrap();
+        }
+        encoder.finish().unwrap();
+
+        let mut actual = vec![];
+        let mut encoder = AsyncEncoder::new(&mut actual, 8, 8);
+        encoder.set_loop_count(Some(0));
+        encoder.set_pixel_aspect_ratio(Some(2.0));
+        encoder.set_transparent_color(Some(Color(0, 255, 0)));
+        for frame in frames.iter() {
+            block_on(encoder.write_frame(frame)).unwrap();
+        }
+        block_on(encoder.finish()).unwrap();
+
+        assert_eq!(expected, actual);
+    }
+}
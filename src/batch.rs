@@ -0,0 +1,210 @@
+use crate::{
+    decode_into_sink, load_with_options, Color, DecodeOptions, FrameMeta, FrameSink, Gif,
+    ImageFrame,
+};
+
+use std::io::Read;
+use std::ops::ControlFlow;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// How many decoded frames [`spawn_decode`] keeps waiting for the caller at most.
+const FRAMES_AHEAD: usize = 8;
+
+/// The results of [`decode_many`], in the order the decodes complete. Every item is the
+/// position of the source in the input along with what decoding it gave.
+///
+/// Dropping it stops the workers once they are done with the GIF they are decoding.
+#[derive(Debug)]
+pub struct DecodeMany {
+    results: Receiver<(usize, Result<Gif, String>)>,
+}
+
+impl Iterator for DecodeMany {
+    type Item = (usize, Result<Gif, String>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Every worker drops its sender once the sources run out.
+        self.results.recv().ok()
+    }
+}
+
+/// Decode every GIF in `sources` with `options`, spread over as many threads as there are
+/// cores, and hand out the results as they complete. Sources are only opened, or otherwise
+/// taken from the iterator, once a thread is free for them, and only a few results are kept
+/// waiting for the caller, so the memory use does not grow with the number of sources.
+///
+/// A GIF that fails to decode does not stop the others.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::DecodeOptions;
+/// use std::fs::{self, File};
+///
+/// let paths = fs::read_dir("<gif directory>")
+///     .expect("Error reading directory")
+///     .map(|entry| entry.expect("Error reading directory").path())
+///     .collect::<Vec<_>>();
+/// let sources = paths
+///     .clone()
+///     .into_iter()
+///     .map(|path| File::open(path).expect("File not found"));
+///
+/// for (i, result) in giffy::decode_many(sources, &DecodeOptions::default()) {
+///     match result {
+///         Ok(gif) => println!("{}: {} frames", paths[i].display(), gif.image_frames.len()),
+///         Err(e) => println!("{}: {}", paths[i].display(), e),
+///     }
+/// }
+/// ```
+pub fn decode_many<I, R>(sources: I, options: &DecodeOptions) -> DecodeMany
+where
+    I: IntoIterator<Item = R>,
+    I::IntoIter: Send + 'static,
+    R: Read,
+{
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let sources = Arc::new(Mutex::new(sources.into_iter().enumerate()));
+    let (sender, results) = mpsc::sync_channel(threads);
+
+    for _ in 0..threads {
+        let sources = Arc::clone(&sources);
+        let sender = sender.clone();
+        let options = *options;
+        thread::spawn(move || loop {
+            // A poisoned lock means another worker panicked in the iterator, so stop too.
+            let next = match sources.lock() {
+                Ok(mut sources) => sources.next(),
+                Err(_) => None,
+            };
+            let (i, mut src) = match next {
+                Some(next) => next,
+                None => break,
+            };
+            let result = load_with_options(&mut src, &options);
+            if sender.send((i, result)).is_err() {
+                // The results are no longer wanted.
+                break;
+            }
+        });
+    }
+
+    DecodeMany { results }
+}
+
+/// Decode a GIF from `src` on a new thread, handing out every frame through the returned
+/// channel as soon as it is composited, such as for a GUI to show the frames as they arrive
+/// without blocking its event loop. If decoding fails, the error is the last item.
+///
+/// Only a few frames are kept waiting for the caller, and dropping the receiver stops the
+/// decode at the next frame.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::DecodeOptions;
+/// use std::fs::File;
+///
+/// let src = File::open("<gif path>").expect("File not found");
+/// let frames = giffy::spawn_decode(src, &DecodeOptions::default());
+/// for frame in frames {
+///     let frame = frame.expect("Error decoding GIF");
+///     println!("frame ready, delay {}", frame.delay_time);
+/// }
+/// ```
+pub fn spawn_decode<R>(mut src: R, options: &DecodeOptions) -> Receiver<Result<ImageFrame, String>>
+where
+    R: Read + Send + 'static,
+{
+    let (sender, frames) = mpsc::sync_channel(FRAMES_AHEAD);
+    let options = *options;
+    thread::spawn(move || {
+        let mut sink = Frames(sender);
+        if let Err(e) = decode_into_sink(&mut src, &options, &mut sink) {
+            // Nobody may be listening any more.
+            let _ = sink.0.send(Err(e));
+        }
+    });
+    frames
+}
+
+// Sends copies of the frames of a decode down a channel.
+struct Frames(SyncSender<Result<ImageFrame, String>>);
+
+impl FrameSink for Frames {
+    fn frame(&mut self, canvas: &[Color], meta: &FrameMeta) -> ControlFlow<()> {
+        let frame = ImageFrame {
+            width: meta.width,
+            height: meta.height,
+            colors: canvas.into(),
+            delay_time: meta.delay_time,
+            placeholder: meta.placeholder,
+        };
+        match self.0.send(Ok(frame)) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn encoded(frames: u8) -> Vec<u8> {
+        let frames = (0..frames).map(|i| vec![Color(i * 50, 0, 0); 4]);
+        testing::encoded(testing::gif(2, 2, frames)).1
+    }
+
+    #[test]
+    fn test_decode_many() {
+        let sources = (0..20)
+            .map(|i| match i {
+                7 => b"GIF89a".to_vec(),
+                _ => encoded(i % 4 + 1),
+            })
+            .map(std::io::Cursor::new)
+            .collect::<Vec<_>>();
+
+        let mut results = decode_many(sources, &DecodeOptions::default()).collect::<Vec<_>>();
+        results.sort_by_key(|(i, _)| *i);
+
+        assert_eq!(20, results.len());
+        for (i, result) in results {
+            match i {
+                7 => assert!(result.is_err()),
+                _ => assert_eq!(i % 4 + 1, result.unwrap().image_frames.len()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_spawn_decode() {
+        let data = encoded(5);
+        let gif = load_with_options(&mut data.as_slice(), &DecodeOptions::default()).unwrap();
+        let frames = spawn_decode(
+            std::io::Cursor::new(data.clone()),
+            &DecodeOptions::default(),
+        )
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+        assert_eq!(gif.image_frames.len(), frames.len());
+        for (expected, actual) in gif.image_frames.iter().zip(&frames) {
+            assert_eq!(expected.colors, actual.colors);
+            assert_eq!(expected.dimensions(), actual.dimensions());
+        }
+
+        // The frames read before the end of the data come first, then the error.
+        let truncated = data[..data.len() - 1].to_vec();
+        let results = spawn_decode(std::io::Cursor::new(truncated), &DecodeOptions::default())
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(6, results.len());
+        assert!(results[..5].iter().all(Result::is_ok));
+        assert!(results[5].is_err());
+    }
+}
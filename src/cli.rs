@@ -0,0 +1,59 @@
+pub mod compose;
+pub mod convert;
+pub mod diff;
+pub mod explode;
+pub mod frame;
+pub mod info;
+pub mod lint;
+pub mod optimize;
+pub mod play;
+pub mod resize;
+pub mod speed;
+pub mod stats;
+pub mod strip;
+
+use giffy::Gif;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Decode the GIF at `path`.
+pub fn load(path: &Path) -> Result<Gif, String> {
+    let file = File::open(path).map_err(|e| format!("Error: {}: {}", path.display(), e))?;
+    giffy::load(&mut BufReader::new(file)).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Encode `data` as standard base64, with padding.
+pub fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut output = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, &b)| bits | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg==", base64(b"f"));
+        assert_eq!("Zm8=", base64(b"fo"));
+        assert_eq!("Zm9v", base64(b"foo"));
+        assert_eq!("Zm9vYmFy", base64(b"foobar"));
+    }
+}
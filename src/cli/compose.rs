@@ -0,0 +1,88 @@
+use giffy::{Gif, ImageFrame};
+use std::path::PathBuf;
+
+/// Build an animated GIF from a sequence of PNG or BMP images of the same size, in the order
+/// given. The colors are reduced to one shared palette, with dithering.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The frames, in order.
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+    /// Where to write the GIF.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// How long every frame shows, in milliseconds. GIFs store delays in hundredths of a
+    /// second, so this is rounded to a multiple of 10.
+    #[arg(short, long, default_value_t = 100)]
+    delay: u32,
+    /// How many times to repeat the animation after it first plays, 0 for forever. Without
+    /// it, the animation plays once.
+    #[arg(short, long = "loop")]
+    loop_count: Option<u16>,
+    /// The size of the palette, from 1 to 256.
+    #[arg(
+        short,
+        long,
+        default_value_t = 256,
+        value_parser = clap::value_parser!(u16).range(1..=256)
+    )]
+    colors: u16,
+    /// Map colors to the palette without dithering, which keeps flat areas flat.
+    #[arg(long)]
+    no_dither: bool,
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let delay_time = u16::try_from((args.delay + 5) / 10)
+        .map_err(|_| format!("Error: delay {} ms is too long", args.delay))?;
+
+    let mut size = None;
+    let mut image_frames = vec![];
+    for path in args.inputs.iter() {
+        let image = image::open(path)
+            .map_err(|e| format!("Error: {}: {}", path.display(), e))?
+            .into_rgb8();
+        let dimensions = image.dimensions();
+        if *size.get_or_insert(dimensions) != dimensions {
+            let (width, height) = size.unwrap();
+            return Err(format!(
+                "Error: {} is {}x{}, expected {}x{} like the first image",
+                path.display(),
+                dimensions.0,
+                dimensions.1,
+                width,
+                height
+            ));
+        }
+
+        image_frames.push(ImageFrame {
+            width: dimensions.0,
+            height: dimensions.1,
+            colors: image.pixels().map(|p| p.0.into()).collect(),
+            delay_time,
+            placeholder: false,
+        });
+    }
+
+    let (width, height) = size.unwrap();
+    let mut gif = Gif {
+        width,
+        height,
+        image_frames,
+        loop_count: args.loop_count,
+        comments: vec![],
+        pixel_aspect_ratio: None,
+        transparent_color: None,
+    };
+    gif.reduce_colors(args.colors as usize, !args.no_dither);
+    gif.save(&args.output)?;
+
+    println!(
+        "{}: {} frames, {}x{}",
+        args.output.display(),
+        gif.image_frames.len(),
+        width,
+        height
+    );
+    Ok(())
+}
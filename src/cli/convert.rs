@@ -0,0 +1,65 @@
+use clap::ValueEnum;
+use giffy::{VideoFormat, VideoOptions};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Convert a GIF to another format.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The GIF to convert.
+    input: PathBuf,
+    /// Where to write the result.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// The format to write, guessed from the extension of the output if missing.
+    #[arg(short, long, value_enum)]
+    format: Option<Format>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// Re-encode as a GIF.
+    Gif,
+    /// A lossless animated WebP.
+    Webp,
+    /// A YUV4MPEG2 video stream, which ffmpeg reads.
+    Y4m,
+    /// Headerless RGBA frames at a constant frame rate.
+    Rgba,
+}
+
+impl Format {
+    fn from_extension(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Format::from_str(&extension, true).ok()
+    }
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let format = args
+        .format
+        .or_else(|| Format::from_extension(&args.output))
+        .ok_or("Error: cannot tell the format from the output, pass --format")?;
+
+    let gif = super::load(&args.input)?;
+    let file = File::create(&args.output)
+        .map_err(|e| format!("Error: {}: {}", args.output.display(), e))?;
+    let mut dst = BufWriter::new(file);
+    match format {
+        Format::Gif => gif.write_to(&mut dst)?,
+        Format::Webp => gif.write_webp(&mut dst)?,
+        Format::Y4m | Format::Rgba => {
+            let options = VideoOptions {
+                format: match format {
+                    Format::Y4m => VideoFormat::Y4m,
+                    _ => VideoFormat::RawRgba,
+                },
+                fps: None,
+            };
+            let (numerator, denominator) = gif.write_video(&mut dst, &options)?;
+            println!("frame rate: {}/{}", numerator, denominator);
+        }
+    }
+    dst.flush().map_err(|e| format!("Error: {}", e))
+}
@@ -0,0 +1,84 @@
+use giffy::{Gif, ImageFrame};
+use image::{Rgb, RgbImage};
+use std::fs;
+use std::path::PathBuf;
+
+/// Compare two GIFs frame by frame, such as a GIF and its re-encode, matching frames by when
+/// they show. Fails if they do not look the same or play for different lengths of time.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The reference GIF.
+    a: PathBuf,
+    /// The GIF to compare with it.
+    b: PathBuf,
+    /// Write an image of every frame with differences to this directory, with the differing
+    /// pixels in red over a dimmed copy of the reference frame.
+    #[arg(long)]
+    images: Option<PathBuf>,
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let a = super::load(&args.a)?;
+    let b = super::load(&args.b)?;
+
+    let duration = |gif: &Gif| {
+        gif.image_frames
+            .iter()
+            .map(|f| f.delay_time as u64 * 10)
+            .sum::<u64>()
+    };
+    println!(
+        "frames: {} vs {}",
+        a.image_frames.len(),
+        b.image_frames.len()
+    );
+    println!("duration: {} ms vs {} ms", duration(&a), duration(&b));
+    if a.loop_count != b.loop_count {
+        println!("loop count: {:?} vs {:?}", a.loop_count, b.loop_count);
+    }
+
+    let comparison = giffy::compare(&a, &b)?;
+    for frame in comparison.frames.iter().filter(|f| f.differing_pixels > 0) {
+        println!(
+            "frame {} vs {}: {} pixels differ, mean error {:.2}, PSNR {:.1} dB",
+            frame.index, frame.other_index, frame.differing_pixels, frame.mean_error, frame.psnr
+        );
+        if let Some(ref dir) = args.images {
+            fs::create_dir_all(dir).map_err(|e| format!("Error: {}", e))?;
+            let path = dir.join(format!("diff-{}.png", frame.index));
+            difference(
+                &a,
+                &a.image_frames[frame.index],
+                &b.image_frames[frame.other_index],
+            )
+            .save(&path)
+            .map_err(|e| format!("Error: {}: {}", path.display(), e))?;
+        }
+    }
+
+    if !comparison.is_identical() {
+        Err(format!(
+            "Error: {} pixels differ, mean error {:.2}, PSNR {:.1} dB",
+            comparison.differing_pixels, comparison.mean_error, comparison.psnr
+        ))
+    } else if (duration(&a), a.loop_count) != (duration(&b), b.loop_count) {
+        Err("Error: the frames look the same, but the timing differs".into())
+    } else {
+        println!("the GIFs look the same");
+        Ok(())
+    }
+}
+
+// An image of `a` dimmed to gray, with the pixels that differ in `b` in red.
+fn difference(gif: &Gif, a: &ImageFrame, b: &ImageFrame) -> RgbImage {
+    RgbImage::from_fn(gif.width, gif.height, |x, y| {
+        let i = (y * gif.width + x) as usize;
+        let (p, q) = (a.colors[i], b.colors[i]);
+        if p == q {
+            let gray = ((p.r() as u32 + p.g() as u32 + p.b() as u32) / 9) as u8;
+            Rgb([gray, gray, gray])
+        } else {
+            Rgb([255, 0, 0])
+        }
+    })
+}
@@ -0,0 +1,256 @@
+use clap::ValueEnum;
+use giffy::{DecodeOptions, Gif};
+use image::ImageFormat;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::json::json_string;
+
+/// Write frames of a GIF to image files, along with a manifest.json listing their delays.
+/// With `--recursive`, do so for every GIF in a directory, several at a time.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The GIF to take the frames from, or the directory with `--recursive`.
+    input: PathBuf,
+    /// The directory to write the frames to, created if missing.
+    #[arg(short, long, default_value = ".")]
+    output: PathBuf,
+    /// The format of the frame files.
+    #[arg(short, long, value_enum, default_value_t = Format::Png)]
+    format: Format,
+    /// Only write these frames, counting from 0, such as `3`, `2-8`, `5-` or `-10`. Both ends
+    /// are included.
+    #[arg(long, value_parser = parse_range)]
+    frames: Option<RangeInclusive<usize>>,
+    /// Explode every GIF in the input directory and its subdirectories. The frames of
+    /// `<input>/a/b.gif` go to `<output>/a/b/`.
+    #[arg(short, long)]
+    recursive: bool,
+    /// Once every GIF in the directory is done, keep watching it and explode new GIFs as they
+    /// appear, until stopped.
+    #[arg(long, requires = "recursive")]
+    watch: bool,
+}
+
+// How often the directory is looked at for new GIFs in watch mode.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+// A GIF file for `decode_many`, which only takes readers, so a file that cannot be opened
+// fails the decode when it is read.
+struct Source(io::Result<BufReader<File>>);
+
+impl Read for Source {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0 {
+            Ok(ref mut file) => file.read(buf),
+            Err(ref e) => Err(io::Error::new(e.kind(), e.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Png,
+    Bmp,
+}
+
+fn parse_range(s: &str) -> Result<RangeInclusive<usize>, String> {
+    let number = |s: &str, default: usize| match s {
+        "" => Ok(default),
+        s => s
+            .parse::<usize>()
+            .map_err(|_| format!("invalid frame number `{}`", s)),
+    };
+    let (start, end) = match s.split_once('-') {
+        Some((start, end)) => (number(start, 0)?, number(end, usize::MAX)?),
+        None => (number(s, 0)?, number(s, 0)?),
+    };
+    if start > end {
+        return Err(format!("the range {} is empty", s));
+    }
+    Ok(start..=end)
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    if !args.recursive {
+        let gif = super::load(&args.input)?;
+        return explode(&gif, &args.input, &args.output, args);
+    }
+
+    let mut paths = vec![];
+    find_gifs(&args.input, &mut paths)?;
+    let failed = explode_many(paths.clone(), args);
+    if !args.watch {
+        return match failed {
+            0 => Ok(()),
+            n => Err(format!("Error: {} of {} GIFs failed", n, paths.len())),
+        };
+    }
+
+    let mut seen = paths.into_iter().collect::<HashSet<_>>();
+    // New GIFs and their size when last looked at. A GIF is only taken once its size stops
+    // changing, so that one still being written is not read half way.
+    let mut pending = HashMap::new();
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+        let mut found = vec![];
+        if let Err(e) = find_gifs(&args.input, &mut found) {
+            eprintln!("{}", e);
+            continue;
+        }
+
+        found.retain(|path| !seen.contains(path));
+        let mut ready = vec![];
+        for path in found {
+            let size = match fs::metadata(&path) {
+                Ok(metadata) => metadata.len(),
+                Err(_) => continue,
+            };
+            if pending.insert(path.clone(), size) == Some(size) {
+                pending.remove(&path);
+                seen.insert(path.clone());
+                ready.push(path);
+            }
+        }
+        explode_many(ready, args);
+    }
+}
+
+// Explode the GIFs at `paths` in parallel, printing the errors. Returns how many failed.
+fn explode_many(paths: Vec<PathBuf>, args: &Args) -> usize {
+    let sources = paths
+        .clone()
+        .into_iter()
+        .map(|path| Source(File::open(path).map(BufReader::new)));
+
+    let mut failed = 0;
+    for (i, result) in giffy::decode_many(sources, &DecodeOptions::default()) {
+        let path = &paths[i];
+        let result = result
+            .map_err(|e| format!("{}: {}", path.display(), e))
+            .and_then(|gif| {
+                explode(
+                    &gif,
+                    path,
+                    &output_dir(&args.input, path, &args.output),
+                    args,
+                )
+            });
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            failed += 1;
+        }
+    }
+    failed
+}
+
+// Add the paths of the GIFs in `dir` and its subdirectories to `paths`, sorted.
+fn find_gifs(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Error: {}: {}", dir.display(), e))?;
+    let mut entries = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Error: {}: {}", dir.display(), e))?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            find_gifs(&path, paths)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("gif"))
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+// Where the frames of the GIF at `path`, found in `input`, go: its path relative to `input`
+// under `output`, without the extension.
+fn output_dir(input: &Path, path: &Path, output: &Path) -> PathBuf {
+    let relative = path.strip_prefix(input).unwrap_or(path);
+    output.join(relative.with_extension(""))
+}
+
+// Write the frames of `gif`, read from `input`, to the directory `output`.
+fn explode(gif: &Gif, input: &Path, output: &Path, args: &Args) -> Result<(), String> {
+    let range = args.frames.clone().unwrap_or(0..=usize::MAX);
+    if *range.start() >= gif.image_frames.len() {
+        return Err(format!(
+            "Error: the GIF only has {} frames",
+            gif.image_frames.len()
+        ));
+    }
+    fs::create_dir_all(output).map_err(|e| format!("Error: {}", e))?;
+
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let (extension, format) = match args.format {
+        Format::Png => ("png", ImageFormat::Png),
+        Format::Bmp => ("bmp", ImageFormat::Bmp),
+    };
+    // Pad the numbers so the files sort in frame order.
+    let digits = gif.image_frames.len().to_string().len();
+
+    let mut entries = vec![];
+    for (i, image) in gif.to_rgba_images()?.iter().enumerate() {
+        if !range.contains(&i) {
+            continue;
+        }
+        let name = format!("{}-{:0width$}.{}", stem, i, extension, width = digits);
+        let path = output.join(&name);
+        image
+            .save_with_format(&path, format)
+            .map_err(|e| format!("Error: {}: {}", path.display(), e))?;
+        println!("{}", path.display());
+
+        entries.push(format!(
+            "{{\"index\":{},\"file\":{},\"delay_ms\":{}}}",
+            i,
+            json_string(&name),
+            gif.image_frames[i].delay_time as u64 * 10
+        ));
+    }
+
+    let manifest = format!(
+        "{{\"width\":{},\"height\":{},\"loop_count\":{},\"frames\":[{}]}}\n",
+        gif.width,
+        gif.height,
+        gif.loop_count.map_or("null".to_string(), |n| n.to_string()),
+        entries.join(",")
+    );
+    let path = output.join("manifest.json");
+    fs::write(&path, manifest).map_err(|e| format!("Error: {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(Ok(3..=3), parse_range("3"));
+        assert_eq!(Ok(2..=8), parse_range("2-8"));
+        assert_eq!(Ok(5..=usize::MAX), parse_range("5-"));
+        assert_eq!(Ok(0..=10), parse_range("-10"));
+        assert!(parse_range("8-2").is_err());
+        assert!(parse_range("a").is_err());
+    }
+
+    #[test]
+    fn test_output_dir() {
+        assert_eq!(
+            PathBuf::from("out/a/b"),
+            output_dir(Path::new("in"), Path::new("in/a/b.gif"), Path::new("out"))
+        );
+        assert_eq!(
+            PathBuf::from("out/c"),
+            output_dir(Path::new("in/"), Path::new("in/c.GIF"), Path::new("out"))
+        );
+    }
+}
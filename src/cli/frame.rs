@@ -0,0 +1,80 @@
+use giffy::IndexedDecoder;
+use image::RgbImage;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Write a single frame of a GIF to an image file, such as a poster frame. Only the frames it
+/// builds on are decoded, so this is quick even for long GIFs.
+#[derive(Debug, clap::Args)]
+#[command(group = clap::ArgGroup::new("which").required(true))]
+pub struct Args {
+    /// The GIF to take the frame from.
+    input: PathBuf,
+    /// The frame to write, counting from 0.
+    #[arg(short, long, group = "which")]
+    index: Option<usize>,
+    /// Write the frame showing at this time, such as `1.5s`, `1500ms` or `1.5`.
+    #[arg(short, long, group = "which", value_parser = parse_time)]
+    at: Option<Duration>,
+    /// Where to write the frame. The format is taken from the extension, such as `.png` or
+    /// `.bmp`.
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+fn parse_time(s: &str) -> Result<Duration, String> {
+    let (number, scale) = match s.strip_suffix("ms") {
+        Some(ms) => (ms, 0.001),
+        None => (s.strip_suffix('s').unwrap_or(s), 1.0),
+    };
+    number
+        .parse::<f64>()
+        .ok()
+        .and_then(|n| Duration::try_from_secs_f64(n * scale).ok())
+        .ok_or_else(|| format!("invalid time `{}`", s))
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let file =
+        File::open(&args.input).map_err(|e| format!("Error: {}: {}", args.input.display(), e))?;
+    let mut decoder = IndexedDecoder::new(BufReader::new(file))
+        .map_err(|e| format!("{}: {}", args.input.display(), e))?;
+
+    let n = match (args.index, args.at) {
+        (Some(n), _) => n,
+        (None, Some(time)) => decoder.index().frame_at(time).ok_or_else(|| {
+            format!(
+                "Error: the animation is over after {:?}",
+                (0..decoder.len())
+                    .map(|i| decoder.index().delay_time(i).unwrap() as u64 * 10)
+                    .map(Duration::from_millis)
+                    .sum::<Duration>()
+            )
+        })?,
+        (None, None) => unreachable!(),
+    };
+
+    let frame = decoder.decode_frame(n)?;
+    RgbImage::from_raw(decoder.width(), decoder.height(), frame.to_rgb_bytes())
+        .ok_or("Error: frame does not match the size of the GIF")?
+        .save(&args.output)
+        .map_err(|e| format!("Error: {}: {}", args.output.display(), e))?;
+    println!("frame {} -> {}", n, args.output.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(Ok(Duration::from_millis(1500)), parse_time("1.5s"));
+        assert_eq!(Ok(Duration::from_millis(1500)), parse_time("1500ms"));
+        assert_eq!(Ok(Duration::from_millis(1500)), parse_time("1.5"));
+        assert!(parse_time("-1s").is_err());
+        assert!(parse_time("soon").is_err());
+    }
+}
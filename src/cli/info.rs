@@ -0,0 +1,170 @@
+use giffy::lowlevel::{self, DataType, DisposalMethod, ParseResult};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use crate::json::json_string;
+
+/// Print the version, size, frames, loop count, palettes and comments of a GIF. The pixels are
+/// not decoded, so this is fast even for long animations.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The GIF to describe.
+    input: PathBuf,
+    /// Print a JSON object instead of text, for scripts.
+    #[arg(long)]
+    json: bool,
+}
+
+// What is printed about every frame.
+struct Frame {
+    left: u16,
+    top: u16,
+    width: u16,
+    height: u16,
+    delay_time: u16,
+    disposal: &'static str,
+    transparent: Option<u8>,
+    interlaced: bool,
+    palette: Option<usize>,
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let file =
+        File::open(&args.input).map_err(|e| format!("Error: {}: {}", args.input.display(), e))?;
+    let result = lowlevel::parse(&mut BufReader::new(file))
+        .map_err(|e| format!("{}: {}", args.input.display(), e))?;
+
+    let mut frames = vec![];
+    let mut comments = vec![];
+    let mut loop_count = None;
+    for block in result.data_blocks.iter() {
+        match block {
+            DataType::TableBasedImageType(image) => {
+                let gce = image.graphic_control_extension.as_ref();
+                let descriptor = &image.image_descriptor;
+                frames.push(Frame {
+                    left: descriptor.left,
+                    top: descriptor.top,
+                    width: descriptor.width,
+                    height: descriptor.height,
+                    delay_time: gce.map_or(0, |gce| gce.delay_time),
+                    disposal: disposal(gce.map(|gce| &gce.disposal_method)),
+                    transparent: gce
+                        .filter(|gce| gce.transparent_color_index_available)
+                        .map(|gce| gce.transparent_color_index),
+                    interlaced: descriptor.interlace_flag,
+                    palette: image.local_color_table.as_ref().map(Vec::len),
+                });
+            }
+            DataType::CommentExtensionType(ext) => comments.push(ext.text.as_str()),
+            DataType::ApplicationExtensionType(ext) => {
+                loop_count = loop_count.or(ext.loop_count());
+            }
+            DataType::PlainTextExtensionType(_) => (),
+        }
+    }
+
+    if args.json {
+        print_json(&result, &frames, loop_count, &comments);
+    } else {
+        print_text(&result, &frames, loop_count, &comments);
+    }
+    Ok(())
+}
+
+fn disposal(method: Option<&DisposalMethod>) -> &'static str {
+    match method {
+        None | Some(DisposalMethod::Unspecified) => "unspecified",
+        Some(DisposalMethod::DoNotDispose) => "none",
+        Some(DisposalMethod::RestoreToBackgroundColor) => "background",
+        Some(DisposalMethod::RestoreToPrevious) => "previous",
+        Some(DisposalMethod::Undefined) => "undefined",
+    }
+}
+
+fn print_text(result: &ParseResult, frames: &[Frame], loop_count: Option<u16>, comments: &[&str]) {
+    let lsd = &result.logical_screen_descriptor;
+    let duration = frames.iter().map(|f| f.delay_time as u64).sum::<u64>();
+
+    println!("version: {}{}", result.header.sig, result.header.version);
+    println!("size: {}x{}", lsd.width, lsd.height);
+    match lsd.global_color_table {
+        Some(ref table) => println!("global palette: {} colors", table.len()),
+        None => println!("global palette: none"),
+    }
+    match loop_count {
+        None => println!("loop: no"),
+        Some(0) => println!("loop: forever"),
+        Some(n) => println!("loop: {} times", n),
+    }
+    println!("frames: {}", frames.len());
+    println!("duration: {} ms", duration * 10);
+    for comment in comments {
+        println!("comment: {}", comment);
+    }
+
+    for (i, frame) in frames.iter().enumerate() {
+        let mut line = format!(
+            "frame {}: {}x{} at {},{}, {} ms, dispose {}",
+            i,
+            frame.width,
+            frame.height,
+            frame.left,
+            frame.top,
+            frame.delay_time as u64 * 10,
+            frame.disposal
+        );
+        if let Some(palette) = frame.palette {
+            line += &format!(", local palette {} colors", palette);
+        }
+        if let Some(index) = frame.transparent {
+            line += &format!(", transparent index {}", index);
+        }
+        if frame.interlaced {
+            line += ", interlaced";
+        }
+        println!("{}", line);
+    }
+}
+
+fn print_json(result: &ParseResult, frames: &[Frame], loop_count: Option<u16>, comments: &[&str]) {
+    let lsd = &result.logical_screen_descriptor;
+    let or_null = |n: Option<usize>| n.map_or("null".to_string(), |n| n.to_string());
+
+    let frames = frames
+        .iter()
+        .map(|frame| {
+            format!(
+                "{{\"left\":{},\"top\":{},\"width\":{},\"height\":{},\"delay_ms\":{},\
+                 \"disposal\":{},\"transparent_index\":{},\"interlaced\":{},\"palette_size\":{}}}",
+                frame.left,
+                frame.top,
+                frame.width,
+                frame.height,
+                frame.delay_time as u64 * 10,
+                json_string(frame.disposal),
+                or_null(frame.transparent.map(usize::from)),
+                frame.interlaced,
+                or_null(frame.palette)
+            )
+        })
+        .collect::<Vec<_>>();
+    let comments = comments
+        .iter()
+        .map(|comment| json_string(comment))
+        .collect::<Vec<_>>();
+
+    println!(
+        "{{\"version\":{},\"width\":{},\"height\":{},\"global_palette_size\":{},\
+         \"loop_count\":{},\"frame_count\":{},\"frames\":[{}],\"comments\":[{}]}}",
+        json_string(&format!("{}{}", result.header.sig, result.header.version)),
+        lsd.width,
+        lsd.height,
+        or_null(lsd.global_color_table.as_ref().map(Vec::len)),
+        or_null(loop_count.map(usize::from)),
+        frames.len(),
+        frames.join(","),
+        comments.join(",")
+    );
+}
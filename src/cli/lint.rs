@@ -0,0 +1,38 @@
+use giffy::Severity;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+/// Check GIFs against the GIF89a spec and print every problem found, with its byte offset.
+/// Fails if any GIF has an error, or a warning with `--strict`.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The GIFs to check.
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+    /// Fail on warnings too.
+    #[arg(long)]
+    strict: bool,
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let mut failed = 0;
+    for path in args.inputs.iter() {
+        let file = File::open(path).map_err(|e| format!("Error: {}: {}", path.display(), e))?;
+        let findings = giffy::validate(&mut BufReader::new(file))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        for finding in findings.iter() {
+            println!("{}: {}", path.display(), finding);
+        }
+        let worst = findings.iter().map(|f| f.severity).max();
+        if worst == Some(Severity::Error) || args.strict && worst.is_some() {
+            failed += 1;
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("Error: {} of {} GIFs failed", n, args.inputs.len())),
+    }
+}
@@ -0,0 +1,60 @@
+use giffy::OptimizeOptions;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+/// Shrink a GIF. Without options, it looks exactly the same afterwards.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The GIF to optimize.
+    input: PathBuf,
+    /// Where to write the optimized GIF. The original is copied if it cannot be made smaller.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Reduce the colors to a shared palette of this size, from 1 to 256. Lossy.
+    #[arg(
+        short,
+        long,
+        value_parser = clap::value_parser!(u16).range(1..=256)
+    )]
+    colors: Option<u16>,
+    /// Dither when reducing the colors.
+    #[arg(long, requires = "colors")]
+    dither: bool,
+    /// Keep the previous pixel where a pixel changed by at most this much, from 0 to 255, in
+    /// every color channel. Lossy, but removes noise that costs a lot of space.
+    #[arg(long, default_value_t = 0)]
+    lossy: u8,
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let src =
+        File::open(&args.input).map_err(|e| format!("Error: {}: {}", args.input.display(), e))?;
+    let dst = File::create(&args.output)
+        .map_err(|e| format!("Error: {}: {}", args.output.display(), e))?;
+    let mut dst = BufWriter::new(dst);
+
+    let report = giffy::optimize(
+        &mut BufReader::new(src),
+        &mut dst,
+        &OptimizeOptions {
+            max_colors: args.colors.map(usize::from),
+            dither: args.dither,
+            lossy: args.lossy,
+            ..OptimizeOptions::default()
+        },
+    )?;
+    dst.flush().map_err(|e| format!("Error: {}", e))?;
+
+    let saved = report.original_size - report.optimized_size;
+    println!(
+        "{} -> {} bytes, saved {} bytes ({:.1}%), {} -> {} frames",
+        report.original_size,
+        report.optimized_size,
+        saved,
+        saved as f64 * 100.0 / report.original_size.max(1) as f64,
+        report.original_frame_count,
+        report.optimized_frame_count
+    );
+    Ok(())
+}
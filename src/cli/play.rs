@@ -0,0 +1,297 @@
+use clap::ValueEnum;
+use giffy::{Color, QuantizeOptions, ResizeFilter};
+use image::{ImageFormat, RgbImage};
+use std::env;
+use std::fmt::Write as _;
+use std::io::Cursor;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Play a GIF in the terminal, with a graphics protocol where the terminal supports one, or
+/// else with 24-bit colors, two pixels per character cell.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The GIF to play.
+    input: PathBuf,
+    /// How to draw the frames.
+    #[arg(short, long, value_enum, default_value_t = Proto::Auto)]
+    proto: Proto,
+    /// The width in characters, or in pixels with a graphics protocol. With half blocks, it
+    /// defaults to the width of the GIF, shrunk to fit the terminal as given by `COLUMNS`, or
+    /// 80 columns. With a graphics protocol, it defaults to the width of the GIF.
+    #[arg(short, long)]
+    width: Option<u32>,
+    /// Play the animation once, however many times the GIF says it should loop.
+    #[arg(long)]
+    once: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Proto {
+    /// The kitty or iTerm2 protocol if the terminal says it is kitty or iTerm2, or else half
+    /// blocks. Terminals do not tell whether they support sixels.
+    Auto,
+    /// Colored upper half block characters, which work in any terminal with 24-bit colors.
+    Blocks,
+    /// DEC sixel graphics, with every frame reduced to 256 colors.
+    Sixel,
+    /// The kitty graphics protocol.
+    Kitty,
+    /// The iTerm2 inline images protocol.
+    Iterm,
+}
+
+impl Proto {
+    // Pick the protocol for `Auto` from the environment.
+    fn detect(self) -> Proto {
+        if self != Proto::Auto {
+            return self;
+        }
+        let var = |name| env::var(name).unwrap_or_default();
+        if var("TERM") == "xterm-kitty" || env::var_os("KITTY_WINDOW_ID").is_some() {
+            Proto::Kitty
+        } else if var("TERM_PROGRAM") == "iTerm.app" {
+            Proto::Iterm
+        } else {
+            Proto::Blocks
+        }
+    }
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let gif = super::load(&args.input)?;
+    if gif.image_frames.is_empty() {
+        return Err("Error: GIF has no frames".into());
+    }
+
+    let proto = args.proto.detect();
+    let width = match (args.width, proto) {
+        (Some(width), _) => width,
+        (None, Proto::Blocks) => {
+            let columns = env::var("COLUMNS")
+                .ok()
+                .and_then(|columns| columns.parse().ok())
+                .unwrap_or(80);
+            gif.width.min(columns)
+        }
+        (None, _) => gif.width,
+    };
+    let width = width.max(1);
+    // Character cells are about twice as tall as wide, and every cell shows two pixels, so the
+    // height scales like the width for both half blocks and pixels.
+    let height = ((gif.height as u64 * width as u64 / gif.width as u64) as u32).max(1);
+    let gif = if (width, height) == (gif.width, gif.height) {
+        gif
+    } else {
+        gif.resize(width, height, ResizeFilter::Bilinear)?
+    };
+
+    let mut stdout = io::stdout().lock();
+    // Clear the screen once, then draw every frame over the previous one.
+    write!(stdout, "\x1b[2J").map_err(|e| format!("Error: {}", e))?;
+    let ticker = if args.once {
+        gif.ticker().once()
+    } else {
+        gif.ticker()
+    };
+    for frame in ticker {
+        let (width, height) = (gif.width as usize, gif.height as usize);
+        let drawn = match proto {
+            Proto::Auto | Proto::Blocks => render(&frame.colors, width),
+            Proto::Sixel => sixel(&frame.colors, width),
+            Proto::Kitty => kitty(&frame.colors, width, height),
+            Proto::Iterm => iterm(&frame.colors, width, height)?,
+        };
+        write!(stdout, "\x1b[H{}", drawn)
+            .and_then(|_| stdout.flush())
+            .map_err(|e| format!("Error: {}", e))?;
+    }
+    Ok(())
+}
+
+// Draw `pixels`, `width` pixels wide, with upper half blocks: the foreground color is the
+// upper pixel and the background color the pixel below it.
+fn render(pixels: &[Color], width: usize) -> String {
+    let mut output = String::new();
+    let rows = pixels.chunks(width).collect::<Vec<_>>();
+    for pair in rows.chunks(2) {
+        for (x, top) in pair[0].iter().enumerate() {
+            let _ = write!(output, "\x1b[38;2;{};{};{}m", top.r(), top.g(), top.b());
+            match pair.get(1) {
+                Some(bottom) => {
+                    let bottom = bottom[x];
+                    let _ = write!(
+                        output,
+                        "\x1b[48;2;{};{};{}m",
+                        bottom.r(),
+                        bottom.g(),
+                        bottom.b()
+                    );
+                }
+                // An odd last row only has upper pixels.
+                None => output.push_str("\x1b[49m"),
+            }
+            output.push('▀');
+        }
+        output.push_str("\x1b[0m\n");
+    }
+    output
+}
+
+// Draw `pixels` as a sixel image. Every band of 6 rows is drawn once per color in it, with a
+// bit set in every sixel for the rows that have that color.
+fn sixel(pixels: &[Color], width: usize) -> String {
+    let options = QuantizeOptions {
+        dither: true,
+        width,
+    };
+    let (palette, indices) = giffy::quantize(pixels, 256, &options);
+    let height = pixels.len() / width;
+
+    let mut output = format!("\x1bPq\"1;1;{};{}", width, height);
+    for (i, c) in palette.iter().enumerate() {
+        let percent = |v: u8| v as u32 * 100 / 255;
+        let _ = write!(
+            output,
+            "#{};2;{};{};{}",
+            i,
+            percent(c.r()),
+            percent(c.g()),
+            percent(c.b())
+        );
+    }
+
+    for band in (0..height).step_by(6) {
+        let rows = band..(band + 6).min(height);
+        let mut used = vec![false; palette.len()];
+        for y in rows.clone() {
+            for &i in indices[y * width..(y + 1) * width].iter() {
+                used[i as usize] = true;
+            }
+        }
+
+        for color in (0..palette.len()).filter(|&i| used[i]) {
+            let _ = write!(output, "#{}", color);
+            let sixels = (0..width).map(|x| {
+                let bits = rows
+                    .clone()
+                    .filter(|y| indices[y * width + x] as usize == color)
+                    .fold(0, |bits, y| bits | 1 << (y - band));
+                (63 + bits) as u8 as char
+            });
+            run_length(&mut output, sixels);
+            // Go back to the start of the band for the next color.
+            output.push('$');
+        }
+        output.push('-');
+    }
+    output.push_str("\x1b\\");
+    output
+}
+
+// Append `sixels`, with runs of the same sixel shortened to `!<count><sixel>`.
+fn run_length(output: &mut String, sixels: impl Iterator<Item = char>) {
+    let mut run: Option<(char, usize)> = None;
+    let flush = |output: &mut String, (c, n): (char, usize)| match n {
+        1..=3 => output.extend(std::iter::repeat_n(c, n)),
+        n => {
+            let _ = write!(output, "!{}{}", n, c);
+        }
+    };
+    for c in sixels {
+        match run {
+            Some((last, ref mut n)) if last == c => *n += 1,
+            _ => {
+                if let Some(run) = run {
+                    flush(output, run);
+                }
+                run = Some((c, 1));
+            }
+        }
+    }
+    if let Some(run) = run {
+        flush(output, run);
+    }
+}
+
+// Draw `pixels` with the kitty graphics protocol as raw RGB, in chunks of at most 4096 bytes
+// of base64. Every frame replaces image 1, and the terminal is asked not to reply.
+fn kitty(pixels: &[Color], width: usize, height: usize) -> String {
+    let rgb = pixels.iter().flat_map(<[u8; 3]>::from).collect::<Vec<_>>();
+    let data = super::base64(&rgb);
+    let chunks = data.as_bytes().chunks(4096).collect::<Vec<_>>();
+
+    let mut output = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        if i == 0 {
+            let _ = write!(output, "\x1b_Ga=T,f=24,i=1,q=2,s={},v={},", width, height);
+        } else {
+            output.push_str("\x1b_G");
+        }
+        let _ = write!(
+            output,
+            "m={};{}\x1b\\",
+            more,
+            std::str::from_utf8(chunk).unwrap()
+        );
+    }
+    output
+}
+
+// Draw `pixels` with the iTerm2 inline images protocol, which takes an image file.
+fn iterm(pixels: &[Color], width: usize, height: usize) -> Result<String, String> {
+    let rgb = pixels.iter().flat_map(<[u8; 3]>::from).collect::<Vec<_>>();
+    let image = RgbImage::from_raw(width as u32, height as u32, rgb)
+        .ok_or("Error: frame does not match the size of the GIF")?;
+    let mut png = Cursor::new(vec![]);
+    image
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| format!("Error: {}", e))?;
+
+    Ok(format!(
+        "\x1b]1337;File=inline=1;width={}px;height={}px;preserveAspectRatio=1:{}\x07",
+        width,
+        height,
+        super::base64(png.get_ref())
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let pixels = [[1, 2, 3], [4, 5, 6], [7, 8, 9]].map(Color::from);
+        assert_eq!(
+            "\x1b[38;2;1;2;3m\x1b[48;2;4;5;6m▀\x1b[0m\n\x1b[38;2;7;8;9m\x1b[49m▀\x1b[0m\n",
+            render(&pixels, 1)
+        );
+        assert_eq!(Proto::Sixel, Proto::Sixel.detect());
+    }
+
+    #[test]
+    fn test_sixel() {
+        // Two colors in a 3x2 image: the first column red and the rest blue.
+        let (red, blue) = (Color::from([255, 0, 0]), Color::from([0, 0, 255]));
+        let drawn = sixel(&[red, blue, blue, red, blue, blue], 3);
+        assert!(drawn.starts_with("\x1bPq\"1;1;3;2"));
+        assert!(drawn.ends_with("\x1b\\"));
+        // Both rows of the first column, then both rows of the other two.
+        assert!(drawn.contains("#0B??$#1?BB$-") || drawn.contains("#0?BB$#1B??$-"));
+
+        let mut output = String::new();
+        run_length(&mut output, "aaaaabcc".chars());
+        assert_eq!("!5abcc", output);
+    }
+
+    #[test]
+    fn test_kitty() {
+        let pixels = vec![Color::from([1, 2, 3]); 4000];
+        let drawn = kitty(&pixels, 80, 50);
+        assert!(drawn.starts_with("\x1b_Ga=T,f=24,i=1,q=2,s=80,v=50,m=1;"));
+        assert_eq!(4, drawn.matches("\x1b_G").count());
+        assert!(drawn.contains("\x1b_Gm=0;"));
+    }
+}
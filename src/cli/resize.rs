@@ -0,0 +1,80 @@
+use clap::ValueEnum;
+use giffy::ResizeFilter;
+use std::path::PathBuf;
+
+/// Resize every frame of a GIF. The frames are decoded, resized and re-encoded with new
+/// palettes.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The GIF to resize.
+    input: PathBuf,
+    /// The new size, such as `320x240`. Leave out the width or the height, as in `320x` or
+    /// `x240`, to keep the aspect ratio.
+    #[arg(value_parser = parse_size)]
+    size: (Option<u32>, Option<u32>),
+    /// Where to write the result.
+    #[arg(short, long)]
+    output: PathBuf,
+    /// How to compute the resized pixels.
+    #[arg(short, long, value_enum, default_value_t = Filter::Bilinear)]
+    filter: Filter,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+enum Filter {
+    /// Take the closest pixel, which keeps pixel art sharp.
+    Nearest,
+    /// Blend the four closest pixels, which is smoother.
+    Bilinear,
+}
+
+fn parse_size(s: &str) -> Result<(Option<u32>, Option<u32>), String> {
+    let (width, height) = s
+        .split_once('x')
+        .ok_or_else(|| format!("expected WIDTHxHEIGHT, got `{}`", s))?;
+    let number = |s: &str| match s {
+        "" => Ok(None),
+        s => match s.parse::<u32>() {
+            Ok(0) | Err(_) => Err(format!("invalid size `{}`", s)),
+            Ok(n) => Ok(Some(n)),
+        },
+    };
+    match (number(width)?, number(height)?) {
+        (None, None) => Err("the width or the height is needed".into()),
+        size => Ok(size),
+    }
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let gif = super::load(&args.input)?;
+    let scale = |n: u32, to: u32, of: u32| ((n as u64 * to as u64 / of as u64) as u32).max(1);
+    let (width, height) = match args.size {
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) => (width, scale(gif.height, width, gif.width.max(1))),
+        (None, Some(height)) => (scale(gif.width, height, gif.height.max(1)), height),
+        (None, None) => unreachable!(),
+    };
+
+    let filter = match args.filter {
+        Filter::Nearest => ResizeFilter::Nearest,
+        Filter::Bilinear => ResizeFilter::Bilinear,
+    };
+    gif.resize(width, height, filter)?.save(&args.output)?;
+    println!("{}x{} -> {}x{}", gif.width, gif.height, width, height);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(Ok((Some(320), Some(240))), parse_size("320x240"));
+        assert_eq!(Ok((Some(320), None)), parse_size("320x"));
+        assert_eq!(Ok((None, Some(240))), parse_size("x240"));
+        assert!(parse_size("x").is_err());
+        assert!(parse_size("320").is_err());
+        assert!(parse_size("0x10").is_err());
+    }
+}
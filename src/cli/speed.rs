@@ -0,0 +1,30 @@
+use giffy::Rewriter;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+/// Speed up or slow down a GIF by scaling its frame delays. The frames are copied as they are,
+/// so nothing is re-encoded and no quality is lost.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The GIF to change.
+    input: PathBuf,
+    /// How many times faster to play, such as `2` for double speed or `0.5` for half speed.
+    factor: f32,
+    /// Where to write the result.
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    if !(args.factor.is_finite() && args.factor > 0.0) {
+        return Err(format!("Error: invalid speed {}", args.factor));
+    }
+
+    let file =
+        File::open(&args.input).map_err(|e| format!("Error: {}: {}", args.input.display(), e))?;
+    let mut rewriter = Rewriter::new(&mut BufReader::new(file))
+        .map_err(|e| format!("{}: {}", args.input.display(), e))?;
+    rewriter.scale_delays(1.0 / args.factor);
+    rewriter.save(&args.output)
+}
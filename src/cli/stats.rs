@@ -0,0 +1,81 @@
+use giffy::lowlevel::{self, DataType};
+use giffy::{Color, DecodeOptions, FrameMeta, FrameSink};
+use std::fs;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Print the compressed size, compression ratio and decode time of every frame of a GIF, and
+/// the decoding throughput, to see where a GIF spends its bytes and time.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The GIF to measure.
+    input: PathBuf,
+}
+
+// Notes how long every frame took to decode, from the end of the frame before it.
+struct Timer {
+    last: Instant,
+    times: Vec<Duration>,
+}
+
+impl FrameSink for Timer {
+    fn frame(&mut self, _canvas: &[Color], _meta: &FrameMeta) -> ControlFlow<()> {
+        let now = Instant::now();
+        self.times.push(now - self.last);
+        self.last = now;
+        ControlFlow::Continue(())
+    }
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let data =
+        fs::read(&args.input).map_err(|e| format!("Error: {}: {}", args.input.display(), e))?;
+    let result = lowlevel::parse(&mut data.as_slice())
+        .map_err(|e| format!("{}: {}", args.input.display(), e))?;
+
+    let start = Instant::now();
+    let mut timer = Timer {
+        last: start,
+        times: vec![],
+    };
+    giffy::decode_into_sink(&mut data.as_slice(), &DecodeOptions::default(), &mut timer)
+        .map_err(|e| format!("{}: {}", args.input.display(), e))?;
+    let total = start.elapsed();
+
+    println!("frame  compressed   pixels   ratio  decode");
+    let images = result.data_blocks.iter().filter_map(|block| match block {
+        DataType::TableBasedImageType(image) => Some(image),
+        _ => None,
+    });
+    let mut compressed = 0;
+    for (i, (image, time)) in images.zip(timer.times.iter()).enumerate() {
+        let bytes = image.image_data.data_sub_blocks.len();
+        let pixels = image.image_descriptor.width as usize * image.image_descriptor.height as usize;
+        compressed += bytes;
+        // Every pixel is a byte before LZW compression.
+        println!(
+            "{:>5}  {:>10}  {:>7}  {:>5.2}x  {:>6.2} ms",
+            i,
+            bytes,
+            pixels,
+            pixels as f64 / bytes.max(1) as f64,
+            time.as_secs_f64() * 1000.0
+        );
+    }
+
+    let seconds = total.as_secs_f64().max(f64::MIN_POSITIVE);
+    println!(
+        "file: {} bytes, {} in image data ({:.1}%)",
+        data.len(),
+        compressed,
+        compressed as f64 * 100.0 / data.len() as f64
+    );
+    println!(
+        "decode: {:.2} ms, {:.1} MB/s, {:.1} frames/s",
+        seconds * 1000.0,
+        data.len() as f64 / seconds / 1_000_000.0,
+        timer.times.len() as f64 / seconds
+    );
+    Ok(())
+}
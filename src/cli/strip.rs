@@ -0,0 +1,32 @@
+use giffy::Rewriter;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::PathBuf;
+
+/// Remove comments, plain text and application extensions other than the loop count from a
+/// GIF, such as to scrub metadata before publishing it. The frames are copied as they are.
+#[derive(Debug, clap::Args)]
+pub struct Args {
+    /// The GIF to strip.
+    input: PathBuf,
+    /// Where to write the result.
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+pub fn run(args: &Args) -> Result<(), String> {
+    let file =
+        File::open(&args.input).map_err(|e| format!("Error: {}: {}", args.input.display(), e))?;
+    let original_size = file.metadata().map_err(|e| format!("Error: {}", e))?.len();
+    let mut rewriter = Rewriter::new(&mut BufReader::new(file))
+        .map_err(|e| format!("{}: {}", args.input.display(), e))?;
+
+    rewriter.strip_metadata();
+    rewriter.save(&args.output)?;
+
+    let size = fs::metadata(&args.output)
+        .map_err(|e| format!("Error: {}: {}", args.output.display(), e))?
+        .len();
+    println!("{} -> {} bytes", original_size, size);
+    Ok(())
+}
@@ -0,0 +1,235 @@
+use crate::{Gif, ImageFrame};
+
+/// How a frame of the first GIF given to [`compare`] differs from the frame of the second GIF
+/// showing at the same time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameDifference {
+    /// The index of the frame in the first GIF.
+    pub index: usize,
+    /// The index of the frame in the second GIF it was compared with.
+    pub other_index: usize,
+    /// The number of pixels that are not exactly the same.
+    pub differing_pixels: usize,
+    /// The mean absolute difference of the color channels, from 0 to 255.
+    pub mean_error: f64,
+    /// The peak signal-to-noise ratio in decibels. Higher is closer, and identical frames give
+    /// infinity.
+    pub psnr: f64,
+}
+
+/// The result of [`compare`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Every frame of the first GIF, in frame order.
+    pub frames: Vec<FrameDifference>,
+    /// The number of differing pixels over all frames.
+    pub differing_pixels: usize,
+    /// The mean absolute difference of the color channels over all frames, from 0 to 255.
+    pub mean_error: f64,
+    /// The peak signal-to-noise ratio over all frames in decibels.
+    pub psnr: f64,
+}
+
+impl Comparison {
+    /// Whether both GIFs show the same pixels at all times.
+    pub fn is_identical(&self) -> bool {
+        self.differing_pixels == 0
+    }
+}
+
+// Running totals of the differences between pixels.
+#[derive(Default)]
+struct Totals {
+    differing_pixels: usize,
+    channels: u64,
+    absolute_error: u64,
+    squared_error: u64,
+}
+
+impl Totals {
+    fn add(&mut self, a: &ImageFrame, b: &ImageFrame) {
+        for (x, y) in a.colors.iter().zip(b.colors.iter()) {
+            if x != y {
+                self.differing_pixels += 1;
+            }
+            for (x, y) in <[u8; 3]>::from(x).into_iter().zip(<[u8; 3]>::from(y)) {
+                let d = x.abs_diff(y) as u64;
+                self.absolute_error += d;
+                self.squared_error += d * d;
+            }
+            self.channels += 3;
+        }
+    }
+
+    fn mean_error(&self) -> f64 {
+        match self.channels {
+            0 => 0.0,
+            n => self.absolute_error as f64 / n as f64,
+        }
+    }
+
+    fn psnr(&self) -> f64 {
+        if self.squared_error == 0 {
+            return f64::INFINITY;
+        }
+        let mse = self.squared_error as f64 / self.channels as f64;
+        10.0 * (255.0 * 255.0 / mse).log10()
+    }
+}
+
+/// Compare the frames of `a` with those of `b`, such as a GIF before and after re-encoding, to
+/// measure the quality lost. Frames are matched by when they show: every frame of `a` is
+/// compared with the frame of `b` showing when it starts, so the GIFs may have different
+/// frame counts, as when duplicate frames were merged. Frames that start at the same time,
+/// such as frames without a delay, are matched in order.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let original = giffy::load(&mut src).expect("Error loading GIF");
+/// let mut src = File::open("<optimized gif path>").expect("File not found");
+/// let optimized = giffy::load(&mut src).expect("Error loading GIF");
+///
+/// let comparison = giffy::compare(&original, &optimized).expect("Error comparing GIFs");
+/// println!("PSNR {:.1} dB", comparison.psnr);
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if the GIFs are not the same size, if either has no
+/// frames or if a frame does not have `width * height` pixels.
+pub fn compare(a: &Gif, b: &Gif) -> Result<Comparison, String> {
+    if (a.width, a.height) != (b.width, b.height) {
+        return Err(format!(
+            "Error: the GIFs are {}x{} and {}x{}",
+            a.width, a.height, b.width, b.height
+        ));
+    }
+    let len = a.width as usize * a.height as usize;
+    for (name, gif) in [("first", a), ("second", b)] {
+        if gif.image_frames.is_empty() {
+            return Err(format!("Error: the {} GIF has no frames", name));
+        }
+        if let Some(i) = gif.image_frames.iter().position(|f| f.colors.len() != len) {
+            return Err(format!(
+                "Error: frame {} of the {} GIF has {} pixels, expected {}",
+                i,
+                name,
+                gif.image_frames[i].colors.len(),
+                len
+            ));
+        }
+    }
+
+    let a_starts = starts(a);
+    let b_starts = starts(b);
+    let mut totals = Totals::default();
+    let mut frames = vec![];
+    for (index, frame) in a.image_frames.iter().enumerate() {
+        let start = a_starts[index];
+        // How many frames before this one start at the same time.
+        let rank = index - a_starts.partition_point(|&s| s < start);
+
+        // The frames of `b` that start last before or at `start`.
+        let last = b_starts.partition_point(|&s| s <= start) - 1;
+        let first = b_starts.partition_point(|&s| s < b_starts[last]);
+        let other_index = if b_starts[last] == start {
+            (first + rank).min(last)
+        } else {
+            last
+        };
+
+        let mut frame_totals = Totals::default();
+        frame_totals.add(frame, &b.image_frames[other_index]);
+        frames.push(FrameDifference {
+            index,
+            other_index,
+            differing_pixels: frame_totals.differing_pixels,
+            mean_error: frame_totals.mean_error(),
+            psnr: frame_totals.psnr(),
+        });
+
+        totals.differing_pixels += frame_totals.differing_pixels;
+        totals.channels += frame_totals.channels;
+        totals.absolute_error += frame_totals.absolute_error;
+        totals.squared_error += frame_totals.squared_error;
+    }
+
+    Ok(Comparison {
+        frames,
+        differing_pixels: totals.differing_pixels,
+        mean_error: totals.mean_error(),
+        psnr: totals.psnr(),
+    })
+}
+
+// When every frame starts, in hundredths of a second.
+fn starts(gif: &Gif) -> Vec<u64> {
+    gif.image_frames
+        .iter()
+        .scan(0, |time, frame| {
+            let start = *time;
+            *time += frame.delay_time as u64;
+            Some(start)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+
+    fn gif(frames: &[(u8, u16)]) -> Gif {
+        Gif {
+            width: 2,
+            height: 1,
+            image_frames: frames
+                .iter()
+                .map(|&(red, delay_time)| ImageFrame {
+                    width: 2,
+                    height: 1,
+                    colors: vec![Color(red, 0, 0), Color(0, 0, 0)].into_boxed_slice(),
+                    delay_time,
+                    placeholder: false,
+                })
+                .collect(),
+            loop_count: None,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let a = gif(&[(0, 10), (10, 10), (10, 10), (30, 10)]);
+        let comparison = compare(&a, &a).unwrap();
+        assert!(comparison.is_identical());
+        assert_eq!(f64::INFINITY, comparison.psnr);
+
+        // The two middle frames merged into one.
+        let b = gif(&[(0, 10), (10, 20), (36, 10)]);
+        let comparison = compare(&a, &b).unwrap();
+        let matched = comparison
+            .frames
+            .iter()
+            .map(|f| f.other_index)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0, 1, 1, 2], matched);
+        assert_eq!(1, comparison.differing_pixels);
+        assert_eq!(1.0, comparison.frames[3].mean_error);
+        assert_eq!(0.25, comparison.mean_error);
+        assert!(comparison.psnr > comparison.frames[3].psnr);
+
+        // Frames without delays are matched in order.
+        let a = gif(&[(0, 0), (10, 0), (20, 0)]);
+        let comparison = compare(&a, &a).unwrap();
+        assert!(comparison.frames.iter().all(|f| f.index == f.other_index));
+
+        assert!(compare(&a, &gif(&[])).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+const MAX_CODE_SIZE: u8 = 12;
+
+pub(crate) struct Compressor<'a> {
+    index_stream: &'a [u8],
+    lzw_min_code_size: u8,
+    clear_code: u16,
+    next_code: u16,
+    code_size: u8,
+    code_table: HashMap<(u16, u8), u16>,
+}
+
+// Refer to https://www.w3.org/Graphics/GIF/spec-gif89a.txt for details.
+impl<'a> Compressor<'a> {
+    pub(crate) fn new(index_stream: &'a [u8], lzw_min_code_size: u8) -> Self {
+        Self {
+            index_stream,
+            lzw_min_code_size,
+            clear_code: 1 << lzw_min_code_size,
+            next_code: (1 << lzw_min_code_size) + 2,
+            code_size: lzw_min_code_size + 1,
+            code_table: HashMap::new(),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.code_table.clear();
+        self.next_code = self.clear_code + 2;
+        self.code_size = self.lzw_min_code_size + 1;
+    }
+
+    fn emit(&mut self, code_writer: &mut CodeWriter, code: u16) {
+        code_writer.write(code, self.code_size);
+
+        // The decoder only learns about a new code once it has read the code that follows it,
+        // so the code size is bumped one code later than the table would suggest.
+        if self.next_code == (1 << self.code_size) && self.code_size < MAX_CODE_SIZE {
+            self.code_size += 1;
+        }
+    }
+
+    pub(crate) fn compress(&mut self) -> Vec<u8> {
+        let mut code_writer = CodeWriter::new();
+
+        self.reset();
+        code_writer.write(self.clear_code, self.code_size);
+
+        let mut iter = self.index_stream.iter();
+        let mut prefix = match iter.next() {
+            Some(k) => *k as u16,
+            None => {
+                code_writer.write(self.clear_code + 1, self.code_size);
+                return code_writer.finish();
+            }
+        };
+
+        for k in iter {
+            if let Some(code) = self.code_table.get(&(prefix, *k)) {
+                prefix = *code;
+                continue;
+            }
+
+            self.emit(&mut code_writer, prefix);
+
+            if self.next_code == (1 << MAX_CODE_SIZE) - 1 {
+                code_writer.write(self.clear_code, self.code_size);
+                self.reset();
+            } else {
+                self.code_table.insert((prefix, *k), self.next_code);
+                self.next_code += 1;
+            }
+
+            prefix = *k as u16;
+        }
+
+        self.emit(&mut code_writer, prefix);
+        code_writer.write(self.clear_code + 1, self.code_size);
+
+        code_writer.finish()
+    }
+}
+
+struct CodeWriter {
+    data: Vec<u8>,
+    buffer: u32,
+    buffered_bits: u8,
+}
+
+impl CodeWriter {
+    fn new() -> Self {
+        Self {
+            data: vec![],
+            buffer: 0,
+            buffered_bits: 0,
+        }
+    }
+
+    fn write(&mut self, code: u16, bits: u8) {
+        self.buffer |= (code as u32) << self.buffered_bits;
+        self.buffered_bits += bits;
+
+        while self.buffered_bits >= 8 {
+            self.data.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.buffered_bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.buffered_bits > 0 {
+            self.data.push(self.buffer as u8);
+        }
+
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decompressor::Decompressor;
+
+    #[test]
+    fn test_compressor_round_trip() {
+        let input = (0..20_000u32)
+            .map(|i| ((i * 7 + i / 13) % 251) as u8)
+            .collect::<Vec<_>>();
+
+        let compressed = Compressor::new(&input, 8).compress();
+        let actual = Decompressor::new(&compressed, 8).decompress().unwrap();
+
+        assert_eq!(
+            input,
+            actual.into_iter().map(|i| i as u8).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_compressor_small_code_size() {
+        let input = vec![1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 1, 1, 1, 0, 0, 0, 0, 2, 2, 2];
+
+        let compressed = Compressor::new(&input, 2).compress();
+        let actual = Decompressor::new(&compressed, 2).decompress().unwrap();
+
+        assert_eq!(
+            input,
+            actual.into_iter().map(|i| i as u8).collect::<Vec<_>>()
+        );
+    }
+}
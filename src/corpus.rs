@@ -0,0 +1,187 @@
+//! A harness for checking the decoder against a directory of reference GIFs.
+//!
+//! Every `<name>.gif` in the directory is paired with a `<name>.manifest` file that describes
+//! what it should decode to: the size, the loop count and, for every frame, its delay, the
+//! color of its first pixel and a checksum of all its pixels. A GIF that should fail to decode
+//! has a manifest holding the error instead.
+//!
+//! Manifests are plain text, so changes in decoder output show up clearly in diffs. Run
+//! [`check_dir`] with `bless` set to write the manifests of new or changed files, then review
+//! them before committing.
+//!
+//! This module is only available with the `corpus` feature.
+
+use crate::Gif;
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// The outcome of [`check_dir`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorpusReport {
+    /// The number of GIFs checked.
+    pub checked: usize,
+    /// The GIFs whose manifests were written because `bless` was set.
+    pub blessed: Vec<PathBuf>,
+    /// The GIFs that did not match their manifest, with the first difference.
+    pub failures: Vec<(PathBuf, String)>,
+}
+
+/// Build the manifest of a decoding result.
+pub fn manifest(result: &Result<Gif, String>) -> String {
+    let gif = match result {
+        Ok(gif) => gif,
+        Err(e) => return format!("error {}\n", e),
+    };
+
+    let mut manifest = format!("size {}x{}\n", gif.width, gif.height);
+    match gif.loop_count {
+        Some(count) => manifest.push_str(&format!("loop {}\n", count)),
+        None => manifest.push_str("loop none\n"),
+    }
+
+    for (i, frame) in gif.image_frames.iter().enumerate() {
+        let first = match frame.colors.first() {
+            Some(c) => format!("{},{},{}", c.r(), c.g(), c.b()),
+            None => "none".into(),
+        };
+        manifest.push_str(&format!(
+            "frame {} delay {} first {} checksum {:016x}\n",
+            i,
+            frame.delay_time,
+            first,
+            checksum(&frame.colors)
+        ));
+    }
+
+    manifest
+}
+
+// 64 bit FNV-1a over the RGB bytes of every pixel.
+fn checksum(colors: &[crate::Color]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for c in colors {
+        for byte in <[u8; 3]>::from(c).iter() {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// Decode every `.gif` file in `dir` and compare the result with its `.manifest` file. With
+/// `bless` set, missing or different manifests are written instead of being reported as
+/// failures.
+///
+/// # Errors
+///
+/// This function will return an error if `dir` cannot be listed or a file cannot be read or
+/// written.
+pub fn check_dir<P>(dir: P, bless: bool) -> Result<CorpusReport, String>
+where
+    P: AsRef<Path>,
+{
+    let mut paths = fs::read_dir(dir)
+        .map_err(|e| format!("Error: {}", e))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Error: {}", e))?;
+    paths.retain(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("gif")));
+    paths.sort();
+
+    let mut report = CorpusReport::default();
+    for path in paths {
+        let mut src = File::open(&path).map_err(|e| format!("Error: {}", e))?;
+        let actual = manifest(&crate::load(&mut src));
+        let manifest_path = path.with_extension("manifest");
+        let expected = fs::read_to_string(&manifest_path).ok();
+
+        report.checked += 1;
+        if expected.as_deref() == Some(actual.as_str()) {
+            continue;
+        }
+
+        if bless {
+            fs::write(&manifest_path, &actual).map_err(|e| format!("Error: {}", e))?;
+            report.blessed.push(path);
+            continue;
+        }
+
+        let difference = match expected {
+            None => "manifest is missing".into(),
+            Some(expected) => first_difference(&expected, &actual),
+        };
+        report.failures.push((path, difference));
+    }
+
+    Ok(report)
+}
+
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => continue,
+            (e, a) => {
+                return format!(
+                    "expected `{}`, got `{}`",
+                    e.unwrap_or("<end>"),
+                    a.unwrap_or("<end>")
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ImageFrame};
+
+    #[test]
+    fn test_manifest() {
+        let gif = Gif {
+            width: 2,
+            height: 1,
+            image_frames: vec![ImageFrame {
+                width: 2,
+                height: 1,
+                colors: vec![Color(1, 2, 3), Color(4, 5, 6)].into_boxed_slice(),
+                delay_time: 7,
+                placeholder: false,
+            }],
+            loop_count: Some(0),
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        };
+
+        let manifest = manifest(&Ok(gif));
+        let mut lines = manifest.lines();
+        assert_eq!(Some("size 2x1"), lines.next());
+        assert_eq!(Some("loop 0"), lines.next());
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("frame 0 delay 7 first 1,2,3 checksum "));
+        assert_eq!(None, lines.next());
+
+        assert_eq!(
+            "error Error: file is not a GIF\n",
+            super::manifest(&Err("Error: file is not a GIF".into()))
+        );
+    }
+
+    #[test]
+    fn test_first_difference() {
+        assert_eq!(
+            "expected `loop 0`, got `loop none`",
+            first_difference("size 1x1\nloop 0\n", "size 1x1\nloop none\n")
+        );
+        assert_eq!(
+            "expected `<end>`, got `frame 0`",
+            first_difference("size 1x1\n", "size 1x1\nframe 0\n")
+        );
+    }
+}
@@ -0,0 +1,62 @@
+// Reads GIF structures from a byte slice, keeping track of the offset. Every read returns
+// `None` if the data ends before it is complete.
+pub(crate) struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    #[inline(always)]
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    #[inline(always)]
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    // Step back `len` bytes, to read them again.
+    pub(crate) fn rewind(&mut self, len: usize) {
+        self.pos -= len;
+    }
+
+    pub(crate) fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Option<u8> {
+        self.read_bytes(1).map(|b| b[0])
+    }
+
+    pub(crate) fn read_u16(&mut self) -> Option<u16> {
+        self.read_bytes(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Option<u32> {
+        self.read_bytes(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Option<u64> {
+        self.read_bytes(8)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+    }
+
+    // Returns the joined data of the sub-blocks, up to and including the block terminator.
+    pub(crate) fn read_data_sub_blocks(&mut self) -> Option<Vec<u8>> {
+        let mut data = vec![];
+        loop {
+            match self.read_u8()? {
+                0 => return Some(data),
+                len => data.extend_from_slice(self.read_bytes(len as usize)?),
+            }
+        }
+    }
+}
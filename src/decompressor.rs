@@ -5,8 +5,12 @@ pub(crate) struct Decompressor<'a> {
     raw_codes: Vec<usize>,
     code_table: Vec<CodeType>,
     code_size: u8,
+    max_len: usize,
+    strict: bool,
 }
 
+const MAX_CODE_SIZE: u8 = 12;
+
 // Refer to https://www.w3.org/Graphics/GIF/spec-gif89a.txt for details.
 impl<'a> Decompressor<'a> {
     pub(crate) fn new(data_sub_blocks: &'a [u8], lzw_min_code_size: u8) -> Self {
@@ -17,9 +21,23 @@ impl<'a> Decompressor<'a> {
             raw_codes: vec![],
             code_table: vec![],
             code_size: lzw_min_code_size + 1,
+            max_len: usize::MAX,
+            strict: false,
         }
     }
 
+    // Stop decompressing once `max_len` indices have been decoded, so that a small stream
+    // cannot expand to more pixels than its image has.
+    pub(crate) fn set_max_len(&mut self, max_len: usize) {
+        self.max_len = max_len;
+    }
+
+    // Fail unless the data ends with an end of information code right after `max_len`
+    // indices, instead of stopping wherever the indices or the data run out.
+    pub(crate) fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
     fn reset(&mut self) {
         self.code_size = self.lzw_min_code_size + 1;
 
@@ -41,31 +59,49 @@ impl<'a> Decompressor<'a> {
     fn decompress_until_clear(
         &mut self,
         code_reader: &mut CodeReader,
-        result: &mut Vec<usize>,
+        result: &mut impl Indices,
     ) -> Result<bool, String> {
         let current;
         if let Some(c) = code_reader.read(self.code_size) {
             current = c;
         } else {
-            return Ok(false);
+            return self.missing_end();
         }
 
-        if let Some(CodeType::Range(begin, end)) = &self.code_table.get(current as usize) {
-            for i in &self.raw_codes[*begin..*end] {
-                result.push(*i);
+        match self.code_table.get(current as usize) {
+            Some(CodeType::Range(begin, end)) => {
+                for i in &self.raw_codes[*begin..*end] {
+                    result.push(*i);
+                }
             }
-        } else {
-            return Err(format!("Invalid code: {}", current));
+            // A clear code may follow another one, and an image without pixels is a clear
+            // code followed by the end of information code.
+            Some(CodeType::Raw(c)) if *c == self.clear_code => return Ok(true),
+            Some(CodeType::Raw(_)) => {
+                if self.strict {
+                    self.expect_no_padding(code_reader)?;
+                }
+                return Ok(false);
+            }
+            None => return Err(format!("Invalid code: {}", current)),
         }
 
         let mut prev = current;
 
         loop {
+            if result.len() >= self.max_len {
+                if self.strict {
+                    self.expect_end(code_reader, result.len())?;
+                }
+                result.truncate(self.max_len);
+                return Ok(false);
+            }
+
             let current;
             if let Some(c) = code_reader.read(self.code_size) {
                 current = c;
             } else {
-                return Ok(false);
+                return self.missing_end();
             }
 
             if (current as usize) < self.code_table.len() {
@@ -85,7 +121,7 @@ impl<'a> Decompressor<'a> {
                             let new_end = self.raw_codes.len();
 
                             if self.code_table.len() == (1 << self.code_size) - 1 {
-                                if self.code_size == 12 {
+                                if self.code_size == MAX_CODE_SIZE {
                                     self.expect_clear_code(code_reader)?;
                                     return Ok(true);
                                 } else {
@@ -104,12 +140,17 @@ impl<'a> Decompressor<'a> {
                         if *c == self.clear_code {
                             return Ok(true);
                         } else if *c == self.clear_code + 1 {
+                            if self.strict {
+                                self.expect_no_padding(code_reader)?;
+                            }
                             return Ok(false);
                         } else {
                             return Err(format!("Invalid single code {}", c));
                         }
                     }
                 }
+            } else if current as usize > self.code_table.len() {
+                return Err(format!("Invalid code: {}", current));
             } else if let CodeType::Range(begin, end) = &self.code_table[prev as usize] {
                 let new_begin = self.raw_codes.len();
                 for i in *begin..*end {
@@ -125,7 +166,7 @@ impl<'a> Decompressor<'a> {
                 }
 
                 if self.code_table.len() == (1 << self.code_size) - 1 {
-                    if self.code_size == 12 {
+                    if self.code_size == MAX_CODE_SIZE {
                         self.expect_clear_code(code_reader)?;
                         return Ok(true);
                     } else {
@@ -158,20 +199,107 @@ impl<'a> Decompressor<'a> {
         Ok(())
     }
 
+    // The data ran out before the end of information code, which only strict mode minds.
+    fn missing_end(&self) -> Result<bool, String> {
+        if self.strict {
+            return Err("Missing end of information code".into());
+        }
+        Ok(false)
+    }
+
+    // Strict mode expects the end of information code once `len` indices have been decoded,
+    // possibly after a clear code.
+    fn expect_end(&mut self, code_reader: &mut CodeReader, len: usize) -> Result<(), String> {
+        if len > self.max_len {
+            return Err(format!("More than {} pixels of data", self.max_len));
+        }
+        loop {
+            match code_reader.read(self.code_size).map(|c| c as usize) {
+                None => return Err("Missing end of information code".into()),
+                Some(c) if c == self.clear_code => self.code_size = self.lzw_min_code_size + 1,
+                Some(c) if c == self.clear_code + 1 => return self.expect_no_padding(code_reader),
+                Some(_) => return Err(format!("More than {} pixels of data", self.max_len)),
+            }
+        }
+    }
+
+    // Strict mode expects no data after the byte the end of information code ends in.
+    fn expect_no_padding(&self, code_reader: &CodeReader) -> Result<(), String> {
+        let padding = code_reader.remaining_bytes();
+        if padding > 0 {
+            return Err(format!(
+                "{} bytes of padding after the end of information code",
+                padding
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
     pub(crate) fn decompress(&mut self) -> Result<Vec<usize>, String> {
+        match self.decompress_partial() {
+            (result, None) => Ok(result),
+            (_, Some(e)) => Err(e),
+        }
+    }
+
+    // Like `decompress`, but keeps the indices decoded before an error.
+    pub(crate) fn decompress_partial(&mut self) -> (Vec<usize>, Option<String>) {
         let mut result = vec![];
+        let error = self.decompress_into(&mut result).err();
+        if let Some(ref e) = error {
+            warn!("invalid LZW data after {} indices: {}", result.len(), e);
+        }
+        (result, error)
+    }
+
+    // Decompresses into `result` as the indices are decoded, so they need not all be kept.
+    pub(crate) fn decompress_into(&mut self, result: &mut impl Indices) -> Result<(), String> {
+        // The clear and end of information codes must fit in the largest code size.
+        if !(1..MAX_CODE_SIZE).contains(&self.lzw_min_code_size) {
+            return Err(format!(
+                "Invalid LZW minimum code size: {}",
+                self.lzw_min_code_size
+            ));
+        }
 
         let mut code_reader = CodeReader::new(self.data_sub_blocks);
         self.expect_clear_code(&mut code_reader)?;
 
         loop {
             self.reset();
-            if !self.decompress_until_clear(&mut code_reader, &mut result)? {
+            if !self.decompress_until_clear(&mut code_reader, result)? {
                 break;
             }
+            trace!("LZW clear code after {} indices", result.len());
         }
 
-        Ok(result)
+        Ok(())
+    }
+}
+
+// Where decompressed color indices go.
+pub(crate) trait Indices {
+    fn push(&mut self, index: usize);
+
+    // The number of indices pushed so far. Decompression stops once it reaches the maximum
+    // length.
+    fn len(&self) -> usize;
+
+    fn truncate(&mut self, len: usize);
+}
+
+impl Indices for Vec<usize> {
+    fn push(&mut self, index: usize) {
+        Vec::push(self, index);
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn truncate(&mut self, len: usize) {
+        Vec::truncate(self, len);
     }
 }
 
@@ -196,6 +324,12 @@ impl<'a> CodeReader<'a> {
         }
     }
 
+    // The number of bytes that no bit has been read from yet.
+    fn remaining_bytes(&self) -> usize {
+        let started = self.index + (self.remaining_bits != 8) as usize;
+        self.data.len().saturating_sub(started)
+    }
+
     fn read(&mut self, mut bits: u8) -> Option<u16> {
         if self.index >= self.data.len() {
             return None;
@@ -379,7 +513,7 @@ mod tests {
             Color(255, 0, 0),
         ];
 
-        let color_table = vec![
+        let color_table = [
             Color(255, 255, 255),
             Color(255, 0, 0),
             Color(0, 0, 255),
@@ -396,4 +530,57 @@ mod tests {
 
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn test_decompressor_limits() {
+        let input = vec![
+            140, 45, 153, 135, 42, 28, 220, 51, 160, 2, 117, 236, 149, 250, 168, 222, 96, 140, 4,
+            145, 76, 1,
+        ];
+
+        let mut decompressor = Decompressor::new(&input, 2);
+        decompressor.set_max_len(7);
+        assert_eq!(7, decompressor.decompress().unwrap().len());
+
+        assert!(Decompressor::new(&input, 12).decompress().is_err());
+        assert!(Decompressor::new(&input, 0).decompress().is_err());
+
+        // A clear code and a color, followed by a code past the next free table entry.
+        let input = [0b1100_1100, 0b0000_0001];
+        assert!(Decompressor::new(&input, 2).decompress().is_err());
+
+        // A clear code followed by the end of information code holds no indices.
+        let input = [0b0010_1100];
+        assert_eq!(
+            Vec::<usize>::new(),
+            Decompressor::new(&input, 2).decompress().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decompressor_strict() {
+        let input = vec![
+            140, 45, 153, 135, 42, 28, 220, 51, 160, 2, 117, 236, 149, 250, 168, 222, 96, 140, 4,
+            145, 76, 1,
+        ];
+        let strict = |input: &[u8], max_len| {
+            let mut decompressor = Decompressor::new(input, 2);
+            decompressor.set_max_len(max_len);
+            decompressor.set_strict(true);
+            decompressor.decompress()
+        };
+        assert_eq!(100, strict(&input, 100).unwrap().len());
+
+        let padded = [&input[..], &[0, 0]].concat();
+        assert!(Decompressor::new(&padded, 2).decompress().is_ok());
+        let e = strict(&padded, 100).unwrap_err();
+        assert!(e.contains("2 bytes of padding"), "{}", e);
+
+        let e = strict(&input, 99).unwrap_err();
+        assert!(e.contains("More than 99 pixels"), "{}", e);
+
+        let cut = &input[..input.len() - 1];
+        assert!(Decompressor::new(cut, 2).decompress().is_ok());
+        assert!(strict(cut, 100).is_err());
+    }
 }
@@ -0,0 +1,218 @@
+use crate::util::display_time;
+use crate::Gif;
+
+use egui::{ColorImage, Context, TextureHandle, TextureOptions};
+use std::time::Duration;
+
+impl Gif {
+    /// Convert every frame to an `egui::ColorImage` of the size of the GIF, in frame order.
+    ///
+    /// This is only available with the `egui` feature.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a frame does not have `width * height` pixels.
+    pub fn to_color_images(&self) -> Result<Vec<ColorImage>, String> {
+        let size = [self.width as usize, self.height as usize];
+        self.image_frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                if frame.colors.len() != size[0] * size[1] {
+                    return Err(format!(
+                        "Error: frame {} has {} pixels, expected {}",
+                        i,
+                        frame.colors.len(),
+                        size[0] * size[1]
+                    ));
+                }
+                Ok(ColorImage::from_rgb(size, frame.as_bytes()))
+            })
+            .collect()
+    }
+}
+
+/// The frames of a GIF uploaded as egui textures, which picks the frame to show for a point in
+/// time. Create it once and keep it around, as creating it uploads every frame. Frames with a
+/// delay of 0 or 10 ms show for 100 ms, like browsers and [`Ticker`](crate::Ticker) do.
+///
+/// This is only available with the `egui` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::GifAnimation;
+/// use std::fs::File;
+///
+/// # fn show(ui: &mut egui::Ui) {
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let gif = giffy::load(&mut src).expect("Error loading GIF");
+/// let animation = GifAnimation::new(ui.ctx(), "my gif", &gif).expect("Error converting GIF");
+///
+/// // Every frame, with the time since the animation started:
+/// let elapsed = std::time::Duration::from_millis(ui.input(|i| (i.time * 1000.0) as u64));
+/// ui.image(animation.texture_at(elapsed));
+/// if let Some(delay) = animation.repaint_after(elapsed) {
+///     ui.ctx().request_repaint_after(delay);
+/// }
+/// # }
+/// ```
+pub struct GifAnimation {
+    textures: Vec<TextureHandle>,
+    // When every frame stops showing, counted from the start of the first frame.
+    ends: Vec<Duration>,
+    // How many times the animation plays, `None` for forever.
+    plays: Option<u32>,
+}
+
+impl GifAnimation {
+    /// Upload the frames of `gif` to `ctx` as textures named after `name`. Pixels are sampled
+    /// with nearest neighbor filtering, which keeps pixel art sharp when scaled up.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the GIF has no frames or if a frame does not have
+    /// `width * height` pixels.
+    pub fn new(ctx: &Context, name: &str, gif: &Gif) -> Result<Self, String> {
+        if gif.image_frames.is_empty() {
+            return Err("Error: GIF has no frames".into());
+        }
+
+        let textures = gif
+            .to_color_images()?
+            .into_iter()
+            .enumerate()
+            .map(|(i, image)| {
+                ctx.load_texture(format!("{}#{}", name, i), image, TextureOptions::NEAREST)
+            })
+            .collect();
+        let ends = gif
+            .image_frames
+            .iter()
+            .scan(Duration::ZERO, |end, frame| {
+                *end += display_time(frame.delay_time);
+                Some(*end)
+            })
+            .collect();
+        // The NETSCAPE2.0 loop count is the number of repeats after the first play.
+        let plays = match gif.loop_count {
+            None => Some(1),
+            Some(0) => None,
+            Some(n) => Some(n as u32 + 1),
+        };
+
+        Ok(Self {
+            textures,
+            ends,
+            plays,
+        })
+    }
+
+    /// The texture of the frame showing `elapsed` after the animation started. Once the
+    /// animation is done playing, it stays on the last frame.
+    pub fn texture_at(&self, elapsed: Duration) -> &TextureHandle {
+        &self.textures[self.frame_at(elapsed).unwrap_or(self.textures.len() - 1)]
+    }
+
+    /// How long after `elapsed` the next frame is due, or `None` if the animation is done.
+    /// Pass it to `Context::request_repaint_after` to keep the animation going.
+    pub fn repaint_after(&self, elapsed: Duration) -> Option<Duration> {
+        let index = self.frame_at(elapsed)?;
+        let total = self.total();
+        let position = Duration::from_nanos((elapsed.as_nanos() % total.as_nanos()) as u64);
+        Some(self.ends[index] - position)
+    }
+
+    /// The number of frames.
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    /// Whether there are no frames, which is never the case.
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+
+    fn total(&self) -> Duration {
+        self.ends[self.ends.len() - 1]
+    }
+
+    // The index of the frame showing at `elapsed`, or `None` once the animation is done.
+    fn frame_at(&self, elapsed: Duration) -> Option<usize> {
+        let total = self.total();
+        if total.is_zero() {
+            return None;
+        }
+        let plays = elapsed.as_nanos() / total.as_nanos();
+        if self.plays.is_some_and(|n| plays >= n as u128) {
+            return None;
+        }
+
+        let position = Duration::from_nanos((elapsed.as_nanos() % total.as_nanos()) as u64);
+        Some(self.ends.iter().position(|&end| position < end).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ImageFrame};
+
+    fn gif(loop_count: Option<u16>) -> Gif {
+        Gif {
+            width: 2,
+            height: 1,
+            image_frames: [10, 0, 20]
+                .iter()
+                .enumerate()
+                .map(|(i, &delay_time)| ImageFrame {
+                    width: 2,
+                    height: 1,
+                    colors: vec![Color(i as u8, 0, 0), Color(0, 0, 0)].into_boxed_slice(),
+                    delay_time,
+                    placeholder: false,
+                })
+                .collect(),
+            loop_count,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        }
+    }
+
+    #[test]
+    fn test_to_color_images() {
+        let images = gif(None).to_color_images().unwrap();
+        assert_eq!(3, images.len());
+        assert_eq!([2, 1], images[2].size);
+        assert_eq!(egui::Color32::from_rgb(2, 0, 0), images[2].pixels[0]);
+    }
+
+    #[test]
+    fn test_animation() {
+        let ctx = Context::default();
+        let ms = Duration::from_millis;
+
+        let animation = GifAnimation::new(&ctx, "test", &gif(None)).unwrap();
+        assert_eq!(3, animation.len());
+        assert_eq!(Some(0), animation.frame_at(ms(0)));
+        // The frame without a delay shows for 100 ms, like the ticker plays it.
+        assert_eq!(Some(1), animation.frame_at(ms(100)));
+        assert_eq!(Some(2), animation.frame_at(ms(200)));
+        assert_eq!(Some(ms(150)), animation.repaint_after(ms(250)));
+        // Played once, stays on the last frame.
+        assert_eq!(None, animation.frame_at(ms(400)));
+        assert_eq!(None, animation.repaint_after(ms(400)));
+        assert_eq!(
+            animation.textures[2].id(),
+            animation.texture_at(ms(400)).id()
+        );
+
+        let animation = GifAnimation::new(&ctx, "test", &gif(Some(1))).unwrap();
+        assert_eq!(Some(0), animation.frame_at(ms(450)));
+        assert_eq!(None, animation.frame_at(ms(800)));
+
+        let animation = GifAnimation::new(&ctx, "test", &gif(Some(0))).unwrap();
+        assert_eq!(Some(2), animation.frame_at(ms(40_000 + 250)));
+    }
+}
@@ -0,0 +1,891 @@
+use crate::compressor::Compressor;
+use crate::parser::*;
+use crate::quantizer::{self, QuantizeOptions};
+use crate::util::Color;
+use crate::writer::Writer;
+use crate::ImageFrame;
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::time::Duration;
+
+/// Controls whether the encoder writes a global color table, per-frame local color tables, or
+/// a mix of both.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PaletteMode {
+    /// Write a global color table shared by as many frames as possible and give local color
+    /// tables only to the frames whose colors it does not cover. Colors are never altered
+    /// beyond what is needed to fit a frame in 256 colors.
+    Auto,
+    /// Write a single global color table and map every frame onto it, which gives the smallest
+    /// files but may lose colors when the frames use more than 256 colors in total.
+    Global,
+    /// Give every frame its own local color table.
+    Local,
+}
+
+/// Encodes image frames into a GIF and writes it to a given `dst`.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::Encoder;
+/// use std::fs::File;
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let gif = giffy::load(&mut src).expect("Invalid GIF");
+///
+/// let mut dst = File::create("<output path>").expect("Unable to create file");
+/// let mut encoder = Encoder::new(&mut dst, gif.width as u16, gif.height as u16);
+/// encoder.set_loop_count(Some(0));
+/// encoder
+///     .write_frames(&gif.image_frames)
+///     .expect("Error encoding frames");
+/// encoder.finish().expect("Error finishing GIF");
+/// ```
+pub struct Encoder<W: Write> {
+    dst: W,
+    width: u16,
+    height: u16,
+    loop_count: Option<u16>,
+    pixel_aspect_ratio: Option<f32>,
+    comments: Vec<String>,
+    palette_mode: PaletteMode,
+    delta_optimization: bool,
+    transparent_color: Option<Color>,
+    sort_palette: bool,
+    global_color_table: Option<GlobalColorTable>,
+    canvas: Option<Box<[Color]>>,
+    started: bool,
+}
+
+// The part of the canvas a frame is written to. `None` marks pixels that are unchanged from
+// the previous frame and are therefore written as transparent.
+pub(crate) struct Region {
+    pub(crate) left: u16,
+    pub(crate) top: u16,
+    pub(crate) width: u16,
+    pub(crate) height: u16,
+    pub(crate) pixels: Vec<Option<Color>>,
+}
+
+struct GlobalColorTable {
+    colors: Vec<Color>,
+    lookup: HashMap<Color, u8>,
+}
+
+impl GlobalColorTable {
+    fn new(colors: Vec<Color>) -> Self {
+        let lookup = colors
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (*c, i as u8))
+            .collect();
+        Self { colors, lookup }
+    }
+
+    // Returns the index of every pixel, or `None` if the table is missing any of the colors.
+    fn exact_indices(&self, pixels: &[Color]) -> Option<Vec<u8>> {
+        pixels.iter().map(|c| self.lookup.get(c).copied()).collect()
+    }
+
+    fn nearest_indices(&mut self, pixels: &[Color]) -> Vec<u8> {
+        pixels
+            .iter()
+            .map(|c| match self.lookup.get(c) {
+                Some(i) => *i,
+                None => {
+                    let i = quantizer::nearest(&self.colors, *c);
+                    self.lookup.insert(*c, i);
+                    i
+                }
+            })
+            .collect()
+    }
+}
+
+impl<W: Write> Encoder<W> {
+    /// Create an encoder for a GIF of the given `width` and `height`.
+    pub fn new(dst: W, width: u16, height: u16) -> Self {
+        Self {
+            dst,
+            width,
+            height,
+            loop_count: None,
+            pixel_aspect_ratio: None,
+            comments: vec![],
+            palette_mode: PaletteMode::Auto,
+            delta_optimization: false,
+            transparent_color: None,
+            sort_palette: false,
+            global_color_table: None,
+            canvas: None,
+            started: false,
+        }
+    }
+
+    /// Encode a sequence of RGBA images into a GIF that loops forever and write it to `dst`.
+    ///
+    /// Each item holds the RGBA bytes of an image, its width, its height and how long it
+    /// should stay on screen. All images must have the same size. Colors are quantized when an
+    /// image has more than 256 of them, unchanged pixels between frames are left out, and the
+    /// alpha channel is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use giffy::Encoder;
+    /// use std::time::Duration;
+    ///
+    /// let red = [255, 0, 0, 255].repeat(4);
+    /// let blue = [0, 0, 255, 255].repeat(4);
+    /// let images = vec![
+    ///     (red, 2, 2, Duration::from_millis(100)),
+    ///     (blue, 2, 2, Duration::from_millis(100)),
+    /// ];
+    ///
+    /// let mut output = vec![];
+    /// Encoder::from_images(&mut output, images).expect("Error encoding GIF");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the images are empty, differ in size, are larger
+    /// than a GIF allows or if writing to `dst` fails.
+    pub fn from_images<I, B>(dst: W, images: I) -> Result<(), String>
+    where
+        I: IntoIterator<Item = (B, u32, u32, Duration)>,
+        B: AsRef<[u8]>,
+    {
+        let mut size = None;
+        let mut frames = vec![];
+
+        for (rgba, width, height, delay) in images {
+            let rgba = rgba.as_ref();
+            if *size.get_or_insert((width, height)) != (width, height) {
+                return Err(format!(
+                    "Error: image {} is {}x{}, expected {}x{}",
+                    frames.len(),
+                    width,
+                    height,
+                    size.unwrap().0,
+                    size.unwrap().1
+                ));
+            }
+
+            if rgba.len() != width as usize * height as usize * 4 {
+                return Err(format!(
+                    "Error: image {} has {} bytes, expected {}",
+                    frames.len(),
+                    rgba.len(),
+                    width as usize * height as usize * 4
+                ));
+            }
+
+            frames.push(ImageFrame {
+                width,
+                height,
+                colors: rgba.chunks_exact(4).map(|c| c[..3].into()).collect(),
+                delay_time: ((delay.as_millis() + 5) / 10).min(u16::MAX as u128) as u16,
+                placeholder: false,
+            });
+        }
+
+        let (width, height) = size.ok_or("Error: no images to encode")?;
+        let width =
+            u16::try_from(width).map_err(|_| format!("Error: width {} is too large", width))?;
+        let height =
+            u16::try_from(height).map_err(|_| format!("Error: height {} is too large", height))?;
+
+        let mut encoder = Encoder::new(dst, width, height);
+        encoder.set_loop_count(Some(0));
+        encoder.set_delta_optimization(true);
+        encoder.write_frames(&frames)?;
+        encoder.finish()
+    }
+
+    /// Set the number of times the animation should loop, `Some(0)` meaning forever.
+    /// `None` (the default) omits the NETSCAPE2.0 extension so the animation plays once.
+    ///
+    /// This has no effect once the first frame has been written.
+    pub fn set_loop_count(&mut self, loop_count: Option<u16>) {
+        self.loop_count = loop_count;
+    }
+
+    /// Set the aspect ratio of a pixel, width over height, written to the logical screen
+    /// descriptor. `None`, the default, means square pixels. The GIF format can only store
+    /// ratios from 1:4 to 4:1, in steps of 1/64, so the ratio is rounded to fit.
+    ///
+    /// This has no effect once the first frame has been written.
+    pub fn set_pixel_aspect_ratio(&mut self, pixel_aspect_ratio: Option<f32>) {
+        self.pixel_aspect_ratio = pixel_aspect_ratio;
+    }
+
+    /// Add a comment extension holding `text`. Comments are written before the first frame.
+    ///
+    /// This has no effect once the first frame has been written.
+    pub fn add_comment<S>(&mut self, text: S)
+    where
+        S: Into<String>,
+    {
+        self.comments.push(text.into());
+    }
+
+    /// Set how color tables are assigned to frames. Defaults to [`PaletteMode::Auto`].
+    ///
+    /// The global color table is chosen when the first frame is written, so only the frames
+    /// passed to that call are taken into account. Use [`Encoder::write_frames`] to have the
+    /// whole animation analyzed.
+    ///
+    /// This has no effect once the first frame has been written.
+    pub fn set_palette_mode(&mut self, palette_mode: PaletteMode) {
+        self.palette_mode = palette_mode;
+    }
+
+    /// Enable or disable delta optimization, which is disabled by default.
+    ///
+    /// When enabled, every frame after the first one only stores the smallest rectangle that
+    /// differs from the previous frame, and pixels inside it that did not change are written
+    /// as transparent. This usually makes animations a lot smaller.
+    ///
+    /// This has no effect once the first frame has been written.
+    pub fn set_delta_optimization(&mut self, delta_optimization: bool) {
+        self.delta_optimization = delta_optimization;
+    }
+
+    /// Set the color that stands for transparent pixels, such as the key of
+    /// [`Gif::key_out`](crate::Gif::key_out). `None`, the default, makes every pixel opaque.
+    ///
+    /// Pixels of this color are written as transparent, and every frame is cleared to the
+    /// background once it has been shown, so that they show what is behind the GIF rather
+    /// than the previous frame. This turns off delta optimization, which relies on frames
+    /// staying on the canvas.
+    ///
+    /// This has no effect once the first frame has been written.
+    pub fn set_transparent_color(&mut self, transparent_color: Option<Color>) {
+        self.transparent_color = transparent_color;
+    }
+
+    /// Enable or disable palette sorting, which is disabled by default.
+    ///
+    /// When enabled, color table entries are ordered by decreasing number of pixels using them
+    /// and the sort flag is set, so that decoders with fewer available colors can keep the
+    /// most important ones by truncating the table.
+    ///
+    /// This has no effect on the global color table once the first frame has been written.
+    pub fn set_sort_palette(&mut self, sort_palette: bool) {
+        self.sort_palette = sort_palette;
+    }
+
+    // One palette entry is kept free for the transparent color when delta optimization is on
+    // or there is a transparent color.
+    fn max_colors(&self) -> usize {
+        if self.may_be_transparent() {
+            255
+        } else {
+            256
+        }
+    }
+
+    // Whether frames may have transparent pixels, which take the index after the palette.
+    fn may_be_transparent(&self) -> bool {
+        self.delta_optimization || self.transparent_color.is_some()
+    }
+
+    // The colors of `frame` but the transparent color, which needs no palette entry.
+    fn opaque_colors(&self, frame: &ImageFrame) -> Vec<Color> {
+        frame
+            .colors
+            .iter()
+            .copied()
+            .filter(|c| Some(*c) != self.transparent_color)
+            .collect()
+    }
+
+    // Whether only the changes from the previous frame are written.
+    fn delta(&self) -> bool {
+        self.delta_optimization && self.transparent_color.is_none()
+    }
+
+    fn choose_global_color_table(&self, frames: &[ImageFrame]) -> Option<Vec<Color>> {
+        let mut palette = self.global_color_table_for(frames)?;
+
+        if self.sort_palette {
+            let mut histogram = HashMap::new();
+            for frame in frames {
+                quantizer::add_to_histogram(&mut histogram, &self.opaque_colors(frame));
+            }
+            quantizer::sort_by_usage(&mut palette, &histogram);
+        }
+
+        Some(palette)
+    }
+
+    fn global_color_table_for(&self, frames: &[ImageFrame]) -> Option<Vec<Color>> {
+        let max_colors = self.max_colors();
+        match self.palette_mode {
+            PaletteMode::Local => None,
+            PaletteMode::Global => {
+                let mut histogram = HashMap::new();
+                for frame in frames {
+                    quantizer::add_to_histogram(&mut histogram, &self.opaque_colors(frame));
+                }
+                Some(quantizer::palette(&histogram, max_colors))
+            }
+            PaletteMode::Auto => {
+                // Pick the frame palette that fully covers the most frames; every frame it
+                // covers saves a local color table.
+                let mut color_sets = HashMap::new();
+                for frame in frames {
+                    let set = sorted_palette(
+                        self.opaque_colors(frame)
+                            .into_iter()
+                            .collect::<HashSet<_>>(),
+                    );
+                    *color_sets.entry(set).or_insert(0) += 1;
+                }
+
+                let union = color_sets.keys().flatten().copied().collect::<HashSet<_>>();
+                if union.len() <= max_colors {
+                    return Some(sorted_palette(union));
+                }
+
+                let color_sets = color_sets
+                    .into_iter()
+                    .map(|(set, count)| (set.into_iter().collect::<HashSet<_>>(), count))
+                    .collect::<Vec<_>>();
+
+                color_sets
+                    .iter()
+                    .filter(|(candidate, _)| candidate.len() <= max_colors)
+                    .max_by_key(|(candidate, _)| {
+                        color_sets
+                            .iter()
+                            .filter(|(set, _)| set.is_subset(candidate))
+                            .map(|(_, count)| *count)
+                            .sum::<usize>()
+                    })
+                    .map(|(candidate, _)| sorted_palette(candidate.iter().copied()))
+            }
+        }
+    }
+
+    fn write_preamble(&mut self, frames: &[ImageFrame]) -> Result<(), String> {
+        if self.started {
+            return Ok(());
+        }
+
+        let global_color_table = self.choose_global_color_table(frames);
+        // Leave room for the transparent index after the colors.
+        let global_color_table_size = global_color_table
+            .as_ref()
+            .map(|table| color_table_size(table.len() + self.may_be_transparent() as usize))
+            .unwrap_or(0);
+
+        let mut writer = Writer::new(&mut self.dst);
+        writer.write_header(&Header {
+            sig: "GIF".into(),
+            version: "89a".into(),
+        })?;
+
+        writer.write_logical_screen_descriptor(&LogicalScreenDescriptor {
+            width: self.width,
+            height: self.height,
+            global_color_table_flag: global_color_table.is_some(),
+            color_resolution: 7,
+            sort_flag: self.sort_palette && global_color_table.is_some(),
+            global_color_table_size,
+            background_color_index: 0,
+            pixel_aspect_ratio: self
+                .pixel_aspect_ratio
+                .map_or(0f32, |ratio| ratio.clamp(16.0 / 64.0, 270.0 / 64.0)),
+            global_color_table: global_color_table.clone(),
+        })?;
+
+        if let Some(loop_count) = self.loop_count {
+            writer
+                .write_application_extension(&ApplicationExtension::netscape_looping(loop_count))?;
+        }
+
+        for text in self.comments.iter() {
+            writer.write_comment_extension(&CommentExtension { text: text.clone() })?;
+        }
+
+        self.global_color_table = global_color_table.map(GlobalColorTable::new);
+        self.started = true;
+        Ok(())
+    }
+
+    /// Encode and write all of `frames`. When called before any other frame has been written,
+    /// the color tables are chosen by looking at all of `frames` at once.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a frame size does not match the GIF size or if
+    /// writing to `dst` fails.
+    pub fn write_frames(&mut self, frames: &[ImageFrame]) -> Result<(), String> {
+        self.write_preamble(frames)?;
+        for frame in frames {
+            self.write_frame(frame)?;
+        }
+        Ok(())
+    }
+
+    /// Encode and write a single frame. The frame must cover the whole GIF canvas.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the frame size does not match the GIF size or if
+    /// writing to `dst` fails.
+    pub fn write_frame(&mut self, frame: &ImageFrame) -> Result<(), String> {
+        let expected_len = self.width as usize * self.height as usize;
+        if frame.colors.len() != expected_len {
+            return Err(format!(
+                "Error: frame has {} pixels, expected {}",
+                frame.colors.len(),
+                expected_len
+            ));
+        }
+
+        self.write_preamble(std::slice::from_ref(frame))?;
+
+        let mut region = self.changed_region(frame);
+        if let Some(key) = self.transparent_color {
+            for pixel in region.pixels.iter_mut() {
+                if *pixel == Some(key) {
+                    *pixel = None;
+                }
+            }
+        }
+
+        let image = self.encode_region(&region, frame.delay_time);
+        Writer::new(&mut self.dst).write_table_based_image(&image)
+    }
+
+    // Encodes `region` as an image shown for `delay_time`, with its `None` pixels transparent,
+    // and keeps the canvas of delta optimization up to date.
+    pub(crate) fn encode_region(&mut self, region: &Region, delay_time: u16) -> TableBasedImage {
+        let transparent = region.pixels.iter().any(Option::is_none);
+        let colors = region.pixels.iter().flatten().copied().collect::<Vec<_>>();
+
+        let global_indices = match (self.palette_mode, self.global_color_table.as_mut()) {
+            (PaletteMode::Global, Some(table)) => Some(table.nearest_indices(&colors)),
+            (PaletteMode::Auto, Some(table)) => table.exact_indices(&colors),
+            _ => None,
+        };
+
+        let (local_color_table, color_indices) = match global_indices {
+            Some(indices) => (None, indices),
+            None => {
+                let max_colors = self.max_colors().min(256 - transparent as usize);
+                let (mut palette, mut indices) =
+                    quantizer::quantize(&colors, max_colors, &QuantizeOptions::default());
+
+                if self.sort_palette {
+                    let mut histogram = HashMap::new();
+                    quantizer::add_to_histogram(&mut histogram, &colors);
+                    let remap = quantizer::sort_by_usage(&mut palette, &histogram);
+                    for i in indices.iter_mut() {
+                        *i = remap[*i as usize];
+                    }
+                }
+
+                (Some(palette), indices)
+            }
+        };
+
+        let palette = match (&local_color_table, &self.global_color_table) {
+            (Some(table), _) => table,
+            (None, Some(table)) => &table.colors,
+            (None, None) => unreachable!(),
+        };
+
+        let transparent_color_index = palette.len() as u8;
+        let mut color_indices = color_indices.into_iter();
+        let indices = region
+            .pixels
+            .iter()
+            .map(|c| match c {
+                Some(_) => color_indices.next().unwrap(),
+                None => transparent_color_index,
+            })
+            .collect::<Vec<_>>();
+
+        if self.delta() {
+            let len = self.width as usize * self.height as usize;
+            let canvas = self
+                .canvas
+                .get_or_insert_with(|| vec![Color(0, 0, 0); len].into());
+            for y in 0..region.height as usize {
+                let offset = (region.top as usize + y) * self.width as usize + region.left as usize;
+                for x in 0..region.width as usize {
+                    let i = indices[y * region.width as usize + x];
+                    if i != transparent_color_index || !transparent {
+                        canvas[offset + x] = palette[i as usize];
+                    }
+                }
+            }
+        }
+
+        let table_size = color_table_size(palette.len() + transparent as usize);
+        // The table is padded as it would be when written, so the image can also be decoded
+        // without being written first.
+        let local_color_table = local_color_table.map(|mut table| {
+            table.resize(1 << (table_size + 1), Color(0, 0, 0));
+            table
+        });
+        let lzw_min_code_size = (table_size + 1).max(2);
+        let data_sub_blocks = Compressor::new(&indices, lzw_min_code_size).compress();
+
+        TableBasedImage {
+            graphic_control_extension: Some(GraphicControlExtension {
+                disposal_method: if self.transparent_color.is_some() {
+                    DisposalMethod::RestoreToBackgroundColor
+                } else {
+                    DisposalMethod::DoNotDispose
+                },
+                user_input_expected: false,
+                transparent_color_index_available: transparent,
+                delay_time,
+                transparent_color_index: if transparent {
+                    transparent_color_index
+                } else {
+                    0
+                },
+            }),
+            image_descriptor: ImageDescriptor {
+                left: region.left,
+                top: region.top,
+                width: region.width,
+                height: region.height,
+                local_color_table_flag: local_color_table.is_some(),
+                interlace_flag: false,
+                sort_flag: self.sort_palette && local_color_table.is_some(),
+                local_color_table_size: if local_color_table.is_some() {
+                    table_size
+                } else {
+                    0
+                },
+            },
+            local_color_table,
+            image_data: ImageData {
+                lzw_min_code_size,
+                data_sub_blocks,
+            },
+        }
+    }
+
+    fn changed_region(&self, frame: &ImageFrame) -> Region {
+        let width = self.width as usize;
+
+        let canvas = match self.canvas {
+            Some(ref canvas) if self.delta() => canvas,
+            _ => {
+                return Region {
+                    left: 0,
+                    top: 0,
+                    width: self.width,
+                    height: self.height,
+                    pixels: frame.colors.iter().copied().map(Some).collect(),
+                };
+            }
+        };
+
+        let mut left = usize::MAX;
+        let mut top = usize::MAX;
+        let mut right = 0;
+        let mut bottom = 0;
+        for (i, (a, b)) in canvas.iter().zip(frame.colors.iter()).enumerate() {
+            if a != b {
+                let (x, y) = (i % width, i / width);
+                left = left.min(x);
+                top = top.min(y);
+                right = right.max(x + 1);
+                bottom = bottom.max(y + 1);
+            }
+        }
+
+        // A frame still has to be written for its delay even if nothing changed.
+        if left == usize::MAX {
+            (left, top, right, bottom) = (0, 0, 1, 1);
+        }
+
+        let mut pixels = Vec::with_capacity((right - left) * (bottom - top));
+        for y in top..bottom {
+            for x in left..right {
+                let i = y * width + x;
+                pixels.push(if canvas[i] == frame.colors[i] {
+                    None
+                } else {
+                    Some(frame.colors[i])
+                });
+            }
+        }
+
+        Region {
+            left: left as u16,
+            top: top as u16,
+            width: (right - left) as u16,
+            height: (bottom - top) as u16,
+            pixels,
+        }
+    }
+
+    /// Write the GIF trailer. No more frames can be written afterwards.
+    pub fn finish(mut self) -> Result<(), String> {
+        self.write_trailer()
+    }
+
+    pub(crate) fn write_trailer(&mut self) -> Result<(), String> {
+        self.write_preamble(&[])?;
+        Writer::new(&mut self.dst).write_trailer()
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        &mut self.dst
+    }
+}
+
+fn sorted_palette<I>(colors: I) -> Vec<Color>
+where
+    I: IntoIterator<Item = Color>,
+{
+    let mut palette = colors.into_iter().collect::<Vec<_>>();
+    palette.sort_by_key(|c| (c.0, c.1, c.2));
+    palette
+}
+
+// Returns the value of the 3-bit color table size field able to hold `len` colors.
+fn color_table_size(len: usize) -> u8 {
+    let mut size = 0;
+    while (1 << (size + 1)) < len {
+        size += 1;
+    }
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Severity;
+
+    fn frame(colors: Vec<Color>) -> ImageFrame {
+        ImageFrame {
+            width: 16,
+            height: 16,
+            colors: colors.into_boxed_slice(),
+            delay_time: 10,
+            placeholder: false,
+        }
+    }
+
+    fn gradient(offset: u8) -> ImageFrame {
+        frame(
+            (0..=255u8)
+                .map(|i| Color(i, offset, 255 - i))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn encode(palette_mode: PaletteMode, frames: &[ImageFrame]) -> Vec<u8> {
+        let mut output = vec![];
+        let mut encoder = Encoder::new(&mut output, 16, 16);
+        encoder.set_palette_mode(palette_mode);
+        encoder.write_frames(frames).unwrap();
+        encoder.finish().unwrap();
+        output
+    }
+
+    fn local_color_table_flags(data: &[u8]) -> Vec<bool> {
+        let gif = Parser::new().parse(&mut &data[..]).unwrap();
+        gif.data_blocks
+            .iter()
+            .filter_map(|block| match block {
+                DataType::TableBasedImageType(image) => {
+                    Some(image.image_descriptor.local_color_table_flag)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_auto_palette_uses_global_table_when_colors_fit() {
+        let red = Color(255, 0, 0);
+        let blue = Color(0, 0, 255);
+        let frames = [frame(vec![red; 256]), frame(vec![blue; 256])];
+
+        let output = encode(PaletteMode::Auto, &frames);
+
+        assert_eq!(0b1000_0000, output[10] & 0b1000_0000);
+        assert_eq!(vec![false, false], local_color_table_flags(&output));
+
+        let gif = crate::load(&mut output.as_slice()).unwrap();
+        assert_eq!(frames[1].colors, gif.image_frames[1].colors);
+    }
+
+    #[test]
+    fn test_auto_palette_falls_back_to_local_tables() {
+        let frames = [gradient(0), gradient(0), gradient(1)];
+
+        let output = encode(PaletteMode::Auto, &frames);
+
+        assert_eq!(vec![false, false, true], local_color_table_flags(&output));
+
+        let gif = crate::load(&mut output.as_slice()).unwrap();
+        for (expected, actual) in frames.iter().zip(gif.image_frames.iter()) {
+            assert_eq!(expected.colors, actual.colors);
+        }
+    }
+
+    #[test]
+    fn test_forced_palette_modes() {
+        let frames = [gradient(0), gradient(1)];
+
+        let output = encode(PaletteMode::Local, &frames);
+        assert_eq!(0, output[10] & 0b1000_0000);
+        assert_eq!(vec![true, true], local_color_table_flags(&output));
+
+        let output = encode(PaletteMode::Global, &frames);
+        assert_eq!(0b1000_0000, output[10] & 0b1000_0000);
+        assert_eq!(vec![false, false], local_color_table_flags(&output));
+    }
+
+    #[test]
+    fn test_delta_optimization() {
+        let base = frame((0..=255u8).map(|i| Color(i / 2, 0, 0)).collect());
+        let mut frames = vec![base.clone()];
+        for i in 1..4 {
+            let mut frame = base.clone();
+            frame.colors[17 * i] = Color(1, 2, 3);
+            frames.push(frame);
+        }
+        frames.push(base);
+
+        let encode = |delta_optimization| {
+            let mut output = vec![];
+            let mut encoder = Encoder::new(&mut output, 16, 16);
+            encoder.set_delta_optimization(delta_optimization);
+            encoder.write_frames(&frames).unwrap();
+            encoder.finish().unwrap();
+            output
+        };
+
+        let full = encode(false);
+        let delta = encode(true);
+        assert!(delta.len() < full.len());
+
+        let gif = crate::load(&mut delta.as_slice()).unwrap();
+        assert_eq!(frames.len(), gif.image_frames.len());
+        for (expected, actual) in frames.iter().zip(gif.image_frames.iter()) {
+            assert_eq!(expected.colors, actual.colors);
+        }
+    }
+
+    #[test]
+    fn test_from_images() {
+        let red = [255, 0, 0, 255].repeat(6);
+        let mut blue = red.clone();
+        blue[12..16].copy_from_slice(&[0, 0, 255, 255]);
+        let images = vec![
+            (red, 3, 2, Duration::from_millis(100)),
+            (blue, 3, 2, Duration::from_millis(42)),
+        ];
+
+        let mut output = vec![];
+        Encoder::from_images(&mut output, images).unwrap();
+
+        let gif = crate::load(&mut output.as_slice()).unwrap();
+        assert_eq!((3, 2), (gif.width, gif.height));
+        assert_eq!(Some(0), gif.loop_count);
+        assert_eq!(
+            vec![10, 4],
+            gif.image_frames
+                .iter()
+                .map(|f| f.delay_time)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Color(0, 0, 255), gif.image_frames[1].colors[3]);
+        assert_eq!(Color(255, 0, 0), gif.image_frames[1].colors[4]);
+    }
+
+    #[test]
+    fn test_transparent_index_fits_global_table() {
+        // Two colors fill a table of two entries, so the transparent index needs a bigger one.
+        let red = [255, 0, 0, 255].repeat(4);
+        // The corners that stay red are left transparent.
+        let mut blue = red.clone();
+        blue[..4].copy_from_slice(&[0, 0, 255, 255]);
+        blue[12..].copy_from_slice(&[0, 0, 255, 255]);
+        let images = vec![
+            (red, 2, 2, Duration::from_millis(100)),
+            (blue, 2, 2, Duration::from_millis(100)),
+        ];
+        let mut output = vec![];
+        Encoder::from_images(&mut output, images).unwrap();
+        let findings = crate::validate(&mut output.as_slice()).unwrap();
+        assert!(
+            findings.iter().all(|f| f.severity != Severity::Error),
+            "{:?}",
+            findings
+        );
+
+        let mut gif = crate::load(&mut output.as_slice()).unwrap();
+        gif.transparent_color = Some(Color(255, 0, 0));
+        let output = gif.to_bytes().unwrap();
+        let findings = crate::validate(&mut output.as_slice()).unwrap();
+        assert!(
+            findings.iter().all(|f| f.severity != Severity::Error),
+            "{:?}",
+            findings
+        );
+    }
+
+    #[test]
+    fn test_from_images_size_mismatch() {
+        let images = vec![
+            (vec![0; 16], 2, 2, Duration::ZERO),
+            (vec![0; 12], 3, 1, Duration::ZERO),
+        ];
+        assert!(Encoder::from_images(vec![], images).is_err());
+    }
+
+    #[test]
+    fn test_sort_palette() {
+        let mut colors = vec![Color(0, 0, 255); 200];
+        colors.extend(vec![Color(255, 0, 0); 50]);
+        colors.extend(vec![Color(0, 255, 0); 6]);
+        let frames = [frame(colors)];
+
+        for palette_mode in [PaletteMode::Auto, PaletteMode::Local] {
+            let mut output = vec![];
+            let mut encoder = Encoder::new(&mut output, 16, 16);
+            encoder.set_palette_mode(palette_mode);
+            encoder.set_sort_palette(true);
+            encoder.write_frames(&frames).unwrap();
+            encoder.finish().unwrap();
+
+            let gif = Parser::new().parse(&mut output.as_slice()).unwrap();
+            let image = match gif.data_blocks.last() {
+                Some(DataType::TableBasedImageType(image)) => image,
+                _ => panic!("missing image"),
+            };
+            let (table, sort_flag) = match palette_mode {
+                PaletteMode::Local => (&image.local_color_table, image.image_descriptor.sort_flag),
+                _ => (
+                    &gif.logical_screen_descriptor.global_color_table,
+                    gif.logical_screen_descriptor.sort_flag,
+                ),
+            };
+
+            assert!(sort_flag);
+            assert_eq!(
+                &[Color(0, 0, 255), Color(255, 0, 0), Color(0, 255, 0)],
+                &table.as_ref().unwrap()[..3]
+            );
+
+            let decoded = crate::load(&mut output.as_slice()).unwrap();
+            assert_eq!(frames[0].colors, decoded.image_frames[0].colors);
+        }
+    }
+}
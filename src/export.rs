@@ -0,0 +1,334 @@
+use crate::inspector::{Block, BlockKind};
+use crate::json::json_string;
+
+use std::fmt::Write;
+
+/// The output format of [`export`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A JSON array of blocks. Color tables and image data are nested in a `children` array
+    /// of the block they belong to.
+    Json,
+    /// A Graphviz digraph. Blocks are chained in file order, and color tables and image data
+    /// hang off the block they belong to with dashed edges.
+    Dot,
+}
+
+/// Describe the structure of a GIF, as listed by [`inspect`](crate::inspect), in the given
+/// `format`. Every block is written with its offset, size and descriptive fields, but no pixel
+/// data, which makes the result small enough to paste into a bug report.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::ExportFormat;
+/// use std::fs::File;
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let blocks = giffy::inspect(&mut src).expect("Error reading GIF");
+/// std::fs::write("structure.dot", giffy::export(&blocks, ExportFormat::Dot))
+///     .expect("Unable to write file");
+/// ```
+pub fn export(blocks: &[Block], format: ExportFormat) -> String {
+    let tree = tree(blocks);
+    match format {
+        ExportFormat::Json => json(&tree),
+        ExportFormat::Dot => dot(&tree),
+    }
+}
+
+// A block with the blocks that belong to it.
+struct Node<'a> {
+    block: &'a Block,
+    children: Vec<&'a Block>,
+}
+
+// Color tables belong to the descriptor before them, and image data to its image descriptor.
+fn tree(blocks: &[Block]) -> Vec<Node<'_>> {
+    let mut nodes: Vec<Node> = vec![];
+    for block in blocks {
+        let child = matches!(
+            block.kind,
+            BlockKind::GlobalColorTable { .. }
+                | BlockKind::LocalColorTable { .. }
+                | BlockKind::ImageData { .. }
+        );
+        match nodes.last_mut() {
+            Some(parent) if child => parent.children.push(block),
+            _ => nodes.push(Node {
+                block,
+                children: vec![],
+            }),
+        }
+    }
+    nodes
+}
+
+enum Value<'a> {
+    Number(u64),
+    Bool(bool),
+    Text(&'a str),
+    Null,
+}
+
+// The name of a block and its fields.
+fn describe(kind: &BlockKind) -> (&'static str, Vec<(&'static str, Value<'_>)>) {
+    use Value::*;
+
+    match kind {
+        BlockKind::Header { version } => ("header", vec![("version", Text(version))]),
+        BlockKind::LogicalScreenDescriptor { width, height } => (
+            "logical_screen_descriptor",
+            vec![
+                ("width", Number(*width as u64)),
+                ("height", Number(*height as u64)),
+            ],
+        ),
+        BlockKind::GlobalColorTable { colors } => (
+            "global_color_table",
+            vec![("colors", Number(*colors as u64))],
+        ),
+        BlockKind::GraphicControlExtension {
+            disposal_method,
+            delay_time,
+            transparent_color_index,
+        } => (
+            "graphic_control_extension",
+            vec![
+                ("disposal_method", Number(*disposal_method as u64)),
+                ("delay_time", Number(*delay_time as u64)),
+                (
+                    "transparent_color_index",
+                    transparent_color_index.map_or(Null, |i| Number(i as u64)),
+                ),
+            ],
+        ),
+        BlockKind::ApplicationExtension {
+            identifier,
+            sub_blocks,
+        } => (
+            "application_extension",
+            vec![
+                ("identifier", Text(identifier)),
+                ("sub_blocks", Number(*sub_blocks as u64)),
+            ],
+        ),
+        BlockKind::CommentExtension { sub_blocks } => (
+            "comment_extension",
+            vec![("sub_blocks", Number(*sub_blocks as u64))],
+        ),
+        BlockKind::PlainTextExtension { sub_blocks } => (
+            "plain_text_extension",
+            vec![("sub_blocks", Number(*sub_blocks as u64))],
+        ),
+        BlockKind::ImageDescriptor {
+            left,
+            top,
+            width,
+            height,
+            interlaced,
+        } => (
+            "image_descriptor",
+            vec![
+                ("left", Number(*left as u64)),
+                ("top", Number(*top as u64)),
+                ("width", Number(*width as u64)),
+                ("height", Number(*height as u64)),
+                ("interlaced", Bool(*interlaced)),
+            ],
+        ),
+        BlockKind::LocalColorTable { colors } => (
+            "local_color_table",
+            vec![("colors", Number(*colors as u64))],
+        ),
+        BlockKind::ImageData {
+            lzw_min_code_size,
+            sub_blocks,
+        } => (
+            "image_data",
+            vec![
+                ("lzw_min_code_size", Number(*lzw_min_code_size as u64)),
+                ("sub_blocks", Number(*sub_blocks as u64)),
+            ],
+        ),
+        BlockKind::Trailer => ("trailer", vec![]),
+        BlockKind::TrailingData => ("trailing_data", vec![]),
+        BlockKind::Invalid { reason } => ("invalid", vec![("reason", Text(reason))]),
+    }
+}
+
+fn json(tree: &[Node]) -> String {
+    let mut output = String::from("[\n");
+    for (i, node) in tree.iter().enumerate() {
+        output.push_str("  ");
+        json_block(&mut output, node.block);
+        if !node.children.is_empty() {
+            // Reopen the object to add the children.
+            output.pop();
+            output.push_str(", \"children\": [\n");
+            for (j, child) in node.children.iter().enumerate() {
+                output.push_str("    ");
+                json_block(&mut output, child);
+                output.push_str(if j + 1 < node.children.len() {
+                    ",\n"
+                } else {
+                    "\n"
+                });
+            }
+            output.push_str("  ]}");
+        }
+        output.push_str(if i + 1 < tree.len() { ",\n" } else { "\n" });
+    }
+    output.push_str("]\n");
+    output
+}
+
+fn json_block(output: &mut String, block: &Block) {
+    let (name, fields) = describe(&block.kind);
+    let _ = write!(
+        output,
+        "{{\"type\": \"{}\", \"offset\": {}, \"size\": {}",
+        name, block.offset, block.size
+    );
+    for (key, value) in fields {
+        let _ = write!(output, ", \"{}\": ", key);
+        match value {
+            Value::Number(n) => {
+                let _ = write!(output, "{}", n);
+            }
+            Value::Bool(b) => {
+                let _ = write!(output, "{}", b);
+            }
+            Value::Text(s) => output.push_str(&json_string(s)),
+            Value::Null => output.push_str("null"),
+        }
+    }
+    output.push('}');
+}
+
+fn dot(tree: &[Node]) -> String {
+    let mut output = String::from("digraph gif {\n    node [shape=box, fontname=monospace];\n");
+
+    let mut id = 0;
+    let mut previous = None;
+    for node in tree {
+        let parent = id;
+        dot_block(&mut output, parent, node.block);
+        id += 1;
+        if let Some(previous) = previous {
+            let _ = writeln!(output, "    b{} -> b{};", previous, parent);
+        }
+        previous = Some(parent);
+
+        for child in node.children.iter() {
+            dot_block(&mut output, id, child);
+            let _ = writeln!(output, "    b{} -> b{} [style=dashed];", parent, id);
+            id += 1;
+        }
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+fn dot_block(output: &mut String, id: usize, block: &Block) {
+    let (name, fields) = describe(&block.kind);
+    let mut label = format!("{}\\n{:#x}, {} bytes", name, block.offset, block.size);
+    for (key, value) in fields {
+        let value = match value {
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Text(s) => s.replace('\\', "\\\\").replace('"', "\\\""),
+            Value::Null => "none".into(),
+        };
+        let _ = write!(label, "\\n{}: {}", key, value);
+    }
+    let _ = writeln!(output, "    b{} [label=\"{}\"];", id, label);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks() -> Vec<Block> {
+        let block = |kind, offset, size| Block { kind, offset, size };
+        vec![
+            block(
+                BlockKind::Header {
+                    version: "89a".into(),
+                },
+                0,
+                6,
+            ),
+            block(
+                BlockKind::LogicalScreenDescriptor {
+                    width: 4,
+                    height: 4,
+                },
+                6,
+                7,
+            ),
+            block(BlockKind::GlobalColorTable { colors: 2 }, 13, 6),
+            block(
+                BlockKind::ImageDescriptor {
+                    left: 0,
+                    top: 0,
+                    width: 4,
+                    height: 4,
+                    interlaced: false,
+                },
+                19,
+                10,
+            ),
+            block(
+                BlockKind::ImageData {
+                    lzw_min_code_size: 2,
+                    sub_blocks: 1,
+                },
+                29,
+                8,
+            ),
+            block(
+                BlockKind::Invalid {
+                    reason: "say \"hi\"".into(),
+                },
+                37,
+                1,
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_export_json() {
+        let expected = concat!(
+            "[\n",
+            "  {\"type\": \"header\", \"offset\": 0, \"size\": 6, \"version\": \"89a\"},\n",
+            "  {\"type\": \"logical_screen_descriptor\", \"offset\": 6, \"size\": 7, ",
+            "\"width\": 4, \"height\": 4, \"children\": [\n",
+            "    {\"type\": \"global_color_table\", \"offset\": 13, \"size\": 6, \"colors\": 2}\n",
+            "  ]},\n",
+            "  {\"type\": \"image_descriptor\", \"offset\": 19, \"size\": 10, \"left\": 0, ",
+            "\"top\": 0, \"width\": 4, \"height\": 4, \"interlaced\": false, \"children\": [\n",
+            "    {\"type\": \"image_data\", \"offset\": 29, \"size\": 8, ",
+            "\"lzw_min_code_size\": 2, \"sub_blocks\": 1}\n",
+            "  ]},\n",
+            "  {\"type\": \"invalid\", \"offset\": 37, \"size\": 1, \"reason\": \"say \\\"hi\\\"\"}\n",
+            "]\n",
+        );
+        assert_eq!(expected, export(&blocks(), ExportFormat::Json));
+    }
+
+    #[test]
+    fn test_export_dot() {
+        let dot = export(&blocks(), ExportFormat::Dot);
+        assert!(dot.starts_with("digraph gif {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains("    b0 [label=\"header\\n0x0, 6 bytes\\nversion: 89a\"];\n"));
+        assert!(dot.contains("    b0 -> b1;\n"));
+        assert!(dot.contains("    b1 -> b2 [style=dashed];\n"));
+        assert!(dot.contains("    b1 -> b3;\n"));
+        assert!(dot.contains("    b3 -> b4 [style=dashed];\n"));
+        assert!(dot.contains("    b3 -> b5;\n"));
+        assert!(dot.contains("reason: say \\\"hi\\\"\"];"));
+    }
+}
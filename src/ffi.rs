@@ -0,0 +1,202 @@
+// The C API declared in include/giffy.h. Keep the two in sync.
+
+use crate::Gif;
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+use std::ptr;
+use std::slice;
+
+/// A decoded GIF, handed to C as an opaque pointer.
+pub struct GiffyGif {
+    gif: Gif,
+    // The frames as RGBA, so C gets a plain byte layout.
+    pixels: Vec<Box<[u8]>>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    // CString cannot hold an interior nul.
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Decode the GIF in the `len` bytes at `data`. Returns `NULL` on failure, in which case
+/// [`giffy_last_error`] describes what went wrong. Free the result with [`giffy_free`].
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, or be `NULL`.
+#[no_mangle]
+pub unsafe extern "C" fn giffy_load_from_bytes(data: *const u8, len: usize) -> *mut GiffyGif {
+    if data.is_null() {
+        set_last_error("Error: data is NULL".into());
+        return ptr::null_mut();
+    }
+
+    let mut bytes = slice::from_raw_parts(data, len);
+    match crate::load(&mut bytes) {
+        Ok(gif) => {
+            let pixels = gif
+                .image_frames
+                .iter()
+                .map(|frame| {
+                    frame
+                        .colors
+                        .iter()
+                        .flat_map(|c| [c.0, c.1, c.2, 255])
+                        .collect()
+                })
+                .collect();
+            Box::into_raw(Box::new(GiffyGif { gif, pixels }))
+        }
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// The message of the last error on this thread, or `NULL` if there was none. The string is
+/// valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn giffy_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// The width of the GIF in pixels.
+///
+/// # Safety
+///
+/// `gif` must come from [`giffy_load_from_bytes`] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn giffy_width(gif: *const GiffyGif) -> u32 {
+    (*gif).gif.width
+}
+
+/// The height of the GIF in pixels.
+///
+/// # Safety
+///
+/// `gif` must come from [`giffy_load_from_bytes`] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn giffy_height(gif: *const GiffyGif) -> u32 {
+    (*gif).gif.height
+}
+
+/// The number of times the animation should loop, 0 for forever or -1 if it does not loop.
+///
+/// # Safety
+///
+/// `gif` must come from [`giffy_load_from_bytes`] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn giffy_loop_count(gif: *const GiffyGif) -> i32 {
+    (*gif).gif.loop_count.map_or(-1, i32::from)
+}
+
+/// The number of frames.
+///
+/// # Safety
+///
+/// `gif` must come from [`giffy_load_from_bytes`] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn giffy_frame_count(gif: *const GiffyGif) -> usize {
+    let frames = &(*gif).gif.image_frames;
+    frames.len()
+}
+
+/// The delay of frame `index` in hundredths of a second, or 0 if there is no such frame.
+///
+/// # Safety
+///
+/// `gif` must come from [`giffy_load_from_bytes`] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn giffy_frame_delay(gif: *const GiffyGif, index: usize) -> u16 {
+    let frames = &(*gif).gif.image_frames;
+    frames.get(index).map_or(0, |frame| frame.delay_time)
+}
+
+/// The pixels of frame `index` as `width * height * 4` bytes of RGBA, row by row, or `NULL` if
+/// there is no such frame. The pixels are owned by `gif`.
+///
+/// # Safety
+///
+/// `gif` must come from [`giffy_load_from_bytes`] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn giffy_frame_pixels(gif: *const GiffyGif, index: usize) -> *const u8 {
+    let pixels = &(*gif).pixels;
+    pixels
+        .get(index)
+        .map_or(ptr::null(), |pixels| pixels.as_ptr())
+}
+
+/// Free a GIF returned by [`giffy_load_from_bytes`]. Does nothing if `gif` is `NULL`.
+///
+/// # Safety
+///
+/// `gif` must come from [`giffy_load_from_bytes`] and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn giffy_free(gif: *mut GiffyGif) {
+    if !gif.is_null() {
+        drop(Box::from_raw(gif));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ImageFrame};
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_load_from_bytes() {
+        let mut data = vec![];
+        Gif {
+            width: 2,
+            height: 1,
+            image_frames: vec![ImageFrame {
+                width: 2,
+                height: 1,
+                colors: vec![Color(1, 2, 3), Color(4, 5, 6)].into_boxed_slice(),
+                delay_time: 7,
+                placeholder: false,
+            }],
+            loop_count: Some(0),
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        }
+        .write_to(&mut data)
+        .unwrap();
+
+        unsafe {
+            let gif = giffy_load_from_bytes(data.as_ptr(), data.len());
+            assert!(!gif.is_null());
+            assert_eq!(2, giffy_width(gif));
+            assert_eq!(1, giffy_height(gif));
+            assert_eq!(0, giffy_loop_count(gif));
+            assert_eq!(1, giffy_frame_count(gif));
+            assert_eq!(7, giffy_frame_delay(gif, 0));
+            let pixels = slice::from_raw_parts(giffy_frame_pixels(gif, 0), 8);
+            assert_eq!(&[1, 2, 3, 255, 4, 5, 6, 255], pixels);
+            assert!(giffy_frame_pixels(gif, 1).is_null());
+            giffy_free(gif);
+        }
+    }
+
+    #[test]
+    fn test_last_error() {
+        unsafe {
+            let data = b"GIF89a";
+            assert!(giffy_load_from_bytes(data.as_ptr(), data.len()).is_null());
+            let error = CStr::from_ptr(giffy_last_error());
+            assert!(error.to_str().unwrap().starts_with("Error: "));
+
+            assert!(giffy_load_from_bytes(ptr::null(), 0).is_null());
+            giffy_free(ptr::null_mut());
+        }
+    }
+}
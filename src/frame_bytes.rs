@@ -0,0 +1,87 @@
+use crate::{Gif, ImageFrame};
+
+use bytes::Bytes;
+
+/// A frame whose pixels are RGB bytes in a `bytes::Bytes`, which is cheap to clone and can be
+/// handed to a network stack, such as the body of a hyper or axum response, without copying.
+///
+/// This is only available with the `bytes` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BytesFrame {
+    /// The colors of the frame as `width * height * 3` bytes of RGB, row by row.
+    pub pixels: Bytes,
+    /// The amount of time this frame should stay on screen, in hundredths of a second.
+    pub delay_time: u16,
+}
+
+impl From<ImageFrame> for BytesFrame {
+    /// Takes over the pixels of `frame` without copying them.
+    fn from(frame: ImageFrame) -> Self {
+        let len = frame.colors.len() * 3;
+        let colors = Box::into_raw(frame.colors);
+        // SAFETY: `Color` is `repr(C)` with three `u8` fields, so it has a size of 3 and an
+        // alignment of 1, and a `[Color]` has the same layout as a `[u8]` three times as long.
+        let pixels =
+            unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(colors as *mut u8, len)) };
+        Self {
+            pixels: Bytes::from(pixels),
+            delay_time: frame.delay_time,
+        }
+    }
+}
+
+impl Gif {
+    /// Turn the frames of this GIF into [`BytesFrame`]s, without copying their pixels.
+    ///
+    /// This is only available with the `bytes` feature.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// let mut src = File::open("<gif path>").expect("File not found");
+    /// let gif = giffy::load(&mut src).expect("Error loading GIF");
+    /// let frames = gif.into_bytes_frames();
+    /// // Every clone shares the same pixels.
+    /// let body = frames[0].pixels.clone();
+    /// ```
+    pub fn into_bytes_frames(self) -> Vec<BytesFrame> {
+        self.image_frames
+            .into_iter()
+            .map(BytesFrame::from)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+
+    #[test]
+    fn test_into_bytes_frames() {
+        let gif = Gif {
+            width: 2,
+            height: 1,
+            image_frames: vec![ImageFrame {
+                width: 2,
+                height: 1,
+                colors: vec![Color(1, 2, 3), Color(4, 5, 6)].into_boxed_slice(),
+                delay_time: 7,
+                placeholder: false,
+            }],
+            loop_count: None,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        };
+        let address = gif.image_frames[0].colors.as_ptr() as *const u8;
+
+        let frames = gif.into_bytes_frames();
+        assert_eq!(1, frames.len());
+        assert_eq!(&[1, 2, 3, 4, 5, 6], &frames[0].pixels[..]);
+        assert_eq!(7, frames[0].delay_time);
+        assert_eq!(address, frames[0].pixels.as_ptr());
+    }
+}
@@ -0,0 +1,238 @@
+use crate::observer::DecodeObserver;
+use crate::parser::{DataType, ParseResult, Parser, TableBasedImage};
+use crate::screen::{Screen, ScreenEvent};
+use crate::{DecodeOptions, Decoder, ImageFrame};
+
+use std::io::Read;
+use std::ops::ControlFlow;
+
+/// Callbacks invoked by [`decode_with_handler`] as it reads a GIF, in file order. Nothing is
+/// collected along the way, so a handler sees every frame as soon as it is decoded and the
+/// memory use stays at about two frames, however long the GIF is.
+///
+/// Every method does nothing by default, so only the interesting ones need to be implemented.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::{DecodeHandler, DecodeOptions, ImageFrame};
+/// use std::fs::File;
+///
+/// #[derive(Default)]
+/// struct Duration {
+///     centiseconds: u64,
+/// }
+///
+/// impl DecodeHandler for Duration {
+///     fn on_frame(&mut self, _index: usize, frame: &ImageFrame) {
+///         self.centiseconds += frame.delay_time as u64;
+///     }
+/// }
+///
+/// let mut duration = Duration::default();
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// giffy::decode_with_handler(&mut src, &DecodeOptions::default(), &mut duration)
+///     .expect("Error decoding GIF");
+/// println!("the animation takes {} ms", duration.centiseconds * 10);
+/// ```
+pub trait DecodeHandler {
+    /// Called once the logical screen descriptor has been read, with the size of every frame.
+    fn on_screen(&mut self, _width: u32, _height: u32) {}
+
+    /// Called with frame `index` once it has been decoded and composited. The frame is only
+    /// borrowed, so clone it to keep it.
+    fn on_frame(&mut self, _index: usize, _frame: &ImageFrame) {}
+
+    /// Called after each of the four passes of interlaced frame `index`, from 0 to 3, with the
+    /// canvas as it would show so far. Only called when
+    /// [`DecodeOptions::interlace_passes`] is set.
+    fn on_interlace_pass(&mut self, _index: usize, _pass: usize, _canvas: &ImageFrame) {}
+
+    /// Called with the text of every comment extension.
+    fn on_comment(&mut self, _text: &str) {}
+
+    /// Called with the identifier, the authentication code and the data of every application
+    /// extension, such as `"NETSCAPE"`, `"2.0"` and the loop count.
+    fn on_app_extension(&mut self, _id: &str, _auth_code: &str, _data: &[u8]) {}
+
+    /// Called when a problem is worked around in lenient mode, such as a frame that had to be
+    /// recovered or left out, or data that stops early.
+    fn on_warning(&mut self, _message: &str) {}
+}
+
+// Hands the warnings and interlace passes of a decode to a handler.
+struct Warnings<'h, H: DecodeHandler>(&'h mut H);
+
+impl<H: DecodeHandler> DecodeObserver for Warnings<'_, H> {
+    fn interlace_pass(&mut self, index: usize, pass: usize, canvas: &ImageFrame) {
+        self.0.on_interlace_pass(index, pass, canvas);
+    }
+
+    fn warning(&mut self, message: &str) {
+        self.0.on_warning(message);
+    }
+}
+
+/// Decode a GIF from `src` in a single pass, calling `handler` for every frame and extension
+/// as soon as it is read. Unlike [`load_with_options`](crate::load_with_options), no frames
+/// are kept, which suits streaming consumers such as video encoders or thumbnailers.
+///
+/// In lenient mode, broken frames are dealt with as set by `options`, and data that stops
+/// early after the logical screen descriptor ends the decode with a warning instead of an
+/// error.
+///
+/// # Errors
+///
+/// This function will return an error if `src` is not in a valid GIF format, as far as the
+/// options allow.
+pub fn decode_with_handler<R, H>(
+    src: &mut R,
+    options: &DecodeOptions,
+    handler: &mut H,
+) -> Result<(), String>
+where
+    R: Read,
+    H: DecodeHandler,
+{
+    let prefix = crate::skip_leading_bytes(src, options)?;
+    let src = &mut prefix.as_slice().chain(src);
+    let mut parser = Parser::new();
+    parser.set_strict(options.strict);
+    let mut screen = Screen::new();
+    // Only the last frame is needed to composite the next one.
+    let mut previous: Vec<ImageFrame> = Vec::with_capacity(1);
+    let mut index = 0;
+
+    let mut decode = |image: TableBasedImage, screen: &ParseResult, handler: &mut H| {
+        let decoder = Decoder::new(screen, *options);
+        let (frame, _) = decoder.decode_next(&previous, index, &image, &mut Warnings(handler))?;
+        if let Some(frame) = frame {
+            handler.on_frame(index, &frame);
+            previous.clear();
+            previous.push(frame);
+        }
+        index += 1;
+        Ok::<(), String>(())
+    };
+
+    let result = screen.read_events(&mut parser, src, |event| {
+        match event {
+            ScreenEvent::Screen(screen) => {
+                let lsd = &screen.logical_screen_descriptor;
+                handler.on_screen(lsd.width as u32, lsd.height as u32);
+            }
+            ScreenEvent::Image(image, screen) => decode(image, screen, handler)?,
+            ScreenEvent::Block(DataType::CommentExtensionType(ext)) => {
+                handler.on_comment(&ext.text)
+            }
+            ScreenEvent::Block(DataType::ApplicationExtensionType(ext)) => {
+                handler.on_app_extension(&ext.id, &ext.auth_code, &ext.data_sub_blocks)
+            }
+            ScreenEvent::Block(_) => (),
+        }
+        Ok(ControlFlow::Continue(()))
+    });
+
+    let e = match result {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+    };
+    let screen = match screen.into_inner() {
+        Some(screen) if options.is_lenient() => screen,
+        _ => return Err(e),
+    };
+    handler.on_warning(&format!("decoding the data read before the error: {}", e));
+    if let Some(image) = parser.take_truncated_image() {
+        decode(image, &screen, handler)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, Color, Gif};
+
+    #[derive(Default)]
+    struct Recorder {
+        screen: Option<(u32, u32)>,
+        frames: Vec<ImageFrame>,
+        comments: Vec<String>,
+        app_extensions: Vec<(String, Vec<u8>)>,
+        warnings: Vec<String>,
+    }
+
+    impl DecodeHandler for Recorder {
+        fn on_screen(&mut self, width: u32, height: u32) {
+            self.screen = Some((width, height));
+        }
+
+        fn on_frame(&mut self, index: usize, frame: &ImageFrame) {
+            assert_eq!(self.frames.len(), index);
+            self.frames.push(frame.clone());
+        }
+
+        fn on_comment(&mut self, text: &str) {
+            self.comments.push(text.into());
+        }
+
+        fn on_app_extension(&mut self, id: &str, auth_code: &str, data: &[u8]) {
+            self.app_extensions
+                .push((format!("{}{}", id, auth_code), data.to_vec()));
+        }
+
+        fn on_warning(&mut self, message: &str) {
+            self.warnings.push(message.into());
+        }
+    }
+
+    fn encoded() -> (Gif, Vec<u8>) {
+        let frames = (0..3).map(|i| vec![Color(i * 50, 0, 0); 4]);
+        testing::encoded(testing::gif(2, 2, frames))
+    }
+
+    #[test]
+    fn test_decode_with_handler() {
+        let (gif, data) = encoded();
+        let mut recorder = Recorder::default();
+        decode_with_handler(
+            &mut data.as_slice(),
+            &DecodeOptions::default(),
+            &mut recorder,
+        )
+        .unwrap();
+
+        assert_eq!(Some((2, 2)), recorder.screen);
+        assert_eq!(gif.image_frames.len(), recorder.frames.len());
+        for (expected, actual) in gif.image_frames.iter().zip(&recorder.frames) {
+            assert_eq!(expected.colors, actual.colors);
+        }
+        assert_eq!(gif.comments, recorder.comments);
+        assert_eq!(
+            vec![("NETSCAPE2.0".to_string(), vec![1, 0, 0])],
+            recorder.app_extensions
+        );
+        assert!(recorder.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_decode_with_handler_truncated() {
+        let (_, data) = encoded();
+        let data = &data[..data.len() - 1];
+
+        let mut recorder = Recorder::default();
+        assert!(
+            decode_with_handler(&mut &data[..], &DecodeOptions::default(), &mut recorder).is_err()
+        );
+
+        let options = DecodeOptions {
+            lenient: true,
+            placeholder_frames: false,
+            ..DecodeOptions::default()
+        };
+        let mut recorder = Recorder::default();
+        decode_with_handler(&mut &data[..], &options, &mut recorder).unwrap();
+        assert_eq!(3, recorder.frames.len());
+        assert_eq!(1, recorder.warnings.len());
+    }
+}
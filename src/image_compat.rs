@@ -0,0 +1,188 @@
+use crate::Gif;
+
+use image::error::{DecodingError, ImageFormatHint};
+use image::metadata::LoopCount;
+use image::{
+    AnimationDecoder, ColorType, Delay, Frame, Frames, ImageDecoder, ImageError, ImageFormat,
+    ImageResult,
+};
+use std::io::Read;
+use std::num::NonZeroU32;
+
+/// Plugs giffy into pipelines built on the `image` crate by implementing its decoder traits.
+///
+/// The GIF is decoded in full when the decoder is created. As an `AnimationDecoder`, every
+/// frame is yielded as a full canvas RGBA image, since giffy frames are already composited. As
+/// an `ImageDecoder`, it reads the first frame as RGB, so `DynamicImage::from_decoder` works
+/// for static GIFs.
+///
+/// This is only available with the `image-compat` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::GifDecoder;
+/// use image::AnimationDecoder;
+/// use std::fs::File;
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let decoder = GifDecoder::new(&mut src).expect("Error loading GIF");
+/// let frames = decoder.into_frames().collect_frames().expect("Error decoding frames");
+/// println!("{} frames", frames.len());
+/// ```
+#[derive(Debug, Clone)]
+pub struct GifDecoder {
+    gif: Gif,
+}
+
+impl GifDecoder {
+    /// Decode the GIF read from `src`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the GIF src is not in a valid GIF format.
+    pub fn new<R>(src: &mut R) -> Result<Self, String>
+    where
+        R: Read,
+    {
+        crate::load(src).map(Self::from)
+    }
+}
+
+impl From<Gif> for GifDecoder {
+    fn from(gif: Gif) -> Self {
+        Self { gif }
+    }
+}
+
+impl AnimationDecoder<'static> for GifDecoder {
+    fn into_frames(self) -> Frames<'static> {
+        let images = match self.gif.to_rgba_images() {
+            Ok(images) => images,
+            Err(e) => return Frames::new(Box::new(std::iter::once(Err(decoding_error(e))))),
+        };
+
+        let frames = images
+            .into_iter()
+            .zip(self.gif.image_frames)
+            .map(|(buffer, frame)| {
+                let delay = Delay::from_numer_denom_ms(frame.delay_time as u32 * 10, 1);
+                Ok(Frame::from_parts(buffer, 0, 0, delay))
+            });
+        Frames::new(Box::new(frames))
+    }
+
+    fn loop_count(&self) -> LoopCount {
+        match self.gif.loop_count {
+            Some(0) => LoopCount::Infinite,
+            Some(n) => LoopCount::Finite(NonZeroU32::new(n as u32).unwrap()),
+            None => LoopCount::Finite(NonZeroU32::new(1).unwrap()),
+        }
+    }
+}
+
+impl ImageDecoder for GifDecoder {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.gif.width, self.gif.height)
+    }
+
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgb8
+    }
+
+    fn read_image(self, buf: &mut [u8]) -> ImageResult<()> {
+        assert_eq!(self.total_bytes(), buf.len() as u64);
+
+        let frame = self
+            .gif
+            .image_frames
+            .first()
+            .ok_or_else(|| decoding_error("Error: GIF has no frames".into()))?;
+        for (c, pixel) in frame.colors.iter().zip(buf.chunks_exact_mut(3)) {
+            pixel.copy_from_slice(&<[u8; 3]>::from(c));
+        }
+        Ok(())
+    }
+
+    fn read_image_boxed(self: Box<Self>, buf: &mut [u8]) -> ImageResult<()> {
+        (*self).read_image(buf)
+    }
+}
+
+fn decoding_error(message: String) -> ImageError {
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Exact(ImageFormat::Gif),
+        message,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ImageFrame};
+
+    #[test]
+    fn test_into_frames() {
+        let gif = Gif {
+            width: 2,
+            height: 1,
+            image_frames: (0..2)
+                .map(|i| ImageFrame {
+                    width: 2,
+                    height: 1,
+                    colors: vec![Color(i, 2, 3), Color(4, 5, 6)].into_boxed_slice(),
+                    delay_time: 5 + i as u16,
+                    placeholder: false,
+                })
+                .collect(),
+            loop_count: Some(0),
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        };
+
+        let decoder = GifDecoder::from(gif);
+        assert!(matches!(decoder.loop_count(), LoopCount::Infinite));
+
+        let frames = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(2, frames.len());
+        for (i, frame) in frames.iter().enumerate() {
+            assert_eq!((2, 1), frame.buffer().dimensions());
+            assert_eq!(
+                &[i as u8, 2, 3, 255, 4, 5, 6, 255],
+                frame.buffer().as_raw().as_slice()
+            );
+            assert_eq!((5 + i as u32) * 10, frame.delay().numer_denom_ms().0);
+        }
+    }
+
+    #[test]
+    fn test_from_decoder() {
+        let gif = Gif {
+            width: 2,
+            height: 1,
+            image_frames: vec![ImageFrame {
+                width: 2,
+                height: 1,
+                colors: vec![Color(1, 2, 3), Color(4, 5, 6)].into_boxed_slice(),
+                delay_time: 0,
+                placeholder: false,
+            }],
+            loop_count: None,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        };
+
+        let image = image::DynamicImage::from_decoder(GifDecoder::from(gif.clone())).unwrap();
+        let image = image.as_rgb8().unwrap();
+        assert_eq!((2, 1), image.dimensions());
+        assert_eq!(&[1, 2, 3, 4, 5, 6], image.as_raw().as_slice());
+
+        let empty = Gif {
+            image_frames: vec![],
+            ..gif
+        };
+        assert!(image::DynamicImage::from_decoder(GifDecoder::from(empty)).is_err());
+    }
+}
@@ -0,0 +1,555 @@
+use crate::cursor::Cursor;
+use crate::observer::DecodeObserver;
+use crate::parser::{DataType, DisposalMethod, Event, ParseResult, Parser, TableBasedImage};
+use crate::screen::{Screen, ScreenEvent};
+use crate::{Color, DecodeOptions, Decoder, ImageFrame};
+
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+/// Where the blocks of every frame are in a GIF, as built by [`IndexedDecoder::new`].
+///
+/// The index can be saved with [`to_bytes`](Self::to_bytes), such as next to the GIF, and
+/// handed to [`IndexedDecoder::with_index`] later to skip reading the whole GIF again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameIndex {
+    pub(crate) frames: Vec<FrameEntry>,
+    pub(crate) loop_count: Option<u16>,
+    // The length of the GIF, up to and including the trailer.
+    pub(crate) len: u64,
+}
+
+// The start of a saved index, followed by the format version.
+const MAGIC: &[u8] = b"GIFFYIDX";
+const VERSION: u8 = 2;
+
+// Where the blocks of a frame are, counted from the start of the GIF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FrameEntry {
+    // The graphic control extension, which is always 8 bytes long.
+    pub(crate) graphic_control_extension: Option<u64>,
+    pub(crate) image: u64,
+    pub(crate) image_len: u64,
+    // In hundredths of a second, 0 without a graphic control extension.
+    pub(crate) delay_time: u16,
+    // Whether the frame can be drawn without knowing the frames before it.
+    pub(crate) keyframe: bool,
+}
+
+impl FrameIndex {
+    /// The number of frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Whether there are no frames.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Whether frame `n` can be drawn without knowing the frames before it, because it covers
+    /// the whole canvas or clears it first. The first frame always is one.
+    pub fn is_keyframe(&self, n: usize) -> bool {
+        self.frames.get(n).is_some_and(|frame| frame.keyframe)
+    }
+
+    /// How long frame `n` stays on screen, in hundredths of a second, or `None` if there is no
+    /// frame `n`.
+    pub fn delay_time(&self, n: usize) -> Option<u16> {
+        self.frames.get(n).map(|frame| frame.delay_time)
+    }
+
+    /// The frame showing `time` after the animation starts, during its first play, or `None`
+    /// if the animation is over by then.
+    pub fn frame_at(&self, time: Duration) -> Option<usize> {
+        let mut end = Duration::ZERO;
+        self.frames.iter().position(|frame| {
+            end += Duration::from_millis(frame.delay_time as u64 * 10);
+            time < end
+        })
+    }
+
+    /// Save the index as a few bytes per frame, to be read back with
+    /// [`from_bytes`](Self::from_bytes).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 16 + 27 * self.frames.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.len.to_le_bytes());
+        match self.loop_count {
+            Some(n) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&n.to_le_bytes());
+            }
+            None => bytes.extend_from_slice(&[0, 0, 0]),
+        }
+        bytes.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+
+        for frame in &self.frames {
+            // Bit 0: keyframe, bit 1: has a graphic control extension.
+            let flags =
+                frame.keyframe as u8 | (frame.graphic_control_extension.is_some() as u8) << 1;
+            bytes.push(flags);
+            bytes.extend_from_slice(&frame.delay_time.to_le_bytes());
+            if let Some(offset) = frame.graphic_control_extension {
+                bytes.extend_from_slice(&offset.to_le_bytes());
+            }
+            bytes.extend_from_slice(&frame.image.to_le_bytes());
+            bytes.extend_from_slice(&frame.image_len.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Read an index saved by [`to_bytes`](Self::to_bytes).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `bytes` is not a saved index, was saved by an
+    /// incompatible version of giffy, or is cut off.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut cursor = Cursor::new(bytes);
+        if cursor.read_bytes(MAGIC.len()) != Some(MAGIC) {
+            return Err("Error: not a frame index".into());
+        }
+        let cut_off = || "Error: frame index is cut off".to_string();
+        let version = cursor.read_u8().ok_or_else(cut_off)?;
+        if version != VERSION {
+            return Err(format!(
+                "Error: unsupported frame index version {}",
+                version
+            ));
+        }
+
+        let len = cursor.read_u64().ok_or_else(cut_off)?;
+        let has_loop_count = cursor.read_u8().ok_or_else(cut_off)? == 1;
+        let loop_count = cursor.read_u16().ok_or_else(cut_off)?;
+        let count = cursor.read_u32().ok_or_else(cut_off)?;
+
+        let mut frames = Vec::with_capacity((count as usize).min(cursor.remaining() / 19));
+        for _ in 0..count {
+            let flags = cursor.read_u8().ok_or_else(cut_off)?;
+            let delay_time = cursor.read_u16().ok_or_else(cut_off)?;
+            let graphic_control_extension = if flags & 0b10 != 0 {
+                Some(cursor.read_u64().ok_or_else(cut_off)?)
+            } else {
+                None
+            };
+            let frame = FrameEntry {
+                graphic_control_extension,
+                image: cursor.read_u64().ok_or_else(cut_off)?,
+                image_len: cursor.read_u64().ok_or_else(cut_off)?,
+                delay_time,
+                keyframe: flags & 0b01 != 0,
+            };
+            if frame
+                .image
+                .checked_add(frame.image_len)
+                .is_none_or(|end| end > len)
+            {
+                return Err("Error: frame index points past the end of the GIF".into());
+            }
+            frames.push(frame);
+        }
+        if cursor.remaining() != 0 {
+            return Err("Error: frame index has trailing data".into());
+        }
+
+        Ok(Self {
+            frames,
+            loop_count: has_loop_count.then_some(loop_count),
+            len,
+        })
+    }
+}
+
+// The blocks the parser reports while scanning, which place the images in the file.
+#[derive(Default)]
+struct Offsets {
+    graphic_control_extension: Option<u64>,
+    frames: Vec<FrameEntry>,
+    end: u64,
+}
+
+impl DecodeObserver for Offsets {
+    fn block_parsed(&mut self, name: &str, offset: usize, size: usize) {
+        match name {
+            "Graphic Control Extension" => self.graphic_control_extension = Some(offset as u64),
+            // A plain text extension takes the graphic control extension before it.
+            "Plain Text Extension" => self.graphic_control_extension = None,
+            "Image" => self.frames.push(FrameEntry {
+                graphic_control_extension: self.graphic_control_extension.take(),
+                image: offset as u64,
+                image_len: size as u64,
+                delay_time: 0,
+                keyframe: false,
+            }),
+            "Trailer" => self.end = (offset + size) as u64,
+            _ => (),
+        }
+    }
+}
+
+/// Decodes any frame of a GIF in a source that supports seeking, such as a file, without
+/// keeping the other frames around or reading the whole GIF again.
+///
+/// Creating the decoder reads the GIF once to note where the blocks of every frame are.
+/// Decoding a frame then seeks back to the blocks of the frames it builds on, starting from the
+/// closest keyframe before it: a frame that covers the whole canvas or clears it first. The
+/// last decoded frame is kept, so going through the frames in order decodes every one once.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::IndexedDecoder;
+/// use std::fs::File;
+///
+/// let src = File::open("<gif path>").expect("File not found");
+/// let mut decoder = IndexedDecoder::new(src).expect("Error indexing GIF");
+/// let middle = decoder.decode_frame(decoder.len() / 2).expect("Error decoding frame");
+/// println!("the middle frame has {} pixels", middle.colors.len());
+/// ```
+#[derive(Debug)]
+pub struct IndexedDecoder<R> {
+    src: R,
+    // Where the GIF starts in `src`.
+    start: u64,
+    // The header and logical screen descriptor, which every image is decoded against.
+    screen: ParseResult,
+    index: FrameIndex,
+    // The last frame decoded, and its number.
+    last: Option<(usize, ImageFrame)>,
+}
+
+impl<R: Read + Seek> IndexedDecoder<R> {
+    /// Read the GIF in `src`, from its current position, and note where every frame is.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `src` is not in a valid GIF format, or if
+    /// reading or seeking fails. The image data is not decompressed until a frame is decoded.
+    pub fn new(mut src: R) -> Result<Self, String> {
+        let start = src.stream_position().map_err(|e| format!("Error: {}", e))?;
+
+        let mut offsets = Offsets::default();
+        let mut screen = Screen::new();
+        let mut images = vec![];
+        let mut loop_count = None;
+        screen.read_events(
+            &mut Parser::with_observer(&mut offsets),
+            &mut src,
+            |event| {
+                match event {
+                    ScreenEvent::Image(image, screen) => {
+                        let delay_time = image
+                            .graphic_control_extension
+                            .as_ref()
+                            .map_or(0, |ext| ext.delay_time);
+                        images.push((is_keyframe(screen, &image), delay_time));
+                    }
+                    ScreenEvent::Block(DataType::ApplicationExtensionType(ext)) => {
+                        loop_count = loop_count.or(ext.loop_count());
+                    }
+                    ScreenEvent::Screen(_) | ScreenEvent::Block(_) => (),
+                }
+                Ok(ControlFlow::Continue(()))
+            },
+        )?;
+
+        let mut frames = offsets.frames;
+        for (i, (frame, (keyframe, delay_time))) in frames.iter_mut().zip(images).enumerate() {
+            frame.keyframe = i == 0 || keyframe;
+            frame.delay_time = delay_time;
+        }
+        let index = FrameIndex {
+            frames,
+            loop_count,
+            len: offsets.end,
+        };
+        Ok(Self {
+            src,
+            start,
+            screen: screen
+                .into_inner()
+                .ok_or("Error: logical screen descriptor is missing")?,
+            index,
+            last: None,
+        })
+    }
+
+    /// Like [`new`](Self::new), but with an index saved earlier instead of reading the whole
+    /// GIF. Only the header and the logical screen descriptor are read.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use giffy::{FrameIndex, IndexedDecoder};
+    /// use std::fs::{self, File};
+    ///
+    /// let src = File::open("<gif path>").expect("File not found");
+    /// let decoder = match fs::read("<index path>") {
+    ///     Ok(bytes) => {
+    ///         let index = FrameIndex::from_bytes(&bytes).expect("Error reading index");
+    ///         IndexedDecoder::with_index(src, index).expect("Error loading GIF")
+    ///     }
+    ///     Err(_) => {
+    ///         let decoder = IndexedDecoder::new(src).expect("Error indexing GIF");
+    ///         fs::write("<index path>", decoder.index().to_bytes()).expect("Error saving index");
+    ///         decoder
+    ///     }
+    /// };
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the header or logical screen descriptor in `src`
+    /// cannot be read, if reading or seeking fails, or if the GIF does not end where the index
+    /// says, which means it changed since the index was built.
+    pub fn with_index(mut src: R, index: FrameIndex) -> Result<Self, String> {
+        let start = src.stream_position().map_err(|e| format!("Error: {}", e))?;
+
+        // Only the header and the logical screen descriptor are needed.
+        let mut screen = Screen::new();
+        screen.read_events(&mut Parser::new(), &mut src, |_| Ok(ControlFlow::Break(())))?;
+
+        let mut decoder = Self {
+            src,
+            start,
+            screen: screen
+                .into_inner()
+                .ok_or("Error: logical screen descriptor is missing")?,
+            index,
+            last: None,
+        };
+        let mut trailer = vec![];
+        let stale = "Error: the GIF does not match the frame index";
+        match decoder.index.len.checked_sub(1) {
+            Some(offset) => decoder
+                .read_at(offset, 1, &mut trailer)
+                .map_err(|_| stale)?,
+            None => return Err(stale.into()),
+        }
+        if trailer != [0x3b] {
+            return Err(stale.into());
+        }
+        Ok(decoder)
+    }
+
+    /// The width of the GIF in pixels.
+    pub fn width(&self) -> u32 {
+        self.screen.logical_screen_descriptor.width as u32
+    }
+
+    /// The height of the GIF in pixels.
+    pub fn height(&self) -> u32 {
+        self.screen.logical_screen_descriptor.height as u32
+    }
+
+    /// The number of times the animation should loop. `None` means it should not loop and
+    /// `Some(0)` that it should loop forever.
+    pub fn loop_count(&self) -> Option<u16> {
+        self.index.loop_count
+    }
+
+    /// The number of frames.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Whether there are no frames.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Where the blocks of every frame are.
+    pub fn index(&self) -> &FrameIndex {
+        &self.index
+    }
+
+    /// Decode frame `n`, composited onto the full canvas like the frames of
+    /// [`load`](crate::load).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no frame `n`, if reading or seeking
+    /// fails, or if a frame it builds on cannot be decoded.
+    pub fn decode_frame(&mut self, n: usize) -> Result<ImageFrame, String> {
+        if n >= self.len() {
+            return Err(format!(
+                "Error: frame {} is out of range, the GIF has {} frames",
+                n,
+                self.len()
+            ));
+        }
+
+        let keyframe = (0..=n)
+            .rev()
+            .find(|&i| self.index.is_keyframe(i))
+            .unwrap_or(0);
+        let (mut frame, from) = match self.last.take() {
+            Some((i, frame)) if (keyframe..=n).contains(&i) => (Some(frame), i + 1),
+            _ => (None, keyframe),
+        };
+
+        for i in from..=n {
+            let image = self.read_image(i)?;
+            let decoder = Decoder::new(&self.screen, DecodeOptions::default());
+            frame = Some(match frame {
+                Some(mut frame) => {
+                    decoder.draw_frame(&mut frame, &image)?;
+                    frame
+                }
+                None if i == 0 => decoder.decode_frame(&[], &image)?.0,
+                None => {
+                    // A keyframe draws over every pixel, so the canvas it starts from does
+                    // not matter.
+                    let mut frame = ImageFrame {
+                        width: self.width(),
+                        height: self.height(),
+                        colors: vec![
+                            Color(0, 0, 0);
+                            self.width() as usize * self.height() as usize
+                        ]
+                        .into_boxed_slice(),
+                        delay_time: 0,
+                        placeholder: false,
+                    };
+                    decoder.draw_frame(&mut frame, &image)?;
+                    frame
+                }
+            });
+        }
+
+        let frame = frame.unwrap();
+        self.last = Some((n, frame.clone()));
+        Ok(frame)
+    }
+
+    // Reads the image of frame `n`, along with its graphic control extension.
+    fn read_image(&mut self, n: usize) -> Result<TableBasedImage, String> {
+        let entry = self.index.frames[n];
+        let mut data = vec![];
+        if let Some(offset) = entry.graphic_control_extension {
+            self.read_at(offset, 8, &mut data)?;
+        }
+        self.read_at(entry.image, entry.image_len, &mut data)?;
+
+        let events = Parser::for_blocks().feed(&data)?;
+        events
+            .into_iter()
+            .find_map(|event| match event {
+                Event::Block(DataType::TableBasedImageType(image)) => Some(image),
+                _ => None,
+            })
+            .ok_or_else(|| format!("Error: frame {} is not where the index says", n))
+    }
+
+    // Appends the `len` bytes at `offset` from the start of the GIF to `data`.
+    fn read_at(&mut self, offset: u64, len: u64, data: &mut Vec<u8>) -> Result<(), String> {
+        self.src
+            .seek(SeekFrom::Start(self.start + offset))
+            .map_err(|e| format!("Error: {}", e))?;
+        let end = data.len();
+        data.resize(end + len as usize, 0);
+        self.src
+            .read_exact(&mut data[end..])
+            .map_err(|e| format!("Error: {}", e))
+    }
+}
+
+// Whether `image` draws over every pixel of the canvas, so the frames before it do not show.
+fn is_keyframe(screen: &ParseResult, image: &TableBasedImage) -> bool {
+    let lsd = &screen.logical_screen_descriptor;
+    let desc = &image.image_descriptor;
+    let covers_canvas =
+        (desc.left, desc.top, desc.width, desc.height) == (0, 0, lsd.width, lsd.height);
+    match image.graphic_control_extension {
+        Some(ref ext) => {
+            ext.disposal_method == DisposalMethod::RestoreToBackgroundColor
+                || covers_canvas && !ext.transparent_color_index_available
+        }
+        None => covers_canvas,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, Gif};
+    use std::io;
+
+    // Frame n shows for n hundredths of a second, so frame 0 has no delay.
+    fn encoded() -> (Gif, Vec<u8>) {
+        let frames = (0..5).map(|i| {
+            let mut colors = vec![Color(10, 20, 30); 6];
+            colors[i % 6] = Color(i as u8 * 40, 0, 0);
+            colors
+        });
+        let mut gif = testing::gif(3, 2, frames);
+        for (i, frame) in gif.image_frames.iter_mut().enumerate() {
+            frame.delay_time = i as u16;
+        }
+        gif.loop_count = Some(3);
+        testing::encoded(gif)
+    }
+
+    #[test]
+    fn test_decode_frame() {
+        let (gif, data) = encoded();
+        let mut decoder = IndexedDecoder::new(io::Cursor::new(data)).unwrap();
+        assert_eq!((3, 2), (decoder.width(), decoder.height()));
+        assert_eq!(5, decoder.len());
+        assert_eq!(Some(3), decoder.loop_count());
+        assert!(decoder.index().is_keyframe(0));
+        assert_eq!(Some(4), decoder.index().delay_time(4));
+
+        // Frame 0 has no delay, so it never shows.
+        let ms = Duration::from_millis;
+        assert_eq!(Some(1), decoder.index().frame_at(ms(0)));
+        assert_eq!(Some(2), decoder.index().frame_at(ms(25)));
+        assert_eq!(None, decoder.index().frame_at(ms(100)));
+
+        // Out of order, backwards and repeated.
+        for n in [3, 1, 4, 4, 0, 2] {
+            let frame = decoder.decode_frame(n).unwrap();
+            assert_eq!(gif.image_frames[n].colors, frame.colors);
+            assert_eq!(gif.image_frames[n].delay_time, frame.delay_time);
+        }
+        assert!(decoder.decode_frame(5).is_err());
+    }
+
+    #[test]
+    fn test_start_offset() {
+        let (gif, data) = encoded();
+        let mut src = io::Cursor::new([b"junk".to_vec(), data].concat());
+        src.set_position(4);
+        let mut decoder = IndexedDecoder::new(src).unwrap();
+        assert_eq!(
+            gif.image_frames[2].colors,
+            decoder.decode_frame(2).unwrap().colors
+        );
+    }
+
+    #[test]
+    fn test_saved_index() {
+        let (gif, data) = encoded();
+        let decoder = IndexedDecoder::new(io::Cursor::new(&data)).unwrap();
+        let bytes = decoder.index().to_bytes();
+        let index = FrameIndex::from_bytes(&bytes).unwrap();
+        assert_eq!(decoder.index(), &index);
+
+        let mut decoder =
+            IndexedDecoder::with_index(io::Cursor::new(&data), index.clone()).unwrap();
+        assert_eq!(Some(3), decoder.loop_count());
+        assert_eq!(
+            gif.image_frames[4].colors,
+            decoder.decode_frame(4).unwrap().colors
+        );
+
+        // A different GIF, or a broken index.
+        let other = &data[..data.len() - 1];
+        assert!(IndexedDecoder::with_index(io::Cursor::new(other), index).is_err());
+        assert!(FrameIndex::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(FrameIndex::from_bytes(b"GIF89a").is_err());
+    }
+}
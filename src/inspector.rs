@@ -0,0 +1,371 @@
+use crate::observer::DecodeObserver;
+use crate::parser::{self, DataType, Event, Parser};
+
+use std::io::Read;
+
+/// The type of a [`Block`], with the fields that are useful for telling blocks apart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockKind {
+    /// The signature and version, such as `GIF89a`.
+    Header {
+        /// The version, `87a` or `89a`.
+        version: String,
+    },
+    /// The size of the logical screen.
+    LogicalScreenDescriptor {
+        /// The width of the logical screen.
+        width: u16,
+        /// The height of the logical screen.
+        height: u16,
+    },
+    /// The color table shared by images without a local color table.
+    GlobalColorTable {
+        /// The number of colors in the table.
+        colors: usize,
+    },
+    /// Rendering settings for the next image or plain text block.
+    GraphicControlExtension {
+        /// The disposal method, as written in the file, with the undefined values 4 to 7 read
+        /// as 4.
+        disposal_method: u8,
+        /// The delay time in hundredths of a second.
+        delay_time: u16,
+        /// The transparent color index, if transparency is enabled.
+        transparent_color_index: Option<u8>,
+    },
+    /// Application specific data, such as the NETSCAPE2.0 looping extension.
+    ApplicationExtension {
+        /// The application identifier followed by the authentication code.
+        identifier: String,
+        /// The number of data sub-blocks.
+        sub_blocks: usize,
+    },
+    /// A text comment.
+    CommentExtension {
+        /// The number of data sub-blocks.
+        sub_blocks: usize,
+    },
+    /// Text to be drawn as a graphic.
+    PlainTextExtension {
+        /// The number of data sub-blocks.
+        sub_blocks: usize,
+    },
+    /// The position and size of an image.
+    ImageDescriptor {
+        /// The column of the left edge of the image.
+        left: u16,
+        /// The row of the top edge of the image.
+        top: u16,
+        /// The width of the image.
+        width: u16,
+        /// The height of the image.
+        height: u16,
+        /// Whether the image rows are interlaced.
+        interlaced: bool,
+    },
+    /// The color table of the preceding image descriptor.
+    LocalColorTable {
+        /// The number of colors in the table.
+        colors: usize,
+    },
+    /// The LZW compressed pixels of the preceding image descriptor.
+    ImageData {
+        /// The LZW minimum code size.
+        lzw_min_code_size: u8,
+        /// The number of data sub-blocks.
+        sub_blocks: usize,
+    },
+    /// The end of the GIF.
+    Trailer,
+    /// Data after the trailer.
+    TrailingData,
+    /// Data that could not be read as a block. It covers the rest of the file.
+    Invalid {
+        /// Why the data could not be read.
+        reason: String,
+    },
+}
+
+/// A block of a GIF file, as listed by [`inspect`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    /// The type of the block.
+    pub kind: BlockKind,
+    /// The byte offset of the block in the file.
+    pub offset: usize,
+    /// The size of the block in bytes, including its data sub-blocks.
+    pub size: usize,
+}
+
+/// List every block of the GIF read from `src` in file order, with its offset and size. Pixel
+/// data is not decompressed, so this is cheap even for large files.
+///
+/// Blocks are read the way [`load`](crate::load) reads them, and listing stops at the first
+/// block that cannot be read, which is returned as a [`BlockKind::Invalid`] block.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// for block in giffy::inspect(&mut src).expect("Error reading GIF") {
+///     println!("{:#010x} {:>8} {:?}", block.offset, block.size, block.kind);
+/// }
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if reading from `src` fails.
+pub fn inspect<R>(src: &mut R) -> Result<Vec<Block>, String>
+where
+    R: Read,
+{
+    let mut data = vec![];
+    src.read_to_end(&mut data)
+        .map_err(|e| format!("Error: {}", e))?;
+
+    let mut boundaries = Boundaries::default();
+    let mut parser = Parser::with_observer(&mut boundaries);
+    // An error is kept by the parser, and the blocks read before it are still handed out.
+    let events = parser.feed(&data).unwrap_or_default();
+    let reason = match parser.error() {
+        Some(e) => Some(e.strip_prefix("Error: ").unwrap_or(e).to_string()),
+        None if !parser.is_done() => Some("file ends inside of this block".to_string()),
+        None => None,
+    };
+
+    let mut blocks = vec![];
+    let mut push = |kind, offset, size| {
+        blocks.push(Block { kind, offset, size });
+        offset + size
+    };
+    // Every block but a graphic control extension is handed out as an event, in file order.
+    let mut events = events.into_iter();
+    let mut end = 0;
+    for (name, offset, size) in boundaries.0 {
+        end = offset + size;
+        if name == "Graphic Control Extension" {
+            let ext = parser::read_graphic_control_extension(&data[offset..end])
+                .ok_or("Error: invalid Graphic Control Extension")?;
+            push(
+                BlockKind::GraphicControlExtension {
+                    disposal_method: ext.disposal_method.value(),
+                    delay_time: ext.delay_time,
+                    transparent_color_index: ext
+                        .transparent_color_index_available
+                        .then_some(ext.transparent_color_index),
+                },
+                offset,
+                size,
+            );
+            continue;
+        }
+
+        let event = events.next().ok_or("Error: block without an event")?;
+        match event {
+            Event::Header(header) => {
+                push(
+                    BlockKind::Header {
+                        version: header.version,
+                    },
+                    offset,
+                    size,
+                );
+            }
+            Event::LogicalScreenDescriptor(lsd) => {
+                let colors = lsd.global_color_table.map_or(0, |table| table.len());
+                let kind = BlockKind::LogicalScreenDescriptor {
+                    width: lsd.width,
+                    height: lsd.height,
+                };
+                let table = push(kind, offset, size - 3 * colors);
+                if colors > 0 {
+                    push(BlockKind::GlobalColorTable { colors }, table, 3 * colors);
+                }
+            }
+            Event::Block(DataType::ApplicationExtensionType(ext)) => {
+                let kind = BlockKind::ApplicationExtension {
+                    identifier: format!("{}{}", ext.id, ext.auth_code),
+                    sub_blocks: ext.sub_blocks().len(),
+                };
+                push(kind, offset, size);
+            }
+            Event::Block(DataType::CommentExtensionType(ext)) => {
+                // The introducer and the label come before the data sub-blocks.
+                let sub_blocks = sub_blocks(size - 2, ext.text.len());
+                push(BlockKind::CommentExtension { sub_blocks }, offset, size);
+            }
+            Event::Block(DataType::PlainTextExtensionType(ext)) => {
+                // So do the 12 bytes of the text grid, with their size.
+                let sub_blocks = sub_blocks(size - 15, ext.plain_text_data.len());
+                push(BlockKind::PlainTextExtension { sub_blocks }, offset, size);
+            }
+            Event::Block(DataType::TableBasedImageType(image)) => {
+                let desc = &image.image_descriptor;
+                let kind = BlockKind::ImageDescriptor {
+                    left: desc.left,
+                    top: desc.top,
+                    width: desc.width,
+                    height: desc.height,
+                    interlaced: desc.interlace_flag,
+                };
+                let mut next = push(kind, offset, IMAGE_DESCRIPTOR_LEN);
+                if let Some(ref table) = image.local_color_table {
+                    let colors = table.len();
+                    next = push(BlockKind::LocalColorTable { colors }, next, 3 * colors);
+                }
+                // The LZW minimum code size comes before the data sub-blocks.
+                let data = &image.image_data;
+                let kind = BlockKind::ImageData {
+                    lzw_min_code_size: data.lzw_min_code_size,
+                    sub_blocks: sub_blocks(end - next - 1, data.data_sub_blocks.len()),
+                };
+                push(kind, next, end - next);
+            }
+            Event::Trailer => {
+                push(BlockKind::Trailer, offset, size);
+            }
+        }
+    }
+
+    let size = data.len() - end;
+    match reason {
+        Some(reason) => push(BlockKind::Invalid { reason }, end, size),
+        None if size > 0 => push(BlockKind::TrailingData, end, size),
+        None => end,
+    };
+    Ok(blocks)
+}
+
+// The size of an image descriptor, with its separator.
+const IMAGE_DESCRIPTOR_LEN: usize = 10;
+
+// The blocks the parser reports, with their names, offsets and sizes.
+#[derive(Default)]
+struct Boundaries(Vec<(String, usize, usize)>);
+
+impl DecodeObserver for Boundaries {
+    fn block_parsed(&mut self, name: &str, offset: usize, size: usize) {
+        self.0.push((name.into(), offset, size));
+    }
+}
+
+// The number of data sub-blocks in `len` bytes of them holding `data` bytes of data. Each one
+// takes a size byte, and they end with a block terminator.
+fn sub_blocks(len: usize, data: usize) -> usize {
+    len.saturating_sub(data + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Encoder, ImageFrame};
+
+    fn sample() -> Vec<u8> {
+        let frame = ImageFrame {
+            width: 4,
+            height: 4,
+            colors: (0..16).map(|p| Color(p * 16, 0, 0)).collect(),
+            delay_time: 10,
+            placeholder: false,
+        };
+
+        let mut output = vec![];
+        let mut encoder = Encoder::new(&mut output, 4, 4);
+        encoder.set_loop_count(Some(0));
+        encoder.add_comment("hi");
+        encoder.write_frame(&frame).unwrap();
+        encoder.finish().unwrap();
+        output
+    }
+
+    fn kinds(blocks: &[Block]) -> Vec<BlockKind> {
+        blocks.iter().map(|b| b.kind.clone()).collect()
+    }
+
+    #[test]
+    fn test_inspect() {
+        let data = sample();
+        let blocks = inspect(&mut data.as_slice()).unwrap();
+
+        assert_eq!(
+            vec![
+                BlockKind::Header {
+                    version: "89a".into()
+                },
+                BlockKind::LogicalScreenDescriptor {
+                    width: 4,
+                    height: 4
+                },
+                BlockKind::GlobalColorTable { colors: 16 },
+                BlockKind::ApplicationExtension {
+                    identifier: "NETSCAPE2.0".into(),
+                    sub_blocks: 1
+                },
+                BlockKind::CommentExtension { sub_blocks: 1 },
+                BlockKind::GraphicControlExtension {
+                    disposal_method: 1,
+                    delay_time: 10,
+                    transparent_color_index: None
+                },
+                BlockKind::ImageDescriptor {
+                    left: 0,
+                    top: 0,
+                    width: 4,
+                    height: 4,
+                    interlaced: false
+                },
+                BlockKind::ImageData {
+                    lzw_min_code_size: 4,
+                    sub_blocks: 1
+                },
+                BlockKind::Trailer,
+            ],
+            kinds(&blocks)
+        );
+
+        // The blocks cover the whole file without gaps.
+        let mut offset = 0;
+        for block in blocks.iter() {
+            assert_eq!(offset, block.offset);
+            offset += block.size;
+        }
+        assert_eq!(data.len(), offset);
+        assert_eq!(48, blocks[2].size);
+    }
+
+    #[test]
+    fn test_inspect_invalid() {
+        let mut data = sample();
+        data.truncate(data.len() - 3);
+
+        // The image is cut off, so it is left out whole.
+        let blocks = inspect(&mut data.as_slice()).unwrap();
+        let image = &blocks[blocks.len() - 1];
+        assert_eq!(
+            BlockKind::Invalid {
+                reason: "file ends inside of this block".into()
+            },
+            image.kind
+        );
+        assert_eq!(data.iter().rposition(|b| *b == 0x2c), Some(image.offset));
+        assert_eq!(data.len() - image.offset, image.size);
+
+        let blocks = inspect(&mut &b"GIF89a"[..]).unwrap();
+        assert_eq!(
+            BlockKind::Invalid {
+                reason: "file ends inside of this block".into()
+            },
+            blocks[1].kind
+        );
+
+        let blocks = inspect(&mut &b"PNG89a"[..]).unwrap();
+        assert_eq!(
+            vec![BlockKind::Invalid {
+                reason: "file is not a GIF".into()
+            }],
+            kinds(&blocks)
+        );
+    }
+}
@@ -0,0 +1,31 @@
+// JSON output helpers. The command line tool declares this module as well, so that the
+// library and the tool quote strings the same way.
+
+// Quotes `s` as a JSON string.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut output = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(
+            r#""a \"b\" \\ c\n\u0001""#,
+            json_string("a \"b\" \\ c\n\u{1}")
+        );
+    }
+}
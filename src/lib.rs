@@ -20,18 +20,126 @@
 //! }
 //! ```
 
+#[macro_use]
+mod macros;
+
+mod appext;
+#[cfg(feature = "arbitrary")]
+mod arbitrary_support;
+#[cfg(feature = "async")]
+mod async_decoder;
+#[cfg(feature = "async")]
+mod async_encoder;
+mod batch;
+mod compare;
+mod compressor;
+#[cfg(feature = "corpus")]
+pub mod corpus;
+mod cursor;
 mod decompressor;
+#[cfg(feature = "egui")]
+mod egui_support;
+mod encoder;
+mod export;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod font;
+#[cfg(feature = "bytes")]
+mod frame_bytes;
+mod handler;
+#[cfg(feature = "image-compat")]
+mod image_compat;
+mod indexed;
+mod inspector;
+mod json;
+#[cfg(feature = "lowlevel")]
+pub mod lowlevel;
+mod mono;
+#[cfg(feature = "node")]
+pub mod node;
+mod observer;
+mod optimizer;
 mod parser;
+mod phash;
+mod plain_text;
+#[cfg(feature = "python")]
+pub mod python;
+mod quantizer;
+mod repair;
+mod rewriter;
+#[cfg(feature = "rkyv")]
+mod rkyv_support;
+mod rows;
+mod screen;
+mod sink;
+mod stream;
+#[cfg(test)]
+mod testing;
+mod ticker;
+mod timeline;
+mod transform;
 mod util;
+mod validator;
+mod video;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "webp")]
+mod webp;
+mod writer;
 
-use decompressor::Decompressor;
+use decompressor::{Decompressor, Indices};
+use observer::NoObserver;
 use parser::*;
-use std::io::Read;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use util::Stopwatch;
 
-pub use util::Color;
+pub use appext::{load_with_app_extensions, AppExtensionHandlers};
+#[cfg(feature = "async")]
+pub use async_decoder::{load_async, AsyncDecoder};
+#[cfg(feature = "async")]
+pub use async_encoder::AsyncEncoder;
+pub use batch::{decode_many, spawn_decode, DecodeMany};
+pub use compare::{compare, Comparison, FrameDifference};
+#[cfg(feature = "egui")]
+pub use egui_support::GifAnimation;
+pub use encoder::{Encoder, PaletteMode};
+pub use export::{export, ExportFormat};
+#[cfg(feature = "bytes")]
+pub use frame_bytes::BytesFrame;
+pub use handler::{decode_with_handler, DecodeHandler};
+#[cfg(feature = "image-compat")]
+pub use image_compat::GifDecoder;
+pub use indexed::{FrameIndex, IndexedDecoder};
+pub use inspector::{inspect, Block, BlockKind};
+pub use observer::DecodeObserver;
+pub use optimizer::{optimize, OptimizeOptions, OptimizeReport};
+pub use phash::PerceptualHash;
+pub use quantizer::{quantize, QuantizeOptions};
+pub use repair::{repair, RepairAction, RepairReport};
+pub use rewriter::Rewriter;
+#[cfg(feature = "rkyv")]
+pub use rkyv_support::access_archived;
+pub use rows::{decode_rows, RowBand, RowSink};
+pub use sink::{decode_into_sink, FrameMeta, FrameSink};
+pub use stream::{load_prefix, PartialGif, StreamDecoder};
+pub use ticker::Ticker;
+pub use timeline::{Timeline, TimelineEntry};
+pub use transform::{CaptionOptions, CaptionPosition, ResizeFilter};
+#[cfg(feature = "rkyv")]
+pub use util::ArchivedColor;
+pub use util::{Color, Rect};
+pub use validator::{validate, Finding, Severity};
+pub use video::{VideoFormat, VideoOptions};
 
 /// This struct holds the width, height and the image frames of the GIF media.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Gif {
     /// The width of the GIF media.
     pub width: u32,
@@ -39,16 +147,345 @@ pub struct Gif {
     pub height: u32,
     /// Individual image frames.
     pub image_frames: Vec<ImageFrame>,
+    /// The number of times the animation should loop, as given by the NETSCAPE2.0 extension.
+    /// `Some(0)` means loop forever and `None` means the extension is absent.
+    pub loop_count: Option<u16>,
+    /// The text of the comment extensions, in file order. They are written back when the GIF
+    /// is saved, so this can be used to embed provenance or credits.
+    pub comments: Vec<String>,
+    /// The aspect ratio of a pixel, width over height, as given by the logical screen
+    /// descriptor. `None` means square pixels, which nearly every GIF has. Use
+    /// [`correct_aspect_ratio`](Gif::correct_aspect_ratio) to resample the frames to square
+    /// pixels.
+    pub pixel_aspect_ratio: Option<f32>,
+    /// The color that stands for transparent pixels, as set by [`key_out`](Gif::key_out) or
+    /// [`load_color_keyed`]. Pixels of this color are written as transparent when the GIF is
+    /// saved. `None` means every pixel is opaque.
+    pub transparent_color: Option<Color>,
+}
+
+impl Gif {
+    /// Encode this GIF and save it to the file at `path`, replacing it if it already exists.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be written or if the GIF is
+    /// too large to be encoded.
+    pub fn save<P>(&self, path: P) -> Result<(), String>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::create(path).map_err(|e| format!("Error: {}", e))?;
+        let mut dst = BufWriter::new(file);
+        self.write_to(&mut dst)?;
+        dst.flush().map_err(|e| format!("Error: {}", e))
+    }
+
+    /// Encode this GIF and write it to a given `dst`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if writing to `dst` fails or if the GIF is too
+    /// large to be encoded.
+    pub fn write_to<W>(&self, dst: &mut W) -> Result<(), String>
+    where
+        W: Write,
+    {
+        let mut encoder = self.encoder(dst)?;
+        encoder.write_frames(&self.image_frames)?;
+        encoder.finish()
+    }
+
+    // An encoder to `dst` set up to write everything about this GIF but its frames.
+    pub(crate) fn encoder<W>(&self, dst: W) -> Result<Encoder<W>, String>
+    where
+        W: Write,
+    {
+        let width = u16::try_from(self.width)
+            .map_err(|_| format!("Error: width {} is too large", self.width))?;
+        let height = u16::try_from(self.height)
+            .map_err(|_| format!("Error: height {} is too large", self.height))?;
+
+        let mut encoder = Encoder::new(dst, width, height);
+        encoder.set_loop_count(self.loop_count);
+        encoder.set_pixel_aspect_ratio(self.pixel_aspect_ratio);
+        encoder.set_transparent_color(self.transparent_color);
+        for comment in self.comments.iter() {
+            encoder.add_comment(comment.as_str());
+        }
+        Ok(encoder)
+    }
+
+    /// Encode this GIF into a new buffer, such as to feed it to a decoder under test.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the GIF is too large to be encoded.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut output = vec![];
+        self.write_to(&mut output)?;
+        Ok(output)
+    }
+
+    /// Encode every frame as a standalone single-frame GIF, in frame order. Each GIF gets a
+    /// color table made for its own frame.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the GIF is too large to be encoded.
+    pub fn split(&self) -> Result<Vec<Vec<u8>>, String> {
+        self.image_frames
+            .iter()
+            .map(|frame| {
+                let gif = Gif {
+                    width: self.width,
+                    height: self.height,
+                    image_frames: vec![frame.clone()],
+                    loop_count: None,
+                    comments: self.comments.clone(),
+                    pixel_aspect_ratio: self.pixel_aspect_ratio,
+                    transparent_color: self.transparent_color,
+                };
+                gif.to_bytes()
+            })
+            .collect()
+    }
+
+    /// Convert every frame to an `image::RgbaImage` of the size of the GIF, in frame order.
+    /// GIF frames are opaque, so every alpha value is 255.
+    ///
+    /// This is only available with the `image` feature.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a frame does not have `width * height` pixels.
+    #[cfg(feature = "image")]
+    pub fn to_rgba_images(&self) -> Result<Vec<image::RgbaImage>, String> {
+        self.image_frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                image::RgbaImage::from_raw(self.width, self.height, frame.to_rgba_bytes())
+                    .ok_or_else(|| {
+                        format!(
+                            "Error: frame {} has {} pixels, expected {}",
+                            i,
+                            frame.colors.len(),
+                            self.width as usize * self.height as usize
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Copy the RGB values of every frame into a single buffer of shape (frames, height,
+    /// width, 3), row by row, which is returned with its shape. This is the layout most tensor
+    /// libraries take, so the buffer can be wrapped without copying it again.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// let mut src = File::open("<gif path>").expect("File not found");
+    /// let gif = giffy::load(&mut src).expect("Error loading GIF");
+    /// let (data, shape) = gif.to_tensor().expect("Error converting GIF");
+    /// assert_eq!(shape.iter().product::<usize>(), data.len());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a frame does not have `width * height` pixels.
+    pub fn to_tensor(&self) -> Result<(Vec<u8>, [usize; 4]), String> {
+        let pixel_count = self.width as usize * self.height as usize;
+        let mut data = Vec::with_capacity(self.image_frames.len() * pixel_count * 3);
+        for (i, frame) in self.image_frames.iter().enumerate() {
+            if frame.colors.len() != pixel_count {
+                return Err(format!(
+                    "Error: frame {} has {} pixels, expected {}",
+                    i,
+                    frame.colors.len(),
+                    pixel_count
+                ));
+            }
+            data.extend_from_slice(frame.as_bytes());
+        }
+
+        let shape = [
+            self.image_frames.len(),
+            self.height as usize,
+            self.width as usize,
+            3,
+        ];
+        Ok((data, shape))
+    }
+
+    /// Convert the whole animation to an array of shape (frames, height, width, 3) holding the
+    /// RGB values of every frame, along with the delay of every frame in hundredths of a second.
+    ///
+    /// This is only available with the `ndarray` feature.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a frame does not have `width * height` pixels.
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray(&self) -> Result<(ndarray::Array4<u8>, Vec<u16>), String> {
+        let (data, shape) = self.to_tensor()?;
+        let array =
+            ndarray::Array4::from_shape_vec(shape, data).map_err(|e| format!("Error: {}", e))?;
+        let delays = self.image_frames.iter().map(|f| f.delay_time).collect();
+        Ok((array, delays))
+    }
 }
 
 /// This struct is used to hold the color information and the delay time of a frame.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct ImageFrame {
-    /// The colors that make up the image frame. This is used for drawing the image frame.
+    /// The width of the image frame in pixels. Decoded frames cover the whole canvas, so this
+    /// is the width of the GIF.
+    pub width: u32,
+    /// The height of the image frame in pixels.
+    pub height: u32,
+    /// The colors that make up the image frame, `width * height` of them row by row. This is
+    /// used for drawing the image frame.
     pub colors: Box<[Color]>,
     /// The amount of time this image frame should stay on screen before moving
     /// on to the next image frame.
     pub delay_time: u16,
+    /// Whether this frame could not be decoded and is a copy of the previous frame instead.
+    /// This is only ever set by [`load_with_options`] with
+    /// [`DecodeOptions::placeholder_frames`] enabled.
+    pub placeholder: bool,
+}
+
+impl ImageFrame {
+    /// The width and height of this frame in pixels.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The number of pixels in this frame.
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// Whether this frame has no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Whether the pixel at column `x` and row `y` is inside this frame.
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// The colors of this frame as `colors.len() * 3` bytes of RGB, row by row, borrowed
+    /// without copying.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// let mut src = File::open("<gif path>").expect("File not found");
+    /// let gif = giffy::load(&mut src).expect("Error loading GIF");
+    /// let rgb: &[u8] = gif.image_frames[0].as_bytes();
+    /// ```
+    pub fn as_bytes(&self) -> &[u8] {
+        // SAFETY: `Color` is `repr(C)` with three `u8` fields, so it has a size of 3 and an
+        // alignment of 1, and a `[Color]` has the same layout as a `[u8]` three times as long.
+        unsafe {
+            std::slice::from_raw_parts(self.colors.as_ptr() as *const u8, self.colors.len() * 3)
+        }
+    }
+
+    /// Copy the colors of this frame into `colors.len() * 3` bytes of RGB, row by row.
+    pub fn to_rgb_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    /// Copy the colors of this frame into `colors.len() * 4` bytes of RGBA, row by row, with
+    /// every pixel opaque.
+    pub fn to_rgba_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.colors.len() * 4);
+        for c in self.colors.iter() {
+            bytes.extend_from_slice(&[c.r(), c.g(), c.b(), 255]);
+        }
+        bytes
+    }
+}
+
+/// Controls how [`load_with_options`] decodes GIFs, such as how it deals with broken ones.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Return what can be decoded instead of failing. A truncated GIF yields the frames read
+    /// before the end of the data, frames with missing pixels or colors outside of their
+    /// color table are recovered by filling in the gaps, and frames that cannot be decoded at
+    /// all are left out. Disabled by default.
+    pub lenient: bool,
+    /// In lenient mode, replace every frame that cannot be decoded with a copy of the
+    /// previous frame, flagged as [`ImageFrame::placeholder`], instead of leaving it out. This
+    /// keeps the timing of the animation intact. Disabled by default.
+    pub placeholder_frames: bool,
+    /// Draw the text of every plain text extension as a frame of its own, over the previous
+    /// frame, like GIF89a intends. Hardly any GIF has them and most decoders skip them, as is
+    /// done when this is disabled, the default. Only [`load_with_options`] and the functions
+    /// built on it render text.
+    pub render_plain_text: bool,
+    /// How many bytes to skip at most while looking for the `GIF8` signature, for GIFs with
+    /// junk in front of them, such as a byte order mark, the headers of an HTTP response or
+    /// the padding of a container they were extracted from. 0, the default, expects the
+    /// signature right at the start.
+    pub max_leading_bytes: usize,
+    /// Report the canvas after each of the four passes of an interlaced frame to
+    /// [`DecodeObserver::interlace_pass`] and [`DecodeHandler::on_interlace_pass`], with the
+    /// rows read so far stretched over the rows still to come, for viewers that show frames
+    /// from coarse to fine as they decode. Disabled by default, as it copies the canvas four
+    /// times per interlaced frame.
+    pub interlace_passes: bool,
+    /// Fail on every deviation from the GIF specification that is otherwise read past, with
+    /// what and where: LZW data without an end of information code, with padding after it or
+    /// with more pixels than its image, images that do not fit in the logical screen, unknown
+    /// versions, reserved disposal methods, invalid LZW code sizes and transparent color
+    /// indices outside of the color table. Meant for validators and the self-tests of encoders.
+    /// Takes precedence over [`lenient`](Self::lenient). Disabled by default.
+    pub strict: bool,
+    /// Return a GIF that is a single picture split into several images, as GIF87a files
+    /// sometimes are, as one frame with every image drawn. A GIF is taken as such when it has
+    /// more than one image, none of them with a delay, and no NETSCAPE2.0 extension to loop
+    /// them. Disabled by default, which returns a frame per image.
+    pub composite_static: bool,
+    /// Merge every run of frames without a delay into the frame with a delay that ends it,
+    /// which has the whole run drawn, as browsers show them. GIFs that draw every frame in
+    /// slices end up with far fewer frames. Frames without a delay that no frame with a delay
+    /// follows are kept, so an animation with no delays at all still plays. Disabled by
+    /// default.
+    pub coalesce_zero_delay: bool,
+}
+
+impl DecodeOptions {
+    // Whether problems are worked around, which strict mode rules out.
+    pub(crate) fn is_lenient(&self) -> bool {
+        self.lenient && !self.strict
+    }
+}
+
+/// How well a frame decoded, as returned by [`load_with_status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameStatus {
+    /// The frame decoded without problems.
+    Ok,
+    /// The frame was damaged and the gaps were filled in with the transparent color, or the
+    /// first color if the frame has no transparency. It is mostly right, but some pixels are
+    /// made up.
+    Recovered,
+    /// The frame could not be decoded, for the given reason. It is either left out or replaced
+    /// by a placeholder, depending on [`DecodeOptions::placeholder_frames`].
+    Failed(String),
 }
 
 /// Attempt to load a GIF from a given `src`.
@@ -60,86 +497,892 @@ pub fn load<R>(src: &mut R) -> Result<Gif, String>
 where
     R: Read,
 {
-    let mut parser = Parser::new(src);
-    let result = parser.parse()?;
+    let result = Parser::new().parse(src)?;
+    decode(&result)
+}
 
-    let decoder = Decoder::new(&result);
-    let frames = decoder.decode()?;
+/// Attempt to load a GIF from the file at `path`.
+///
+/// # Example
+///
+/// ```no_run
+/// let gif = giffy::load_from_path("<gif path>").expect("Error loading GIF");
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if the file cannot be read or if it is not in a valid
+/// GIF format.
+pub fn load_from_path<P>(path: P) -> Result<Gif, String>
+where
+    P: AsRef<Path>,
+{
+    let file = File::open(path).map_err(|e| format!("Error: {}", e))?;
+    load(&mut BufReader::new(file))
+}
+
+/// Attempt to load a GIF from `data`, such as a file read in full or embedded with
+/// `include_bytes!`.
+///
+/// # Errors
+///
+/// This function will return an error if `data` is not in a valid GIF format.
+pub fn load_from_bytes(data: &[u8]) -> Result<Gif, String> {
+    load(&mut &data[..])
+}
 
-    Ok(Gif {
+/// Attempt to load a GIF from a given `src`, dealing with broken data as set by `options`.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::DecodeOptions;
+/// use std::fs::File;
+///
+/// let options = DecodeOptions {
+///     lenient: true,
+///     placeholder_frames: true,
+///     ..DecodeOptions::default()
+/// };
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let gif = giffy::load_with_options(&mut src, &options).expect("Error loading GIF");
+/// let broken = gif.image_frames.iter().filter(|f| f.placeholder).count();
+/// println!("{} of {} frames are broken", broken, gif.image_frames.len());
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if the GIF src is not in a valid GIF format. In
+/// lenient mode, only a broken header or logical screen descriptor is an error.
+pub fn load_with_options<R>(src: &mut R, options: &DecodeOptions) -> Result<Gif, String>
+where
+    R: Read,
+{
+    load_with_status(src, options).map(|(gif, _)| gif)
+}
+
+/// Attempt to load a GIF from a given `src` like [`load_with_options`], and also return the
+/// status of every image in the file, in file order. Rendered plain text extensions count as
+/// images that decoded without problems.
+///
+/// Without [`DecodeOptions::placeholder_frames`], frames that failed are left out of
+/// [`Gif::image_frames`], so the statuses only line up with the frames when placeholders are
+/// enabled or nothing failed, and the images are not drawn into one frame by
+/// [`DecodeOptions::composite_static`] or [`DecodeOptions::coalesce_zero_delay`].
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::{DecodeOptions, FrameStatus};
+/// use std::fs::File;
+///
+/// let options = DecodeOptions {
+///     lenient: true,
+///     placeholder_frames: true,
+///     ..DecodeOptions::default()
+/// };
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let (gif, status) = giffy::load_with_status(&mut src, &options).expect("Error loading GIF");
+/// if status.iter().any(|s| *s != FrameStatus::Ok) {
+///     println!("This GIF is partially damaged");
+/// }
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error in the same cases as [`load_with_options`].
+pub fn load_with_status<R>(
+    src: &mut R,
+    options: &DecodeOptions,
+) -> Result<(Gif, Vec<FrameStatus>), String>
+where
+    R: Read,
+{
+    load_observed(src, options, &mut NoObserver, None)
+}
+
+/// Attempt to load a GIF from a given `src` like [`load_with_options`], reporting progress to
+/// `observer` along the way.
+///
+/// # Errors
+///
+/// This function will return an error in the same cases as [`load_with_options`]. The error
+/// is passed to [`DecodeObserver::error`] first.
+pub fn load_with_observer<R, O>(
+    src: &mut R,
+    options: &DecodeOptions,
+    observer: &mut O,
+) -> Result<Gif, String>
+where
+    R: Read,
+    O: DecodeObserver,
+{
+    load_observed(src, options, observer, None).map(|(gif, _)| gif)
+}
+
+/// Attempt to load a GIF from a given `src` like [`load_with_options`], drawing it over
+/// `backdrop` instead of its background color, such as to show it over the themed background
+/// of a chat. Transparent pixels show the backdrop, and frames that restore the background
+/// restore the backdrop. Every frame comes out opaque.
+///
+/// `backdrop` is `width * height` pixels of RGBA, row by row, and must be the size of the
+/// GIF. Where it is not opaque, it is blended over black.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::DecodeOptions;
+/// use std::fs::File;
+///
+/// let (width, height) = (320, 240);
+/// let theme = [30, 30, 46, 255].repeat(width * height);
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let gif = giffy::load_onto(&mut src, &DecodeOptions::default(), &theme, 320, 240)
+///     .expect("Error loading GIF");
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error in the same cases as [`load_with_options`], or if
+/// `backdrop` does not hold `width * height` pixels or is not the size of the GIF.
+pub fn load_onto<R>(
+    src: &mut R,
+    options: &DecodeOptions,
+    backdrop: &[u8],
+    width: u32,
+    height: u32,
+) -> Result<Gif, String>
+where
+    R: Read,
+{
+    if backdrop.len() != width as usize * height as usize * 4 {
+        return Err(format!(
+            "Error: backdrop has {} bytes, expected {}",
+            backdrop.len(),
+            width as usize * height as usize * 4
+        ));
+    }
+    let blend = |c: u8, alpha: u8| ((c as u32 * alpha as u32 + 127) / 255) as u8;
+    let backdrop = ImageFrame {
+        width,
+        height,
+        colors: backdrop
+            .chunks_exact(4)
+            .map(|p| Color(blend(p[0], p[3]), blend(p[1], p[3]), blend(p[2], p[3])))
+            .collect(),
+        delay_time: 0,
+        placeholder: false,
+    };
+    load_observed(src, options, &mut NoObserver, Some(&backdrop)).map(|(gif, _)| gif)
+}
+
+/// Attempt to load a GIF from a given `src` like [`load_with_options`], filling transparent
+/// pixels with the color `key` instead of the background color, for sprite pipelines and
+/// overlays that use color keying instead of alpha. Frames that restore the background
+/// restore the key.
+///
+/// If `key` is in a color table of the GIF, a nearby color that is in none of them is
+/// used instead, so that no opaque pixel is mistaken for a transparent one. The key that was
+/// used is returned with the GIF, and set as its [`Gif::transparent_color`] so that the
+/// transparent pixels stay transparent when it is saved.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::{Color, DecodeOptions};
+/// use std::fs::File;
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let magenta = Color::from([255, 0, 255]);
+/// let (gif, key) = giffy::load_color_keyed(&mut src, &DecodeOptions::default(), magenta)
+///     .expect("Error loading GIF");
+/// println!("transparent pixels are {:?}", key);
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error in the same cases as [`load_with_options`].
+pub fn load_color_keyed<R>(
+    src: &mut R,
+    options: &DecodeOptions,
+    key: Color,
+) -> Result<(Gif, Color), String>
+where
+    R: Read,
+{
+    let result = parse_observed(src, options, &mut NoObserver)?;
+    let key = free_color(&result, key);
+    let lsd = &result.logical_screen_descriptor;
+    let backdrop = ImageFrame {
+        width: lsd.width as u32,
+        height: lsd.height as u32,
+        colors: vec![key; lsd.width as usize * lsd.height as usize].into_boxed_slice(),
+        delay_time: 0,
+        placeholder: false,
+    };
+    let (mut gif, _) = decode_with_options(&result, *options, &mut NoObserver, Some(&backdrop))?;
+    gif.transparent_color = Some(key);
+    Ok((gif, key))
+}
+
+// A color near `key` that is in no color table of `result`, found by flipping the low bits of
+// blue first, then of green and red.
+fn free_color(result: &ParseResult, key: Color) -> Color {
+    let mut used: HashSet<Color> = HashSet::new();
+    used.extend(
+        result
+            .logical_screen_descriptor
+            .global_color_table
+            .iter()
+            .flatten(),
+    );
+    for block in &result.data_blocks {
+        if let DataType::TableBasedImageType(image) = block {
+            used.extend(image.local_color_table.iter().flatten());
+        }
+    }
+
+    let Color(r, g, b) = key;
+    (0..1u32 << 24)
+        .map(|i| Color(r ^ (i >> 16) as u8, g ^ (i >> 8) as u8, b ^ i as u8))
+        .find(|color| !used.contains(color))
+        .unwrap_or(key)
+}
+
+fn load_observed<R>(
+    src: &mut R,
+    options: &DecodeOptions,
+    observer: &mut dyn DecodeObserver,
+    backdrop: Option<&ImageFrame>,
+) -> Result<(Gif, Vec<FrameStatus>), String>
+where
+    R: Read,
+{
+    let result = parse_observed(src, options, observer)
+        .and_then(|result| decode_with_options(&result, *options, observer, backdrop));
+    if let Err(ref e) = result {
+        observer.error(e);
+    }
+    result
+}
+
+// Parses `src` as set by `options`. In lenient mode, data that stops early is warned about
+// and what was read before it is returned.
+fn parse_observed<R>(
+    src: &mut R,
+    options: &DecodeOptions,
+    observer: &mut dyn DecodeObserver,
+) -> Result<ParseResult, String>
+where
+    R: Read,
+{
+    let prefix = skip_leading_bytes(src, options)?;
+    let src = &mut prefix.as_slice().chain(src);
+
+    let (result, error) = {
+        let mut parser = Parser::with_observer(observer);
+        parser.set_strict(options.strict);
+        if options.is_lenient() {
+            parser.parse_partial(src)?
+        } else {
+            (parser.parse(src)?, None)
+        }
+    };
+    if let Some(e) = error {
+        // The parser already logs why it stopped.
+        observer.warning(&format!("decoding the data read before the error: {}", e));
+    }
+    Ok(result)
+}
+
+// Reads `src` up to and including the GIF signature, skipping at most
+// `options.max_leading_bytes` bytes before it, and returns the signature so it can be read
+// again in front of the rest of `src`. Nothing is read if no bytes may be skipped.
+fn skip_leading_bytes<R>(src: &mut R, options: &DecodeOptions) -> Result<Vec<u8>, String>
+where
+    R: Read,
+{
+    const SIGNATURE: &[u8] = b"GIF8";
+    if options.max_leading_bytes == 0 {
+        return Ok(vec![]);
+    }
+
+    let mut window = Vec::with_capacity(SIGNATURE.len());
+    let mut skipped = 0;
+    let mut byte = [0];
+    while window != SIGNATURE {
+        if window.len() == SIGNATURE.len() {
+            if skipped == options.max_leading_bytes {
+                return Err(format!(
+                    "Error: no GIF signature in the first {} bytes",
+                    skipped + SIGNATURE.len()
+                ));
+            }
+            window.remove(0);
+            skipped += 1;
+        }
+        src.read_exact(&mut byte)
+            .map_err(|_| "Error: no GIF signature before the end of the data".to_string())?;
+        window.push(byte[0]);
+    }
+    if skipped > 0 {
+        warn!("skipped {} bytes before the GIF signature", skipped);
+    }
+    Ok(window)
+}
+
+fn decode(result: &ParseResult) -> Result<Gif, String> {
+    decode_with_options(result, DecodeOptions::default(), &mut NoObserver, None).map(|(gif, _)| gif)
+}
+
+fn decode_with_options(
+    result: &ParseResult,
+    options: DecodeOptions,
+    observer: &mut dyn DecodeObserver,
+    backdrop: Option<&ImageFrame>,
+) -> Result<(Gif, Vec<FrameStatus>), String> {
+    let mut decoder = Decoder::new(result, options);
+    if let Some(backdrop) = backdrop {
+        let lsd = &result.logical_screen_descriptor;
+        if backdrop.dimensions() != (lsd.width as u32, lsd.height as u32) {
+            return Err(format!(
+                "Error: backdrop is {}x{}, expected {}x{} like the GIF",
+                backdrop.width, backdrop.height, lsd.width, lsd.height
+            ));
+        }
+        decoder.backdrop = Some(&backdrop.colors);
+    }
+    let (mut frames, status) = decoder.decode(observer)?;
+    if options.composite_static && frames.len() > 1 && is_static_composite(result) {
+        // Every frame is drawn over the one before, so the last one has every image.
+        frames.drain(..frames.len() - 1);
+    }
+    if options.coalesce_zero_delay {
+        // A frame with a delay is drawn over the run before it, so only it is kept. Going
+        // backwards, a frame without a delay is part of such a run if one came after it.
+        let mut run_ends = false;
+        let mut keep = frames
+            .iter()
+            .rev()
+            .map(|frame| {
+                let merged = frame.delay_time == 0 && run_ends;
+                run_ends = frame.delay_time != 0 || merged;
+                !merged
+            })
+            .collect::<Vec<_>>();
+        frames.retain(|_| keep.pop().unwrap());
+    }
+
+    let gif = Gif {
         image_frames: frames,
         width: result.logical_screen_descriptor.width as u32,
         height: result.logical_screen_descriptor.height as u32,
+        loop_count: loop_count(result),
+        comments: comments(result),
+        pixel_aspect_ratio: pixel_aspect_ratio(result),
+        transparent_color: None,
+    };
+    Ok((gif, status))
+}
+
+// Strict mode rejects a transparent color index past the end of the color table, which other
+// decoders may draw as a color or not at all.
+fn check_transparent_index(index: usize, color_table: &[Color]) -> Result<(), String> {
+    if index >= color_table.len() {
+        return Err(format!(
+            "Error: transparent color index {} is outside of the color table of {} colors",
+            index,
+            color_table.len()
+        ));
+    }
+    Ok(())
+}
+
+// Whether the images of `result` make up a single picture instead of an animation: there is
+// more than one, none of them has a delay and nothing loops them.
+fn is_static_composite(result: &ParseResult) -> bool {
+    let mut images = 0;
+    for block in result.data_blocks.iter() {
+        if let DataType::TableBasedImageType(image) = block {
+            if Decoder::graphic_control(image).3 != 0 {
+                return false;
+            }
+            images += 1;
+        }
+    }
+    images > 1 && loop_count(result).is_none()
+}
+
+fn pixel_aspect_ratio(result: &ParseResult) -> Option<f32> {
+    let ratio = result.logical_screen_descriptor.pixel_aspect_ratio;
+    (ratio != 0f32).then_some(ratio)
+}
+
+fn loop_count(result: &ParseResult) -> Option<u16> {
+    result.data_blocks.iter().find_map(|block| match block {
+        DataType::ApplicationExtensionType(ext) => ext.loop_count(),
+        _ => None,
     })
 }
 
+fn comments(result: &ParseResult) -> Vec<String> {
+    result
+        .data_blocks
+        .iter()
+        .filter_map(|block| match block {
+            DataType::CommentExtensionType(ext) => Some(ext.text.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
 struct Decoder<'a> {
     data: &'a ParseResult,
+    options: DecodeOptions,
+    // What the canvas starts as and is restored to, instead of the background color.
+    backdrop: Option<&'a [Color]>,
 }
 
-impl<'a> Decoder<'a> {
-    fn new(input: &'a ParseResult) -> Self {
-        Self { data: input }
+impl<'a> Decoder<'a> {
+    fn new(input: &'a ParseResult, options: DecodeOptions) -> Self {
+        Self {
+            data: input,
+            options,
+            backdrop: None,
+        }
+    }
+
+    fn decode(
+        &self,
+        observer: &mut dyn DecodeObserver,
+    ) -> Result<(Vec<ImageFrame>, Vec<FrameStatus>), String> {
+        let mut frames = vec![];
+        let mut status = vec![];
+
+        let mut index = 0;
+        for block in self.data.data_blocks.iter() {
+            let (frame, frame_status) = match block {
+                DataType::TableBasedImageType(image) => {
+                    self.decode_next(&frames, index, image, observer)?
+                }
+                DataType::PlainTextExtensionType(ext) if self.options.render_plain_text => {
+                    (Some(self.create_text_frame(&frames, ext)), FrameStatus::Ok)
+                }
+                _ => continue,
+            };
+            frames.extend(frame);
+            status.push(frame_status);
+            index += 1;
+        }
+
+        Ok((frames, status))
+    }
+
+    // Decodes frame `index` after `frames`, dealing with a broken image as set by the options.
+    // The frame is `None` if it is left out.
+    fn decode_next(
+        &self,
+        frames: &[ImageFrame],
+        index: usize,
+        image: &TableBasedImage,
+        observer: &mut dyn DecodeObserver,
+    ) -> Result<(Option<ImageFrame>, FrameStatus), String> {
+        let stopwatch = Stopwatch::start();
+        let passes: Option<(usize, &mut dyn DecodeObserver)> = if self.options.interlace_passes {
+            Some((index, &mut *observer))
+        } else {
+            None
+        };
+        match self.decode_frame_with(frames, image, passes) {
+            Ok((frame, recovered)) => {
+                observer.frame_decoded(index, &frame, stopwatch.elapsed());
+                if recovered {
+                    observer.warning(&format!("frame {} is damaged and was recovered", index));
+                    Ok((Some(frame), FrameStatus::Recovered))
+                } else {
+                    Ok((Some(frame), FrameStatus::Ok))
+                }
+            }
+            Err(e) if self.options.is_lenient() => {
+                warn!("frame {} cannot be decoded: {}", index, e);
+                observer.warning(&format!("frame {} cannot be decoded: {}", index, e));
+                let frame = if self.options.placeholder_frames {
+                    Some(self.create_placeholder_frame(frames, image))
+                } else {
+                    None
+                };
+                Ok((frame, FrameStatus::Failed(e)))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // Returns the frame, and whether gaps in its data had to be filled in.
+    fn decode_frame(
+        &self,
+        frames: &[ImageFrame],
+        image: &TableBasedImage,
+    ) -> Result<(ImageFrame, bool), String> {
+        self.decode_frame_with(frames, image, None)
+    }
+
+    // Like `decode_frame`, reporting the passes of an interlaced image as frame `index` to the
+    // observer, if there is one.
+    fn decode_frame_with(
+        &self,
+        frames: &[ImageFrame],
+        image: &TableBasedImage,
+        mut passes: Option<(usize, &mut dyn DecodeObserver)>,
+    ) -> Result<(ImageFrame, bool), String> {
+        let desc = &image.image_descriptor;
+        debug!(
+            "decoding frame {}: {}x{} at ({}, {}), delay {}",
+            frames.len(),
+            desc.width,
+            desc.height,
+            desc.left,
+            desc.top,
+            image
+                .graphic_control_extension
+                .as_ref()
+                .map_or(0, |ext| ext.delay_time)
+        );
+
+        if let Some(previous) = frames.last() {
+            let mut frame = previous.clone();
+            let recovered = self.draw_frame_with(&mut frame, image, passes.take())?;
+            return Ok((frame, recovered));
+        }
+
+        let lsd = &self.data.logical_screen_descriptor;
+        let covers_canvas =
+            (desc.left, desc.top, desc.width, desc.height) == (0, 0, lsd.width, lsd.height);
+        if covers_canvas && self.backdrop.is_none() && passes.is_none() {
+            let (index_table, color_table, recovered) = self.decompress(image, None)?;
+            let frame = self.create_first_frame(
+                &index_table,
+                color_table,
+                desc.interlace_flag,
+                Self::graphic_control(image).3,
+            )?;
+            return Ok((frame, recovered));
+        }
+
+        // The first frame is drawn over the background when it does not cover the whole
+        // canvas, or over the backdrop.
+        let color_table = image
+            .local_color_table
+            .as_deref()
+            .or(lsd.global_color_table.as_deref())
+            .unwrap_or(&[]);
+        let mut frame = ImageFrame {
+            width: lsd.width as u32,
+            height: lsd.height as u32,
+            colors: self.blank_canvas(color_table),
+            delay_time: 0,
+            placeholder: false,
+        };
+        let recovered = self.draw_frame_with(&mut frame, image, passes)?;
+        Ok((frame, recovered))
+    }
+
+    // The transparency flag, transparent color index, disposal method and delay time of
+    // `image`.
+    fn graphic_control(image: &TableBasedImage) -> (bool, u8, DisposalMethod, u16) {
+        match image.graphic_control_extension {
+            Some(ref ext) => (
+                ext.transparent_color_index_available,
+                ext.transparent_color_index,
+                ext.disposal_method,
+                ext.delay_time,
+            ),
+            None => (false, 0, DisposalMethod::Unspecified, 0),
+        }
+    }
+
+    // The color table of `image`, its own or the global one.
+    fn color_table<'i>(&'i self, image: &'i TableBasedImage) -> Result<&'i [Color], String> {
+        match image.local_color_table {
+            Some(ref table) => Ok(table),
+            None => Ok(self
+                .data
+                .logical_screen_descriptor
+                .global_color_table
+                .as_ref()
+                .ok_or("Global color table is missing!")?),
+        }
+    }
+
+    // Returns the color indices of `image`, its color table, and whether gaps in its data had
+    // to be filled in. `on_pass` is called with the indices stored so far as each of the four
+    // passes of an interlaced image is read, and for the passes left once gaps are filled in.
+    fn decompress<'i>(
+        &'i self,
+        image: &'i TableBasedImage,
+        on_pass: Option<OnPass>,
+    ) -> Result<(Vec<usize>, &'i [Color], bool), String> {
+        let color_table = self.color_table(image)?;
+        let (transparent_flag, transparent_color_index, _, _) = Self::graphic_control(image);
+        if self.options.strict && transparent_flag {
+            check_transparent_index(transparent_color_index as usize, color_table)?;
+        }
+
+        let lsd = &self.data.logical_screen_descriptor;
+        let desc = &image.image_descriptor;
+        if desc.left as usize + desc.width as usize > lsd.width as usize
+            || desc.top as usize + desc.height as usize > lsd.height as usize
+        {
+            return Err(format!(
+                "Error: image at ({}, {}) of size {}x{} does not fit in {}x{}",
+                desc.left, desc.top, desc.width, desc.height, lsd.width, lsd.height
+            ));
+        }
+
+        let pixel_count = desc.width as usize * desc.height as usize;
+        let mut decompressor = Decompressor::new(
+            &image.image_data.data_sub_blocks,
+            image.image_data.lzw_min_code_size,
+        );
+        decompressor.set_max_len(pixel_count);
+        decompressor.set_strict(self.options.strict);
+
+        let height = desc.height as usize;
+        let mut ends = [0; 4];
+        let mut rows = 0;
+        for (end, (start, step)) in ends.iter_mut().zip([(0, 8), (4, 8), (2, 4), (1, 2)]) {
+            rows += height.saturating_sub(start).div_ceil(step);
+            *end = rows * desc.width as usize;
+        }
+        let mut sink = PassIndices {
+            indices: vec![],
+            ends,
+            passes: 0,
+            on_pass,
+        };
+
+        let mut recovered = false;
+        if let Err(e) = decompressor.decompress_into(&mut sink) {
+            if !self.options.is_lenient() {
+                return Err(e);
+            }
+            warn!(
+                "invalid LZW data after {} indices: {}",
+                sink.indices.len(),
+                e
+            );
+            if sink.indices.is_empty() {
+                return Err(e);
+            }
+            recovered = true;
+        } else if sink.indices.is_empty() && self.options.is_lenient() {
+            return Err("Error: image data is missing".into());
+        }
+        let PassIndices {
+            indices: mut index_table,
+            passes,
+            on_pass,
+            ..
+        } = sink;
+
+        let fill = if transparent_flag {
+            transparent_color_index as usize
+        } else {
+            0
+        };
+        if index_table.len() < pixel_count {
+            if !self.options.is_lenient() {
+                return Err(format!(
+                    "Error: image data has {} pixels, expected {}",
+                    index_table.len(),
+                    pixel_count
+                ));
+            }
+            index_table.resize(pixel_count, fill);
+            recovered = true;
+        }
+
+        let transparent = |i: usize| transparent_flag && i == transparent_color_index as usize;
+        if index_table
+            .iter()
+            .any(|i| *i >= color_table.len() && !transparent(*i))
+        {
+            if !self.options.is_lenient() {
+                return Err("Error: color index is outside of the color table".into());
+            }
+            for i in index_table.iter_mut() {
+                if *i >= color_table.len() && !transparent(*i) {
+                    *i = fill;
+                }
+            }
+            recovered = true;
+        }
+
+        if let Some(on_pass) = on_pass {
+            for pass in passes..4 {
+                on_pass(pass, &index_table);
+            }
+        }
+
+        Ok((index_table, color_table, recovered))
+    }
+
+    // Draws `image` onto `frame`, the canvas as the previous frame left it, and returns whether
+    // gaps in its data had to be filled in. `frame` is left alone if this fails.
+    fn draw_frame(&self, frame: &mut ImageFrame, image: &TableBasedImage) -> Result<bool, String> {
+        self.draw_frame_with(frame, image, None)
+    }
+
+    // Like `draw_frame`, reporting the passes of an interlaced image as frame `index` to the
+    // observer, if there is one.
+    fn draw_frame_with(
+        &self,
+        frame: &mut ImageFrame,
+        image: &TableBasedImage,
+        passes: Option<(usize, &mut dyn DecodeObserver)>,
+    ) -> Result<bool, String> {
+        let (transparent_flag, transparent_color_index, disposal_method, delay_time) =
+            Self::graphic_control(image);
+
+        let top = image.image_descriptor.top as usize;
+        let height = image.image_descriptor.height as usize;
+        let left = image.image_descriptor.left as usize;
+        let width = image.image_descriptor.width as usize;
+        let image_width = self.data.logical_screen_descriptor.width as usize;
+        let transparent = |i: usize| transparent_flag && i == transparent_color_index as usize;
+
+        // The canvas each pass is drawn over, as the disposal method leaves it.
+        let mut observed = match passes {
+            Some((index, observer)) if image.image_descriptor.interlace_flag => {
+                let mut canvas = frame.clone();
+                self.dispose(&mut canvas, disposal_method, self.color_table(image)?)?;
+                Some((index, observer, canvas, self.color_table(image)?))
+            }
+            _ => None,
+        };
+        let mut on_pass = observed
+            .as_mut()
+            .map(|(index, observer, canvas, color_table)| {
+                move |pass: usize, indices: &[usize]| {
+                    // After each pass, every row shows the last row read at or above it.
+                    let step = [8, 4, 2, 1][pass];
+                    let mut shown = canvas.clone();
+                    for y in 0..height {
+                        let offset = (top + y) * image_width + left;
+                        let row = stored_row(y - y % step, height) * width;
+                        let row = indices.get(row..(row + width).min(indices.len()));
+                        for (x, i) in row.unwrap_or(&[]).iter().enumerate() {
+                            match color_table.get(*i) {
+                                Some(c) if !transparent(*i) => shown.colors[offset + x] = *c,
+                                _ => (),
+                            }
+                        }
+                    }
+                    observer.interlace_pass(*index, pass, &shown);
+                }
+            });
+        let (index_table, color_table, recovered) =
+            self.decompress(image, on_pass.as_mut().map(|f| f as OnPass))?;
+
+        let result = index_table
+            .iter()
+            .map(|i| (!transparent(*i)).then(|| color_table[*i]))
+            .collect::<Vec<_>>();
+
+        self.dispose(frame, disposal_method, color_table)?;
+        frame.delay_time = delay_time;
+        frame.placeholder = false;
+
+        let result = if image.image_descriptor.interlace_flag {
+            Self::deinterlace(result, width, height)
+        } else {
+            result
+        };
+
+        for y in 0..height {
+            let offset = (top + y) * image_width + left;
+            for x in 0..width {
+                let c = result[y * width + x];
+                if let Some(c) = c {
+                    frame.colors[offset + x] = c;
+                }
+            }
+        }
+
+        Ok(recovered)
+    }
+
+    // Clears `frame` as `disposal_method` asks before the next image is drawn over it.
+    fn dispose(
+        &self,
+        frame: &mut ImageFrame,
+        disposal_method: DisposalMethod,
+        color_table: &[Color],
+    ) -> Result<(), String> {
+        match disposal_method {
+            DisposalMethod::RestoreToBackgroundColor => match self.backdrop {
+                Some(backdrop) => frame.colors.copy_from_slice(backdrop),
+                None => frame.colors.fill(self.background_color(color_table)),
+            },
+            DisposalMethod::DoNotDispose | DisposalMethod::Unspecified => (),
+            d => return Err(format!("Dispose method {:?} not supported", d)),
+        }
+        Ok(())
+    }
+
+    // Draws the text of `ext` over the previous frame, or over the background if it comes
+    // first.
+    fn create_text_frame(&self, frames: &[ImageFrame], ext: &PlainTextExtension) -> ImageFrame {
+        let lsd = &self.data.logical_screen_descriptor;
+        let color_table = lsd.global_color_table.as_deref().unwrap_or(&[]);
+        let mut frame = match frames.last() {
+            Some(previous) => previous.clone(),
+            None => ImageFrame {
+                width: lsd.width as u32,
+                height: lsd.height as u32,
+                colors: self.blank_canvas(color_table),
+                delay_time: 0,
+                placeholder: false,
+            },
+        };
+
+        let graphic_control = ext.graphic_control_extension.as_ref();
+        if matches!(
+            graphic_control.map(|ext| ext.disposal_method),
+            Some(DisposalMethod::RestoreToBackgroundColor)
+        ) {
+            frame.colors = self.blank_canvas(color_table);
+        }
+        frame.delay_time = graphic_control.map_or(0, |ext| ext.delay_time);
+        frame.placeholder = false;
+        plain_text::draw(&mut frame, ext, color_table);
+        frame
     }
 
-    fn decode(&self) -> Result<Vec<ImageFrame>, String> {
-        let mut frames = vec![];
+    // Stands in for a frame that cannot be decoded: the previous frame is shown for the
+    // broken frame's delay, or the background if it is the first frame.
+    fn create_placeholder_frame(
+        &self,
+        frames: &[ImageFrame],
+        image: &TableBasedImage,
+    ) -> ImageFrame {
+        let delay_time = image
+            .graphic_control_extension
+            .as_ref()
+            .map_or(0, |ext| ext.delay_time);
 
-        for block in self.data.data_blocks.iter() {
-            if let DataType::TableBasedImageType(image) = block {
-                let color_table = {
-                    if image.local_color_table.is_some() {
-                        image.local_color_table.as_ref().unwrap()
-                    } else {
-                        self.data
-                            .logical_screen_descriptor
-                            .global_color_table
-                            .as_ref()
-                            .ok_or("Global color table is missing!")?
-                    }
-                };
+        let lsd = &self.data.logical_screen_descriptor;
+        let colors = match frames.last() {
+            Some(previous) => previous.colors.clone(),
+            None => self.blank_canvas(lsd.global_color_table.as_deref().unwrap_or(&[])),
+        };
 
-                let (transparent_flag, transparent_color_index, disposal_method, delay_time) =
-                    match image.graphic_control_extension {
-                        Some(ref ext) => (
-                            ext.transparent_color_index_available,
-                            ext.transparent_color_index,
-                            ext.disposal_method,
-                            ext.delay_time,
-                        ),
-                        None => (false, 0, DisposalMethod::Unspecified, 0),
-                    };
-
-                let mut decompressor = Decompressor::new(
-                    &image.image_data.data_sub_blocks,
-                    image.image_data.lzw_min_code_size,
-                );
-
-                let index_table = decompressor.decompress()?;
-
-                if frames.is_empty() {
-                    frames.push(self.create_first_frame(
-                        &index_table,
-                        &color_table,
-                        image.image_descriptor.interlace_flag,
-                        delay_time,
-                    )?);
-                } else {
-                    frames.push(self.create_frame(
-                        &frames,
-                        &image,
-                        &index_table,
-                        &color_table,
-                        disposal_method,
-                        transparent_flag,
-                        transparent_color_index,
-                        delay_time,
-                    )?);
-                }
-            }
+        ImageFrame {
+            width: lsd.width as u32,
+            height: lsd.height as u32,
+            colors,
+            delay_time,
+            placeholder: true,
         }
-
-        Ok(frames)
     }
 
     fn create_first_frame(
@@ -149,9 +1392,10 @@ impl<'a> Decoder<'a> {
         interlace_flag: bool,
         delay_time: u16,
     ) -> Result<ImageFrame, String> {
+        // A transparent index outside of the color table has no color to show.
         let result = index_table
             .iter()
-            .map(|i| Some(color_table[*i]))
+            .map(|i| color_table.get(*i).copied())
             .collect::<Vec<_>>();
 
         let result = if interlace_flag {
@@ -171,79 +1415,30 @@ impl<'a> Decoder<'a> {
             .into_boxed_slice();
 
         Ok(ImageFrame {
+            width: self.data.logical_screen_descriptor.width as u32,
+            height: self.data.logical_screen_descriptor.height as u32,
             delay_time,
             colors: result,
+            placeholder: false,
         })
     }
 
-    fn create_frame(
-        &self,
-        frames: &[ImageFrame],
-        image: &TableBasedImage,
-        index_table: &[usize],
-        color_table: &[Color],
-        disposal_method: DisposalMethod,
-        transparent_flag: bool,
-        transparent_color_index: u8,
-        delay_time: u16,
-    ) -> Result<ImageFrame, String> {
-        let top = image.image_descriptor.top as usize;
-        let height = image.image_descriptor.height as usize;
-        let left = image.image_descriptor.left as usize;
-        let width = image.image_descriptor.width as usize;
-        let image_width = self.data.logical_screen_descriptor.width as usize;
-
-        let result = if transparent_flag {
-            index_table
-                .iter()
-                .map(|i| {
-                    if *i == transparent_color_index as usize {
-                        None
-                    } else {
-                        Some(color_table[*i])
-                    }
-                })
-                .collect::<Vec<_>>()
-        } else {
-            index_table
-                .iter()
-                .map(|i| Some(color_table[*i]))
-                .collect::<Vec<_>>()
-        };
-
-        let mut new_frame = match disposal_method {
-            DisposalMethod::RestoreToBackgroundColor => ImageFrame {
-                delay_time,
-                colors: vec![
-                    color_table[self.data.logical_screen_descriptor.background_color_index
-                        as usize];
-                    frames.last().unwrap().colors.len()
-                ]
-                .into_boxed_slice(),
-            },
-            DisposalMethod::DoNotDispose | DisposalMethod::Unspecified => {
-                frames.last().unwrap().clone()
-            }
-            d => return Err(format!("Dispose method {:?} not supported", d)),
-        };
-
-        let result = if image.image_descriptor.interlace_flag {
-            Self::deinterlace(result, width, height)
-        } else {
-            result
-        };
-
-        for y in 0..height {
-            let offset = (top + y) * image_width + left;
-            for x in 0..width {
-                let c = result[y * width + x];
-                if let Some(c) = c {
-                    new_frame.colors[offset + x] = c;
-                }
+    // A canvas with nothing drawn on it yet: the backdrop, or else the background color from
+    // `color_table`.
+    fn blank_canvas(&self, color_table: &[Color]) -> Box<[Color]> {
+        match self.backdrop {
+            Some(backdrop) => backdrop.into(),
+            None => {
+                let lsd = &self.data.logical_screen_descriptor;
+                vec![self.background_color(color_table); lsd.width as usize * lsd.height as usize]
+                    .into_boxed_slice()
             }
         }
+    }
 
-        Ok(new_frame)
+    fn background_color(&self, color_table: &[Color]) -> Color {
+        let index = self.data.logical_screen_descriptor.background_color_index as usize;
+        color_table.get(index).copied().unwrap_or(Color(0, 0, 0))
     }
 
     // Refer to https://www.w3.org/Graphics/GIF/spec-gif89a.txt for details.
@@ -254,9 +1449,9 @@ impl<'a> Decoder<'a> {
         let passes = [(0, 8), (4, 8), (2, 4), (1, 2)];
 
         for (start, step) in passes.iter() {
-            'l: for y in (*start..height as usize).step_by(*step) {
-                for x in 0..width as usize {
-                    let index_dst = y * width as usize + x;
+            'l: for y in (*start..height).step_by(*step) {
+                for x in 0..width {
+                    let index_dst = y * width + x;
                     if index_dst >= result.len() {
                         break 'l;
                     }
@@ -271,10 +1466,58 @@ impl<'a> Decoder<'a> {
     }
 }
 
+// Called with the pass and the color indices read so far.
+type OnPass<'a> = &'a mut dyn FnMut(usize, &[usize]);
+
+// Collects the color indices of an image, calling `on_pass` once the rows of each of the four
+// passes of an interlaced image are in.
+struct PassIndices<'a> {
+    indices: Vec<usize>,
+    // The number of indices at the end of each pass.
+    ends: [usize; 4],
+    // The number of passes reported so far.
+    passes: usize,
+    on_pass: Option<OnPass<'a>>,
+}
+
+impl Indices for PassIndices<'_> {
+    fn push(&mut self, index: usize) {
+        self.indices.push(index);
+        if let Some(on_pass) = self.on_pass.as_mut() {
+            while self.passes < 4 && self.indices.len() == self.ends[self.passes] {
+                on_pass(self.passes, &self.indices);
+                self.passes += 1;
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.indices.truncate(len);
+    }
+}
+
+// The row of an interlaced image of `height` rows at which image row `y` is stored.
+// Refer to https://www.w3.org/Graphics/GIF/spec-gif89a.txt for details.
+fn stored_row(y: usize, height: usize) -> usize {
+    let mut n = 0;
+    for (start, step) in [(0, 8), (4, 8), (2, 4), (1, 2)] {
+        if y % step == start {
+            return n + y / step;
+        }
+        n += height.saturating_sub(start).div_ceil(step);
+    }
+    n
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use std::time::Duration;
 
     struct MockReader<'a> {
         data: &'a [u8],
@@ -288,9 +1531,7 @@ mod tests {
             if self.remaining > 0 {
                 let offset = self.data.len() - self.remaining;
 
-                for i in 0..buf.len() {
-                    buf[i] = self.data[offset + i];
-                }
+                buf.copy_from_slice(&self.data[offset..offset + buf.len()]);
 
                 self.remaining -= buf.len();
                 count += buf.len();
@@ -300,14 +1541,15 @@ mod tests {
         }
     }
 
+    const SAMPLE_GIF: [u8; 69] = [
+        71, 73, 70, 56, 57, 97, 10, 0, 10, 0, 145, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0, 255, 0, 0,
+        0, 33, 249, 4, 0, 0, 0, 0, 0, 44, 0, 0, 0, 0, 10, 0, 10, 0, 0, 2, 22, 140, 45, 153, 135,
+        42, 28, 220, 51, 160, 2, 117, 236, 149, 250, 168, 222, 96, 140, 4, 145, 76, 1, 0, 59,
+    ];
+
     #[test]
     fn test_sample_gif() {
-        let input = vec![
-            71, 73, 70, 56, 57, 97, 10, 0, 10, 0, 145, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0, 255,
-            0, 0, 0, 33, 249, 4, 0, 0, 0, 0, 0, 44, 0, 0, 0, 0, 10, 0, 10, 0, 0, 2, 22, 140, 45,
-            153, 135, 42, 28, 220, 51, 160, 2, 117, 236, 149, 250, 168, 222, 96, 140, 4, 145, 76,
-            1, 0, 59,
-        ];
+        let input = SAMPLE_GIF;
 
         let mut reader = MockReader {
             data: &input,
@@ -418,11 +1660,10 @@ mod tests {
         ]
         .into_boxed_slice()];
 
-        let mut parser = Parser::new(&mut reader);
-        let result = parser.parse().unwrap();
+        let result = Parser::new().parse(&mut reader).unwrap();
 
-        let decoder = Decoder::new(&result);
-        let actual = decoder.decode().unwrap();
+        let decoder = Decoder::new(&result, DecodeOptions::default());
+        let (actual, _) = decoder.decode(&mut NoObserver).unwrap();
 
         let mut v = vec![];
         for i in actual.iter() {
@@ -431,4 +1672,773 @@ mod tests {
 
         assert_eq!(expected, v);
     }
+
+    #[test]
+    fn test_write_to_round_trip() {
+        let mut gif = load(&mut &SAMPLE_GIF[..]).unwrap();
+
+        let mut frame = gif.image_frames[0].clone();
+        frame.colors[0] = Color(0, 255, 0);
+        frame.delay_time = 20;
+        gif.image_frames.push(frame);
+        gif.loop_count = Some(0);
+        gif.comments.push("generated by giffy".into());
+        assert_eq!(None, gif.pixel_aspect_ratio);
+        gif.pixel_aspect_ratio = Some(0.5);
+
+        let mut output = vec![];
+        gif.write_to(&mut output).unwrap();
+
+        let actual = load(&mut output.as_slice()).unwrap();
+        assert_eq!(gif.width, actual.width);
+        assert_eq!(gif.height, actual.height);
+        assert_eq!(gif.loop_count, actual.loop_count);
+        assert_eq!(gif.comments, actual.comments);
+        assert_eq!(gif.pixel_aspect_ratio, actual.pixel_aspect_ratio);
+        assert_eq!(gif.image_frames.len(), actual.image_frames.len());
+        for (expected, actual) in gif.image_frames.iter().zip(actual.image_frames.iter()) {
+            assert_eq!(expected.colors, actual.colors);
+            assert_eq!(expected.delay_time, actual.delay_time);
+        }
+    }
+
+    #[test]
+    fn test_write_to_round_trip_empty() {
+        let gif = crate::testing::gif(0, 0, [vec![]]);
+
+        let mut output = vec![];
+        gif.write_to(&mut output).unwrap();
+
+        let actual = load(&mut output.as_slice()).unwrap();
+        assert_eq!((0, 0), (actual.width, actual.height));
+        assert_eq!(1, actual.image_frames.len());
+        assert!(actual.image_frames[0].colors.is_empty());
+    }
+
+    #[test]
+    fn test_split() {
+        let mut gif = load(&mut &SAMPLE_GIF[..]).unwrap();
+        let mut frame = gif.image_frames[0].clone();
+        frame.colors.fill(Color(0, 255, 0));
+        gif.image_frames.push(frame);
+
+        let parts = gif.split().unwrap();
+        assert_eq!(2, parts.len());
+
+        for (expected, part) in gif.image_frames.iter().zip(parts.iter()) {
+            let actual = load(&mut part.as_slice()).unwrap();
+            assert_eq!(1, actual.image_frames.len());
+            assert_eq!(expected.colors, actual.image_frames[0].colors);
+        }
+    }
+
+    #[test]
+    fn test_load_from_bytes() {
+        let gif = load_from_bytes(&SAMPLE_GIF).unwrap();
+        assert_eq!((10, 10), (gif.width, gif.height));
+        assert_eq!(1, gif.image_frames.len());
+        assert!(load_from_bytes(&SAMPLE_GIF[..10]).is_err());
+    }
+
+    #[test]
+    fn test_load_from_path() {
+        let path = std::env::temp_dir().join(format!("giffy-{}-load.gif", std::process::id()));
+        std::fs::write(&path, SAMPLE_GIF).unwrap();
+        let gif = load_from_path(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(1, gif.unwrap().image_frames.len());
+        assert!(load_from_path(&path).is_err());
+    }
+
+    // Two frames of two pixels, red and blue then blue and blue, with blue transparent and
+    // the second frame restoring the background.
+    fn transparent_blue() -> (Color, Color, Vec<u8>) {
+        let (red, blue) = (Color(255, 0, 0), Color(0, 0, 255));
+        let gif = Gif {
+            width: 2,
+            height: 1,
+            image_frames: [vec![red, blue], vec![blue, blue]]
+                .into_iter()
+                .map(|colors| ImageFrame {
+                    width: 2,
+                    height: 1,
+                    colors: colors.into_boxed_slice(),
+                    delay_time: 10,
+                    placeholder: false,
+                })
+                .collect(),
+            loop_count: None,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        };
+
+        let mut result = Parser::new()
+            .parse(&mut gif.to_bytes().unwrap().as_slice())
+            .unwrap();
+        let global = result.logical_screen_descriptor.global_color_table.clone();
+        let images = result
+            .data_blocks
+            .iter_mut()
+            .filter_map(|block| match block {
+                DataType::TableBasedImageType(image) => Some(image),
+                _ => None,
+            });
+        for (i, image) in images.enumerate() {
+            let table = image.local_color_table.clone().or(global.clone()).unwrap();
+            let ext = image.graphic_control_extension.as_mut().unwrap();
+            ext.transparent_color_index_available = true;
+            ext.transparent_color_index = table.iter().position(|c| *c == blue).unwrap() as u8;
+            if i == 1 {
+                ext.disposal_method = DisposalMethod::RestoreToBackgroundColor;
+            }
+        }
+        let mut data = vec![];
+        writer::Writer::new(&mut data).write(&result).unwrap();
+        (red, blue, data)
+    }
+
+    #[test]
+    fn test_load_onto() {
+        let (red, _, data) = transparent_blue();
+        let backdrop = [10, 20, 30, 255, 100, 100, 100, 128];
+        let options = DecodeOptions::default();
+        let composed = load_onto(&mut data.as_slice(), &options, &backdrop, 2, 1).unwrap();
+        let (first, second) = (Color(10, 20, 30), Color(50, 50, 50));
+        assert_eq!(vec![red, second], composed.image_frames[0].colors.to_vec());
+        assert_eq!(
+            vec![first, second],
+            composed.image_frames[1].colors.to_vec()
+        );
+
+        assert!(load_onto(&mut data.as_slice(), &options, &backdrop[..4], 1, 1).is_err());
+        assert!(load_onto(&mut data.as_slice(), &options, &backdrop, 1, 1).is_err());
+    }
+
+    #[test]
+    fn test_load_color_keyed() {
+        let (red, blue, data) = transparent_blue();
+        let options = DecodeOptions::default();
+
+        let magenta = Color(255, 0, 255);
+        let (gif, key) = load_color_keyed(&mut data.as_slice(), &options, magenta).unwrap();
+        assert_eq!(magenta, key);
+        assert_eq!(Some(magenta), gif.transparent_color);
+        assert_eq!(vec![red, magenta], gif.image_frames[0].colors.to_vec());
+        assert_eq!(vec![magenta; 2], gif.image_frames[1].colors.to_vec());
+
+        // Red is in the color table, so another key is picked.
+        let (gif, key) = load_color_keyed(&mut data.as_slice(), &options, red).unwrap();
+        assert_ne!(red, key);
+        assert_ne!(blue, key);
+        assert_eq!(vec![red, key], gif.image_frames[0].colors.to_vec());
+    }
+
+    #[test]
+    fn test_leading_bytes() {
+        let mut data = b"\xef\xbb\xbfHTTP/1.1 200 OK\r\n\r\n".to_vec();
+        data.extend_from_slice(&SAMPLE_GIF);
+        assert!(load(&mut data.as_slice()).is_err());
+
+        let mut options = DecodeOptions {
+            max_leading_bytes: 64,
+            ..DecodeOptions::default()
+        };
+        let gif = load_with_options(&mut data.as_slice(), &options).unwrap();
+        assert_eq!(1, gif.image_frames.len());
+        let gif = load_with_options(&mut &SAMPLE_GIF[..], &options).unwrap();
+        assert_eq!(1, gif.image_frames.len());
+
+        options.max_leading_bytes = 10;
+        let error = load_with_options(&mut data.as_slice(), &options).unwrap_err();
+        assert!(error.contains("signature"), "{}", error);
+        assert!(load_with_options(&mut &b"GIF"[..], &options).is_err());
+    }
+
+    #[test]
+    fn test_frame_dimensions() {
+        let gif = load(&mut &SAMPLE_GIF[..]).unwrap();
+        let frame = &gif.image_frames[0];
+        assert_eq!((10, 10), frame.dimensions());
+        assert_eq!(100, frame.len());
+        assert!(frame.contains(9, 0));
+        assert!(!frame.contains(10, 0));
+        assert!(!frame.contains(0, 10));
+    }
+
+    #[test]
+    fn test_frame_bytes() {
+        let frame = ImageFrame {
+            width: 2,
+            height: 1,
+            colors: vec![Color(1, 2, 3), Color(4, 5, 6)].into_boxed_slice(),
+            delay_time: 0,
+            placeholder: false,
+        };
+        assert_eq!(&[1, 2, 3, 4, 5, 6], frame.as_bytes());
+        assert_eq!(
+            frame.colors.as_ptr() as *const u8,
+            frame.as_bytes().as_ptr()
+        );
+        assert_eq!(vec![1, 2, 3, 4, 5, 6], frame.to_rgb_bytes());
+        assert_eq!(vec![1, 2, 3, 255, 4, 5, 6, 255], frame.to_rgba_bytes());
+    }
+
+    #[test]
+    fn test_to_tensor() {
+        let (frames, input) = animation();
+        let mut gif = load(&mut input.as_slice()).unwrap();
+        let (data, shape) = gif.to_tensor().unwrap();
+        assert_eq!([3, 4, 4, 3], shape);
+        assert_eq!(3 * 4 * 4 * 3, data.len());
+        for (i, frame) in frames.iter().enumerate() {
+            let offset = ((i * 4 + 1) * 4 + 2) * 3;
+            assert_eq!(<[u8; 3]>::from(frame.colors[6]), data[offset..offset + 3]);
+        }
+
+        gif.height = 5;
+        assert!(gif.to_tensor().is_err());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn test_to_rgba_images() {
+        let mut gif = load(&mut &SAMPLE_GIF[..]).unwrap();
+        let images = gif.to_rgba_images().unwrap();
+        assert_eq!(1, images.len());
+        assert_eq!((10, 10), images[0].dimensions());
+        assert_eq!(&[255, 0, 0, 255], &images[0].as_raw()[..4]);
+
+        gif.width = 11;
+        assert!(gif.to_rgba_images().is_err());
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn test_to_ndarray() {
+        let (frames, input) = animation();
+        let mut gif = load(&mut input.as_slice()).unwrap();
+        let (array, delays) = gif.to_ndarray().unwrap();
+        assert_eq!(&[3, 4, 4, 3], array.shape());
+        assert_eq!(vec![10, 11, 12], delays);
+        for (i, frame) in frames.iter().enumerate() {
+            let c = frame.colors[6];
+            assert_eq!(c.r(), array[[i, 1, 2, 0]]);
+            assert_eq!(c.g(), array[[i, 1, 2, 1]]);
+            assert_eq!(c.b(), array[[i, 1, 2, 2]]);
+        }
+
+        gif.width = 5;
+        assert!(gif.to_ndarray().is_err());
+    }
+
+    fn single_frame_gif() -> Vec<u8> {
+        let frame = ImageFrame {
+            width: 4,
+            height: 4,
+            colors: (0..16).map(|i| Color(i * 16, 0, 0)).collect(),
+            delay_time: 10,
+            placeholder: false,
+        };
+
+        let mut output = vec![];
+        let mut encoder = Encoder::new(&mut output, 4, 4);
+        encoder.write_frame(&frame).unwrap();
+        encoder.finish().unwrap();
+        output
+    }
+
+    #[test]
+    fn test_first_frame_smaller_than_canvas() {
+        let mut input = single_frame_gif();
+        let original = load(&mut input.as_slice()).unwrap();
+
+        // Grow the logical screen to 6x5.
+        input[6] = 6;
+        input[8] = 5;
+        let gif = load(&mut input.as_slice()).unwrap();
+        assert_eq!(30, gif.image_frames[0].colors.len());
+        for y in 0..5 {
+            for x in 0..6 {
+                let expected = if x < 4 && y < 4 {
+                    original.image_frames[0].colors[y * 4 + x]
+                } else {
+                    Color(0, 0, 0)
+                };
+                assert_eq!(expected, gif.image_frames[0].colors[y * 6 + x]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_malformed_images_are_rejected() {
+        let input = single_frame_gif();
+        let descriptor = input.iter().rposition(|b| *b == 0x2c).unwrap();
+
+        // Move the image off the canvas.
+        let mut data = input.clone();
+        data[descriptor + 1] = 1;
+        assert!(load(&mut data.as_slice()).is_err());
+
+        // Claim a larger image than there is data for.
+        let mut data = input.clone();
+        data[6] = 8;
+        data[descriptor + 5] = 8;
+        assert!(load(&mut data.as_slice()).is_err());
+
+        // Claim a tiny image with lots of data.
+        let mut data = input[..descriptor].to_vec();
+        data.extend_from_slice(&[0x2c, 0, 0, 0, 0, 1, 0, 1, 0, 0, 2]);
+        for _ in 0..10 {
+            data.push(255);
+            data.extend_from_slice(&[0; 255]);
+        }
+        data.extend_from_slice(&[0, 0x3b]);
+        let error = load(&mut data.as_slice()).unwrap_err();
+        assert!(error.contains("exceed"), "{}", error);
+    }
+
+    #[test]
+    fn test_composite_static() {
+        // The top and bottom half of a picture.
+        let slices = [Color(200, 0, 0), Color(0, 0, 200)]
+            .iter()
+            .enumerate()
+            .map(|(i, c)| ImageFrame {
+                width: 2,
+                height: 2,
+                colors: (0..4)
+                    .map(|p| if p / 2 <= i { *c } else { Color(0, 0, 0) })
+                    .collect(),
+                delay_time: 0,
+                placeholder: false,
+            })
+            .collect::<Vec<_>>();
+        let mut gif = Gif {
+            width: 2,
+            height: 2,
+            image_frames: slices,
+            loop_count: None,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        };
+        let options = DecodeOptions {
+            composite_static: true,
+            ..DecodeOptions::default()
+        };
+
+        let data = gif.to_bytes().unwrap();
+        assert_eq!(2, load_from_bytes(&data).unwrap().image_frames.len());
+        let (composite, status) = load_with_status(&mut data.as_slice(), &options).unwrap();
+        assert_eq!(1, composite.image_frames.len());
+        assert_eq!(gif.image_frames[1].colors, composite.image_frames[0].colors);
+        assert_eq!(vec![FrameStatus::Ok; 2], status);
+
+        // Looping or a delay makes it an animation.
+        gif.loop_count = Some(0);
+        let data = gif.to_bytes().unwrap();
+        let animation = load_with_options(&mut data.as_slice(), &options).unwrap();
+        assert_eq!(2, animation.image_frames.len());
+        gif.loop_count = None;
+        gif.image_frames[0].delay_time = 5;
+        let data = gif.to_bytes().unwrap();
+        let animation = load_with_options(&mut data.as_slice(), &options).unwrap();
+        assert_eq!(2, animation.image_frames.len());
+    }
+
+    #[test]
+    fn test_coalesce_zero_delay() {
+        // The delays of the frames that are kept.
+        let coalesce = |delays: &[u16]| {
+            let gif = Gif {
+                width: 1,
+                height: 1,
+                image_frames: delays
+                    .iter()
+                    .enumerate()
+                    .map(|(i, delay_time)| ImageFrame {
+                        width: 1,
+                        height: 1,
+                        colors: vec![Color(i as u8 * 40, 0, 0)].into_boxed_slice(),
+                        delay_time: *delay_time,
+                        placeholder: false,
+                    })
+                    .collect(),
+                loop_count: Some(0),
+                comments: vec![],
+                pixel_aspect_ratio: None,
+                transparent_color: None,
+            };
+            let data = gif.to_bytes().unwrap();
+            let options = DecodeOptions {
+                coalesce_zero_delay: true,
+                ..DecodeOptions::default()
+            };
+            let coalesced = load_with_options(&mut data.as_slice(), &options).unwrap();
+            coalesced
+                .image_frames
+                .iter()
+                .map(|frame| {
+                    // Every frame is a copy of the one with the same color.
+                    let i = gif
+                        .image_frames
+                        .iter()
+                        .position(|f| f.colors == frame.colors)
+                        .unwrap();
+                    assert_eq!(gif.image_frames[i].delay_time, frame.delay_time);
+                    i
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // The runs end at frames 2 and 4, and the frames after them have no delay to merge
+        // into.
+        assert_eq!(vec![2, 4, 5, 6], coalesce(&[0, 0, 7, 0, 5, 0, 0]));
+        // Browsers play an animation without delays at their slowest frame rate.
+        assert_eq!(vec![0, 1, 2], coalesce(&[0, 0, 0]));
+        assert_eq!(vec![1, 2], coalesce(&[0, 3, 4]));
+    }
+
+    #[test]
+    fn test_strict() {
+        let input = single_frame_gif();
+        let descriptor = input.iter().rposition(|b| *b == 0x2c).unwrap();
+        let strict = DecodeOptions {
+            strict: true,
+            ..DecodeOptions::default()
+        };
+        let load_strict = |data: &[u8]| load_with_options(&mut &data[..], &strict);
+        assert!(load_strict(&input).is_ok());
+
+        let mut data = input.clone();
+        data[3..6].copy_from_slice(b"88a");
+        assert!(load(&mut data.as_slice()).is_ok());
+        let error = load_strict(&data).unwrap_err();
+        assert!(error.contains("unknown GIF version: 88a"), "{}", error);
+
+        // The image is read past, and fails later in the default mode.
+        let mut data = input.clone();
+        data[descriptor + 1] = 1;
+        let error = load_strict(&data).unwrap_err();
+        let at = format!("image at {:#x}", descriptor);
+        assert!(error.contains(&at), "{}", error);
+
+        // An extra sub-block after the end of information code.
+        let mut data = input.clone();
+        let terminator = data.len() - 2;
+        data.splice(terminator..terminator, [1, 0]);
+        assert!(load(&mut data.as_slice()).is_ok());
+        let error = load_strict(&data).unwrap_err();
+        assert!(error.contains("1 bytes of padding"), "{}", error);
+
+        // A transparent color index past the 16 colors of the table.
+        let mut data = input.clone();
+        let gce = data.windows(3).position(|w| w == [0x21, 0xf9, 4]).unwrap();
+        data[gce + 3] |= 1;
+        data[gce + 6] = 200;
+        assert!(load(&mut data.as_slice()).is_ok());
+        let error = load_strict(&data).unwrap_err();
+        assert!(error.contains("transparent color index 200"), "{}", error);
+
+        // Strict mode takes precedence over lenient mode.
+        let data = &input[..input.len() - 1];
+        let options = DecodeOptions {
+            lenient: true,
+            ..strict
+        };
+        assert!(load_with_options(&mut &data[..], &options).is_err());
+        let options = DecodeOptions {
+            strict: false,
+            ..options
+        };
+        assert!(load_with_options(&mut &data[..], &options).is_ok());
+    }
+
+    fn animation() -> (Vec<ImageFrame>, Vec<u8>) {
+        let frames = (0..3)
+            .map(|i| ImageFrame {
+                width: 4,
+                height: 4,
+                colors: (0..16).map(|p| Color(p * 16, i * 100, 0)).collect(),
+                delay_time: 10 + i as u16,
+                placeholder: false,
+            })
+            .collect::<Vec<_>>();
+        let mut output = vec![];
+        let mut encoder = Encoder::new(&mut output, 4, 4);
+        encoder.write_frames(&frames).unwrap();
+        encoder.finish().unwrap();
+        (frames, output)
+    }
+
+    // The offset of the Graphic Control Extension of frame `n`.
+    fn graphic_control_extension(input: &[u8], n: usize) -> usize {
+        let gce = input
+            .windows(3)
+            .enumerate()
+            .filter(|(_, w)| *w == [0x21, 0xf9, 4])
+            .nth(n)
+            .unwrap()
+            .0;
+        assert_eq!(0x2c, input[gce + 8]);
+        gce
+    }
+
+    #[test]
+    fn test_placeholder_frames() {
+        let (frames, mut input) = animation();
+
+        // Break the LZW minimum code size of the second frame.
+        let gce = graphic_control_extension(&input, 1);
+        input[gce + 18] = 0;
+        assert!(load(&mut input.as_slice()).is_err());
+
+        let mut options = DecodeOptions {
+            lenient: true,
+            placeholder_frames: false,
+            ..DecodeOptions::default()
+        };
+        let gif = load_with_options(&mut input.as_slice(), &options).unwrap();
+        assert_eq!(2, gif.image_frames.len());
+        assert_eq!(12, gif.image_frames[1].delay_time);
+
+        options.placeholder_frames = true;
+        let gif = load_with_options(&mut input.as_slice(), &options).unwrap();
+        assert_eq!(3, gif.image_frames.len());
+        let flags = gif
+            .image_frames
+            .iter()
+            .map(|f| f.placeholder)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![false, true, false], flags);
+        assert_eq!(gif.image_frames[0].colors, gif.image_frames[1].colors);
+        assert_eq!(11, gif.image_frames[1].delay_time);
+        assert_eq!(frames[2].colors, gif.image_frames[2].colors);
+
+        // A truncated GIF keeps the frames before the end of the data.
+        let truncated = &input[..gce + 8];
+        assert!(load(&mut &truncated[..]).is_err());
+        let gif = load_with_options(&mut &truncated[..], &options).unwrap();
+        assert_eq!(1, gif.image_frames.len());
+    }
+
+    #[test]
+    fn test_frame_status() {
+        let (frames, mut input) = animation();
+        let options = DecodeOptions {
+            lenient: true,
+            placeholder_frames: true,
+            ..DecodeOptions::default()
+        };
+
+        let (_, status) = load_with_status(&mut input.as_slice(), &options).unwrap();
+        assert_eq!(vec![FrameStatus::Ok; 3], status);
+
+        // Break the second frame and cut off the end of the image data of the third.
+        let gce = graphic_control_extension(&input, 1);
+        input[gce + 18] = 0;
+        let gce = graphic_control_extension(&input, 2);
+        input[gce + 19] -= 3;
+        let end = input.len() - 2;
+        input.drain(end - 3..end);
+
+        let (gif, status) = load_with_status(&mut input.as_slice(), &options).unwrap();
+        assert_eq!(3, gif.image_frames.len());
+        assert_eq!(FrameStatus::Ok, status[0]);
+        assert!(matches!(status[1], FrameStatus::Failed(_)));
+        assert_eq!(FrameStatus::Recovered, status[2]);
+        assert_eq!(frames[2].colors[..4], gif.image_frames[2].colors[..4]);
+
+        // Colors outside of the color table are filled in as well. This is a 2x2 image with
+        // two colors whose pixels refer to four.
+        let data = compressor::Compressor::new(&[0, 1, 2, 3], 2).compress();
+        let mut input = b"GIF89a\x02\x00\x02\x00\x80\x00\x00".to_vec();
+        input.extend_from_slice(&[255, 255, 255, 0, 0, 0]);
+        input.extend_from_slice(&[0x2c, 0, 0, 0, 0, 2, 0, 2, 0, 0, 2, data.len() as u8]);
+        input.extend_from_slice(&data);
+        input.extend_from_slice(&[0, 0x3b]);
+        assert!(load(&mut input.as_slice()).is_err());
+
+        let (gif, status) = load_with_status(&mut input.as_slice(), &options).unwrap();
+        assert_eq!(vec![FrameStatus::Recovered], status);
+        let white = Color(255, 255, 255);
+        assert_eq!(
+            [white, Color(0, 0, 0), white, white],
+            *gif.image_frames[0].colors
+        );
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        blocks: Vec<(String, usize, usize)>,
+        frames: Vec<usize>,
+        passes: Vec<(usize, usize, Vec<Color>)>,
+        warnings: Vec<String>,
+        errors: Vec<String>,
+    }
+
+    impl DecodeObserver for Recorder {
+        fn block_parsed(&mut self, name: &str, offset: usize, size: usize) {
+            self.blocks.push((name.into(), offset, size));
+        }
+
+        fn frame_decoded(&mut self, index: usize, _frame: &ImageFrame, _elapsed: Duration) {
+            self.frames.push(index);
+        }
+
+        fn interlace_pass(&mut self, index: usize, pass: usize, canvas: &ImageFrame) {
+            self.passes.push((index, pass, canvas.colors.to_vec()));
+        }
+
+        fn warning(&mut self, message: &str) {
+            self.warnings.push(message.into());
+        }
+
+        fn error(&mut self, message: &str) {
+            self.errors.push(message.into());
+        }
+    }
+
+    #[test]
+    fn test_observer() {
+        let (_, mut input) = animation();
+
+        let mut recorder = Recorder::default();
+        load_with_observer(
+            &mut input.as_slice(),
+            &DecodeOptions::default(),
+            &mut recorder,
+        )
+        .unwrap();
+        let names = recorder
+            .blocks
+            .iter()
+            .map(|(name, _, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "Header",
+                "Logical Screen Descriptor",
+                "Graphic Control Extension",
+                "Image",
+                "Graphic Control Extension",
+                "Image",
+                "Graphic Control Extension",
+                "Image",
+                "Trailer",
+            ],
+            names
+        );
+        // The blocks cover the whole file without gaps.
+        let mut offset = 0;
+        for (_, block_offset, size) in recorder.blocks.iter() {
+            assert_eq!(offset, *block_offset);
+            offset += size;
+        }
+        assert_eq!(input.len(), offset);
+        assert_eq!(vec![0, 1, 2], recorder.frames);
+        assert!(recorder.warnings.is_empty());
+        assert!(recorder.errors.is_empty());
+
+        let gce = graphic_control_extension(&input, 1);
+        input[gce + 18] = 0;
+
+        let mut recorder = Recorder::default();
+        let options = DecodeOptions {
+            lenient: true,
+            placeholder_frames: false,
+            ..DecodeOptions::default()
+        };
+        load_with_observer(&mut input.as_slice(), &options, &mut recorder).unwrap();
+        assert_eq!(vec![0, 2], recorder.frames);
+        assert_eq!(1, recorder.warnings.len());
+        assert!(recorder.errors.is_empty());
+
+        let mut recorder = Recorder::default();
+        let error = load_with_observer(
+            &mut input.as_slice(),
+            &DecodeOptions::default(),
+            &mut recorder,
+        )
+        .unwrap_err();
+        assert_eq!(vec![error], recorder.errors);
+    }
+
+    #[test]
+    fn test_interlace_passes() {
+        // A column of eight rows, stored in the order in which the passes read them.
+        let stored = (0..8).map(|i| Color(i * 30, 0, 0)).collect::<Vec<_>>();
+        let gif = Gif {
+            width: 1,
+            height: 8,
+            image_frames: vec![ImageFrame {
+                width: 1,
+                height: 8,
+                colors: stored.clone().into_boxed_slice(),
+                delay_time: 0,
+                placeholder: false,
+            }],
+            loop_count: None,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        };
+        let mut result = Parser::new()
+            .parse(&mut gif.to_bytes().unwrap().as_slice())
+            .unwrap();
+        for block in result.data_blocks.iter_mut() {
+            if let DataType::TableBasedImageType(image) = block {
+                image.image_descriptor.interlace_flag = true;
+            }
+        }
+        let mut data = vec![];
+        writer::Writer::new(&mut data).write(&result).unwrap();
+
+        let mut recorder = Recorder::default();
+        let options = DecodeOptions {
+            interlace_passes: true,
+            ..DecodeOptions::default()
+        };
+        let gif = load_with_observer(&mut data.as_slice(), &options, &mut recorder).unwrap();
+
+        // The rows of each pass, as indices into `stored`, stretched down.
+        let expected = [
+            [0, 0, 0, 0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 1, 1, 1, 1],
+            [0, 0, 2, 2, 1, 1, 3, 3],
+            [0, 4, 2, 5, 1, 6, 3, 7],
+        ];
+        assert_eq!(4, recorder.passes.len());
+        for (pass, (rows, (index, n, canvas))) in expected.iter().zip(&recorder.passes).enumerate()
+        {
+            assert_eq!((0, pass), (*index, *n));
+            assert_eq!(rows.map(|i| stored[i]).to_vec(), *canvas);
+        }
+        assert_eq!(recorder.passes[3].2, gif.image_frames[0].colors.to_vec());
+
+        let mut recorder = Recorder::default();
+        load_with_observer(
+            &mut data.as_slice(),
+            &DecodeOptions::default(),
+            &mut recorder,
+        )
+        .unwrap();
+        assert!(recorder.passes.is_empty());
+
+        // The passes are reported as they are read, so those before bad data still show.
+        for block in result.data_blocks.iter_mut() {
+            if let DataType::TableBasedImageType(image) = block {
+                image.image_data.data_sub_blocks.truncate(2);
+            }
+        }
+        let mut data = vec![];
+        writer::Writer::new(&mut data).write(&result).unwrap();
+        let mut recorder = Recorder::default();
+        assert!(load_with_observer(&mut data.as_slice(), &options, &mut recorder).is_err());
+        assert_eq!(2, recorder.passes.len());
+        for (rows, (_, _, canvas)) in expected.iter().zip(&recorder.passes) {
+            assert_eq!(rows.map(|i| stored[i]).to_vec(), *canvas);
+        }
+    }
 }
@@ -0,0 +1,109 @@
+//! The blocks of a GIF as they are stored, for tools that inspect or remux GIFs without
+//! decoding their pixels, such as to change delays, strip extensions or join animations.
+//!
+//! [`parse`] reads every block, with the image data left LZW compressed, and [`write`] writes
+//! them back. Blocks that are not changed are written back byte for byte, apart from how the
+//! data of images, comments and plain text is split into sub-blocks.
+//!
+//! This module is only available with the `lowlevel` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use giffy::lowlevel::{self, DataType};
+//! use std::fs::File;
+//!
+//! let mut src = File::open("<gif path>").expect("File not found");
+//! let mut result = lowlevel::parse(&mut src).expect("Error parsing GIF");
+//!
+//! // Drop the comments and make every frame last a tenth of a second.
+//! result
+//!     .data_blocks
+//!     .retain(|block| !matches!(block, DataType::CommentExtensionType(_)));
+//! for block in result.data_blocks.iter_mut() {
+//!     if let DataType::TableBasedImageType(image) = block {
+//!         if let Some(ref mut ext) = image.graphic_control_extension {
+//!             ext.delay_time = 10;
+//!         }
+//!     }
+//! }
+//!
+//! let mut dst = File::create("<output path>").expect("Error creating file");
+//! lowlevel::write(&result, &mut dst).expect("Error writing GIF");
+//! ```
+
+use crate::parser::Parser;
+use crate::writer::Writer;
+
+use std::io::{Read, Write};
+
+pub use crate::parser::{
+    ApplicationExtension, CommentExtension, DataType, DisposalMethod, GraphicControlExtension,
+    Header, ImageData, ImageDescriptor, LogicalScreenDescriptor, ParseResult, PlainTextExtension,
+    TableBasedImage,
+};
+
+/// Read every block of the GIF in `src`, up to the trailer.
+///
+/// # Errors
+///
+/// This function will return an error if `src` is not in a valid GIF format. The image data is
+/// not decompressed, so broken image data is not an error.
+pub fn parse<R: Read>(src: &mut R) -> Result<ParseResult, String> {
+    Parser::new().parse(src)
+}
+
+/// Write the blocks in `result` to `dst`, followed by the trailer.
+///
+/// # Errors
+///
+/// This function will return an error if a block cannot be stored, such as a header that is
+/// not 6 characters long, or if writing to `dst` fails.
+pub fn write<W: Write>(result: &ParseResult, dst: &mut W) -> Result<(), String> {
+    Writer::new(dst).write(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Gif, ImageFrame};
+
+    #[test]
+    fn test_parse_write() {
+        let mut data = vec![];
+        Gif {
+            width: 2,
+            height: 1,
+            image_frames: vec![
+                ImageFrame {
+                    width: 2,
+                    height: 1,
+                    colors: vec![Color(1, 2, 3), Color(4, 5, 6)].into_boxed_slice(),
+                    delay_time: 7,
+                    placeholder: false,
+                };
+                2
+            ],
+            loop_count: Some(0),
+            comments: vec!["hello".into()],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        }
+        .write_to(&mut data)
+        .unwrap();
+
+        let mut result = parse(&mut data.as_slice()).unwrap();
+        let mut written = vec![];
+        write(&result, &mut written).unwrap();
+        assert_eq!(data, written);
+
+        result
+            .data_blocks
+            .retain(|block| !matches!(block, DataType::CommentExtensionType(_)));
+        let mut written = vec![];
+        write(&result, &mut written).unwrap();
+        let gif = crate::load(&mut written.as_slice()).unwrap();
+        assert!(gif.comments.is_empty());
+        assert_eq!(2, gif.image_frames.len());
+    }
+}
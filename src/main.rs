@@ -0,0 +1,70 @@
+//! The `giffy` command line tool, built with the `cli` feature. Every subcommand is a thin
+//! wrapper around the library API of the same name.
+
+mod cli;
+mod json;
+
+use clap::{Parser, Subcommand};
+use std::process::ExitCode;
+
+/// Inspect, convert and optimize GIFs.
+#[derive(Debug, Parser)]
+#[command(name = "giffy", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    Info(cli::info::Args),
+    Diff(cli::diff::Args),
+    Explode(cli::explode::Args),
+    Convert(cli::convert::Args),
+    Compose(cli::compose::Args),
+    Frame(cli::frame::Args),
+    Lint(cli::lint::Args),
+    Optimize(cli::optimize::Args),
+    Play(cli::play::Args),
+    Resize(cli::resize::Args),
+    Speed(cli::speed::Args),
+    Stats(cli::stats::Args),
+    Strip(cli::strip::Args),
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Info(args) => cli::info::run(&args),
+        Command::Diff(args) => cli::diff::run(&args),
+        Command::Explode(args) => cli::explode::run(&args),
+        Command::Convert(args) => cli::convert::run(&args),
+        Command::Compose(args) => cli::compose::run(&args),
+        Command::Frame(args) => cli::frame::run(&args),
+        Command::Lint(args) => cli::lint::run(&args),
+        Command::Optimize(args) => cli::optimize::run(&args),
+        Command::Play(args) => cli::play::run(&args),
+        Command::Resize(args) => cli::resize::run(&args),
+        Command::Speed(args) => cli::speed::run(&args),
+        Command::Stats(args) => cli::stats::run(&args),
+        Command::Strip(args) => cli::strip::run(&args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{Color, Gif};
+
+// The 4x4 Bayer matrix, whose thresholds spread evenly over every 4x4 block of pixels.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+impl Gif {
+    /// Convert every frame to grayscale with `bits` bits per pixel, 1, 2 or 4, for
+    /// monochrome and e-paper displays. Levels go from 0 for black to `2^bits - 1` for white,
+    /// and are dithered with a 4x4 Bayer matrix, which keeps flat areas steady from one frame
+    /// to the next.
+    ///
+    /// Every frame is packed row by row, with the leftmost pixel in the highest bits of a
+    /// byte, and every row starts on a new byte, as most display drivers take it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let gif = giffy::load_from_path("<gif path>").expect("Error loading GIF");
+    /// let frames = gif.to_packed_gray(1).expect("Error converting GIF");
+    /// let stride = (gif.width as usize).div_ceil(8);
+    /// assert_eq!(stride * gif.height as usize, frames[0].len());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `bits` is not 1, 2 or 4, or if a frame does not
+    /// have `width * height` pixels.
+    pub fn to_packed_gray(&self, bits: u8) -> Result<Vec<Vec<u8>>, String> {
+        if !matches!(bits, 1 | 2 | 4) {
+            return Err(format!("Error: {} bits per pixel is not 1, 2 or 4", bits));
+        }
+        let width = self.width as usize;
+        let pixel_count = width * self.height as usize;
+        let stride = (width * bits as usize).div_ceil(8);
+
+        self.image_frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                if frame.colors.len() != pixel_count {
+                    return Err(format!(
+                        "Error: frame {} has {} pixels, expected {}",
+                        i,
+                        frame.colors.len(),
+                        pixel_count
+                    ));
+                }
+                let mut packed = vec![0; stride * self.height as usize];
+                for (y, row) in frame.colors.chunks_exact(width.max(1)).enumerate() {
+                    for (x, c) in row.iter().enumerate() {
+                        let level = dither(*c, x, y, bits);
+                        let bit = x * bits as usize;
+                        packed[y * stride + bit / 8] |= level << (8 - bits as usize - bit % 8);
+                    }
+                }
+                Ok(packed)
+            })
+            .collect()
+    }
+}
+
+// The level of `c`, out of `2^bits`, at (`x`, `y`).
+fn dither(c: Color, x: usize, y: usize, bits: u8) -> u8 {
+    let max = (1u32 << bits) - 1;
+    // Luma from 0 to 255 * 1000.
+    let luma = 299 * c.r() as u32 + 587 * c.g() as u32 + 114 * c.b() as u32;
+    // Scaled to 0..=max in 16ths, then rounded up past the threshold of the pixel.
+    let sixteenths = luma * max * 16 / (255 * 1000);
+    let threshold = BAYER[y % 4][x % 4] as u32;
+    ((sixteenths + 15 - threshold) / 16).min(max) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ImageFrame;
+
+    fn gif(colors: Vec<Color>, width: u32) -> Gif {
+        let height = colors.len() as u32 / width;
+        Gif {
+            width,
+            height,
+            image_frames: vec![ImageFrame {
+                width,
+                height,
+                colors: colors.into_boxed_slice(),
+                delay_time: 0,
+                placeholder: false,
+            }],
+            loop_count: None,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        }
+    }
+
+    #[test]
+    fn test_to_packed_gray() {
+        let (black, white) = (Color(0, 0, 0), Color(255, 255, 255));
+        // Rows of 10 pixels take 2 bytes at 1 bit per pixel.
+        let mut colors = vec![white; 10];
+        colors.extend([black; 10]);
+        let frames = gif(colors, 10).to_packed_gray(1).unwrap();
+        assert_eq!(vec![vec![0xff, 0xc0, 0, 0]], frames);
+
+        // Half gray lights up half of every 4x4 block.
+        let gray = gif(vec![Color(128, 128, 128); 16], 4);
+        let frames = gray.to_packed_gray(1).unwrap();
+        let lit = frames[0].iter().map(|b| b.count_ones()).sum::<u32>();
+        assert_eq!(8, lit);
+
+        // At 4 bits, two pixels share a byte.
+        let frames = gif(vec![white, black, white], 3).to_packed_gray(4).unwrap();
+        assert_eq!(vec![vec![0xf0, 0xf0]], frames);
+        let frames = gray.to_packed_gray(2).unwrap();
+        assert!(frames[0].iter().all(|b| *b != 0 && *b != 0xff));
+
+        assert!(gray.to_packed_gray(3).is_err());
+    }
+}
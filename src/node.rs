@@ -0,0 +1,71 @@
+//! Node.js bindings, built with `napi-rs`.
+//!
+//! This module is only available with the `node` feature. Build the addon with
+//! `cargo rustc --release --lib --crate-type cdylib --features node` and copy
+//! `target/release/libgiffy.so` (or `.dylib`, or `giffy.dll`) to `giffy.node`, then use it from
+//! Node.js:
+//!
+//! ```js
+//! const { decode } = require("./giffy.node");
+//! const fs = require("fs");
+//!
+//! const gif = decode(fs.readFileSync("animation.gif"));
+//! const first = gif.frames[0];
+//! console.log(`${gif.width}x${gif.height}, ${gif.frames.length} frames, first for ${first.delayMs} ms`);
+//! ```
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+/// A frame of a GIF decoded by [`decode`].
+#[napi(object)]
+pub struct DecodedFrame {
+    /// The pixels of the frame as `width * height * 4` bytes of RGBA, row by row.
+    pub data: Buffer,
+    /// How long the frame stays on screen, in milliseconds.
+    pub delay_ms: u32,
+}
+
+/// A GIF decoded by [`decode`].
+#[napi(object)]
+pub struct DecodedGif {
+    /// The width of the GIF in pixels.
+    pub width: u32,
+    /// The height of the GIF in pixels.
+    pub height: u32,
+    /// The number of times the animation should loop, `undefined` if it does not loop and 0 if
+    /// it loops forever.
+    pub loop_count: Option<u32>,
+    /// The frames, each composited onto the full canvas.
+    pub frames: Vec<DecodedFrame>,
+}
+
+/// Decode the GIF in `data`.
+///
+/// # Errors
+///
+/// This function will throw an `Error` if `data` is not in a valid GIF format.
+#[napi]
+pub fn decode(data: Buffer) -> napi::Result<DecodedGif> {
+    let gif = crate::load(&mut &data[..]).map_err(napi::Error::from_reason)?;
+
+    let frames = gif
+        .image_frames
+        .iter()
+        .map(|frame| DecodedFrame {
+            data: frame
+                .colors
+                .iter()
+                .flat_map(|c| [c.0, c.1, c.2, 255])
+                .collect::<Vec<_>>()
+                .into(),
+            delay_ms: frame.delay_time as u32 * 10,
+        })
+        .collect();
+    Ok(DecodedGif {
+        width: gif.width,
+        height: gif.height,
+        loop_count: gif.loop_count.map(u32::from),
+        frames,
+    })
+}
@@ -0,0 +1,59 @@
+use crate::ImageFrame;
+
+use std::time::Duration;
+
+/// Callbacks for following a decode as it happens, such as to feed a metrics system. Pass an
+/// implementation to [`load_with_observer`](crate::load_with_observer).
+///
+/// Every method does nothing by default, so only the interesting ones need to be implemented.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::{DecodeObserver, DecodeOptions, ImageFrame};
+/// use std::fs::File;
+/// use std::time::Duration;
+///
+/// #[derive(Default)]
+/// struct Timings {
+///     total: Duration,
+/// }
+///
+/// impl DecodeObserver for Timings {
+///     fn frame_decoded(&mut self, _index: usize, _frame: &ImageFrame, elapsed: Duration) {
+///         self.total += elapsed;
+///     }
+/// }
+///
+/// let mut timings = Timings::default();
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// giffy::load_with_observer(&mut src, &DecodeOptions::default(), &mut timings)
+///     .expect("Error loading GIF");
+/// println!("decoding took {:?}", timings.total);
+/// ```
+pub trait DecodeObserver {
+    /// Called after a block has been read, with its name, its byte offset in the file and its
+    /// size in bytes. An image is reported as a single block, including its color table and
+    /// data.
+    fn block_parsed(&mut self, _name: &str, _offset: usize, _size: usize) {}
+
+    /// Called after frame `index` has been decoded and composited, with the time it took.
+    fn frame_decoded(&mut self, _index: usize, _frame: &ImageFrame, _elapsed: Duration) {}
+
+    /// Called after each of the four passes of interlaced frame `index`, from 0 to 3, with the
+    /// canvas as it would show so far. Only called when
+    /// [`DecodeOptions::interlace_passes`](crate::DecodeOptions::interlace_passes) is set.
+    fn interlace_pass(&mut self, _index: usize, _pass: usize, _canvas: &ImageFrame) {}
+
+    /// Called when a problem is worked around in lenient mode, such as a frame that had to be
+    /// recovered or left out.
+    fn warning(&mut self, _message: &str) {}
+
+    /// Called with the error that made decoding fail, right before it is returned.
+    fn error(&mut self, _message: &str) {}
+}
+
+// Used when nobody is listening.
+pub(crate) struct NoObserver;
+
+impl DecodeObserver for NoObserver {}
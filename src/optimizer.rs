@@ -0,0 +1,296 @@
+use crate::encoder::PaletteMode;
+use crate::{Gif, ImageFrame};
+
+use std::io::{Read, Write};
+
+/// Controls what [`optimize`] is allowed to do to a GIF.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptimizeOptions {
+    /// Only store the rectangle that changed since the previous frame and write unchanged
+    /// pixels inside it as transparent. Enabled by default.
+    pub delta_optimization: bool,
+    /// Merge consecutive identical frames into one, adding up their delays. Enabled by default.
+    pub merge_duplicate_frames: bool,
+    /// Re-quantize all frames to at most this many colors. This is lossy. Disabled by default.
+    pub max_colors: Option<usize>,
+    /// Dither when re-quantizing to `max_colors`. Disabled by default.
+    pub dither: bool,
+    /// Keep the previous pixel where a pixel changed by at most this much in every color
+    /// channel since the previous frame. This is lossy, but turns noise into unchanged pixels,
+    /// which delta optimization stores as transparent. 0, the default, keeps every pixel.
+    pub lossy: u8,
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        Self {
+            delta_optimization: true,
+            merge_duplicate_frames: true,
+            max_colors: None,
+            dither: false,
+            lossy: 0,
+        }
+    }
+}
+
+/// Sizes before and after [`optimize`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OptimizeReport {
+    /// The size of the original GIF in bytes.
+    pub original_size: usize,
+    /// The size of the optimized GIF in bytes.
+    pub optimized_size: usize,
+    /// The number of frames of the original GIF.
+    pub original_frame_count: usize,
+    /// The number of frames of the optimized GIF.
+    pub optimized_frame_count: usize,
+}
+
+/// Attempt to shrink the GIF read from `src` and write the result to `dst`.
+///
+/// The frames are decoded and re-encoded with a global color table where possible, and
+/// depending on `options`, with duplicate frames merged, colors reduced and only the changed
+/// part of every frame stored. If the result is not smaller than the original, the original
+/// is written to `dst` unchanged.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::OptimizeOptions;
+/// use std::fs::File;
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let mut dst = File::create("<output path>").expect("Unable to create file");
+/// let report = giffy::optimize(&mut src, &mut dst, &OptimizeOptions::default())
+///     .expect("Error optimizing GIF");
+/// println!("{} -> {} bytes", report.original_size, report.optimized_size);
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if the GIF src is not in a valid GIF format or if
+/// writing to `dst` fails.
+pub fn optimize<R, W>(
+    src: &mut R,
+    dst: &mut W,
+    options: &OptimizeOptions,
+) -> Result<OptimizeReport, String>
+where
+    R: Read,
+    W: Write,
+{
+    let mut original = vec![];
+    src.read_to_end(&mut original)
+        .map_err(|e| format!("Error: {}", e))?;
+
+    let mut gif = crate::load(&mut original.as_slice())?;
+    let original_frame_count = gif.image_frames.len();
+
+    if let Some(max_colors) = options.max_colors {
+        gif.reduce_colors(max_colors, options.dither);
+    }
+
+    if options.lossy > 0 {
+        keep_similar_pixels(&mut gif.image_frames, options.lossy);
+    }
+
+    if options.merge_duplicate_frames {
+        merge_duplicate_frames(&mut gif.image_frames);
+    }
+
+    let optimized = encode(&gif, options.delta_optimization)?;
+    let (output, frame_count) = if optimized.len() < original.len() {
+        (&optimized, gif.image_frames.len())
+    } else {
+        (&original, original_frame_count)
+    };
+    dst.write_all(output).map_err(|e| format!("Error: {}", e))?;
+
+    Ok(OptimizeReport {
+        original_size: original.len(),
+        optimized_size: output.len(),
+        original_frame_count,
+        optimized_frame_count: frame_count,
+    })
+}
+
+// Copy every pixel that is within `threshold` of the pixel shown before it from the previous
+// frame, as it will be shown after this is done.
+fn keep_similar_pixels(frames: &mut [ImageFrame], threshold: u8) {
+    for i in 1..frames.len() {
+        let (shown, rest) = frames.split_at_mut(i);
+        let previous = &shown[i - 1];
+        for (c, p) in rest[0].colors.iter_mut().zip(previous.colors.iter()) {
+            let similar = <[u8; 3]>::from(*c)
+                .into_iter()
+                .zip(<[u8; 3]>::from(p))
+                .all(|(a, b)| a.abs_diff(b) <= threshold);
+            if similar {
+                *c = *p;
+            }
+        }
+    }
+}
+
+fn merge_duplicate_frames(frames: &mut Vec<ImageFrame>) {
+    let mut merged: Vec<ImageFrame> = Vec::with_capacity(frames.len());
+
+    for frame in frames.drain(..) {
+        match merged.last_mut() {
+            Some(last) if last.colors == frame.colors => {
+                last.delay_time = last.delay_time.saturating_add(frame.delay_time);
+            }
+            _ => merged.push(frame),
+        }
+    }
+
+    *frames = merged;
+}
+
+fn encode(gif: &Gif, delta_optimization: bool) -> Result<Vec<u8>, String> {
+    let mut output = vec![];
+    let mut encoder = gif.encoder(&mut output)?;
+    encoder.set_palette_mode(PaletteMode::Auto);
+    encoder.set_delta_optimization(delta_optimization);
+    encoder.write_frames(&gif.image_frames)?;
+    encoder.finish()?;
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Encoder};
+
+    fn animation() -> Vec<u8> {
+        let mut frames = vec![];
+        for i in 0..6 {
+            let mut colors = (0..64 * 64)
+                .map(|p| Color((p % 8) as u8 * 30, (p / 512) as u8 * 30, 0))
+                .collect::<Vec<_>>();
+            colors[i / 2] = Color(255, 255, 255);
+            frames.push(ImageFrame {
+                width: 64,
+                height: 64,
+                colors: colors.into_boxed_slice(),
+                delay_time: 10,
+                placeholder: false,
+            });
+        }
+
+        let mut output = vec![];
+        let mut encoder = Encoder::new(&mut output, 64, 64);
+        encoder.set_palette_mode(PaletteMode::Local);
+        encoder.write_frames(&frames).unwrap();
+        encoder.finish().unwrap();
+        output
+    }
+
+    #[test]
+    fn test_optimize() {
+        let input = animation();
+        let original = crate::load(&mut input.as_slice()).unwrap();
+
+        let mut output = vec![];
+        let report = optimize(
+            &mut input.as_slice(),
+            &mut output,
+            &OptimizeOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(input.len(), report.original_size);
+        assert_eq!(output.len(), report.optimized_size);
+        assert!(report.optimized_size < report.original_size);
+        assert_eq!(6, report.original_frame_count);
+        assert_eq!(3, report.optimized_frame_count);
+
+        let optimized = crate::load(&mut output.as_slice()).unwrap();
+        for (i, frame) in optimized.image_frames.iter().enumerate() {
+            assert_eq!(original.image_frames[i * 2].colors, frame.colors);
+            assert_eq!(20, frame.delay_time);
+        }
+    }
+
+    #[test]
+    fn test_optimize_keeps_comments() {
+        let mut gif = crate::load(&mut animation().as_slice()).unwrap();
+        gif.comments = vec!["made by hand".into(), "CC0".into()];
+        let input = gif.to_bytes().unwrap();
+
+        let mut output = vec![];
+        optimize(
+            &mut input.as_slice(),
+            &mut output,
+            &OptimizeOptions::default(),
+        )
+        .unwrap();
+        let optimized = crate::load(&mut output.as_slice()).unwrap();
+        assert_eq!(gif.comments, optimized.comments);
+    }
+
+    #[test]
+    fn test_optimize_lossy() {
+        // A flat frame with noise that changes every frame.
+        let frames = (0..4u8)
+            .map(|i| ImageFrame {
+                width: 32,
+                height: 32,
+                colors: (0..32 * 32u32)
+                    .map(|p| {
+                        let noise = (p * 7 + i as u32 * 13).is_multiple_of(5);
+                        Color(100 + noise as u8 * 3, 100, 100)
+                    })
+                    .collect(),
+                delay_time: 10,
+                placeholder: false,
+            })
+            .collect::<Vec<_>>();
+        let mut input = vec![];
+        let mut encoder = Encoder::new(&mut input, 32, 32);
+        encoder.write_frames(&frames).unwrap();
+        encoder.finish().unwrap();
+
+        let mut lossless = vec![];
+        optimize(
+            &mut input.as_slice(),
+            &mut lossless,
+            &OptimizeOptions::default(),
+        )
+        .unwrap();
+
+        let options = OptimizeOptions {
+            lossy: 4,
+            ..OptimizeOptions::default()
+        };
+        let mut lossy = vec![];
+        let report = optimize(&mut input.as_slice(), &mut lossy, &options).unwrap();
+        assert!(lossy.len() < lossless.len());
+        // Every frame looks like the first one.
+        assert_eq!(1, report.optimized_frame_count);
+    }
+
+    #[test]
+    fn test_optimize_never_grows() {
+        let input = animation();
+        let mut optimized = vec![];
+        optimize(
+            &mut input.as_slice(),
+            &mut optimized,
+            &OptimizeOptions::default(),
+        )
+        .unwrap();
+
+        let mut output = vec![];
+        let report = optimize(
+            &mut optimized.as_slice(),
+            &mut output,
+            &OptimizeOptions::default(),
+        )
+        .unwrap();
+
+        assert!(report.optimized_size <= report.original_size);
+        assert!(output.len() <= optimized.len());
+    }
+}
@@ -1,25 +1,40 @@
+use crate::observer::DecodeObserver;
 use crate::util::Color;
+use crate::writer::Writer;
 
 use std::io::Read;
-use std::mem;
-
-#[derive(Debug)]
-pub(crate) struct Header {
-    pub(crate) sig: String,
-    pub(crate) version: String,
+use std::ops::ControlFlow;
+
+/// The header at the start of every GIF.
+#[derive(Debug, Clone)]
+pub struct Header {
+    /// The signature, `"GIF"`.
+    pub sig: String,
+    /// The version, `"87a"` or `"89a"`.
+    pub version: String,
 }
 
-#[derive(Debug)]
-pub(crate) struct LogicalScreenDescriptor {
-    pub(crate) width: u16,
-    pub(crate) height: u16,
-    pub(crate) global_color_table_flag: bool,
-    pub(crate) color_resolution: u8,
-    pub(crate) sort_flag: bool,
-    pub(crate) global_color_table_size: u8,
-    pub(crate) background_color_index: u8,
-    pub(crate) pixel_aspect_ratio: f32,
-    pub(crate) global_color_table: Option<Vec<Color>>,
+/// The size of the canvas and the global color table, which follow the header.
+#[derive(Debug, Clone)]
+pub struct LogicalScreenDescriptor {
+    /// The width of the canvas in pixels.
+    pub width: u16,
+    /// The height of the canvas in pixels.
+    pub height: u16,
+    /// Whether there is a global color table.
+    pub global_color_table_flag: bool,
+    /// The number of bits per primary color of the original image, minus 1.
+    pub color_resolution: u8,
+    /// Whether the global color table is sorted by decreasing importance.
+    pub sort_flag: bool,
+    /// The global color table holds `2^(global_color_table_size + 1)` colors.
+    pub global_color_table_size: u8,
+    /// The index of the background color in the global color table.
+    pub background_color_index: u8,
+    /// The aspect ratio of a pixel, width over height, or 0 if it is not given.
+    pub pixel_aspect_ratio: f32,
+    /// The global color table, if there is one.
+    pub global_color_table: Option<Vec<Color>>,
 }
 
 #[derive(Debug)]
@@ -39,252 +54,879 @@ enum ExtensionType {
     Unknown(u8),
 }
 
-#[derive(Debug)]
-pub(crate) enum DataType {
+/// A block after the logical screen descriptor. Graphic control extensions are not blocks of
+/// their own, but part of the image or plain text extension they apply to.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone)]
+pub enum DataType {
+    /// An application extension.
     ApplicationExtensionType(ApplicationExtension),
+    /// A comment extension.
     CommentExtensionType(CommentExtension),
+    /// A plain text extension.
     PlainTextExtensionType(PlainTextExtension),
+    /// An image.
     TableBasedImageType(TableBasedImage),
 }
 
-#[derive(Debug)]
-pub(crate) struct GraphicControlExtension {
-    pub(crate) disposal_method: DisposalMethod,
-    pub(crate) user_input_expected: bool,
-    pub(crate) transparent_color_index_available: bool,
-    pub(crate) delay_time: u16,
-    pub(crate) transparent_color_index: u8,
+/// How a graphic rendering block is shown, such as for how long.
+#[derive(Debug, Clone)]
+pub struct GraphicControlExtension {
+    /// What to do with the graphic once it has been shown.
+    pub disposal_method: DisposalMethod,
+    /// Whether the animation should wait for user input before moving on.
+    pub user_input_expected: bool,
+    /// Whether `transparent_color_index` is used.
+    pub transparent_color_index_available: bool,
+    /// How long the graphic stays on screen, in hundredths of a second.
+    pub delay_time: u16,
+    /// The color index that leaves the pixel below unchanged.
+    pub transparent_color_index: u8,
 }
 
+/// What to do with a graphic once it has been shown.
 #[derive(Debug, Copy, Clone, PartialEq)]
-pub(crate) enum DisposalMethod {
+pub enum DisposalMethod {
+    /// No disposal is given.
     Unspecified,
+    /// Leave the graphic in place.
     DoNotDispose,
+    /// Restore the area of the graphic to the background color.
     RestoreToBackgroundColor,
+    /// Restore the area of the graphic to what it was before.
     RestoreToPrevious,
+    /// One of the values the GIF89a specification leaves undefined.
     Undefined,
 }
 
-#[derive(Debug)]
-pub(crate) struct TableBasedImage {
-    pub(crate) graphic_control_extension: Option<GraphicControlExtension>,
-    pub(crate) image_descriptor: ImageDescriptor,
-    pub(crate) local_color_table: Option<Vec<Color>>,
-    pub(crate) image_data: ImageData,
+impl DisposalMethod {
+    // The value stored in a graphic control extension, where the undefined ones are written
+    // as 4.
+    pub(crate) fn value(self) -> u8 {
+        match self {
+            DisposalMethod::Unspecified => 0,
+            DisposalMethod::DoNotDispose => 1,
+            DisposalMethod::RestoreToBackgroundColor => 2,
+            DisposalMethod::RestoreToPrevious => 3,
+            DisposalMethod::Undefined => 4,
+        }
+    }
 }
 
-#[derive(Debug)]
-pub(crate) struct ImageDescriptor {
-    pub(crate) left: u16,
-    pub(crate) top: u16,
-    pub(crate) width: u16,
-    pub(crate) height: u16,
-    pub(crate) local_color_table_flag: bool,
-    pub(crate) interlace_flag: bool,
-    pub(crate) sort_flag: bool,
-    pub(crate) local_color_table_size: u8,
+/// An image, with its descriptor, local color table and compressed data.
+#[derive(Debug, Clone)]
+pub struct TableBasedImage {
+    /// The graphic control extension that applies to the image, if any.
+    pub graphic_control_extension: Option<GraphicControlExtension>,
+    /// Where the image is on the canvas, and how it is stored.
+    pub image_descriptor: ImageDescriptor,
+    /// The local color table, if there is one.
+    pub local_color_table: Option<Vec<Color>>,
+    /// The LZW compressed color indices.
+    pub image_data: ImageData,
 }
 
-#[derive(Debug)]
-pub(crate) struct ImageData {
-    pub(crate) lzw_min_code_size: u8,
-    pub(crate) data_sub_blocks: Vec<u8>,
+/// Where an image is on the canvas, and how it is stored.
+#[derive(Debug, Clone)]
+pub struct ImageDescriptor {
+    /// The column of the left edge of the image on the canvas.
+    pub left: u16,
+    /// The row of the top edge of the image on the canvas.
+    pub top: u16,
+    /// The width of the image in pixels.
+    pub width: u16,
+    /// The height of the image in pixels.
+    pub height: u16,
+    /// Whether there is a local color table.
+    pub local_color_table_flag: bool,
+    /// Whether the rows are stored in the four pass interlaced order.
+    pub interlace_flag: bool,
+    /// Whether the local color table is sorted by decreasing importance.
+    pub sort_flag: bool,
+    /// The local color table holds `2^(local_color_table_size + 1)` colors.
+    pub local_color_table_size: u8,
 }
 
-#[derive(Debug)]
-pub(crate) struct PlainTextExtension {
-    pub(crate) graphic_control_extension: Option<GraphicControlExtension>,
-    pub(crate) text_grid_left_pos: u16,
-    pub(crate) text_grid_top_pos: u16,
-    pub(crate) text_grid_width: u16,
-    pub(crate) text_grid_height: u16,
-    pub(crate) char_cell_width: u8,
-    pub(crate) char_cell_height: u8,
-    pub(crate) text_fg_color_index: u8,
-    pub(crate) text_bg_color_index: u8,
-    pub(crate) plain_text_data: String,
+/// The LZW compressed color indices of an image.
+#[derive(Debug, Clone)]
+pub struct ImageData {
+    /// The number of bits of the initial codes, minus 1.
+    pub lzw_min_code_size: u8,
+    /// The compressed data, with the data sub-blocks joined together.
+    pub data_sub_blocks: Vec<u8>,
 }
 
-#[derive(Debug)]
-pub(crate) struct ApplicationExtension {
-    pub(crate) id: String,
-    pub(crate) auth_code: String,
-    pub(crate) data_sub_blocks: Vec<u8>,
+/// Text to draw on the canvas, which hardly any decoder supports.
+#[derive(Debug, Clone)]
+pub struct PlainTextExtension {
+    /// The graphic control extension that applies to the text, if any.
+    pub graphic_control_extension: Option<GraphicControlExtension>,
+    /// The column of the left edge of the text grid.
+    pub text_grid_left_pos: u16,
+    /// The row of the top edge of the text grid.
+    pub text_grid_top_pos: u16,
+    /// The width of the text grid in pixels.
+    pub text_grid_width: u16,
+    /// The height of the text grid in pixels.
+    pub text_grid_height: u16,
+    /// The width of a character cell in pixels.
+    pub char_cell_width: u8,
+    /// The height of a character cell in pixels.
+    pub char_cell_height: u8,
+    /// The index of the text color in the global color table.
+    pub text_fg_color_index: u8,
+    /// The index of the background color in the global color table.
+    pub text_bg_color_index: u8,
+    /// The text.
+    pub plain_text_data: String,
+}
+
+/// Data for a particular application, such as the loop count of the NETSCAPE2.0 extension.
+#[derive(Debug, Clone)]
+pub struct ApplicationExtension {
+    /// The application identifier, 8 characters.
+    pub id: String,
+    /// The application authentication code, 3 characters.
+    pub auth_code: String,
+    /// The data, with the data sub-blocks joined together.
+    pub data_sub_blocks: Vec<u8>,
+    /// The size of every data sub-block, in file order, which some extensions such as XMP
+    /// depend on. Empty for an extension that was not parsed, whose data is taken as split
+    /// into sub-blocks of 255 bytes.
+    pub sub_block_sizes: Vec<u8>,
+}
+
+impl ApplicationExtension {
+    // The NETSCAPE2.0 extension holds the number of times the animation should loop.
+    pub(crate) fn netscape_looping(loop_count: u16) -> Self {
+        let [lo, hi] = loop_count.to_le_bytes();
+        Self {
+            id: "NETSCAPE".into(),
+            auth_code: "2.0".into(),
+            data_sub_blocks: vec![1, lo, hi],
+            sub_block_sizes: vec![3],
+        }
+    }
+
+    /// The data sub-blocks as they were stored, as split by
+    /// [`sub_block_sizes`](Self::sub_block_sizes) when they add up to the data.
+    pub fn sub_blocks(&self) -> Vec<&[u8]> {
+        let total: usize = self.sub_block_sizes.iter().map(|&n| n as usize).sum();
+        if total != self.data_sub_blocks.len() {
+            return self.data_sub_blocks.chunks(255).collect();
+        }
+        let mut rest = &self.data_sub_blocks[..];
+        self.sub_block_sizes
+            .iter()
+            .map(|&n| {
+                let (block, tail) = rest.split_at(n as usize);
+                rest = tail;
+                block
+            })
+            .collect()
+    }
+
+    /// The loop count if this is a NETSCAPE2.0 extension, where 0 means loop forever.
+    pub fn loop_count(&self) -> Option<u16> {
+        if self.id == "NETSCAPE"
+            && self.auth_code == "2.0"
+            && self.data_sub_blocks.len() >= 3
+            && self.data_sub_blocks[0] == 1
+        {
+            Some(u16::from_le_bytes([
+                self.data_sub_blocks[1],
+                self.data_sub_blocks[2],
+            ]))
+        } else {
+            None
+        }
+    }
+}
+
+/// A comment.
+#[derive(Debug, Clone)]
+pub struct CommentExtension {
+    /// The text of the comment.
+    pub text: String,
 }
 
+/// Every block of a GIF, in file order.
+#[derive(Debug, Clone)]
+pub struct ParseResult {
+    /// The header.
+    pub header: Header,
+    /// The logical screen descriptor.
+    pub logical_screen_descriptor: LogicalScreenDescriptor,
+    /// The blocks between the logical screen descriptor and the trailer.
+    pub data_blocks: Vec<DataType>,
+}
+
+// What `Parser::feed` found in the data, in the order it appears.
 #[derive(Debug)]
-pub(crate) struct CommentExtension {
-    pub(crate) text: String,
+pub(crate) enum Event {
+    Header(Header),
+    LogicalScreenDescriptor(LogicalScreenDescriptor),
+    Block(DataType),
+    Trailer,
 }
 
 #[derive(Debug)]
-pub(crate) struct ParseResult {
-    pub(crate) header: Header,
-    pub(crate) logical_screen_descriptor: LogicalScreenDescriptor,
-    pub(crate) data_blocks: Vec<DataType>,
+enum State {
+    Header,
+    LogicalScreenDescriptor,
+    Blocks,
+    Done,
 }
 
+// A block whose data sub-blocks are being read.
 #[derive(Debug)]
-pub(crate) struct Parser<'a, T: Read> {
-    src: &'a mut T,
+enum Pending {
+    // Keeps the size of every data sub-block read so far.
+    ApplicationExtension {
+        id: String,
+        auth_code: String,
+        sizes: Vec<u8>,
+    },
+    CommentExtension,
+    PlainTextExtension(PlainTextExtension),
+    // Fails once the data sub-blocks exceed the given number of bytes.
+    Image(TableBasedImage, usize),
+}
+
+// Why reading from the buffered data stopped.
+enum Stop {
+    // The data ran out, and at least this many more bytes are needed.
+    NeedMore(usize),
+    Error(String),
+}
+
+impl From<String> for Stop {
+    fn from(e: String) -> Self {
+        Stop::Error(e)
+    }
+}
+
+// Parses a GIF from data fed to it in chunks of any size, without doing any I/O itself. Reading
+// from a `Read` (`parse`) and pushing chunks (`StreamDecoder`) both drive this state machine.
+pub(crate) struct Parser<'a> {
+    observer: Option<&'a mut dyn DecodeObserver>,
+    state: State,
+    // The data fed so far, of which the first `pos` bytes have been consumed.
+    buffer: Vec<u8>,
+    pos: usize,
+    // The number of bytes consumed so far, and where the block being read starts.
+    offset: usize,
+    block_offset: usize,
+    // A Graphic Control Extension waiting for the graphic rendering block it applies to.
+    graphic_control_extension: Option<GraphicControlExtension>,
+    // The block whose data sub-blocks are being read, and the data read so far.
+    pending: Option<Pending>,
+    sub_blocks: Vec<u8>,
+    // The smallest number of bytes that must be fed before the parser can move on.
+    bytes_needed: usize,
+    // The error that stopped parsing, returned again by every later `feed`.
+    error: Option<String>,
+    // Whether deviations from the specification that can be read past are errors, and the
+    // size of the logical screen to check images against.
+    strict: bool,
+    screen: Option<(u16, u16)>,
 }
 
-impl<'a, T: Read> Parser<'a, T> {
-    pub(crate) fn new(src: &'a mut T) -> Self {
-        Self { src }
+impl std::fmt::Debug for Parser<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Parser")
+            .field("state", &self.state)
+            .field("offset", &self.offset)
+            .field("pending", &self.pending)
+            .field("bytes_needed", &self.bytes_needed)
+            .field("error", &self.error)
+            .field("strict", &self.strict)
+            .finish_non_exhaustive()
     }
+}
 
-    pub(crate) fn parse(&mut self) -> Result<ParseResult, String> {
-        let header = self.read_header()?;
-        if header.sig != "GIF" {
-            return Err("Error: file is not a GIF".into());
+impl<'a> Parser<'a> {
+    pub(crate) fn new() -> Self {
+        Self {
+            observer: None,
+            state: State::Header,
+            buffer: Vec::new(),
+            pos: 0,
+            offset: 0,
+            block_offset: 0,
+            graphic_control_extension: None,
+            pending: None,
+            sub_blocks: Vec::new(),
+            // The size of the header, the first thing to read.
+            bytes_needed: 6,
+            error: None,
+            strict: false,
+            screen: None,
         }
+    }
 
-        let logical_screen_descriptor = self.read_logical_screen_descriptor()?;
+    // Fail on every deviation from the specification, such as an image that does not fit in
+    // the logical screen, instead of reading past it.
+    pub(crate) fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    // Like `new`, but starts right after the logical screen descriptor, such as for reading
+    // blocks found through an index.
+    pub(crate) fn for_blocks() -> Self {
+        Self {
+            state: State::Blocks,
+            bytes_needed: 1,
+            ..Self::new()
+        }
+    }
+
+    // Like `new`, but reports every block read to `observer`.
+    pub(crate) fn with_observer(observer: &'a mut dyn DecodeObserver) -> Self {
+        Self {
+            observer: Some(observer),
+            ..Self::new()
+        }
+    }
 
+    pub(crate) fn parse<R: Read>(&mut self, src: &mut R) -> Result<ParseResult, String> {
+        match self.parse_partial(src)? {
+            (result, None) => Ok(result),
+            (_, Some(e)) => Err(e),
+        }
+    }
+
+    // Parse the data blocks up to the first error instead of failing, returning that error
+    // along with the blocks read before it. If the error happened while reading image data,
+    // the image is kept with the data read so far. Only a broken header or logical screen
+    // descriptor is fatal.
+    pub(crate) fn parse_partial<R: Read>(
+        &mut self,
+        src: &mut R,
+    ) -> Result<(ParseResult, Option<String>), String> {
+        let mut header = None;
+        let mut logical_screen_descriptor = None;
         let mut data_blocks = Vec::new();
-        loop {
-            match self.read_block_type()? {
-                BlockType::TableBasedImage => {
-                    let table_based_image = self.read_table_based_image(None)?;
-                    data_blocks.push(DataType::TableBasedImageType(table_based_image));
+
+        let error = self
+            .read_events(src, |event| {
+                match event {
+                    Event::Header(h) => header = Some(h),
+                    Event::LogicalScreenDescriptor(lsd) => logical_screen_descriptor = Some(lsd),
+                    Event::Block(block) => data_blocks.push(block),
+                    Event::Trailer => (),
                 }
+                Ok(ControlFlow::Continue(()))
+            })
+            .err();
 
-                BlockType::Extension(extension_type) => match extension_type {
-                    ExtensionType::ApplicationExtension => {
-                        let ext = self.read_application_extension()?;
-                        data_blocks.push(DataType::ApplicationExtensionType(ext));
-                    }
+        let (header, logical_screen_descriptor) = match (header, logical_screen_descriptor) {
+            (Some(header), Some(lsd)) => (header, lsd),
+            _ => return Err(error.unwrap_or_default()),
+        };
+        if let Some(image) = self.take_truncated_image() {
+            data_blocks.push(DataType::TableBasedImageType(image));
+        }
+        if let Some(ref e) = error {
+            warn!("stopped parsing at {:#x}: {}", self.block_offset, e);
+        }
 
-                    ExtensionType::CommentExtension => {
-                        let ext = self.read_comment_extension()?;
-                        data_blocks.push(DataType::CommentExtensionType(ext));
-                    }
+        Ok((
+            ParseResult {
+                header,
+                logical_screen_descriptor,
+                data_blocks,
+            },
+            error,
+        ))
+    }
 
-                    ExtensionType::GraphicControlExtension => {
-                        let mut graphic_control_extension =
-                            Some(self.read_graphic_control_extension()?);
-
-                        // Ref: https://www.w3.org/Graphics/GIF/spec-gif89a.txt
-                        // The scope of this Extension is the graphic
-                        // rendering block that follows it; ** it is possible for other extensions to
-                        // be present between this block and its target **. This block can modify the
-                        // Image Descriptor Block and the Plain Text Extension.
-
-                        let next_block_type: Result<BlockType, String> = loop {
-                            let block_type = self.read_block_type()?;
-                            match block_type {
-                                BlockType::Extension(ref extension_type) => match extension_type {
-                                    ExtensionType::ApplicationExtension => {
-                                        let ext = self.read_application_extension()?;
-                                        data_blocks.push(DataType::ApplicationExtensionType(ext));
-                                    }
-
-                                    ExtensionType::CommentExtension => {
-                                        let ext = self.read_comment_extension()?;
-                                        data_blocks.push(DataType::CommentExtensionType(ext));
-                                    }
-
-                                    ExtensionType::GraphicControlExtension => {
-                                        graphic_control_extension
-                                            .replace(self.read_graphic_control_extension()?);
-                                    }
-
-                                    _ => break Ok(block_type),
-                                },
-
-                                BlockType::Unknown(x) => {
-                                    return Err(format!("Error: unknown block type: {:x}", x));
-                                }
-
-                                _ => break Ok(block_type),
-                            }
-                        };
-
-                        match next_block_type? {
-                            BlockType::TableBasedImage => {
-                                let table_based_image =
-                                    self.read_table_based_image(graphic_control_extension)?;
-                                data_blocks.push(DataType::TableBasedImageType(table_based_image));
-                            }
-
-                            BlockType::Extension(extension_type) => match extension_type {
-                                ExtensionType::PlainTextExtension => {
-                                    let ext =
-                                        self.read_plain_text_extension(graphic_control_extension)?;
-                                    data_blocks.push(DataType::PlainTextExtensionType(ext));
-                                }
-
-                                ExtensionType::Unknown(x) => {
-                                    return Err(format!("Error: unknown extension type: {:x}", x));
-                                }
-
-                                x => {
-                                    return Err(format!("Error: unknown extension type: {:?}", x));
-                                }
-                            },
-
-                            BlockType::Unknown(x) => {
-                                return Err(format!("Error: unknown block type: {:x}", x));
-                            }
-
-                            x => {
-                                return Err(format!("Error: unknown block type: {:?}", x));
-                            }
-                        }
-                    }
+    // Feed the data in `src` until the trailer, handing every event to `f`, and stop at the
+    // first error or once `f` breaks. Reading only as much as the parser needs leaves `src`
+    // right after the trailer.
+    pub(crate) fn read_events<R: Read>(
+        &mut self,
+        src: &mut R,
+        mut f: impl FnMut(Event) -> Result<ControlFlow<()>, String>,
+    ) -> Result<(), String> {
+        let mut buffer = Vec::new();
+        while !self.is_done() {
+            buffer.resize(self.bytes_needed(), 0);
+            src.read_exact(&mut buffer)
+                .map_err(|e| format!("Error: {}", e))?;
+            for event in self.feed(&buffer)? {
+                if f(event)?.is_break() {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Append `data` to the data fed so far and parse as much of it as possible, returning
+    // every header, descriptor and block completed by it. Data after the trailer is ignored.
+    //
+    // After an error, the events completed before it are returned, and the error is returned
+    // by the next call and every one after it.
+    pub(crate) fn feed(&mut self, data: &[u8]) -> Result<Vec<Event>, String> {
+        if let Some(ref e) = self.error {
+            return Err(e.clone());
+        }
+        self.buffer.extend_from_slice(data);
 
-                    ExtensionType::PlainTextExtension => {
-                        let ext = self.read_plain_text_extension(None)?;
-                        data_blocks.push(DataType::PlainTextExtensionType(ext));
+        let mut events = Vec::new();
+        while !self.is_done() {
+            let step = if self.pending.is_some() {
+                self.read_sub_block()
+            } else {
+                match self.state {
+                    State::Header => self.read_header(),
+                    State::LogicalScreenDescriptor => self.read_logical_screen_descriptor(),
+                    State::Blocks => self.read_block(),
+                    State::Done => unreachable!(),
+                }
+            };
+            match step {
+                Ok(event) => events.extend(event),
+                Err(Stop::NeedMore(len)) => {
+                    self.bytes_needed = len;
+                    break;
+                }
+                Err(Stop::Error(e)) => {
+                    self.error = Some(e);
+                    break;
+                }
+            }
+        }
+
+        if self.is_done() {
+            self.buffer.clear();
+        } else {
+            self.buffer.drain(..self.pos);
+        }
+        self.pos = 0;
+
+        match self.error {
+            Some(ref e) if events.is_empty() => Err(e.clone()),
+            _ => Ok(events),
+        }
+    }
+
+    // The smallest number of bytes that must be fed before the next event, or before the next
+    // data sub-block is read. 0 once the trailer has been read or parsing failed.
+    pub(crate) fn bytes_needed(&self) -> usize {
+        if self.is_done() || self.error.is_some() {
+            0
+        } else {
+            self.bytes_needed
+        }
+    }
+
+    // The error that stopped parsing, if any.
+    pub(crate) fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    // The data that takes a parser that has just read the same header and logical screen
+    // descriptor to where this one is: the blocks it is in the middle of, followed by the data
+    // it has not consumed yet.
+    pub(crate) fn unfinished(&self) -> Result<Vec<u8>, String> {
+        if let Some(ref e) = self.error {
+            return Err(e.clone());
+        }
+        if self.is_done() {
+            return Ok(vec![0x3b]);
+        }
+
+        let mut data = vec![];
+        let mut writer = Writer::new(&mut data);
+        if let Some(ref ext) = self.graphic_control_extension {
+            writer.write_graphic_control_extension(ext)?;
+        }
+        if let Some(ref pending) = self.pending {
+            // The block is written without its data, which is still in `sub_blocks`.
+            match pending {
+                Pending::ApplicationExtension { id, auth_code, .. } => writer
+                    .write_application_extension(&ApplicationExtension {
+                        id: id.clone(),
+                        auth_code: auth_code.clone(),
+                        data_sub_blocks: vec![],
+                        sub_block_sizes: vec![],
+                    })?,
+                Pending::CommentExtension => writer.write_comment_extension(&CommentExtension {
+                    text: String::new(),
+                })?,
+                Pending::PlainTextExtension(ext) => writer.write_plain_text_extension(ext)?,
+                Pending::Image(image, _) => writer.write_table_based_image(image)?,
+            }
+            // The block terminator, as the block is not finished.
+            data.pop();
+            for sub_block in self.sub_blocks.chunks(255) {
+                data.push(sub_block.len() as u8);
+                data.extend_from_slice(sub_block);
+            }
+        }
+        data.extend_from_slice(&self.buffer[self.pos..]);
+        Ok(data)
+    }
+
+    // Whether the trailer has been read.
+    pub(crate) fn is_done(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    // The image whose data sub-blocks were being read when the data ran out or parsing failed,
+    // with the data read so far.
+    pub(crate) fn take_truncated_image(&mut self) -> Option<TableBasedImage> {
+        match self.pending.take() {
+            Some(Pending::Image(mut image, _)) => {
+                image.image_data.data_sub_blocks = std::mem::take(&mut self.sub_blocks);
+                Some(image)
+            }
+            _ => None,
+        }
+    }
+
+    // Reports the block that started at `block_offset` and ends here.
+    fn block_parsed(&mut self, name: &str) {
+        if let Some(ref mut observer) = self.observer {
+            observer.block_parsed(name, self.block_offset, self.offset - self.block_offset);
+        }
+    }
+
+    // A cursor over the data that has not been consumed yet.
+    fn cursor(&self) -> Cursor<'_> {
+        Cursor {
+            data: &self.buffer[self.pos..],
+            pos: 0,
+        }
+    }
+
+    fn consume(&mut self, len: usize) {
+        self.pos += len;
+        self.offset += len;
+    }
+
+    // Consume the first `len` bytes of a new block.
+    fn start_block(&mut self, len: usize) {
+        self.block_offset = self.offset;
+        self.consume(len);
+    }
+
+    fn read_header(&mut self) -> Result<Option<Event>, Stop> {
+        let mut cursor = self.cursor();
+        let header = cursor.header()?;
+        if header.sig != "GIF" {
+            return Err("Error: file is not a GIF".to_string().into());
+        }
+        if self.strict && header.version != "87a" && header.version != "89a" {
+            return Err(format!("Error: unknown GIF version: {}", header.version).into());
+        }
+
+        let len = cursor.pos;
+        self.start_block(len);
+        self.block_parsed("Header");
+        self.state = State::LogicalScreenDescriptor;
+        Ok(Some(Event::Header(header)))
+    }
+
+    fn read_logical_screen_descriptor(&mut self) -> Result<Option<Event>, Stop> {
+        let mut cursor = self.cursor();
+        let lsd = cursor.logical_screen_descriptor()?;
+        debug!(
+            "{}x{}, global color table: {}",
+            lsd.width,
+            lsd.height,
+            lsd.global_color_table
+                .as_ref()
+                .map(|t| t.len())
+                .unwrap_or(0)
+        );
+
+        let len = cursor.pos;
+        self.start_block(len);
+        self.screen = Some((lsd.width, lsd.height));
+        self.block_parsed("Logical Screen Descriptor");
+        self.state = State::Blocks;
+        Ok(Some(Event::LogicalScreenDescriptor(lsd)))
+    }
+
+    // Reads a whole block without data sub-blocks, or the start of one with data sub-blocks.
+    fn read_block(&mut self) -> Result<Option<Event>, Stop> {
+        let mut cursor = self.cursor();
+        let pending = match cursor.block_type()? {
+            BlockType::TableBasedImage => {
+                let (image_descriptor, local_color_table, lzw_min_code_size) =
+                    cursor.image_header()?;
+                if self.strict {
+                    self.check_image(&image_descriptor, lzw_min_code_size)?;
+                }
+                let len = cursor.pos;
+                self.start_block(len);
+                trace!(
+                    "{:#x}: image {}x{} at ({}, {})",
+                    self.block_offset,
+                    image_descriptor.width,
+                    image_descriptor.height,
+                    image_descriptor.left,
+                    image_descriptor.top
+                );
+
+                // Every pixel takes at most a 12 bit code and a clear code, so anything beyond
+                // that is not image data.
+                let max_len =
+                    3 * image_descriptor.width as usize * image_descriptor.height as usize + 1024;
+                let image = TableBasedImage {
+                    graphic_control_extension: self.graphic_control_extension.take(),
+                    image_descriptor,
+                    local_color_table,
+                    image_data: ImageData {
+                        lzw_min_code_size,
+                        data_sub_blocks: Vec::new(),
+                    },
+                };
+                Pending::Image(image, max_len)
+            }
+
+            BlockType::Extension(extension_type) => match extension_type {
+                ExtensionType::ApplicationExtension => {
+                    let (id, auth_code) = cursor.application_extension_header()?;
+                    let len = cursor.pos;
+                    self.start_block(len);
+                    trace!(
+                        "{:#x}: application extension {}{}",
+                        self.block_offset,
+                        id,
+                        auth_code
+                    );
+                    Pending::ApplicationExtension {
+                        id,
+                        auth_code,
+                        sizes: vec![],
                     }
+                }
+
+                ExtensionType::CommentExtension => {
+                    let len = cursor.pos;
+                    self.start_block(len);
+                    trace!("{:#x}: comment extension", self.block_offset);
+                    Pending::CommentExtension
+                }
 
-                    ExtensionType::Unknown(x) => {
-                        return Err(format!("Error: unknown extension type: {:x}", x));
+                ExtensionType::GraphicControlExtension => {
+                    // Ref: https://www.w3.org/Graphics/GIF/spec-gif89a.txt
+                    // The scope of this Extension is the graphic
+                    // rendering block that follows it; ** it is possible for other extensions to
+                    // be present between this block and its target **. This block can modify the
+                    // Image Descriptor Block and the Plain Text Extension.
+                    let ext = cursor.graphic_control_extension()?;
+                    if self.strict && matches!(ext.disposal_method, DisposalMethod::Undefined) {
+                        return Err(format!(
+                            "Error: Graphic Control Extension at {:#x} has a reserved disposal \
+                             method",
+                            self.offset
+                        )
+                        .into());
                     }
-                },
+                    if self.strict && self.graphic_control_extension.is_some() {
+                        return Err(format!(
+                            "Error: Graphic Control Extension at {:#x} follows another one \
+                             without a graphic rendering block in between",
+                            self.offset
+                        )
+                        .into());
+                    }
+                    let len = cursor.pos;
+                    self.start_block(len);
+                    trace!("{:#x}: graphic control extension", self.block_offset);
+                    self.block_parsed("Graphic Control Extension");
+                    self.graphic_control_extension = Some(ext);
+                    return Ok(None);
+                }
+
+                ExtensionType::PlainTextExtension => {
+                    let mut ext = cursor.plain_text_extension_header()?;
+                    let len = cursor.pos;
+                    self.start_block(len);
+                    trace!("{:#x}: plain text extension", self.block_offset);
+                    ext.graphic_control_extension = self.graphic_control_extension.take();
+                    Pending::PlainTextExtension(ext)
+                }
 
-                BlockType::Trailer => break,
+                ExtensionType::Unknown(x) => {
+                    return Err(format!("Error: unknown extension type: {:x}", x).into());
+                }
+            },
 
-                BlockType::Unknown(x) => {
-                    return Err(format!("Error: unknown block type: {:x}", x));
+            BlockType::Trailer => {
+                if self.graphic_control_extension.is_some() {
+                    return Err("Error: unknown block type: Trailer".to_string().into());
                 }
+                let len = cursor.pos;
+                self.start_block(len);
+                trace!("{:#x}: trailer", self.block_offset);
+                self.block_parsed("Trailer");
+                self.state = State::Done;
+                return Ok(Some(Event::Trailer));
+            }
+
+            BlockType::Unknown(x) => {
+                return Err(format!("Error: unknown block type: {:x}", x).into());
+            }
+        };
+
+        self.pending = Some(pending);
+        Ok(None)
+    }
+
+    // The checks of strict mode on an image at the start of the unconsumed data.
+    fn check_image(&self, desc: &ImageDescriptor, lzw_min_code_size: u8) -> Result<(), Stop> {
+        if let Some((width, height)) = self.screen {
+            if desc.left as usize + desc.width as usize > width as usize
+                || desc.top as usize + desc.height as usize > height as usize
+            {
+                return Err(format!(
+                    "Error: image at {:#x} of size {}x{} at ({}, {}) does not fit in {}x{}",
+                    self.offset, desc.width, desc.height, desc.left, desc.top, width, height
+                )
+                .into());
             }
         }
+        if !(2..=8).contains(&lzw_min_code_size) {
+            return Err(format!(
+                "Error: image at {:#x} has an invalid LZW minimum code size: {}",
+                self.offset, lzw_min_code_size
+            )
+            .into());
+        }
+        Ok(())
+    }
 
-        Ok(ParseResult {
-            header,
-            logical_screen_descriptor,
-            data_blocks,
-        })
+    // Reads the next data sub-block of the pending block, finishing the block at the block
+    // terminator.
+    fn read_sub_block(&mut self) -> Result<Option<Event>, Stop> {
+        let mut cursor = Cursor {
+            data: &self.buffer[self.pos..],
+            pos: 0,
+        };
+        let block_size = cursor.u8()? as usize;
+        self.sub_blocks.extend_from_slice(cursor.bytes(block_size)?);
+        let len = cursor.pos;
+        self.consume(len);
+        if let Some(Pending::ApplicationExtension { ref mut sizes, .. }) = self.pending {
+            if block_size > 0 {
+                sizes.push(block_size as u8);
+            }
+        }
+
+        match self.pending {
+            // Block terminator value is 0x00
+            _ if block_size == 0 => (),
+            Some(Pending::Image(_, max_len)) if self.sub_blocks.len() > max_len => {
+                return Err(format!("Error: data sub-blocks exceed {} bytes", max_len).into());
+            }
+            _ => return Ok(None),
+        }
+
+        let data = std::mem::take(&mut self.sub_blocks);
+        let block = match self.pending.take() {
+            Some(Pending::ApplicationExtension {
+                id,
+                auth_code,
+                sizes,
+            }) => {
+                self.block_parsed("Application Extension");
+                DataType::ApplicationExtensionType(ApplicationExtension {
+                    id,
+                    auth_code,
+                    data_sub_blocks: data,
+                    sub_block_sizes: sizes,
+                })
+            }
+            Some(Pending::CommentExtension) => {
+                let text = String::from_utf8(data).map_err(|e| format!("Error: {}", e))?;
+                self.block_parsed("Comment Extension");
+                DataType::CommentExtensionType(CommentExtension { text })
+            }
+            Some(Pending::PlainTextExtension(mut ext)) => {
+                ext.plain_text_data =
+                    String::from_utf8(data).map_err(|e| format!("Error: {}", e))?;
+                self.block_parsed("Plain Text Extension");
+                DataType::PlainTextExtensionType(ext)
+            }
+            Some(Pending::Image(mut image, _)) => {
+                image.image_data.data_sub_blocks = data;
+                self.block_parsed("Image");
+                DataType::TableBasedImageType(image)
+            }
+            None => unreachable!(),
+        };
+        Ok(Some(Event::Block(block)))
     }
+}
+
+// Reads the graphic control extension `block`, the bytes of a block the parser reported as
+// one. It is not handed out as a block of its own, but kept for the block it applies to.
+pub(crate) fn read_graphic_control_extension(block: &[u8]) -> Option<GraphicControlExtension> {
+    let mut cursor = Cursor {
+        data: block,
+        pos: 0,
+    };
+    match cursor.block_type().ok()? {
+        BlockType::Extension(ExtensionType::GraphicControlExtension) => {
+            cursor.graphic_control_extension().ok()
+        }
+        _ => None,
+    }
+}
+
+// Reads from the start of the data fed to a `Parser`. Nothing is consumed until the parser
+// consumes `pos` bytes, so a read that runs out of data can be retried once more is fed.
+struct Cursor<'b> {
+    data: &'b [u8],
+    pos: usize,
+}
 
+impl<'b> Cursor<'b> {
     #[inline(always)]
-    fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<(), String> {
-        self.src
-            .read_exact(buffer)
-            .map_err(|e| format!("Error: {}", e))
+    fn bytes(&mut self, len: usize) -> Result<&'b [u8], Stop> {
+        let end = self.pos + len;
+        if end > self.data.len() {
+            return Err(Stop::NeedMore(end - self.data.len()));
+        }
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
     }
 
     #[inline(always)]
-    fn read_u8(&mut self) -> Result<u8, String> {
-        let mut buffer = [0u8; 1];
-        self.read_bytes(&mut buffer)?;
-        Ok(buffer[0])
+    fn u8(&mut self) -> Result<u8, Stop> {
+        Ok(self.bytes(1)?[0])
     }
 
     #[inline(always)]
-    fn read_u16(&mut self) -> Result<u16, String> {
-        let mut buffer = [0u8; 2];
-        self.read_bytes(&mut buffer)?;
-        Ok(unsafe { mem::transmute(buffer) })
+    fn u16(&mut self) -> Result<u16, Stop> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn str(&mut self, len: usize) -> Result<String, Stop> {
+        let bytes = self.bytes(len)?;
+        Ok(std::str::from_utf8(bytes)
+            .map(|s| s.into())
+            .map_err(|e| format!("Error: {}", e))?)
     }
 
-    fn read_block_type(&mut self) -> Result<BlockType, String> {
-        match self.read_u8()? {
+    fn color_table(&mut self, size: u8) -> Result<Vec<Color>, Stop> {
+        let table = self.bytes(3 * (1 << (size + 1)))?;
+        Ok(table.chunks_exact(3).map(|a| a.into()).collect())
+    }
+
+    fn block_type(&mut self) -> Result<BlockType, Stop> {
+        match self.u8()? {
             0x2c => Ok(BlockType::TableBasedImage),
             0x21 => {
-                let extension_type = match self.read_u8()? {
+                let extension_type = match self.u8()? {
                     0xf9 => ExtensionType::GraphicControlExtension,
                     0xfe => ExtensionType::CommentExtension,
                     0x01 => ExtensionType::PlainTextExtension,
@@ -298,22 +940,13 @@ impl<'a, T: Read> Parser<'a, T> {
         }
     }
 
-    fn read_header(&mut self) -> Result<Header, String> {
-        let mut buffer = [0u8; 6];
-        self.read_bytes(&mut buffer)?;
-
-        let sig = std::str::from_utf8(&buffer[0..3])
-            .map(|s| s.into())
-            .map_err(|e| format!("Error: {}", e))?;
-
-        let version = std::str::from_utf8(&buffer[3..])
-            .map(|s| s.into())
-            .map_err(|e| format!("Error: {}", e))?;
-
+    fn header(&mut self) -> Result<Header, Stop> {
+        let sig = self.str(3)?;
+        let version = self.str(3)?;
         Ok(Header { sig, version })
     }
 
-    fn read_logical_screen_descriptor(&mut self) -> Result<LogicalScreenDescriptor, String> {
+    fn logical_screen_descriptor(&mut self) -> Result<LogicalScreenDescriptor, Stop> {
         let mut lsd = LogicalScreenDescriptor {
             width: 0,
             height: 0,
@@ -326,22 +959,22 @@ impl<'a, T: Read> Parser<'a, T> {
             global_color_table: None,
         };
 
-        lsd.width = self.read_u16()?;
-        lsd.height = self.read_u16()?;
+        lsd.width = self.u16()?;
+        lsd.height = self.u16()?;
 
         // Global Color Table Flag       1 Bit
         // Color Resolution              3 Bits
         // Sort Flag                     1 Bit
         // Size of Global Color Table    3 Bits
-        let packed_fields = self.read_u8()?;
+        let packed_fields = self.u8()?;
         lsd.global_color_table_flag = (packed_fields >> 7) == 1;
         lsd.color_resolution = (packed_fields & 0b0111_0000) >> 4;
         lsd.sort_flag = ((packed_fields & 0b0000_1000) >> 3) == 1;
         lsd.global_color_table_size = packed_fields & 0b0000_0111;
 
-        lsd.background_color_index = self.read_u8()?;
+        lsd.background_color_index = self.u8()?;
         lsd.pixel_aspect_ratio = {
-            let val = self.read_u8()?;
+            let val = self.u8()?;
             if val == 0 {
                 val as f32
             } else {
@@ -350,18 +983,13 @@ impl<'a, T: Read> Parser<'a, T> {
         };
 
         if lsd.global_color_table_flag {
-            let size = 3 * (1 << (lsd.global_color_table_size + 1));
-            let mut table = vec![0u8; size];
-            self.read_bytes(&mut table)?;
-
-            let global_color_table = table.chunks_exact(3).map(|a| a.into()).collect::<Vec<_>>();
-            lsd.global_color_table = Some(global_color_table);
+            lsd.global_color_table = Some(self.color_table(lsd.global_color_table_size)?);
         }
 
         Ok(lsd)
     }
 
-    fn read_image_descriptor(&mut self) -> Result<ImageDescriptor, String> {
+    fn image_descriptor(&mut self) -> Result<ImageDescriptor, Stop> {
         let mut image_desc = ImageDescriptor {
             left: 0,
             top: 0,
@@ -373,12 +1001,12 @@ impl<'a, T: Read> Parser<'a, T> {
             local_color_table_size: 0,
         };
 
-        image_desc.left = self.read_u16()?;
-        image_desc.top = self.read_u16()?;
-        image_desc.width = self.read_u16()?;
-        image_desc.height = self.read_u16()?;
+        image_desc.left = self.u16()?;
+        image_desc.top = self.u16()?;
+        image_desc.width = self.u16()?;
+        image_desc.height = self.u16()?;
 
-        let packed_fields = self.read_u8()?;
+        let packed_fields = self.u8()?;
         image_desc.local_color_table_flag = (packed_fields >> 7) == 1;
         image_desc.interlace_flag = ((packed_fields & 0b0100_0000) >> 6) == 1;
         image_desc.sort_flag = ((packed_fields & 0b0010_0000) >> 5) == 1;
@@ -387,101 +1015,45 @@ impl<'a, T: Read> Parser<'a, T> {
         Ok(image_desc)
     }
 
-    fn read_table_based_image(
-        &mut self,
-        graphic_control_extension: Option<GraphicControlExtension>,
-    ) -> Result<TableBasedImage, String> {
-        let image_descriptor = self.read_image_descriptor()?;
+    // The image descriptor, the local color table and the LZW minimum code size.
+    fn image_header(&mut self) -> Result<(ImageDescriptor, Option<Vec<Color>>, u8), Stop> {
+        let image_descriptor = self.image_descriptor()?;
         let local_color_table = if image_descriptor.local_color_table_flag {
-            let size = 3 * (1 << (image_descriptor.local_color_table_size + 1));
-            let mut table = vec![0u8; size];
-            self.read_bytes(&mut table)?;
-            let table = table.chunks_exact(3).map(|a| a.into()).collect::<Vec<_>>();
-            Some(table)
+            Some(self.color_table(image_descriptor.local_color_table_size)?)
         } else {
             None
         };
-
-        let lzw_min_code_size = self.read_u8()?;
-        let data_sub_blocks = self.read_data_sub_blocks()?;
-
-        Ok(TableBasedImage {
-            graphic_control_extension,
-            image_descriptor,
-            local_color_table,
-            image_data: ImageData {
-                lzw_min_code_size,
-                data_sub_blocks,
-            },
-        })
-    }
-
-    fn read_data_sub_blocks(&mut self) -> Result<Vec<u8>, String> {
-        let mut sub_blocks = Vec::new();
-        let mut buffer = [0u8; 256];
-
-        loop {
-            let block_size = self.read_u8()?;
-
-            // Block terminator value is 0x00
-            if block_size == 0 {
-                break;
-            }
-
-            self.read_bytes(&mut buffer[..block_size as usize])?;
-
-            sub_blocks.extend_from_slice(&buffer[..block_size as usize]);
-        }
-
-        Ok(sub_blocks)
+        let lzw_min_code_size = self.u8()?;
+        Ok((image_descriptor, local_color_table, lzw_min_code_size))
     }
 
-    fn read_application_extension(&mut self) -> Result<ApplicationExtension, String> {
-        let block_size = self.read_u8()?;
+    // The application identifier and authentication code.
+    fn application_extension_header(&mut self) -> Result<(String, String), Stop> {
+        let block_size = self.u8()?;
         if block_size != 11 {
             return Err(format!(
                 "Error: invalid Application Extension block size: {}",
                 block_size
-            ));
+            )
+            .into());
         }
 
-        let id = {
-            let mut buffer = [0u8; 8];
-            self.read_bytes(&mut buffer)?;
-            std::str::from_utf8(&buffer).unwrap().into()
-        };
-
-        let auth_code = {
-            let mut buffer = [0u8; 3];
-            self.read_bytes(&mut buffer)?;
-            std::str::from_utf8(&buffer).unwrap().into()
-        };
-
-        let data_sub_blocks = self.read_data_sub_blocks()?;
-
-        Ok(ApplicationExtension {
-            id,
-            auth_code,
-            data_sub_blocks,
-        })
-    }
-
-    fn read_comment_extension(&mut self) -> Result<CommentExtension, String> {
-        let data = self.read_data_sub_blocks()?;
-        let text = String::from_utf8(data).map_err(|e| format!("Error: {}", e))?;
-        Ok(CommentExtension { text })
+        let id = self.str(8)?;
+        let auth_code = self.str(3)?;
+        Ok((id, auth_code))
     }
 
-    fn read_graphic_control_extension(&mut self) -> Result<GraphicControlExtension, String> {
-        let block_size = self.read_u8()?;
+    fn graphic_control_extension(&mut self) -> Result<GraphicControlExtension, Stop> {
+        let block_size = self.u8()?;
         if block_size != 4 {
             return Err(format!(
                 "Error: invalid Graphic Control Extension block size: {}",
                 block_size
-            ));
+            )
+            .into());
         }
 
-        let packed_fields = self.read_u8()?;
+        let packed_fields = self.u8()?;
         let disposal_method = match (packed_fields & 0b0001_1100) >> 2 {
             0 => DisposalMethod::Unspecified,
             1 => DisposalMethod::DoNotDispose,
@@ -489,18 +1061,22 @@ impl<'a, T: Read> Parser<'a, T> {
             3 => DisposalMethod::RestoreToPrevious,
             4..=7 => DisposalMethod::Undefined,
             x => {
-                return Err(format!("Error: invalid disposal method: {}", x));
+                return Err(format!("Error: invalid disposal method: {}", x).into());
             }
         };
 
         let user_input_expected = ((packed_fields & 0b0000_0010) >> 1) == 1;
 
         let transparent_color_index_available = (packed_fields & 0b0000_0001) == 1;
-        let delay_time = self.read_u16()?;
-        let transparent_color_index = self.read_u8()?;
-
-        if self.read_u8()? != 0 {
-            return Err("Error: block terminator not found for Graphic Control Extension".into());
+        let delay_time = self.u16()?;
+        let transparent_color_index = self.u8()?;
+
+        if self.u8()? != 0 {
+            return Err(
+                "Error: block terminator not found for Graphic Control Extension"
+                    .to_string()
+                    .into(),
+            );
         }
 
         Ok(GraphicControlExtension {
@@ -512,42 +1088,108 @@ impl<'a, T: Read> Parser<'a, T> {
         })
     }
 
-    fn read_plain_text_extension(
-        &mut self,
-        graphic_control_extension: Option<GraphicControlExtension>,
-    ) -> Result<PlainTextExtension, String> {
-        let block_size = self.read_u8()?;
+    // Everything but the text, which is in the data sub-blocks.
+    fn plain_text_extension_header(&mut self) -> Result<PlainTextExtension, Stop> {
+        let block_size = self.u8()?;
         if block_size != 12 {
             return Err(format!(
                 "Error: invalid Plain Text Extension block size: {}",
                 block_size
-            ));
+            )
+            .into());
         }
 
-        let text_grid_left_pos = self.read_u16()?;
-        let text_grid_top_pos = self.read_u16()?;
-        let text_grid_width = self.read_u16()?;
-        let text_grid_height = self.read_u16()?;
+        Ok(PlainTextExtension {
+            graphic_control_extension: None,
+            text_grid_left_pos: self.u16()?,
+            text_grid_top_pos: self.u16()?,
+            text_grid_width: self.u16()?,
+            text_grid_height: self.u16()?,
+            char_cell_width: self.u8()?,
+            char_cell_height: self.u8()?,
+            text_fg_color_index: self.u8()?,
+            text_bg_color_index: self.u8()?,
+            plain_text_data: String::new(),
+        })
+    }
+}
 
-        let char_cell_width = self.read_u8()?;
-        let char_cell_height = self.read_u8()?;
-        let text_fg_color_index = self.read_u8()?;
-        let text_bg_color_index = self.read_u8()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
 
-        let data = self.read_data_sub_blocks()?;
-        let plain_text_data = String::from_utf8(data).map_err(|e| format!("Error: {}", e))?;
+    fn encoded() -> Vec<u8> {
+        let frames = vec![vec![Color(1, 2, 3), Color(4, 5, 6)]; 2];
+        testing::encoded(testing::gif(2, 1, frames)).1
+    }
 
-        Ok(PlainTextExtension {
-            graphic_control_extension,
-            text_grid_left_pos,
-            text_grid_top_pos,
-            text_grid_width,
-            text_grid_height,
-            char_cell_width,
-            char_cell_height,
-            text_fg_color_index,
-            text_bg_color_index,
-            plain_text_data,
-        })
+    fn names(events: &[Event]) -> Vec<&'static str> {
+        events
+            .iter()
+            .map(|event| match event {
+                Event::Header(_) => "header",
+                Event::LogicalScreenDescriptor(_) => "lsd",
+                Event::Block(DataType::ApplicationExtensionType(_)) => "application",
+                Event::Block(DataType::CommentExtensionType(_)) => "comment",
+                Event::Block(DataType::PlainTextExtensionType(_)) => "plain text",
+                Event::Block(DataType::TableBasedImageType(_)) => "image",
+                Event::Trailer => "trailer",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_feed() {
+        let data = encoded();
+        let mut parser = Parser::new();
+        let all = parser.feed(&data).unwrap();
+        assert!(parser.is_done());
+        assert_eq!(0, parser.bytes_needed());
+
+        // Any split of the data gives the same events.
+        let mut parser = Parser::new();
+        assert!(parser.feed(&data[..4]).unwrap().is_empty());
+        assert_eq!(2, parser.bytes_needed());
+        let mut events = vec![];
+        for byte in &data[4..] {
+            events.extend(parser.feed(&[*byte]).unwrap());
+        }
+        assert_eq!(names(&all), names(&events));
+        assert!(names(&all).ends_with(&["image", "image", "trailer"]));
+
+        // Data after the trailer is ignored.
+        assert!(parser.feed(&data).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_feed_error() {
+        let mut data = encoded();
+        let trailer = data.len() - 1;
+        data[trailer] = 0x99;
+
+        // The blocks before the error come first, then the error sticks.
+        let mut parser = Parser::new();
+        assert!(!parser.feed(&data).unwrap().is_empty());
+        assert_eq!(Some("Error: unknown block type: 99"), parser.error());
+        assert!(parser.feed(&[0x3b]).is_err());
+        assert_eq!(0, parser.bytes_needed());
+
+        assert!(Parser::new().feed(b"PNG89a").is_err());
+    }
+
+    #[test]
+    fn test_parse_partial_keeps_truncated_image() {
+        let data = encoded();
+        let (result, error) = Parser::new()
+            .parse_partial(&mut &data[..data.len() - 3])
+            .unwrap();
+        assert!(error.is_some());
+        let images = result
+            .data_blocks
+            .iter()
+            .filter(|block| matches!(block, DataType::TableBasedImageType(_)))
+            .count();
+        assert_eq!(2, images);
     }
 }
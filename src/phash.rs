@@ -0,0 +1,193 @@
+use crate::{Gif, ImageFrame};
+
+use std::fmt;
+
+// How many frames are hashed, spread evenly over the duration of the animation.
+const SAMPLES: usize = 4;
+// The size frames are shrunk to: every row gives 8 bits, one per pair of neighboring cells.
+const GRID_WIDTH: usize = 9;
+const GRID_HEIGHT: usize = 8;
+
+/// A fingerprint of an animation that stays the same or close under re-encoding, resizing,
+/// color reduction and small retiming, as returned by [`Gif::perceptual_hash`].
+///
+/// It holds a 64 bit dHash for each of 4 frames picked at evenly spaced times, and prints as
+/// 64 hex digits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PerceptualHash {
+    /// The hashes of the sampled frames, in time order.
+    pub hashes: [u64; SAMPLES],
+}
+
+impl PerceptualHash {
+    /// The number of bits that differ between this hash and `other`, from 0 to 256. GIFs that
+    /// look the same usually differ by a handful of bits, while unrelated GIFs differ by about
+    /// half of them.
+    pub fn distance(&self, other: &PerceptualHash) -> u32 {
+        self.hashes
+            .iter()
+            .zip(other.hashes.iter())
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum()
+    }
+}
+
+impl fmt::Display for PerceptualHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for hash in self.hashes.iter() {
+            write!(f, "{:016x}", hash)?;
+        }
+        Ok(())
+    }
+}
+
+impl Gif {
+    /// Compute a perceptual hash of this GIF, such as to find duplicates of an animation
+    /// that was re-encoded or resized. Compare hashes with [`PerceptualHash::distance`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// let mut src = File::open("<gif path>").expect("File not found");
+    /// let a = giffy::load(&mut src).expect("Error loading GIF");
+    /// let mut src = File::open("<other gif path>").expect("File not found");
+    /// let b = giffy::load(&mut src).expect("Error loading GIF");
+    ///
+    /// let (a, b) = (a.perceptual_hash().unwrap(), b.perceptual_hash().unwrap());
+    /// if a.distance(&b) <= 16 {
+    ///     println!("{} and {} are probably the same animation", a, b);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the GIF has no frames or if a frame does not have
+    /// `width * height` pixels.
+    pub fn perceptual_hash(&self) -> Result<PerceptualHash, String> {
+        let len = self.width as usize * self.height as usize;
+        if self.image_frames.is_empty() || len == 0 {
+            return Err("Error: GIF has no frames".into());
+        }
+        if let Some(i) = self.image_frames.iter().position(|f| f.colors.len() != len) {
+            return Err(format!(
+                "Error: frame {} has {} pixels, expected {}",
+                i,
+                self.image_frames[i].colors.len(),
+                len
+            ));
+        }
+
+        let starts = self
+            .image_frames
+            .iter()
+            .scan(0, |time, frame| {
+                let start = *time;
+                *time += frame.delay_time as u64;
+                Some(start)
+            })
+            .collect::<Vec<_>>();
+        let total = starts[starts.len() - 1] + self.image_frames.last().unwrap().delay_time as u64;
+
+        let mut hashes = [0; SAMPLES];
+        for (i, hash) in hashes.iter_mut().enumerate() {
+            // Without delays, there is no timing to go by, so sample by position instead.
+            let index = if total == 0 {
+                i * self.image_frames.len() / SAMPLES
+            } else {
+                let time = total * i as u64 / SAMPLES as u64;
+                starts.partition_point(|&start| start <= time) - 1
+            };
+            *hash = self.dhash(&self.image_frames[index]);
+        }
+        Ok(PerceptualHash { hashes })
+    }
+
+    // Shrink `frame` to a grid of average brightness and set a bit for every cell that is
+    // brighter than its right neighbor.
+    fn dhash(&self, frame: &ImageFrame) -> u64 {
+        let (width, height) = (self.width as usize, self.height as usize);
+        // The pixels covered by cell `i` of `cells` along a side of `size` pixels, at least one.
+        let span = |i: usize, cells: usize, size: usize| {
+            let start = (i * size / cells).min(size - 1);
+            start..((i + 1) * size / cells).max(start + 1)
+        };
+
+        let mut grid = [[0u64; GRID_WIDTH]; GRID_HEIGHT];
+        for (y, row) in grid.iter_mut().enumerate() {
+            let rows = span(y, GRID_HEIGHT, height);
+            for (x, cell) in row.iter_mut().enumerate() {
+                let columns = span(x, GRID_WIDTH, width);
+                let mut sum = 0;
+                for r in rows.clone() {
+                    for c in frame.colors[r * width..][columns.clone()].iter() {
+                        sum += 299 * c.r() as u64 + 587 * c.g() as u64 + 114 * c.b() as u64;
+                    }
+                }
+                *cell = sum / (rows.len() * columns.len()) as u64;
+            }
+        }
+
+        let mut hash = 0;
+        for row in grid.iter() {
+            for pair in row.windows(2) {
+                hash = hash << 1 | (pair[0] > pair[1]) as u64;
+            }
+        }
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ResizeFilter};
+
+    // A pattern of 4x4 pixel blocks of varied brightness, one per cell of the hash grid.
+    fn blocks(seed: usize, frames: usize) -> Gif {
+        let colors = (0..36 * 32)
+            .map(|i| {
+                let (x, y) = (i % 36 / 4, i / 36 / 4);
+                let level = ((x * seed + y * 7 + x * y) % 13 * 20) as u8;
+                Color(level, level / 2, 255 - level)
+            })
+            .collect::<Vec<_>>();
+        Gif {
+            width: 36,
+            height: 32,
+            image_frames: (0..frames)
+                .map(|_| ImageFrame {
+                    width: 36,
+                    height: 32,
+                    colors: colors.clone().into_boxed_slice(),
+                    delay_time: 10,
+                    placeholder: false,
+                })
+                .collect(),
+            loop_count: None,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        }
+    }
+
+    #[test]
+    fn test_perceptual_hash() {
+        let gif = blocks(5, 3);
+        let hash = gif.perceptual_hash().unwrap();
+        assert_eq!(64, hash.to_string().len());
+
+        let resized = gif.resize(20, 12, ResizeFilter::Bilinear).unwrap();
+        assert!(hash.distance(&resized.perceptual_hash().unwrap()) <= 16);
+
+        let mut reduced = gif.clone();
+        reduced.reduce_colors(8, false);
+        assert!(hash.distance(&reduced.perceptual_hash().unwrap()) <= 16);
+
+        let other = blocks(11, 3).perceptual_hash().unwrap();
+        assert!(hash.distance(&other) >= 64);
+
+        assert!(blocks(5, 0).perceptual_hash().is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::font::{self, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::parser::PlainTextExtension;
+use crate::{Color, ImageFrame};
+
+// Draws the text of `ext` onto `frame` the way the GIF89a spec lays it out: a grid of
+// character cells filled left to right and top to bottom, every cell painted with the
+// background color and the glyph stretched over it in the foreground color. The colors are
+// indices into `color_table`, and text that does not fit in the grid or on the canvas is cut
+// off.
+pub(crate) fn draw(frame: &mut ImageFrame, ext: &PlainTextExtension, color_table: &[Color]) {
+    let (fg, bg) = match (
+        color_table.get(ext.text_fg_color_index as usize),
+        color_table.get(ext.text_bg_color_index as usize),
+    ) {
+        (Some(&fg), Some(&bg)) => (fg, bg),
+        _ => {
+            warn!("plain text colors are outside of the global color table");
+            return;
+        }
+    };
+
+    let (width, height) = (frame.width as usize, frame.height as usize);
+    let (left, top) = (
+        ext.text_grid_left_pos as usize,
+        ext.text_grid_top_pos as usize,
+    );
+    let grid_right = (left + ext.text_grid_width as usize).min(width);
+    let grid_bottom = (top + ext.text_grid_height as usize).min(height);
+    for y in top..grid_bottom {
+        frame.colors[y * width + left.min(grid_right)..y * width + grid_right].fill(bg);
+    }
+
+    let (cell_width, cell_height) = (ext.char_cell_width as usize, ext.char_cell_height as usize);
+    if cell_width == 0 || cell_height == 0 {
+        return;
+    }
+    let columns = ext.text_grid_width as usize / cell_width;
+    let rows = ext.text_grid_height as usize / cell_height;
+
+    for (i, c) in ext.plain_text_data.chars().take(columns * rows).enumerate() {
+        let cell_left = left + i % columns * cell_width;
+        let cell_top = top + i / columns * cell_height;
+        for y in 0..cell_height {
+            for x in 0..cell_width {
+                let (px, py) = (cell_left + x, cell_top + y);
+                if px >= width || py >= height {
+                    continue;
+                }
+                let (gx, gy) = (x * GLYPH_WIDTH / cell_width, y * GLYPH_HEIGHT / cell_height);
+                if font::is_set(c, gx, gy) {
+                    frame.colors[py * width + px] = fg;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+    use crate::{load, load_with_options, Color, DecodeOptions, Gif, ImageFrame};
+
+    // A 10x10 GIF with a plain text extension showing "I" in a 5x8 cell at (1, 1).
+    fn with_text() -> Vec<u8> {
+        let mut data = Gif {
+            width: 10,
+            height: 10,
+            image_frames: vec![ImageFrame {
+                width: 10,
+                height: 10,
+                colors: (0..100)
+                    .map(|i| {
+                        if i < 50 {
+                            Color(255, 0, 0)
+                        } else {
+                            Color(0, 0, 255)
+                        }
+                    })
+                    .collect(),
+                delay_time: 10,
+                placeholder: false,
+            }],
+            loop_count: None,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        }
+        .to_bytes()
+        .unwrap();
+
+        let trailer = data.pop().unwrap();
+        data.extend_from_slice(&[0x21, 0x01, 12, 1, 0, 1, 0, 5, 0, 8, 0, 5, 8, 1, 0]);
+        data.extend_from_slice(&[1, b'I', 0, trailer]);
+        data
+    }
+
+    #[test]
+    fn test_plain_text() {
+        let data = with_text();
+        assert_eq!(1, load(&mut data.as_slice()).unwrap().image_frames.len());
+
+        let options = DecodeOptions {
+            render_plain_text: true,
+            ..DecodeOptions::default()
+        };
+        let gif = load_with_options(&mut data.as_slice(), &options).unwrap();
+        assert_eq!(2, gif.image_frames.len());
+
+        let screen = Parser::new().parse(&mut data.as_slice()).unwrap();
+        let table = screen.logical_screen_descriptor.global_color_table.unwrap();
+        let (fg, bg) = (table[1], table[0]);
+        let (image, text) = (&gif.image_frames[0], &gif.image_frames[1]);
+        let pixel = |x: usize, y: usize| text.colors[y * 10 + x];
+        // The middle column of the "I" is set from the top of the cell down.
+        assert_eq!(fg, pixel(1 + 2, 1));
+        assert_eq!(fg, pixel(1 + 2, 4));
+        assert_eq!(bg, pixel(1, 4));
+        assert_eq!(bg, pixel(1 + 4, 1 + 7));
+        // Outside of the grid, the previous frame shows through.
+        assert_eq!(image.colors[0], pixel(0, 0));
+        assert_eq!(image.colors[99], pixel(9, 9));
+    }
+}
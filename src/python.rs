@@ -0,0 +1,130 @@
+//! Python bindings, built with `pyo3`.
+//!
+//! This module is only available with the `python` feature. Build the extension module with
+//! `cargo rustc --release --lib --crate-type cdylib --features python,pyo3/extension-module` and
+//! copy `target/release/libgiffy.so` (or `.dylib`, or `giffy.dll`) to `giffy.so` (or
+//! `giffy.pyd` on Windows), then use it from Python:
+//!
+//! ```python
+//! import giffy
+//! import numpy as np
+//!
+//! with open("animation.gif", "rb") as f:
+//!     gif = giffy.load(f.read())
+//! frames = [np.frombuffer(frame, np.uint8).reshape(gif.height, gif.width, 3) for frame in gif.frames]
+//! print(len(frames), "frames, delays in ms:", gif.delays)
+//! ```
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+/// A decoded GIF, as returned by [`load`].
+#[pyclass(name = "Gif", module = "giffy", frozen)]
+pub struct PyGif {
+    /// The width of the GIF in pixels.
+    #[pyo3(get)]
+    width: u32,
+    /// The height of the GIF in pixels.
+    #[pyo3(get)]
+    height: u32,
+    /// The number of times the animation should loop, `None` if it does not loop and 0 if it
+    /// loops forever.
+    #[pyo3(get)]
+    loop_count: Option<u16>,
+    /// How long every frame stays on screen, in milliseconds.
+    #[pyo3(get)]
+    delays: Vec<u32>,
+    frames: Vec<Py<PyBytes>>,
+}
+
+#[pymethods]
+impl PyGif {
+    /// Every frame as `height * width * 3` bytes of RGB, row by row. Any object supporting the
+    /// buffer protocol works with `numpy.frombuffer`, so no copy is needed.
+    #[getter]
+    fn frames(&self, py: Python<'_>) -> Vec<Py<PyBytes>> {
+        self.frames
+            .iter()
+            .map(|frame| frame.clone_ref(py))
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.frames.len()
+    }
+}
+
+/// Decode the GIF in `data`.
+///
+/// # Errors
+///
+/// This function raises a `ValueError` if `data` is not in a valid GIF format.
+#[pyfunction]
+pub fn load(py: Python<'_>, data: &[u8]) -> PyResult<PyGif> {
+    let gif = crate::load(&mut &data[..]).map_err(PyValueError::new_err)?;
+
+    let frames = gif
+        .image_frames
+        .iter()
+        .map(|frame| PyBytes::new(py, frame.as_bytes()).unbind())
+        .collect();
+    Ok(PyGif {
+        width: gif.width,
+        height: gif.height,
+        loop_count: gif.loop_count,
+        delays: gif
+            .image_frames
+            .iter()
+            .map(|frame| frame.delay_time as u32 * 10)
+            .collect(),
+        frames,
+    })
+}
+
+#[pymodule]
+fn giffy(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(load, module)?)?;
+    module.add_class::<PyGif>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Gif, ImageFrame};
+
+    #[test]
+    fn test_load() {
+        let mut data = vec![];
+        Gif {
+            width: 2,
+            height: 1,
+            image_frames: vec![ImageFrame {
+                width: 2,
+                height: 1,
+                colors: vec![Color(1, 2, 3), Color(4, 5, 6)].into_boxed_slice(),
+                delay_time: 7,
+                placeholder: false,
+            }],
+            loop_count: Some(0),
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        }
+        .write_to(&mut data)
+        .unwrap();
+
+        Python::initialize();
+        Python::attach(|py| {
+            let gif = load(py, &data).unwrap();
+            assert_eq!((2, 1), (gif.width, gif.height));
+            assert_eq!(Some(0), gif.loop_count);
+            assert_eq!(vec![70], gif.delays);
+            assert_eq!(1, gif.__len__());
+            assert_eq!(&[1, 2, 3, 4, 5, 6], gif.frames(py)[0].as_bytes(py));
+
+            assert!(load(py, b"GIF89a").is_err());
+        });
+    }
+}
@@ -0,0 +1,282 @@
+use crate::util::Color;
+
+use std::collections::HashMap;
+
+/// Controls how [`quantize`] maps pixels to the palette.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct QuantizeOptions {
+    /// Map pixels with Floyd-Steinberg dithering, which spreads the error of every pixel to its
+    /// neighbors. This hides banding in gradients at the cost of noise. Disabled by default.
+    pub dither: bool,
+    /// The width of the image in pixels, which dithering needs to find the pixels below each
+    /// pixel. Ignored without dithering.
+    pub width: usize,
+}
+
+/// Reduce `pixels` to a palette of at most `max_colors` colors, returning the palette and the
+/// palette index of every pixel. Colors are kept exact when they already fit in the palette,
+/// otherwise the palette is built with median cut. This is the quantizer the encoder uses,
+/// and is useful on its own for displays with a limited palette.
+///
+/// `max_colors` is clamped between 1 and 256.
+///
+/// # Example
+///
+/// ```
+/// use giffy::{Color, QuantizeOptions};
+///
+/// let pixels = [[255, 0, 0], [250, 0, 0], [0, 0, 255], [0, 0, 250]].map(Color::from);
+/// let (palette, indices) = giffy::quantize(&pixels, 2, &QuantizeOptions::default());
+/// assert_eq!(2, palette.len());
+/// assert_eq!(indices[0], indices[1]);
+/// assert_ne!(indices[0], indices[2]);
+/// ```
+pub fn quantize(
+    pixels: &[Color],
+    max_colors: usize,
+    options: &QuantizeOptions,
+) -> (Vec<Color>, Vec<u8>) {
+    let mut histogram = HashMap::new();
+    add_to_histogram(&mut histogram, pixels);
+
+    let palette = palette(&histogram, max_colors);
+    if options.dither && histogram.len() > palette.len() {
+        let indices = dither(pixels, options.width.max(1), &palette);
+        return (palette, indices);
+    }
+
+    let lookup = histogram
+        .keys()
+        .map(|c| (*c, nearest(&palette, *c)))
+        .collect::<HashMap<_, _>>();
+    let indices = pixels.iter().map(|c| lookup[c]).collect();
+
+    (palette, indices)
+}
+
+/// Map every pixel of a `width` pixels wide image to its closest `palette` entry, spreading
+/// the error to neighboring pixels with Floyd-Steinberg dithering.
+pub(crate) fn dither(pixels: &[Color], width: usize, palette: &[Color]) -> Vec<u8> {
+    let mut errors = vec![[0i32; 3]; pixels.len()];
+    let mut indices = Vec::with_capacity(pixels.len());
+
+    for (i, c) in pixels.iter().enumerate() {
+        let wanted = [
+            (c.0 as i32 + errors[i][0] / 16).clamp(0, 255),
+            (c.1 as i32 + errors[i][1] / 16).clamp(0, 255),
+            (c.2 as i32 + errors[i][2] / 16).clamp(0, 255),
+        ];
+        let index = nearest(
+            palette,
+            Color(wanted[0] as u8, wanted[1] as u8, wanted[2] as u8),
+        );
+        indices.push(index);
+
+        let actual = palette[index as usize];
+        let error = [
+            wanted[0] - actual.0 as i32,
+            wanted[1] - actual.1 as i32,
+            wanted[2] - actual.2 as i32,
+        ];
+
+        let x = i % width;
+        let mut spread = |j: usize, weight: i32| {
+            if let Some(e) = errors.get_mut(j) {
+                for ch in 0..3 {
+                    e[ch] += error[ch] * weight;
+                }
+            }
+        };
+        if x + 1 < width {
+            spread(i + 1, 7);
+            spread(i + width + 1, 1);
+        }
+        if x > 0 {
+            spread(i + width - 1, 3);
+        }
+        spread(i + width, 5);
+    }
+
+    indices
+}
+
+pub(crate) fn add_to_histogram(histogram: &mut HashMap<Color, u32>, pixels: &[Color]) {
+    for c in pixels {
+        *histogram.entry(*c).or_insert(0u32) += 1;
+    }
+}
+
+/// Build a palette of at most `max_colors` colors for the colors in `histogram`.
+pub(crate) fn palette(histogram: &HashMap<Color, u32>, max_colors: usize) -> Vec<Color> {
+    let max_colors = max_colors.clamp(1, 256);
+
+    if histogram.len() <= max_colors {
+        let mut palette = histogram.keys().copied().collect::<Vec<_>>();
+        palette.sort_by_key(|c| (c.0, c.1, c.2));
+        palette
+    } else {
+        median_cut(
+            histogram.iter().map(|(c, n)| (*c, *n)).collect(),
+            max_colors,
+        )
+    }
+}
+
+/// Sort `palette` by decreasing number of pixels in `histogram` using each color, returning the
+/// new index of every original palette entry.
+pub(crate) fn sort_by_usage(palette: &mut Vec<Color>, histogram: &HashMap<Color, u32>) -> Vec<u8> {
+    let mut usage = vec![0u64; palette.len()];
+    for (c, n) in histogram {
+        usage[nearest(palette, *c) as usize] += *n as u64;
+    }
+
+    let mut order = (0..palette.len()).collect::<Vec<_>>();
+    order.sort_by_key(|i| std::cmp::Reverse(usage[*i]));
+
+    let mut remap = vec![0u8; palette.len()];
+    for (new, old) in order.iter().enumerate() {
+        remap[*old] = new as u8;
+    }
+    *palette = order.iter().map(|i| palette[*i]).collect();
+
+    remap
+}
+
+pub(crate) fn nearest(palette: &[Color], c: Color) -> u8 {
+    let mut best = 0;
+    let mut best_distance = u32::MAX;
+
+    for (i, p) in palette.iter().enumerate() {
+        let distance = distance(*p, c);
+        if distance < best_distance {
+            best = i;
+            best_distance = distance;
+            if distance == 0 {
+                break;
+            }
+        }
+    }
+
+    best as u8
+}
+
+#[inline(always)]
+fn distance(a: Color, b: Color) -> u32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
+
+#[inline(always)]
+fn channel(c: Color, channel: usize) -> u8 {
+    match channel {
+        0 => c.0,
+        1 => c.1,
+        _ => c.2,
+    }
+}
+
+// Returns the channel with the widest range and that range.
+fn widest_channel(colors: &[(Color, u32)]) -> (usize, u8) {
+    (0..3)
+        .map(|ch| {
+            let min = colors
+                .iter()
+                .map(|(c, _)| channel(*c, ch))
+                .min()
+                .unwrap_or(0);
+            let max = colors
+                .iter()
+                .map(|(c, _)| channel(*c, ch))
+                .max()
+                .unwrap_or(0);
+            (ch, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap()
+}
+
+fn median_cut(colors: Vec<(Color, u32)>, max_colors: usize) -> Vec<Color> {
+    let mut boxes = vec![colors];
+
+    while boxes.len() < max_colors {
+        let candidate = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| (i, widest_channel(b)))
+            .max_by_key(|(_, (_, range))| *range);
+
+        let (index, ch) = match candidate {
+            Some((index, (ch, range))) if range > 0 => (index, ch),
+            _ => break,
+        };
+
+        let mut b = boxes.swap_remove(index);
+        b.sort_by_key(|(c, _)| channel(*c, ch));
+
+        let total = b.iter().map(|(_, n)| *n as u64).sum::<u64>();
+        let mut acc = 0u64;
+        let mut split = 1;
+        for (i, (_, n)) in b.iter().enumerate() {
+            acc += *n as u64;
+            if acc * 2 >= total {
+                split = (i + 1).clamp(1, b.len() - 1);
+                break;
+            }
+        }
+
+        let rest = b.split_off(split);
+        boxes.push(b);
+        boxes.push(rest);
+    }
+
+    boxes
+        .iter()
+        .map(|b| {
+            let total = b.iter().map(|(_, n)| *n as u64).sum::<u64>().max(1);
+            let sum = |ch| {
+                b.iter()
+                    .map(|(c, n)| channel(*c, ch) as u64 * *n as u64)
+                    .sum::<u64>()
+            };
+            Color(
+                ((sum(0) + total / 2) / total) as u8,
+                ((sum(1) + total / 2) / total) as u8,
+                ((sum(2) + total / 2) / total) as u8,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantize() {
+        let pixels = (0..64u8)
+            .map(|i| Color(i * 4, 255 - i * 4, 0))
+            .collect::<Vec<_>>();
+
+        let (palette, indices) = quantize(&pixels, 256, &QuantizeOptions::default());
+        assert_eq!(64, palette.len());
+        assert!(pixels
+            .iter()
+            .zip(&indices)
+            .all(|(c, &i)| palette[i as usize] == *c));
+
+        for dither in [false, true] {
+            let options = QuantizeOptions { dither, width: 8 };
+            let (palette, indices) = quantize(&pixels, 4, &options);
+            assert!(palette.len() <= 4);
+            assert_eq!(pixels.len(), indices.len());
+            assert!(indices.iter().all(|&i| (i as usize) < palette.len()));
+        }
+
+        let (palette, indices) = quantize(&pixels, 0, &QuantizeOptions::default());
+        assert_eq!(1, palette.len());
+        assert!(indices.iter().all(|&i| i == 0));
+    }
+}
@@ -0,0 +1,341 @@
+use crate::compressor::Compressor;
+use crate::decompressor::Decompressor;
+use crate::parser::*;
+use crate::writer::Writer;
+
+use std::io::{Read, Write};
+
+/// Something [`repair`] had to drop or make up to produce a valid GIF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairAction {
+    /// The input could not be read past a certain point. Everything after the last readable
+    /// block was discarded and a trailer was added.
+    Truncated {
+        /// Why reading stopped.
+        reason: String,
+    },
+    /// A frame could not be recovered and was left out.
+    DroppedFrame {
+        /// The position of the frame in the input, counting from 0.
+        frame: usize,
+        /// Why the frame could not be recovered.
+        reason: String,
+    },
+    /// The image data of a frame ended early and the missing pixels were filled in with the
+    /// transparent color, or the first color if the frame has no transparency.
+    PaddedFrame {
+        /// The position of the frame in the input, counting from 0.
+        frame: usize,
+        /// The number of pixels filled in.
+        missing_pixels: usize,
+    },
+    /// Pixels of a frame referred to colors outside of its color table and were replaced with
+    /// the first color.
+    ReplacedColorIndices {
+        /// The position of the frame in the input, counting from 0.
+        frame: usize,
+        /// The number of pixels replaced.
+        count: usize,
+    },
+    /// A frame used a disposal method that cannot be rendered, and now leaves the frame in place.
+    ReplacedDisposalMethod {
+        /// The position of the frame in the input, counting from 0.
+        frame: usize,
+    },
+}
+
+/// What [`repair`] recovered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairReport {
+    /// The number of frames in the repaired GIF.
+    pub frame_count: usize,
+    /// Everything that was dropped or made up, in the order it was found.
+    pub actions: Vec<RepairAction>,
+}
+
+/// Salvage the frames that can be recovered from the corrupt or truncated GIF read from `src`
+/// and write them to `dst` as a valid GIF.
+///
+/// Frames that decode fine are copied as they are. Frames with short or broken image data are
+/// padded and re-compressed, and frames that cannot be recovered at all are left out. Every
+/// change is listed in the returned [`RepairReport`].
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let mut dst = File::create("<output path>").expect("Unable to create file");
+/// let report = giffy::repair(&mut src, &mut dst).expect("Error repairing GIF");
+/// for action in report.actions {
+///     println!("{:?}", action);
+/// }
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if the header or logical screen descriptor of the GIF
+/// cannot be read, if no frame can be recovered or if writing to `dst` fails.
+pub fn repair<R, W>(src: &mut R, dst: &mut W) -> Result<RepairReport, String>
+where
+    R: Read,
+    W: Write,
+{
+    let (mut result, error) = Parser::new().parse_partial(src)?;
+
+    let mut actions = vec![];
+    if let Some(reason) = error {
+        actions.push(RepairAction::Truncated { reason });
+    }
+
+    if result.header.version != "87a" && result.header.version != "89a" {
+        result.header.version = "89a".into();
+    }
+
+    let mut data_blocks = Vec::with_capacity(result.data_blocks.len());
+    let mut frame = 0;
+    let mut frame_count = 0;
+    for block in result.data_blocks.drain(..) {
+        match block {
+            DataType::TableBasedImageType(mut image) => {
+                match repair_image(
+                    &result.logical_screen_descriptor,
+                    &mut image,
+                    frame,
+                    &mut actions,
+                ) {
+                    Ok(()) => {
+                        data_blocks.push(DataType::TableBasedImageType(image));
+                        frame_count += 1;
+                    }
+                    Err(reason) => actions.push(RepairAction::DroppedFrame { frame, reason }),
+                }
+                frame += 1;
+            }
+            block => data_blocks.push(block),
+        }
+    }
+    result.data_blocks = data_blocks;
+
+    if frame_count == 0 {
+        return Err("Error: no frames could be recovered".into());
+    }
+
+    // Make sure the result actually decodes before writing it.
+    crate::decode(&result)?;
+    Writer::new(dst).write(&result)?;
+
+    Ok(RepairReport {
+        frame_count,
+        actions,
+    })
+}
+
+// Fix up `image` so that it decodes, or return why it cannot be recovered.
+fn repair_image(
+    lsd: &LogicalScreenDescriptor,
+    image: &mut TableBasedImage,
+    frame: usize,
+    actions: &mut Vec<RepairAction>,
+) -> Result<(), String> {
+    let desc = &image.image_descriptor;
+    let (left, top) = (desc.left as usize, desc.top as usize);
+    let (width, height) = (desc.width as usize, desc.height as usize);
+
+    if width == 0 || height == 0 {
+        return Err("frame is empty".into());
+    }
+    if left + width > lsd.width as usize || top + height > lsd.height as usize {
+        return Err("frame lies outside of the canvas".into());
+    }
+
+    let color_table = match image.local_color_table {
+        Some(ref table) => table,
+        None => lsd
+            .global_color_table
+            .as_ref()
+            .ok_or("color table is missing")?,
+    };
+
+    let lzw_min_code_size = image.image_data.lzw_min_code_size;
+    if !(1..=11).contains(&lzw_min_code_size) {
+        return Err(format!(
+            "invalid LZW minimum code size: {}",
+            lzw_min_code_size
+        ));
+    }
+
+    let pixel_count = width * height;
+    let mut decompressor = Decompressor::new(&image.image_data.data_sub_blocks, lzw_min_code_size);
+    decompressor.set_max_len(pixel_count);
+    let (mut indices, error) = decompressor.decompress_partial();
+    if indices.is_empty() {
+        return Err(error.unwrap_or_else(|| "image data is missing".into()));
+    }
+
+    if let Some(ref mut ext) = image.graphic_control_extension {
+        let unsupported = match ext.disposal_method {
+            DisposalMethod::RestoreToPrevious | DisposalMethod::Undefined => true,
+            DisposalMethod::RestoreToBackgroundColor => {
+                lsd.background_color_index as usize >= color_table.len()
+            }
+            _ => false,
+        };
+        if unsupported {
+            ext.disposal_method = DisposalMethod::DoNotDispose;
+            actions.push(RepairAction::ReplacedDisposalMethod { frame });
+        }
+    }
+
+    let fill = match image.graphic_control_extension {
+        Some(ref ext) if ext.transparent_color_index_available => {
+            ext.transparent_color_index as usize
+        }
+        _ => 0,
+    };
+
+    let missing_pixels = pixel_count.saturating_sub(indices.len());
+    if missing_pixels > 0 {
+        indices.resize(pixel_count, fill);
+        actions.push(RepairAction::PaddedFrame {
+            frame,
+            missing_pixels,
+        });
+    }
+
+    let mut replaced = 0;
+    for i in indices.iter_mut() {
+        if *i >= color_table.len() {
+            *i = 0;
+            replaced += 1;
+        }
+    }
+    if replaced > 0 {
+        actions.push(RepairAction::ReplacedColorIndices {
+            frame,
+            count: replaced,
+        });
+    }
+
+    if error.is_some() || missing_pixels > 0 || replaced > 0 {
+        // Color tables hold a power of two number of colors, so this is enough to fit every
+        // index.
+        let lzw_min_code_size = (color_table.len().trailing_zeros() as u8).max(2);
+        let indices = indices.iter().map(|i| *i as u8).collect::<Vec<_>>();
+        image.image_data = ImageData {
+            lzw_min_code_size,
+            data_sub_blocks: Compressor::new(&indices, lzw_min_code_size).compress(),
+        };
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Encoder, ImageFrame, PaletteMode};
+
+    fn animation() -> Vec<u8> {
+        let frames = (0..2)
+            .map(|i| ImageFrame {
+                width: 32,
+                height: 32,
+                colors: (0..32 * 32)
+                    .map(|p| Color((p % 32) as u8 * 8, (p / 32) as u8 * 8, i * 100))
+                    .collect(),
+                delay_time: 10,
+                placeholder: false,
+            })
+            .collect::<Vec<_>>();
+
+        let mut output = vec![];
+        let mut encoder = Encoder::new(&mut output, 32, 32);
+        encoder.set_palette_mode(PaletteMode::Local);
+        encoder.write_frames(&frames).unwrap();
+        encoder.finish().unwrap();
+        output
+    }
+
+    #[test]
+    fn test_repair_truncated() {
+        let input = animation();
+        let original = crate::load(&mut input.as_slice()).unwrap();
+
+        let truncated = &input[..input.len() - 100];
+        assert!(crate::load(&mut &truncated[..]).is_err());
+
+        let mut output = vec![];
+        let report = repair(&mut &truncated[..], &mut output).unwrap();
+        assert_eq!(2, report.frame_count);
+        assert!(matches!(report.actions[0], RepairAction::Truncated { .. }));
+        assert!(matches!(
+            report.actions[1],
+            RepairAction::PaddedFrame { frame: 1, .. }
+        ));
+
+        let repaired = crate::load(&mut output.as_slice()).unwrap();
+        assert_eq!(2, repaired.image_frames.len());
+        assert_eq!(
+            original.image_frames[0].colors,
+            repaired.image_frames[0].colors
+        );
+        assert_eq!(
+            original.image_frames[1].colors[..32],
+            repaired.image_frames[1].colors[..32]
+        );
+    }
+
+    #[test]
+    fn test_repair_disposal_method() {
+        let mut input = animation();
+        let gce = input
+            .windows(3)
+            .rposition(|w| w == [0x21, 0xf9, 4])
+            .unwrap();
+        input[gce + 3] = 3 << 2;
+        assert!(crate::load(&mut input.as_slice()).is_err());
+
+        let mut output = vec![];
+        let report = repair(&mut input.as_slice(), &mut output).unwrap();
+        assert_eq!(2, report.frame_count);
+        assert_eq!(
+            vec![RepairAction::ReplacedDisposalMethod { frame: 1 }],
+            report.actions
+        );
+        assert!(crate::load(&mut output.as_slice()).is_ok());
+    }
+
+    #[test]
+    fn test_repair_drops_broken_frame() {
+        let mut input = animation();
+
+        // Move the second frame off the canvas.
+        let image = input
+            .windows(3)
+            .rposition(|w| w == [0x21, 0xf9, 4])
+            .unwrap()
+            + 8;
+        assert_eq!(0x2c, input[image]);
+        input[image + 1] = 16;
+
+        let mut output = vec![];
+        let report = repair(&mut input.as_slice(), &mut output).unwrap();
+        assert_eq!(1, report.frame_count);
+        assert_eq!(
+            vec![RepairAction::DroppedFrame {
+                frame: 1,
+                reason: "frame lies outside of the canvas".into(),
+            }],
+            report.actions
+        );
+        assert_eq!(
+            1,
+            crate::load(&mut output.as_slice())
+                .unwrap()
+                .image_frames
+                .len()
+        );
+    }
+}
@@ -0,0 +1,744 @@
+use crate::decompressor::Decompressor;
+use crate::encoder::{Encoder, PaletteMode, Region};
+use crate::parser::*;
+use crate::writer::Writer;
+use crate::{Gif, ImageFrame};
+
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::ops::RangeBounds;
+use std::path::Path;
+
+/// Edits a GIF at the block level. Image data is copied as-is, so no quality is lost and no
+/// time is spent decoding or re-encoding frames.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::Rewriter;
+/// use std::fs::File;
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let mut rewriter = Rewriter::new(&mut src).expect("Invalid GIF");
+/// rewriter.scale_delays(0.5);
+///
+/// let mut dst = File::create("<output path>").expect("Unable to create file");
+/// rewriter.write_to(&mut dst).expect("Error writing GIF");
+/// ```
+#[derive(Debug)]
+pub struct Rewriter {
+    result: ParseResult,
+}
+
+impl Rewriter {
+    /// Parse the GIF from a given `src`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the GIF src is not in a valid GIF format.
+    pub fn new<R>(src: &mut R) -> Result<Self, String>
+    where
+        R: Read,
+    {
+        let result = Parser::new().parse(src)?;
+        Ok(Self { result })
+    }
+
+    fn graphic_control_extensions(&mut self) -> impl Iterator<Item = &mut GraphicControlExtension> {
+        self.result
+            .data_blocks
+            .iter_mut()
+            .filter_map(|block| match block {
+                DataType::TableBasedImageType(image) => image.graphic_control_extension.as_mut(),
+                DataType::PlainTextExtensionType(ext) => ext.graphic_control_extension.as_mut(),
+                _ => None,
+            })
+    }
+
+    /// Multiply the delay time of every frame by `factor`, so `2.0` plays the animation at half
+    /// speed and `0.5` at double speed. Delays are rounded to the nearest hundredth of a second
+    /// and are kept at least 1 if they were not 0 before.
+    pub fn scale_delays(&mut self, factor: f32) {
+        for ext in self.graphic_control_extensions() {
+            if ext.delay_time != 0 {
+                let delay_time = (ext.delay_time as f32 * factor).round();
+                ext.delay_time = delay_time.clamp(1.0, u16::MAX as f32) as u16;
+            }
+        }
+    }
+
+    /// Set the delay time of every frame to `delay_time`, in hundredths of a second.
+    pub fn set_delays(&mut self, delay_time: u16) {
+        for block in self.result.data_blocks.iter_mut() {
+            let ext = match block {
+                DataType::TableBasedImageType(image) => &mut image.graphic_control_extension,
+                DataType::PlainTextExtensionType(ext) => &mut ext.graphic_control_extension,
+                _ => continue,
+            };
+
+            graphic_control_extension(ext).delay_time = delay_time;
+        }
+    }
+
+    /// Remove comment extensions, plain text extensions and every application extension other
+    /// than the NETSCAPE2.0 looping extension. Image data is not touched.
+    pub fn strip_metadata(&mut self) {
+        self.result.data_blocks.retain(|block| match block {
+            DataType::ApplicationExtensionType(ext) => ext.loop_count().is_some(),
+            DataType::CommentExtensionType(_) | DataType::PlainTextExtensionType(_) => false,
+            DataType::TableBasedImageType(_) => true,
+        });
+    }
+
+    /// The number of image frames in the GIF.
+    pub fn frame_count(&self) -> usize {
+        self.result
+            .data_blocks
+            .iter()
+            .filter(|block| matches!(block, DataType::TableBasedImageType(_)))
+            .count()
+    }
+
+    /// Keep only the frames for which `f` returns `true` when given the frame index.
+    ///
+    /// The delay of a frame removed from between two kept frames is added to the kept frame
+    /// before it, so the remaining frames keep their timing. Frames removed before the first or
+    /// after the last kept frame are dropped along with their delays.
+    ///
+    /// Frames are copied as they are, so if a kept frame only stores what changed since a
+    /// removed frame, it will be drawn on top of a different image than before. This is most
+    /// noticeable when the first frame is removed from an animation made of partial frames.
+    pub fn retain_frames<F>(&mut self, mut f: F)
+    where
+        F: FnMut(usize) -> bool,
+    {
+        let keep = (0..self.frame_count()).map(&mut f).collect::<Vec<_>>();
+        let last_kept = match keep.iter().rposition(|k| *k) {
+            Some(i) => i,
+            None => {
+                self.result
+                    .data_blocks
+                    .retain(|block| !matches!(block, DataType::TableBasedImageType(_)));
+                return;
+            }
+        };
+
+        let mut index = 0;
+        let mut previous_kept: Option<usize> = None;
+        let mut data_blocks = Vec::with_capacity(self.result.data_blocks.len());
+
+        for block in self.result.data_blocks.drain(..) {
+            let image = match block {
+                DataType::TableBasedImageType(image) => image,
+                block => {
+                    data_blocks.push(block);
+                    continue;
+                }
+            };
+
+            if keep[index] {
+                previous_kept = Some(data_blocks.len());
+                data_blocks.push(DataType::TableBasedImageType(image));
+            } else if let (Some(i), true) = (previous_kept, index < last_kept) {
+                let delay_time = image
+                    .graphic_control_extension
+                    .map(|ext| ext.delay_time)
+                    .unwrap_or(0);
+
+                if let DataType::TableBasedImageType(ref mut kept) = data_blocks[i] {
+                    let ext = graphic_control_extension(&mut kept.graphic_control_extension);
+                    ext.delay_time = ext.delay_time.saturating_add(delay_time);
+                }
+            }
+
+            index += 1;
+        }
+
+        self.result.data_blocks = data_blocks;
+    }
+
+    /// Keep only the frames whose index is in `range`, e.g. `10..20` to extract ten frames.
+    /// See [`Rewriter::retain_frames`] for caveats.
+    pub fn retain_frame_range<R>(&mut self, range: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.retain_frames(|i| range.contains(&i));
+    }
+
+    /// The number of times the animation loops, as given by the NETSCAPE2.0 extension.
+    /// `Some(0)` means loop forever and `None` means the extension is absent.
+    pub fn loop_count(&self) -> Option<u16> {
+        crate::loop_count(&self.result)
+    }
+
+    /// Add, change or, with `None`, remove the NETSCAPE2.0 extension. `Some(0)` makes the
+    /// animation loop forever.
+    pub fn set_loop_count(&mut self, loop_count: Option<u16>) {
+        self.result.data_blocks.retain(|block| {
+            !matches!(block, DataType::ApplicationExtensionType(ext) if ext.loop_count().is_some())
+        });
+
+        if let Some(loop_count) = loop_count {
+            // The extension has to come before the first image to take effect.
+            self.result.data_blocks.insert(
+                0,
+                DataType::ApplicationExtensionType(ApplicationExtension::netscape_looping(
+                    loop_count,
+                )),
+            );
+        }
+    }
+
+    /// Decode the GIF in its current state, e.g. to get the pixels of a frame to edit before
+    /// passing it to [`Rewriter::replace_frame`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the image data is not valid.
+    pub fn decode(&self) -> Result<Gif, String> {
+        crate::decode(&self.result)
+    }
+
+    // Returns the position in `data_blocks` of the image frame at `index`.
+    fn frame_position(&self, index: usize) -> Option<usize> {
+        self.result
+            .data_blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| matches!(block, DataType::TableBasedImageType(_)))
+            .map(|(i, _)| i)
+            .nth(index)
+    }
+
+    // Encodes `frames` with local color tables so the global color table can stay as it is.
+    fn encode_frames(&self, frames: &[ImageFrame]) -> Result<Vec<DataType>, String> {
+        let lsd = &self.result.logical_screen_descriptor;
+        let mut encoder = Encoder::new(vec![], lsd.width, lsd.height);
+        encoder.set_palette_mode(PaletteMode::Local);
+        encoder.write_frames(frames)?;
+        encoder.write_trailer()?;
+
+        let encoded = Parser::new().parse(&mut encoder.get_mut().as_slice())?;
+        Ok(encoded
+            .data_blocks
+            .into_iter()
+            .filter(|block| matches!(block, DataType::TableBasedImageType(_)))
+            .collect())
+    }
+
+    /// Encode `frames` and insert them before the frame at `index`, or after the last frame if
+    /// `index` equals [`Rewriter::frame_count`]. The existing frames are left untouched.
+    ///
+    /// The new frames must cover the whole GIF canvas. Each gets its own local color table so
+    /// that the global color table of the GIF can stay as it is. They are disposed of like the
+    /// frame before them, or restored to the background if inserted first, so the frame
+    /// following them is drawn on top of the same image as before. This does not hold if the
+    /// frame before them is not disposed of at all: a following frame that only stores what
+    /// changed since then is drawn on top of the last inserted frame, as with
+    /// [`Rewriter::retain_frames`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `index` is out of bounds or if a frame size does
+    /// not match the GIF size.
+    pub fn insert_frames(&mut self, index: usize, frames: &[ImageFrame]) -> Result<(), String> {
+        let frame_count = self.frame_count();
+        if index > frame_count {
+            return Err(format!(
+                "Error: insertion index {} is out of bounds, the GIF has {} frames",
+                index, frame_count
+            ));
+        }
+
+        // Whatever the frame before leaves for the next frame is left by the inserted ones too.
+        let disposal_method = match index.checked_sub(1).and_then(|i| self.frame_position(i)) {
+            Some(position) => match self.result.data_blocks[position] {
+                DataType::TableBasedImageType(ref image) => image
+                    .graphic_control_extension
+                    .as_ref()
+                    .map_or(DisposalMethod::Unspecified, |ext| ext.disposal_method),
+                _ => unreachable!(),
+            },
+            None => DisposalMethod::RestoreToBackgroundColor,
+        };
+
+        let mut images = self.encode_frames(frames)?;
+        for block in images.iter_mut() {
+            if let DataType::TableBasedImageType(image) = block {
+                graphic_control_extension(&mut image.graphic_control_extension).disposal_method =
+                    disposal_method;
+            }
+        }
+
+        let position = self
+            .frame_position(index)
+            .unwrap_or(self.result.data_blocks.len());
+
+        self.result.data_blocks.splice(position..position, images);
+        Ok(())
+    }
+
+    /// Encode `frame` and use it in place of the frame at `index`. Only this frame is
+    /// re-encoded, all the others are left untouched.
+    ///
+    /// The new frame must cover the whole GIF canvas, so the composited frames returned by
+    /// [`Rewriter::decode`] can be edited and passed back as they are. Only the rectangle the
+    /// original frame covered is re-encoded, and its disposal method and transparency are kept,
+    /// so frames following it draw on top of the same image as before. The delay of the frame
+    /// is taken from `frame`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `index` is out of bounds, if the frame size does
+    /// not match the GIF size or if the image data is not valid.
+    pub fn replace_frame(&mut self, index: usize, frame: &ImageFrame) -> Result<(), String> {
+        let frame_count = self.frame_count();
+        let position = self.frame_position(index).ok_or_else(|| {
+            format!(
+                "Error: frame index {} is out of bounds, the GIF has {} frames",
+                index, frame_count
+            )
+        })?;
+
+        let lsd = &self.result.logical_screen_descriptor;
+        let (canvas_width, canvas_height) = (lsd.width, lsd.height);
+        let expected_len = canvas_width as usize * canvas_height as usize;
+        if frame.colors.len() != expected_len {
+            return Err(format!(
+                "Error: frame has {} pixels, expected {}",
+                frame.colors.len(),
+                expected_len
+            ));
+        }
+
+        let original = match self.result.data_blocks[position] {
+            DataType::TableBasedImageType(ref image) => image,
+            _ => unreachable!(),
+        };
+        let descriptor = &original.image_descriptor;
+        let transparent_pixels = transparent_pixels(original)?;
+
+        // A pixel stays transparent if it was and the edit left it showing what it did before.
+        let composited = match transparent_pixels {
+            Some(_) => Some(self.decode()?.image_frames.swap_remove(index)),
+            None => None,
+        };
+
+        let mut pixels = Vec::with_capacity(descriptor.width as usize * descriptor.height as usize);
+        for y in 0..descriptor.height as usize {
+            for x in 0..descriptor.width as usize {
+                let (cx, cy) = (descriptor.left as usize + x, descriptor.top as usize + y);
+                if cx >= canvas_width as usize || cy >= canvas_height as usize {
+                    pixels.push(None);
+                    continue;
+                }
+
+                let color = frame.colors[cy * canvas_width as usize + cx];
+                let unchanged = match (&transparent_pixels, &composited) {
+                    (Some(transparent), Some(composited)) => {
+                        transparent[y * descriptor.width as usize + x]
+                            && composited.colors[cy * canvas_width as usize + cx] == color
+                    }
+                    _ => false,
+                };
+                pixels.push(if unchanged { None } else { Some(color) });
+            }
+        }
+
+        let region = Region {
+            left: descriptor.left,
+            top: descriptor.top,
+            width: descriptor.width,
+            height: descriptor.height,
+            pixels,
+        };
+        let mut encoder = Encoder::new(vec![], canvas_width, canvas_height);
+        encoder.set_palette_mode(PaletteMode::Local);
+        let mut image = encoder.encode_region(&region, frame.delay_time);
+
+        let encoded = image.graphic_control_extension.take();
+        let mut ext = original.graphic_control_extension.clone();
+        let ext_mut = graphic_control_extension(&mut ext);
+        ext_mut.delay_time = frame.delay_time;
+        if let Some(encoded) = encoded {
+            ext_mut.transparent_color_index_available = encoded.transparent_color_index_available;
+            ext_mut.transparent_color_index = encoded.transparent_color_index;
+        }
+        image.graphic_control_extension = ext;
+
+        self.result.data_blocks[position] = DataType::TableBasedImageType(image);
+        Ok(())
+    }
+
+    /// Set the delay time of the frame at `index`, in hundredths of a second. This does not
+    /// touch the image data of the frame.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `index` is out of bounds.
+    pub fn set_delay(&mut self, index: usize, delay_time: u16) -> Result<(), String> {
+        let frame_count = self.frame_count();
+        let position = self.frame_position(index).ok_or_else(|| {
+            format!(
+                "Error: frame index {} is out of bounds, the GIF has {} frames",
+                index, frame_count
+            )
+        })?;
+
+        if let DataType::TableBasedImageType(ref mut image) = self.result.data_blocks[position] {
+            graphic_control_extension(&mut image.graphic_control_extension).delay_time = delay_time;
+        }
+        Ok(())
+    }
+
+    /// Save the edited GIF to the file at `path`, replacing it if it already exists. The GIF
+    /// is fully parsed up front, so `path` may be the file it was read from.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file cannot be written.
+    pub fn save<P>(&self, path: P) -> Result<(), String>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::create(path).map_err(|e| format!("Error: {}", e))?;
+        let mut dst = BufWriter::new(file);
+        self.write_to(&mut dst)?;
+        dst.flush().map_err(|e| format!("Error: {}", e))
+    }
+
+    /// Write the edited GIF to a given `dst`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if writing to `dst` fails.
+    pub fn write_to<W>(&self, dst: &mut W) -> Result<(), String>
+    where
+        W: Write,
+    {
+        Writer::new(dst).write(&self.result)
+    }
+}
+
+// Returns for every pixel of `image`, row by row, whether it is transparent, or `None` if the
+// image has no transparent color.
+fn transparent_pixels(image: &TableBasedImage) -> Result<Option<Vec<bool>>, String> {
+    let index = match image.graphic_control_extension {
+        Some(ref ext) if ext.transparent_color_index_available => ext.transparent_color_index,
+        _ => return Ok(None),
+    };
+
+    let descriptor = &image.image_descriptor;
+    let (width, height) = (descriptor.width as usize, descriptor.height as usize);
+    let (indices, error) = Decompressor::new(
+        &image.image_data.data_sub_blocks,
+        image.image_data.lzw_min_code_size,
+    )
+    .decompress_partial();
+    if indices.is_empty() {
+        if let Some(error) = error {
+            return Err(error);
+        }
+    }
+
+    let mut transparent = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row = if descriptor.interlace_flag {
+            crate::stored_row(y, height)
+        } else {
+            y
+        };
+        for x in 0..width {
+            transparent.push(indices.get(row * width + x) == Some(&(index as usize)));
+        }
+    }
+    Ok(Some(transparent))
+}
+
+fn graphic_control_extension(
+    ext: &mut Option<GraphicControlExtension>,
+) -> &mut GraphicControlExtension {
+    ext.get_or_insert(GraphicControlExtension {
+        disposal_method: DisposalMethod::Unspecified,
+        user_input_expected: false,
+        transparent_color_index_available: false,
+        delay_time: 0,
+        transparent_color_index: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_GIF: [u8; 69] = [
+        71, 73, 70, 56, 57, 97, 10, 0, 10, 0, 145, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0, 255, 0, 0,
+        0, 33, 249, 4, 0, 10, 0, 0, 0, 44, 0, 0, 0, 0, 10, 0, 10, 0, 0, 2, 22, 140, 45, 153, 135,
+        42, 28, 220, 51, 160, 2, 117, 236, 149, 250, 168, 222, 96, 140, 4, 145, 76, 1, 0, 59,
+    ];
+
+    #[test]
+    fn test_write_to_is_lossless() {
+        let rewriter = Rewriter::new(&mut &SAMPLE_GIF[..]).unwrap();
+
+        let mut output = vec![];
+        rewriter.write_to(&mut output).unwrap();
+
+        assert_eq!(&SAMPLE_GIF[..], &output[..]);
+    }
+
+    #[test]
+    fn test_scale_and_set_delays() {
+        let mut rewriter = Rewriter::new(&mut &SAMPLE_GIF[..]).unwrap();
+        rewriter.scale_delays(2.5);
+
+        let mut output = vec![];
+        rewriter.write_to(&mut output).unwrap();
+
+        let mut expected = SAMPLE_GIF;
+        expected[29] = 25;
+        assert_eq!(&expected[..], &output[..]);
+
+        rewriter.set_delays(300);
+        output.clear();
+        rewriter.write_to(&mut output).unwrap();
+
+        let gif = crate::load(&mut output.as_slice()).unwrap();
+        assert_eq!(300, gif.image_frames[0].delay_time);
+    }
+
+    #[test]
+    fn test_set_loop_count() {
+        let mut rewriter = Rewriter::new(&mut &SAMPLE_GIF[..]).unwrap();
+        assert_eq!(None, rewriter.loop_count());
+
+        rewriter.set_loop_count(Some(0));
+        rewriter.set_loop_count(Some(3));
+        assert_eq!(Some(3), rewriter.loop_count());
+
+        let mut output = vec![];
+        rewriter.write_to(&mut output).unwrap();
+        assert_eq!(SAMPLE_GIF.len() + 19, output.len());
+
+        let mut rewriter = Rewriter::new(&mut output.as_slice()).unwrap();
+        assert_eq!(Some(3), rewriter.loop_count());
+
+        rewriter.set_loop_count(None);
+        output.clear();
+        rewriter.write_to(&mut output).unwrap();
+        assert_eq!(&SAMPLE_GIF[..], &output[..]);
+    }
+
+    fn animation(delays: &[u16]) -> Vec<u8> {
+        let frames = delays
+            .iter()
+            .enumerate()
+            .map(|(i, delay_time)| crate::ImageFrame {
+                width: 2,
+                height: 2,
+                colors: vec![crate::Color(i as u8, 0, 0); 4].into_boxed_slice(),
+                delay_time: *delay_time,
+                placeholder: false,
+            })
+            .collect::<Vec<_>>();
+
+        let mut output = vec![];
+        let mut encoder = crate::Encoder::new(&mut output, 2, 2);
+        encoder.write_frames(&frames).unwrap();
+        encoder.finish().unwrap();
+        output
+    }
+
+    fn frames(rewriter: &Rewriter) -> Vec<(u8, u16)> {
+        let mut output = vec![];
+        rewriter.write_to(&mut output).unwrap();
+        crate::load(&mut output.as_slice())
+            .unwrap()
+            .image_frames
+            .iter()
+            .map(|f| (f.colors[0].r(), f.delay_time))
+            .collect()
+    }
+
+    #[test]
+    fn test_retain_frames() {
+        let input = animation(&[1, 2, 3, 4, 5]);
+
+        let mut rewriter = Rewriter::new(&mut input.as_slice()).unwrap();
+        rewriter.retain_frames(|i| i % 2 == 0);
+        assert_eq!(3, rewriter.frame_count());
+        assert_eq!(vec![(0, 3), (2, 7), (4, 5)], frames(&rewriter));
+
+        let mut rewriter = Rewriter::new(&mut input.as_slice()).unwrap();
+        rewriter.retain_frame_range(1..3);
+        assert_eq!(vec![(1, 2), (2, 3)], frames(&rewriter));
+    }
+
+    #[test]
+    fn test_insert_frames() {
+        let input = animation(&[1, 2]);
+        let card = |i: u8| ImageFrame {
+            width: 2,
+            height: 2,
+            colors: vec![crate::Color(100 + i, 0, 0); 4].into_boxed_slice(),
+            delay_time: 50,
+            placeholder: false,
+        };
+
+        let mut rewriter = Rewriter::new(&mut input.as_slice()).unwrap();
+        rewriter.insert_frames(0, &[card(0)]).unwrap();
+        rewriter.insert_frames(2, &[card(1), card(2)]).unwrap();
+        rewriter.insert_frames(5, &[card(3)]).unwrap();
+
+        assert_eq!(
+            vec![(100, 50), (0, 1), (101, 50), (102, 50), (1, 2), (103, 50)],
+            frames(&rewriter)
+        );
+        assert!(rewriter.insert_frames(7, &[card(4)]).is_err());
+    }
+
+    #[test]
+    fn test_insert_frames_keeps_disposal() {
+        let (red, green, blue, key) = (
+            crate::Color(255, 0, 0),
+            crate::Color(0, 255, 0),
+            crate::Color(0, 0, 255),
+            crate::Color(1, 2, 3),
+        );
+        let gif = crate::testing::gif(2, 2, [vec![red; 4], vec![green, key, key, key]]);
+
+        // The background shows through the transparent pixels of the second frame, as the
+        // first one is restored to the background after being shown.
+        let mut input = vec![];
+        let mut encoder = crate::Encoder::new(&mut input, 2, 2);
+        encoder.set_transparent_color(Some(key));
+        encoder.write_frames(&gif.image_frames).unwrap();
+        encoder.finish().unwrap();
+
+        let mut rewriter = Rewriter::new(&mut input.as_slice()).unwrap();
+        let before = rewriter.decode().unwrap();
+        let card = ImageFrame {
+            colors: vec![blue; 4].into_boxed_slice(),
+            ..before.image_frames[0].clone()
+        };
+        rewriter
+            .insert_frames(1, std::slice::from_ref(&card))
+            .unwrap();
+        rewriter
+            .insert_frames(0, std::slice::from_ref(&card))
+            .unwrap();
+
+        let after = rewriter.decode().unwrap();
+        let colors = after
+            .image_frames
+            .iter()
+            .map(|f| f.colors.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                card.colors.clone(),
+                before.image_frames[0].colors.clone(),
+                card.colors,
+                before.image_frames[1].colors.clone(),
+            ],
+            colors
+        );
+    }
+
+    #[test]
+    fn test_replace_frame() {
+        let input = animation(&[1, 2, 3]);
+
+        let mut rewriter = Rewriter::new(&mut input.as_slice()).unwrap();
+        let mut frame = rewriter.decode().unwrap().image_frames[1].clone();
+        frame.colors[0] = crate::Color(200, 0, 0);
+        frame.delay_time = 9;
+        rewriter.replace_frame(1, &frame).unwrap();
+        rewriter.set_delay(2, 7).unwrap();
+
+        assert_eq!(vec![(0, 1), (200, 9), (2, 7)], frames(&rewriter));
+        assert!(rewriter.replace_frame(3, &frame).is_err());
+        assert!(rewriter.set_delay(3, 1).is_err());
+    }
+
+    #[test]
+    fn test_replace_frame_keeps_disposal_and_transparency() {
+        let (red, green, blue, key) = (
+            crate::Color(255, 0, 0),
+            crate::Color(0, 255, 0),
+            crate::Color(0, 0, 255),
+            crate::Color(1, 2, 3),
+        );
+        let gif = crate::testing::gif(2, 2, [vec![red, key, red, red], vec![green, key, key, key]]);
+
+        // Both frames are restored to the background after being shown, so the background
+        // shows through the transparent pixels of the second frame.
+        let mut input = vec![];
+        let mut encoder = crate::Encoder::new(&mut input, 2, 2);
+        encoder.set_transparent_color(Some(key));
+        encoder.write_frames(&gif.image_frames).unwrap();
+        encoder.finish().unwrap();
+
+        let mut rewriter = Rewriter::new(&mut input.as_slice()).unwrap();
+        let before = rewriter.decode().unwrap();
+        let mut frame = before.image_frames[0].clone();
+        frame.colors[0] = blue;
+        rewriter.replace_frame(0, &frame).unwrap();
+
+        let image = match rewriter
+            .frame_position(0)
+            .map(|i| &rewriter.result.data_blocks[i])
+        {
+            Some(DataType::TableBasedImageType(image)) => image,
+            _ => unreachable!(),
+        };
+        let ext = image.graphic_control_extension.as_ref().unwrap();
+        assert_eq!(
+            DisposalMethod::RestoreToBackgroundColor,
+            ext.disposal_method
+        );
+        assert!(ext.transparent_color_index_available);
+        assert_eq!(
+            Some(vec![false, true, false, false]),
+            transparent_pixels(image).unwrap()
+        );
+
+        let after = rewriter.decode().unwrap();
+        assert_eq!(frame.colors, after.image_frames[0].colors);
+        assert_eq!(before.image_frames[1].colors, after.image_frames[1].colors);
+    }
+
+    #[test]
+    fn test_strip_metadata() {
+        let mut rewriter = Rewriter::new(&mut &SAMPLE_GIF[..]).unwrap();
+        rewriter.set_loop_count(Some(0));
+        rewriter.result.data_blocks.insert(
+            0,
+            DataType::CommentExtensionType(CommentExtension {
+                text: "generated by giffy".into(),
+            }),
+        );
+        rewriter.result.data_blocks.insert(
+            0,
+            DataType::ApplicationExtensionType(ApplicationExtension {
+                id: "XMP Data".into(),
+                auth_code: "XMP".into(),
+                data_sub_blocks: vec![1, 2, 3],
+                sub_block_sizes: vec![3],
+            }),
+        );
+
+        rewriter.strip_metadata();
+
+        let mut output = vec![];
+        rewriter.write_to(&mut output).unwrap();
+
+        let mut expected = Rewriter::new(&mut &SAMPLE_GIF[..]).unwrap();
+        expected.set_loop_count(Some(0));
+        let mut expected_output = vec![];
+        expected.write_to(&mut expected_output).unwrap();
+
+        assert_eq!(expected_output, output);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::util::ArchivedColor;
+use crate::{ArchivedGif, Gif};
+
+use rkyv::rancor::Error;
+use rkyv::util::AlignedVec;
+
+impl Gif {
+    /// Serialize this GIF with rkyv, pixels and all, such as to pre-decode an animation at
+    /// build time. Reading it back with [`access_archived`](crate::access_archived) takes no
+    /// decoding and no copying, so the result can be memory-mapped and drawn from directly.
+    ///
+    /// This is only available with the `rkyv` feature. The format is rkyv's, so it can only be
+    /// read by the version of giffy, and of rkyv, that wrote it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the GIF cannot be serialized.
+    pub fn to_archive(&self) -> Result<AlignedVec, String> {
+        rkyv::to_bytes::<Error>(self).map_err(|e| format!("Error: {}", e))
+    }
+}
+
+/// View `bytes`, as written by [`Gif::to_archive`], as a GIF without deserializing it. The
+/// bytes are checked once, which is much faster than decoding, and every frame is then read in
+/// place: `archived.image_frames[i].colors` are the pixels of frame `i`.
+///
+/// `bytes` must be aligned to 16 bytes, which memory-mapped files and [`AlignedVec`] are.
+///
+/// This is only available with the `rkyv` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs;
+///
+/// let gif = giffy::load(&mut fs::File::open("<gif path>").expect("File not found"))
+///     .expect("Error loading GIF");
+/// let archive = gif.to_archive().expect("Error archiving GIF");
+///
+/// let archived = giffy::access_archived(&archive).expect("Error reading archive");
+/// let first = &archived.image_frames[0];
+/// println!("{} pixels, first one has red {}", first.colors.len(), first.colors[0].r());
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if `bytes` is not a valid archive or is not aligned.
+pub fn access_archived(bytes: &[u8]) -> Result<&ArchivedGif, String> {
+    rkyv::access::<ArchivedGif, Error>(bytes).map_err(|e| format!("Error: {}", e))
+}
+
+impl ArchivedColor {
+    /// Get the Red component.
+    #[inline(always)]
+    pub fn r(&self) -> u8 {
+        self.0
+    }
+
+    /// Get the Green component.
+    #[inline(always)]
+    pub fn g(&self) -> u8 {
+        self.1
+    }
+
+    /// Get the Blue component.
+    #[inline(always)]
+    pub fn b(&self) -> u8 {
+        self.2
+    }
+}
+
+impl From<&ArchivedColor> for [u8; 3] {
+    fn from(c: &ArchivedColor) -> Self {
+        [c.r(), c.g(), c.b()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ImageFrame};
+
+    #[test]
+    fn test_archive() {
+        let gif = Gif {
+            width: 2,
+            height: 1,
+            image_frames: (0..3)
+                .map(|i| ImageFrame {
+                    width: 2,
+                    height: 1,
+                    colors: vec![Color(i, 1, 2), Color(3, 4, 5)].into_boxed_slice(),
+                    delay_time: 10 * i as u16,
+                    placeholder: false,
+                })
+                .collect(),
+            loop_count: Some(0),
+            comments: vec!["hello".into()],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        };
+
+        let archive = gif.to_archive().unwrap();
+        let archived = access_archived(&archive).unwrap();
+        assert_eq!(2, archived.width);
+        assert_eq!(Some(0), archived.loop_count.as_ref().map(|n| n.to_native()));
+        assert_eq!("hello", archived.comments[0].as_str());
+        assert_eq!(3, archived.image_frames.len());
+        for (expected, actual) in gif.image_frames.iter().zip(archived.image_frames.iter()) {
+            let colors = actual.colors.iter().map(<[u8; 3]>::from);
+            assert!(colors.eq(expected.colors.iter().map(<[u8; 3]>::from)));
+            assert_eq!(expected.delay_time, actual.delay_time);
+        }
+
+        let deserialized = rkyv::deserialize::<Gif, Error>(archived).unwrap();
+        assert_eq!(
+            gif.image_frames[2].colors,
+            deserialized.image_frames[2].colors
+        );
+
+        assert!(access_archived(&archive[..archive.len() - 4]).is_err());
+    }
+}
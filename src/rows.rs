@@ -0,0 +1,420 @@
+use crate::decompressor::{Decompressor, Indices};
+use crate::parser::{ParseResult, Parser, TableBasedImage};
+use crate::screen::{Screen, ScreenEvent};
+use crate::{Color, DecodeOptions, Rect};
+
+use std::io::Read;
+use std::ops::ControlFlow;
+
+/// Which rows of a frame a band handed out by [`decode_rows`] holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowBand {
+    /// The position of the frame in the animation, counting from 0.
+    pub frame: usize,
+    /// Where the image of the frame is on the canvas.
+    pub rect: Rect,
+    /// The first row of the band, counting from the top of the image.
+    pub y: u32,
+    /// The number of rows in the band.
+    pub rows: u32,
+    /// How long the frame stays on screen, in hundredths of a second.
+    pub delay_time: u16,
+}
+
+/// Consumes the rows of every frame of a GIF as they are decompressed, as driven by
+/// [`decode_rows`].
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::{Color, DecodeOptions, RowBand, RowSink};
+/// use std::fs::File;
+/// use std::ops::ControlFlow;
+///
+/// // Prints how dark every band of the first frame is, and stops after it.
+/// struct Darkness;
+///
+/// impl RowSink for Darkness {
+///     fn rows(&mut self, band: &RowBand, pixels: &[Option<Color>]) -> ControlFlow<()> {
+///         if band.frame > 0 {
+///             return ControlFlow::Break(());
+///         }
+///         let dark = pixels.iter().flatten().filter(|c| c.r() < 64).count();
+///         println!("rows {}..{}: {} dark pixels", band.y, band.y + band.rows, dark);
+///         ControlFlow::Continue(())
+///     }
+/// }
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// giffy::decode_rows(&mut src, &DecodeOptions::default(), 16, &mut Darkness)
+///     .expect("Error decoding GIF");
+/// ```
+pub trait RowSink {
+    /// Called once the logical screen descriptor has been read, with the size of the canvas.
+    fn screen(&mut self, _width: u32, _height: u32) {}
+
+    /// Called with `band.rows * band.rect.width` pixels of a frame, row by row, as soon as they
+    /// are decompressed. Transparent pixels are `None`. Return `ControlFlow::Break` to stop
+    /// decoding.
+    fn rows(&mut self, band: &RowBand, pixels: &[Option<Color>]) -> ControlFlow<()>;
+}
+
+/// Decode a GIF from `src` in a single pass, handing the rows of every frame to `sink` in
+/// bands of `band_height` rows as the decompressor produces them. Frames are not composited,
+/// so no canvas is ever held and only a band of decoded pixels is kept at a time. The
+/// compressed data of a frame is still read in full before it is decoded, so memory use grows
+/// with the largest compressed frame. This suits consumers that work a band at a time, such as
+/// printers.
+///
+/// The rows of an interlaced frame are handed out one at a time, in the order in which they
+/// are stored. Broken frames and data that stops early are dealt with as set by `options`: in
+/// lenient mode, the rows that are missing or broken are filled in like
+/// [`load_with_options`](crate::load_with_options) does, and a frame that does not fit on the
+/// canvas is skipped. Returns whether the sink stopped the decode before the end of the GIF.
+///
+/// # Errors
+///
+/// This function will return an error if `src` is not in a valid GIF format, as far as the
+/// options allow. The rows handed to the sink before the error stay handed.
+pub fn decode_rows<R, S>(
+    src: &mut R,
+    options: &DecodeOptions,
+    band_height: u32,
+    sink: &mut S,
+) -> Result<bool, String>
+where
+    R: Read,
+    S: RowSink,
+{
+    let prefix = crate::skip_leading_bytes(src, options)?;
+    let src = &mut prefix.as_slice().chain(src);
+    let mut parser = Parser::new();
+    parser.set_strict(options.strict);
+    let mut screen = Screen::new();
+    let mut frame = 0;
+    let mut stopped = false;
+
+    let mut decode = |image: TableBasedImage, screen: &ParseResult, sink: &mut S| {
+        let lsd = &screen.logical_screen_descriptor;
+        let size = (lsd.width as u32, lsd.height as u32);
+        let global = lsd.global_color_table.as_deref();
+        let flow = decode_image(&image, frame, size, global, options, band_height, sink);
+        frame += 1;
+        match flow {
+            Err(e) if options.is_lenient() => {
+                warn!("frame {} cannot be decoded: {}", frame - 1, e);
+                Ok(ControlFlow::Continue(()))
+            }
+            flow => flow,
+        }
+    };
+
+    let result = screen.read_events(&mut parser, src, |event| match event {
+        ScreenEvent::Screen(screen) => {
+            let lsd = &screen.logical_screen_descriptor;
+            sink.screen(lsd.width as u32, lsd.height as u32);
+            Ok(ControlFlow::Continue(()))
+        }
+        ScreenEvent::Image(image, screen) => {
+            let flow = decode(image, screen, sink)?;
+            stopped = flow.is_break();
+            Ok(flow)
+        }
+        ScreenEvent::Block(_) => Ok(ControlFlow::Continue(())),
+    });
+
+    let e = match result {
+        Ok(()) => return Ok(stopped),
+        Err(e) => e,
+    };
+    let screen = match screen.into_inner() {
+        Some(screen) if options.is_lenient() => screen,
+        _ => return Err(e),
+    };
+    warn!("decoding the data read before the error: {}", e);
+    if let Some(image) = parser.take_truncated_image() {
+        return decode(image, &screen, sink).map(|flow| flow.is_break());
+    }
+    Ok(false)
+}
+
+fn decode_image<S: RowSink>(
+    image: &TableBasedImage,
+    frame: usize,
+    (width, height): (u32, u32),
+    global_color_table: Option<&[Color]>,
+    options: &DecodeOptions,
+    band_height: u32,
+    sink: &mut S,
+) -> Result<ControlFlow<()>, String> {
+    let desc = &image.image_descriptor;
+    let rect = Rect::new(
+        desc.left as u32,
+        desc.top as u32,
+        desc.width as u32,
+        desc.height as u32,
+    );
+    if rect.left + rect.width > width || rect.top + rect.height > height {
+        return Err(format!(
+            "Error: image at ({}, {}) of size {}x{} does not fit in {}x{}",
+            rect.left, rect.top, rect.width, rect.height, width, height
+        ));
+    }
+    let color_table = image
+        .local_color_table
+        .as_deref()
+        .or(global_color_table)
+        .ok_or("Global color table is missing!")?;
+    let (transparent, delay_time) = match image.graphic_control_extension {
+        Some(ref ext) => (
+            ext.transparent_color_index_available
+                .then_some(ext.transparent_color_index as usize),
+            ext.delay_time,
+        ),
+        None => (None, 0),
+    };
+    if let Some(index) = transparent.filter(|_| options.strict) {
+        crate::check_transparent_index(index, color_table)?;
+    }
+    if rect.width == 0 || rect.height == 0 {
+        return Ok(ControlFlow::Continue(()));
+    }
+
+    let rows_per_band = if desc.interlace_flag {
+        1
+    } else {
+        band_height.clamp(1, rect.height)
+    };
+    let mut bands = Bands {
+        sink,
+        band: RowBand {
+            frame,
+            rect,
+            y: 0,
+            rows: 0,
+            delay_time,
+        },
+        color_table,
+        transparent,
+        lenient: options.is_lenient(),
+        interlaced: desc.interlace_flag,
+        pixels: Vec::with_capacity(rows_per_band as usize * rect.width as usize),
+        band_len: rows_per_band as usize * rect.width as usize,
+        done: 0,
+        limit: rect.width as usize * rect.height as usize,
+        flow: ControlFlow::Continue(()),
+        error: None,
+    };
+
+    let mut decompressor = Decompressor::new(
+        &image.image_data.data_sub_blocks,
+        image.image_data.lzw_min_code_size,
+    );
+    decompressor.set_max_len(bands.limit);
+    decompressor.set_strict(options.strict);
+    let result = decompressor.decompress_into(&mut bands);
+    // Once the sink stops, the rest of the data is of no concern.
+    if bands.flow.is_break() {
+        return Ok(bands.flow);
+    }
+    if let Some(e) = bands.error.take().or(result.err()) {
+        if !options.is_lenient() || bands.len() == 0 {
+            return Err(e);
+        }
+    }
+
+    let count = bands.done + bands.pixels.len();
+    if count < bands.limit {
+        if !options.is_lenient() {
+            return Err(format!(
+                "Error: image data has {} pixels, expected {}",
+                count, bands.limit
+            ));
+        }
+        warn!("frame {} is damaged and was recovered", frame);
+        let fill = transparent.unwrap_or(0);
+        while bands.done + bands.pixels.len() < bands.limit && bands.flow.is_continue() {
+            bands.push(fill);
+        }
+    }
+    Ok(bands.flow)
+}
+
+// Hands the color indices of an image to a sink a band of rows at a time, as they are
+// decompressed.
+struct Bands<'a, S: RowSink> {
+    sink: &'a mut S,
+    // The band being filled.
+    band: RowBand,
+    color_table: &'a [Color],
+    transparent: Option<usize>,
+    lenient: bool,
+    interlaced: bool,
+    pixels: Vec<Option<Color>>,
+    band_len: usize,
+    // The number of pixels handed to the sink so far, and in the whole image.
+    done: usize,
+    limit: usize,
+    flow: ControlFlow<()>,
+    error: Option<String>,
+}
+
+impl<S: RowSink> Bands<'_, S> {
+    fn flush(&mut self) {
+        let width = self.band.rect.width as usize;
+        let row = (self.done / width) as u32;
+        self.band.y = if self.interlaced {
+            interlaced_row(row, self.band.rect.height)
+        } else {
+            row
+        };
+        self.band.rows = (self.pixels.len() / width) as u32;
+        self.flow = self.sink.rows(&self.band, &self.pixels);
+        self.done += self.pixels.len();
+        self.pixels.clear();
+    }
+}
+
+impl<S: RowSink> Indices for Bands<'_, S> {
+    fn push(&mut self, index: usize) {
+        if self.flow.is_break() || self.error.is_some() || self.len() >= self.limit {
+            return;
+        }
+        let color = match self.color_table.get(index) {
+            _ if self.transparent == Some(index) => None,
+            Some(c) => Some(*c),
+            None if self.lenient => match self.transparent {
+                Some(_) => None,
+                None => self.color_table.first().copied(),
+            },
+            None => {
+                self.error = Some("Error: color index is outside of the color table".into());
+                return;
+            }
+        };
+        self.pixels.push(color);
+        if self.pixels.len() == self.band_len || self.len() == self.limit {
+            self.flush();
+        }
+    }
+
+    // Reports the whole image as read once the sink stops or an error comes up, to stop the
+    // decompressor.
+    fn len(&self) -> usize {
+        if self.flow.is_break() || self.error.is_some() {
+            self.limit
+        } else {
+            self.done + self.pixels.len()
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.pixels.truncate(len.saturating_sub(self.done));
+    }
+}
+
+// The row of an interlaced image of `height` rows that is stored as row `n`.
+// Refer to https://www.w3.org/Graphics/GIF/spec-gif89a.txt for details.
+fn interlaced_row(mut n: u32, height: u32) -> u32 {
+    for (start, step) in [(0, 8), (4, 8), (2, 4), (1, 2)] {
+        let rows = height.saturating_sub(start).div_ceil(step);
+        if n < rows {
+            return start + n * step;
+        }
+        n -= rows;
+    }
+    n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, Gif};
+
+    // Keeps copies of the bands, and stops after `limit` of them.
+    struct Collect {
+        bands: Vec<(RowBand, Vec<Option<Color>>)>,
+        limit: usize,
+    }
+
+    impl RowSink for Collect {
+        fn rows(&mut self, band: &RowBand, pixels: &[Option<Color>]) -> ControlFlow<()> {
+            self.bands.push((band.clone(), pixels.to_vec()));
+            if self.bands.len() == self.limit {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        }
+    }
+
+    fn encoded() -> (Gif, Vec<u8>) {
+        let frames = (0..2).map(|i| (0..10).map(|j| Color(i * 100 + j, 0, 0)).collect());
+        testing::encoded(testing::gif(2, 5, frames))
+    }
+
+    #[test]
+    fn test_decode_rows() {
+        let (gif, data) = encoded();
+        let mut sink = Collect {
+            bands: vec![],
+            limit: usize::MAX,
+        };
+        let stopped = decode_rows(
+            &mut data.as_slice(),
+            &DecodeOptions::default(),
+            2,
+            &mut sink,
+        )
+        .unwrap();
+        assert!(!stopped);
+
+        // Bands of two rows, and the last one of the rest.
+        let bands = sink
+            .bands
+            .iter()
+            .map(|(band, _)| (band.frame, band.y, band.rows))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (0, 0, 2),
+                (0, 2, 2),
+                (0, 4, 1),
+                (1, 0, 2),
+                (1, 2, 2),
+                (1, 4, 1)
+            ],
+            bands
+        );
+        assert_eq!(11, sink.bands[3].0.delay_time);
+        for (i, frame) in gif.image_frames.iter().enumerate() {
+            let pixels = sink
+                .bands
+                .iter()
+                .filter(|(band, _)| band.frame == i)
+                .flat_map(|(_, pixels)| pixels.iter().map(|c| c.unwrap()))
+                .collect::<Vec<_>>();
+            assert_eq!(frame.colors.to_vec(), pixels);
+        }
+
+        let mut sink = Collect {
+            bands: vec![],
+            limit: 2,
+        };
+        let stopped = decode_rows(
+            &mut data.as_slice(),
+            &DecodeOptions::default(),
+            2,
+            &mut sink,
+        )
+        .unwrap();
+        assert!(stopped);
+        assert_eq!(2, sink.bands.len());
+    }
+
+    #[test]
+    fn test_interlaced_row() {
+        let rows = (0..10).map(|n| interlaced_row(n, 10)).collect::<Vec<_>>();
+        assert_eq!(vec![0, 8, 4, 2, 6, 1, 3, 5, 7, 9], rows);
+    }
+}
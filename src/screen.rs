@@ -0,0 +1,87 @@
+use crate::parser::{DataType, Event, Header, ParseResult, Parser, TableBasedImage};
+
+use std::io::Read;
+use std::ops::ControlFlow;
+
+// An event of the parser, as seen by a decoder that decodes every image as soon as it is read.
+pub(crate) enum ScreenEvent<'s> {
+    // The header and logical screen descriptor have been read.
+    Screen(&'s ParseResult),
+    // An image, with the header and logical screen descriptor it is decoded against.
+    Image(TableBasedImage, &'s ParseResult),
+    // Any other block.
+    Block(DataType),
+}
+
+// Keeps the header and logical screen descriptor, which every image is decoded against, as the
+// events of a parser go by. No blocks are kept.
+#[derive(Debug, Default)]
+pub(crate) struct Screen {
+    header: Option<Header>,
+    result: Option<ParseResult>,
+}
+
+impl Screen {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    // Takes in `event`, returning what a decoder needs to see of it, if anything.
+    pub(crate) fn event(&mut self, event: Event) -> Result<Option<ScreenEvent<'_>>, String> {
+        match event {
+            Event::Header(header) => {
+                self.header = Some(header);
+                Ok(None)
+            }
+            Event::LogicalScreenDescriptor(logical_screen_descriptor) => {
+                self.result = self.header.take().map(|header| ParseResult {
+                    header,
+                    logical_screen_descriptor,
+                    data_blocks: vec![],
+                });
+                Ok(self.result.as_ref().map(ScreenEvent::Screen))
+            }
+            Event::Block(DataType::TableBasedImageType(image)) => {
+                let result = self
+                    .result
+                    .as_ref()
+                    .ok_or("Error: image before the logical screen descriptor")?;
+                Ok(Some(ScreenEvent::Image(image, result)))
+            }
+            Event::Block(block) => Ok(Some(ScreenEvent::Block(block))),
+            Event::Trailer => Ok(None),
+        }
+    }
+
+    // Reads the events of `src` with `parser`, handing `f` what it needs to see of them, until
+    // the trailer or until `f` breaks.
+    pub(crate) fn read_events<R, F>(
+        &mut self,
+        parser: &mut Parser,
+        src: &mut R,
+        mut f: F,
+    ) -> Result<(), String>
+    where
+        R: Read,
+        F: FnMut(ScreenEvent) -> Result<ControlFlow<()>, String>,
+    {
+        parser.read_events(src, |event| match self.event(event)? {
+            Some(event) => f(event),
+            None => Ok(ControlFlow::Continue(())),
+        })
+    }
+
+    // The header, if it has been read but the logical screen descriptor has not.
+    pub(crate) fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    // The header and logical screen descriptor, once both have been read.
+    pub(crate) fn get(&self) -> Option<&ParseResult> {
+        self.result.as_ref()
+    }
+
+    pub(crate) fn into_inner(self) -> Option<ParseResult> {
+        self.result
+    }
+}
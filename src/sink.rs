@@ -0,0 +1,219 @@
+use crate::parser::{ParseResult, Parser, TableBasedImage};
+use crate::screen::{Screen, ScreenEvent};
+use crate::{Color, DecodeOptions, Decoder, ImageFrame};
+
+use std::io::Read;
+use std::ops::ControlFlow;
+
+/// What [`decode_into_sink`] knows about a frame besides its pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameMeta {
+    /// The position of the frame in the animation, counting from 0.
+    pub index: usize,
+    /// The width of the canvas in pixels.
+    pub width: u32,
+    /// The height of the canvas in pixels.
+    pub height: u32,
+    /// How long the frame stays on screen, in hundredths of a second.
+    pub delay_time: u16,
+    /// Whether the frame stands in for one that could not be decoded, as set by
+    /// [`DecodeOptions::placeholder_frames`].
+    pub placeholder: bool,
+}
+
+/// Consumes the frames of a GIF one at a time, as driven by [`decode_into_sink`].
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::{Color, DecodeOptions, FrameMeta, FrameSink};
+/// use std::fs::File;
+/// use std::io::Write;
+/// use std::ops::ControlFlow;
+///
+/// // Writes every frame as raw RGB, as many video encoders take on their input.
+/// struct RawRgb<W: Write>(W);
+///
+/// impl<W: Write> FrameSink for RawRgb<W> {
+///     fn frame(&mut self, canvas: &[Color], _meta: &FrameMeta) -> ControlFlow<()> {
+///         let rgb = canvas.iter().flat_map(<[u8; 3]>::from).collect::<Vec<_>>();
+///         match self.0.write_all(&rgb) {
+///             Ok(()) => ControlFlow::Continue(()),
+///             Err(_) => ControlFlow::Break(()),
+///         }
+///     }
+/// }
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let mut sink = RawRgb(std::io::stdout().lock());
+/// giffy::decode_into_sink(&mut src, &DecodeOptions::default(), &mut sink)
+///     .expect("Error decoding GIF");
+/// ```
+pub trait FrameSink {
+    /// Called with every composited frame, `meta.width * meta.height` pixels row by row. The
+    /// canvas is drawn over in place for the next frame, so copy what must be kept. Return
+    /// `ControlFlow::Break` to stop decoding.
+    fn frame(&mut self, canvas: &[Color], meta: &FrameMeta) -> ControlFlow<()>;
+}
+
+/// Decode a GIF from `src` in a single pass, handing every frame to `sink` as soon as it is
+/// composited. Every frame is drawn onto the same canvas, so no memory is allocated per frame
+/// and no frames are kept.
+///
+/// Broken frames and data that stops early are dealt with as set by `options`, like
+/// [`decode_with_handler`](crate::decode_with_handler) does. Returns whether the sink stopped
+/// the decode before the end of the GIF.
+///
+/// # Errors
+///
+/// This function will return an error if `src` is not in a valid GIF format, as far as the
+/// options allow.
+pub fn decode_into_sink<R, S>(
+    src: &mut R,
+    options: &DecodeOptions,
+    sink: &mut S,
+) -> Result<bool, String>
+where
+    R: Read,
+    S: FrameSink,
+{
+    let prefix = crate::skip_leading_bytes(src, options)?;
+    let src = &mut prefix.as_slice().chain(src);
+    let mut parser = Parser::new();
+    parser.set_strict(options.strict);
+    let mut screen = Screen::new();
+    let mut canvas: Option<ImageFrame> = None;
+    let mut index = 0;
+    let mut stopped = false;
+
+    let mut decode = |image: TableBasedImage, screen: &ParseResult| {
+        let decoder = Decoder::new(screen, *options);
+        let drawn = match canvas {
+            Some(ref mut frame) => decoder.draw_frame(frame, &image).map(|_| ()),
+            None => decoder
+                .decode_frame(&[], &image)
+                .map(|(frame, _)| canvas = Some(frame)),
+        };
+        match drawn {
+            Ok(()) => (),
+            Err(e) if options.is_lenient() => {
+                warn!("frame {} cannot be decoded: {}", index, e);
+                if !options.placeholder_frames {
+                    return Ok(ControlFlow::Continue(()));
+                }
+                canvas = Some(decoder.create_placeholder_frame(canvas.as_slice(), &image));
+            }
+            Err(e) => return Err(e),
+        }
+
+        let frame = canvas.as_ref().unwrap();
+        let lsd = &screen.logical_screen_descriptor;
+        let meta = FrameMeta {
+            index,
+            width: lsd.width as u32,
+            height: lsd.height as u32,
+            delay_time: frame.delay_time,
+            placeholder: frame.placeholder,
+        };
+        index += 1;
+        Ok(sink.frame(&frame.colors, &meta))
+    };
+
+    let result = screen.read_events(&mut parser, src, |event| match event {
+        ScreenEvent::Image(image, screen) => {
+            let flow = decode(image, screen)?;
+            stopped = flow.is_break();
+            Ok(flow)
+        }
+        ScreenEvent::Screen(_) | ScreenEvent::Block(_) => Ok(ControlFlow::Continue(())),
+    });
+
+    let e = match result {
+        Ok(()) => return Ok(stopped),
+        Err(e) => e,
+    };
+    let screen = match screen.into_inner() {
+        Some(screen) if options.is_lenient() => screen,
+        _ => return Err(e),
+    };
+    warn!("decoding the data read before the error: {}", e);
+    if let Some(image) = parser.take_truncated_image() {
+        return decode(image, &screen).map(|flow| flow.is_break());
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, Gif};
+
+    // Keeps copies of the frames, and stops after `limit` of them.
+    struct Collect {
+        frames: Vec<(Vec<Color>, FrameMeta)>,
+        canvases: Vec<*const Color>,
+        limit: usize,
+    }
+
+    impl FrameSink for Collect {
+        fn frame(&mut self, canvas: &[Color], meta: &FrameMeta) -> ControlFlow<()> {
+            self.frames.push((canvas.to_vec(), meta.clone()));
+            self.canvases.push(canvas.as_ptr());
+            if self.frames.len() == self.limit {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        }
+    }
+
+    fn encoded() -> (Gif, Vec<u8>) {
+        let frames = (0..3).map(|i| {
+            vec![
+                Color(i * 50, 0, 0),
+                Color(0, 0, 0),
+                Color(0, 0, 0),
+                Color(1, 1, 1),
+            ]
+        });
+        testing::encoded(testing::gif(2, 2, frames))
+    }
+
+    #[test]
+    fn test_decode_into_sink() {
+        let (gif, data) = encoded();
+        let mut sink = Collect {
+            frames: vec![],
+            canvases: vec![],
+            limit: usize::MAX,
+        };
+        let stopped =
+            decode_into_sink(&mut data.as_slice(), &DecodeOptions::default(), &mut sink).unwrap();
+        assert!(!stopped);
+
+        assert_eq!(gif.image_frames.len(), sink.frames.len());
+        for (i, (expected, (colors, meta))) in gif.image_frames.iter().zip(&sink.frames).enumerate()
+        {
+            assert_eq!(&expected.colors[..], &colors[..]);
+            assert_eq!(i, meta.index);
+            assert_eq!((2, 2), (meta.width, meta.height));
+            assert_eq!(expected.delay_time, meta.delay_time);
+        }
+        // Every frame was drawn onto the same canvas.
+        assert!(sink.canvases.iter().all(|&p| p == sink.canvases[0]));
+    }
+
+    #[test]
+    fn test_decode_into_sink_stops() {
+        let (_, data) = encoded();
+        let mut sink = Collect {
+            frames: vec![],
+            canvases: vec![],
+            limit: 1,
+        };
+        let stopped =
+            decode_into_sink(&mut data.as_slice(), &DecodeOptions::default(), &mut sink).unwrap();
+        assert!(stopped);
+        assert_eq!(1, sink.frames.len());
+    }
+}
@@ -0,0 +1,527 @@
+use crate::cursor::Cursor;
+use crate::parser::{DataType, Parser};
+use crate::screen::{Screen, ScreenEvent};
+use crate::writer::Writer;
+use crate::{DecodeOptions, Decoder, Gif, ImageFrame};
+
+// The start of a saved decoder state, followed by the format version.
+const STATE_MAGIC: &[u8] = b"GIFFYSTATE";
+const STATE_VERSION: u8 = 1;
+
+/// Decodes a GIF from chunks of bytes as they arrive, such as during a download, and hands out
+/// every frame as soon as its image data is complete. This allows showing an animation while
+/// the rest of it is still loading.
+///
+/// Frames are kept until they are taken with [`take_frames`](Self::take_frames), so take them
+/// as they come when decoding a long stream.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::StreamDecoder;
+/// use std::fs::File;
+/// use std::io::Read;
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let mut decoder = StreamDecoder::new();
+/// let mut chunk = [0; 4096];
+/// while !decoder.is_finished() {
+///     let len = src.read(&mut chunk).expect("Error reading file");
+///     if len == 0 {
+///         break;
+///     }
+///     decoder.push(&chunk[..len]).expect("Error decoding GIF");
+///     for frame in decoder.take_frames() {
+///         println!("frame ready, delay {}", frame.delay_time);
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct StreamDecoder {
+    parser: Parser<'static>,
+    // The header and logical screen descriptor, which every image is decoded against. Images
+    // are decoded as soon as they are complete, so no blocks are kept.
+    screen: Screen,
+    // The frames that have not been taken yet.
+    frames: Vec<ImageFrame>,
+    // The last frame decoded, once it has been taken, as the next frame is drawn over it.
+    canvas: Option<ImageFrame>,
+    loop_count: Option<u16>,
+    comments: Vec<String>,
+}
+
+impl Default for StreamDecoder {
+    fn default() -> Self {
+        Self {
+            parser: Parser::new(),
+            screen: Screen::new(),
+            frames: vec![],
+            canvas: None,
+            loop_count: None,
+            comments: vec![],
+        }
+    }
+}
+
+impl StreamDecoder {
+    /// Create a decoder that has not been given any data yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `chunk` to the data received so far and return the frames it completed, if any.
+    /// Data after the trailer is ignored.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the data received so far is not in a valid GIF
+    /// format. Running out of data is not an error.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<&[ImageFrame], String> {
+        let start = self.frames.len();
+        for event in self.parser.feed(chunk)? {
+            match self.screen.event(event)? {
+                Some(ScreenEvent::Image(image, screen)) => {
+                    let decoder = Decoder::new(screen, DecodeOptions::default());
+                    let previous = self.frames.last().or(self.canvas.as_ref());
+                    let (frame, _) = decoder
+                        .decode_frame(previous.map_or(&[][..], std::slice::from_ref), &image)?;
+                    self.frames.push(frame);
+                }
+                Some(ScreenEvent::Block(block)) => self.block(block),
+                Some(ScreenEvent::Screen(_)) | None => (),
+            }
+        }
+        // The blocks before an error are handed out along with it, so check for one here.
+        match self.parser.error() {
+            Some(e) => Err(e.into()),
+            None => Ok(&self.frames[start..]),
+        }
+    }
+
+    fn block(&mut self, block: DataType) {
+        match block {
+            DataType::ApplicationExtensionType(ext) => {
+                self.loop_count = self.loop_count.or(ext.loop_count());
+            }
+            DataType::CommentExtensionType(ext) => self.comments.push(ext.text),
+            DataType::TableBasedImageType(_) | DataType::PlainTextExtensionType(_) => (),
+        }
+    }
+
+    /// The width of the GIF, once enough data has been pushed to know it.
+    pub fn width(&self) -> Option<u32> {
+        self.size().map(|(width, _)| width)
+    }
+
+    /// The height of the GIF, once enough data has been pushed to know it.
+    pub fn height(&self) -> Option<u32> {
+        self.size().map(|(_, height)| height)
+    }
+
+    fn size(&self) -> Option<(u32, u32)> {
+        let lsd = &self.screen.get()?.logical_screen_descriptor;
+        Some((lsd.width as u32, lsd.height as u32))
+    }
+
+    /// The number of times the animation should loop, from the blocks received so far. `None`
+    /// means it should not loop and `Some(0)` that it should loop forever.
+    pub fn loop_count(&self) -> Option<u16> {
+        self.loop_count
+    }
+
+    /// The comments received so far.
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// The frames decoded so far that have not been taken with
+    /// [`take_frames`](Self::take_frames).
+    pub fn frames(&self) -> &[ImageFrame] {
+        &self.frames
+    }
+
+    /// Remove and return the frames decoded so far. Only the canvas the next frame is drawn
+    /// over is kept, so taking the frames as they come keeps memory use flat however long the
+    /// GIF is.
+    pub fn take_frames(&mut self) -> Vec<ImageFrame> {
+        let frames = std::mem::take(&mut self.frames);
+        if let Some(last) = frames.last() {
+            self.canvas = Some(last.clone());
+        }
+        frames
+    }
+
+    /// The smallest number of bytes that must still be pushed before the decoder can move on to
+    /// the next block, or to the next data sub-block of the block being read. This is 0 once
+    /// the trailer has been received or the data turned out not to be a valid GIF.
+    pub fn bytes_needed(&self) -> usize {
+        self.parser.bytes_needed()
+    }
+
+    /// Whether the trailer has been received, after which there are no more frames.
+    pub fn is_finished(&self) -> bool {
+        self.parser.is_done()
+    }
+
+    /// Save where the decoder is, such as to resume the decode in another process with
+    /// [`restore`](Self::restore). The snapshot holds the header, the logical screen
+    /// descriptor, the loop count and comments, the last frame decoded, which the next one is
+    /// drawn over, and the data of the block being received, still compressed. Other frames
+    /// are not saved.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the decoder failed, as there is nothing to
+    /// resume.
+    pub fn save(&self) -> Result<Vec<u8>, String> {
+        // The data that takes a new decoder to where this one is.
+        let mut data = vec![];
+        let mut writer = Writer::new(&mut data);
+        match (self.screen.header(), self.screen.get()) {
+            (Some(header), _) => writer.write_header(header)?,
+            (_, Some(result)) => {
+                writer.write_header(&result.header)?;
+                writer.write_logical_screen_descriptor(&result.logical_screen_descriptor)?;
+            }
+            _ => (),
+        }
+        data.extend(self.parser.unfinished()?);
+
+        let mut state = STATE_MAGIC.to_vec();
+        state.push(STATE_VERSION);
+        state.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        state.extend_from_slice(&data);
+        match self.loop_count {
+            Some(n) => {
+                state.push(1);
+                state.extend_from_slice(&n.to_le_bytes());
+            }
+            None => state.extend_from_slice(&[0, 0, 0]),
+        }
+        state.extend_from_slice(&(self.comments.len() as u32).to_le_bytes());
+        for comment in &self.comments {
+            state.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+            state.extend_from_slice(comment.as_bytes());
+        }
+        match self.frames.last().or(self.canvas.as_ref()) {
+            Some(frame) => {
+                state.push(1 | (frame.placeholder as u8) << 1);
+                state.extend_from_slice(&frame.delay_time.to_le_bytes());
+                state.extend_from_slice(&(frame.colors.len() as u64).to_le_bytes());
+                state.extend_from_slice(frame.as_bytes());
+            }
+            None => state.push(0),
+        }
+        Ok(state)
+    }
+
+    /// Create a decoder that carries on where the one that [`save`](Self::save)d `state` was.
+    /// Its frames start with the last frame decoded before the snapshot, if any.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use giffy::StreamDecoder;
+    ///
+    /// # fn next_chunk() -> Vec<u8> { vec![] }
+    /// let mut decoder = StreamDecoder::new();
+    /// decoder.push(&next_chunk()).expect("Error decoding GIF");
+    /// let state = decoder.save().expect("Error saving decoder");
+    ///
+    /// // Later, or somewhere else:
+    /// let mut decoder = StreamDecoder::restore(&state).expect("Error restoring decoder");
+    /// decoder.push(&next_chunk()).expect("Error decoding GIF");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `state` was not saved by [`save`](Self::save),
+    /// was saved by an incompatible version of giffy, or is cut off.
+    pub fn restore(state: &[u8]) -> Result<Self, String> {
+        let mut cursor = Cursor::new(state);
+        if cursor.read_bytes(STATE_MAGIC.len()) != Some(STATE_MAGIC) {
+            return Err("Error: not a saved decoder state".into());
+        }
+        let cut_off = || "Error: saved decoder state is cut off".to_string();
+        let version = cursor.read_u8().ok_or_else(cut_off)?;
+        if version != STATE_VERSION {
+            return Err(format!(
+                "Error: unsupported saved decoder state version {}",
+                version
+            ));
+        }
+
+        let len = cursor.read_u64().ok_or_else(cut_off)?;
+        let data = cursor.read_bytes(len as usize).ok_or_else(cut_off)?;
+        // Feeding the data again leaves the decoder where the saved one was, without any
+        // frames to decode.
+        let mut decoder = Self::new();
+        decoder.push(data)?;
+
+        let has_loop_count = cursor.read_u8().ok_or_else(cut_off)? == 1;
+        let loop_count = cursor.read_u16().ok_or_else(cut_off)?;
+        let comment_count = cursor.read_u32().ok_or_else(cut_off)?;
+        let mut comments = vec![];
+        for _ in 0..comment_count {
+            let len = cursor.read_u32().ok_or_else(cut_off)?;
+            let text = cursor.read_bytes(len as usize).ok_or_else(cut_off)?;
+            comments.push(String::from_utf8(text.to_vec()).map_err(|e| format!("Error: {}", e))?);
+        }
+        let flags = cursor.read_u8().ok_or_else(cut_off)?;
+        let last = if flags & 1 != 0 {
+            let delay_time = cursor.read_u16().ok_or_else(cut_off)?;
+            let len = cursor.read_u64().ok_or_else(cut_off)?;
+            let pixels = cursor
+                .read_bytes((len as usize).saturating_mul(3))
+                .ok_or_else(cut_off)?;
+            let (width, height) = decoder
+                .size()
+                .ok_or("Error: saved frame without a screen")?;
+            if len != width as u64 * height as u64 {
+                return Err("Error: saved frame does not match the screen".into());
+            }
+            Some(ImageFrame {
+                width,
+                height,
+                colors: pixels.chunks_exact(3).map(|c| c.into()).collect(),
+                delay_time,
+                placeholder: flags & 0b10 != 0,
+            })
+        } else {
+            None
+        };
+
+        decoder.loop_count = has_loop_count.then_some(loop_count);
+        decoder.comments = comments;
+        decoder.frames.extend(last);
+        Ok(decoder)
+    }
+
+    /// Turn the decoded GIF into a [`Gif`], once the trailer has been received. Frames taken
+    /// with [`take_frames`](Self::take_frames) are not part of it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the trailer has not been received yet.
+    pub fn into_gif(self) -> Result<Gif, String> {
+        if !self.is_finished() {
+            return Err("Error: the GIF is not complete".into());
+        }
+        self.into_partial_gif()
+            .ok_or_else(|| "Error: the GIF is not complete".into())
+    }
+
+    // The GIF decoded so far, or `None` if the logical screen descriptor has not been received.
+    fn into_partial_gif(self) -> Option<Gif> {
+        let (width, height) = self.size()?;
+        let pixel_aspect_ratio = crate::pixel_aspect_ratio(self.screen.get()?);
+        Some(Gif {
+            width,
+            height,
+            image_frames: self.frames,
+            loop_count: self.loop_count,
+            comments: self.comments,
+            pixel_aspect_ratio,
+            transparent_color: None,
+        })
+    }
+}
+
+/// What [`load_prefix`] could decode from the start of a GIF.
+#[derive(Debug, Clone)]
+pub struct PartialGif {
+    /// The GIF as far as it could be decoded, with every frame whose data is complete.
+    pub gif: Gif,
+    /// Whether the data held the whole GIF, up to the trailer.
+    pub complete: bool,
+    /// The smallest number of bytes that must be appended to the data before decoding can move
+    /// on. See [`StreamDecoder::bytes_needed`].
+    pub bytes_needed: usize,
+}
+
+/// Decode as much as possible from `data`, the first bytes of a GIF, such as the result of an
+/// HTTP range request. Returns the metadata and every frame whose data is complete, along with
+/// how many more bytes are needed to continue.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::Read;
+///
+/// let mut data = vec![];
+/// File::open("<gif path>")
+///     .expect("File not found")
+///     .take(64 * 1024)
+///     .read_to_end(&mut data)
+///     .expect("Error reading file");
+/// let partial = giffy::load_prefix(&data).expect("Error loading GIF");
+/// if let Some(frame) = partial.gif.image_frames.first() {
+///     println!("first frame has {} pixels", frame.colors.len());
+/// }
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if `data` is not in a valid GIF format or if it ends
+/// before the first data block.
+pub fn load_prefix(data: &[u8]) -> Result<PartialGif, String> {
+    let mut decoder = StreamDecoder::new();
+    decoder.push(data)?;
+
+    let (complete, bytes_needed) = (decoder.is_finished(), decoder.bytes_needed());
+    let gif = decoder.into_partial_gif().ok_or_else(|| {
+        format!(
+            "Error: the data ends in the header, at least {} more bytes are needed",
+            bytes_needed
+        )
+    })?;
+    Ok(PartialGif {
+        gif,
+        complete,
+        bytes_needed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, Color, Gif};
+
+    fn encoded() -> (Gif, Vec<u8>) {
+        let frames = (0..3).map(|i| vec![Color(i * 50, 0, 0); 4]);
+        testing::encoded(testing::gif(2, 2, frames))
+    }
+
+    #[test]
+    fn test_push() {
+        let (gif, data) = encoded();
+        let mut decoder = StreamDecoder::new();
+
+        assert!(decoder.push(&data[..5]).unwrap().is_empty());
+        assert_eq!(None, decoder.width());
+
+        let mut counts = vec![];
+        for chunk in data[5..].chunks(3) {
+            counts.push(decoder.push(chunk).unwrap().len());
+        }
+        assert_eq!(Some(2), decoder.width());
+        assert_eq!(Some(2), decoder.height());
+        assert_eq!(Some(0), decoder.loop_count());
+        assert!(decoder.is_finished());
+
+        // The frames came in one at a time.
+        assert_eq!(3, counts.iter().sum::<usize>());
+        assert!(counts.iter().all(|&n| n <= 1));
+        for (expected, actual) in gif.image_frames.iter().zip(decoder.frames()) {
+            assert_eq!(expected.colors, actual.colors);
+        }
+
+        assert!(decoder.push(&[0x3b]).unwrap().is_empty());
+        assert_eq!(
+            gif.image_frames.len(),
+            decoder.into_gif().unwrap().image_frames.len()
+        );
+
+        let mut decoder = StreamDecoder::new();
+        decoder.push(&data[..data.len() - 1]).unwrap();
+        assert!(decoder.into_gif().is_err());
+    }
+
+    #[test]
+    fn test_load_prefix() {
+        let (gif, data) = encoded();
+
+        // Cut off in the middle of the last image.
+        let partial = load_prefix(&data[..data.len() - 5]).unwrap();
+        assert!(!partial.complete);
+        assert!(partial.bytes_needed > 0);
+        assert_eq!(2, partial.gif.image_frames.len());
+        assert_eq!(
+            gif.image_frames[1].colors,
+            partial.gif.image_frames[1].colors
+        );
+        assert_eq!(Some(0), partial.gif.loop_count);
+
+        // Only the trailer is missing.
+        let partial = load_prefix(&data[..data.len() - 1]).unwrap();
+        assert!(!partial.complete);
+        assert_eq!(1, partial.bytes_needed);
+        assert_eq!(3, partial.gif.image_frames.len());
+
+        let partial = load_prefix(&data).unwrap();
+        assert!(partial.complete);
+        assert_eq!(0, partial.bytes_needed);
+
+        assert!(load_prefix(&data[..10]).is_err());
+    }
+
+    #[test]
+    fn test_take_frames() {
+        let (gif, data) = encoded();
+        let mut decoder = StreamDecoder::new();
+
+        let mut frames = vec![];
+        for chunk in data.chunks(7) {
+            decoder.push(chunk).unwrap();
+            frames.extend(decoder.take_frames());
+            assert!(decoder.frames().is_empty());
+        }
+
+        assert!(decoder.is_finished());
+        assert_eq!(gif.image_frames.len(), frames.len());
+        for (expected, actual) in gif.image_frames.iter().zip(&frames) {
+            assert_eq!(expected.colors, actual.colors);
+        }
+        assert!(decoder.into_gif().unwrap().image_frames.is_empty());
+    }
+
+    #[test]
+    fn test_push_invalid() {
+        let mut decoder = StreamDecoder::new();
+        assert!(decoder.push(b"PNG").unwrap().is_empty());
+        assert!(decoder.push(b"GIF89a").is_err());
+
+        let (_, mut data) = encoded();
+        let trailer = data.len() - 1;
+        data[trailer] = 0x99;
+        assert!(StreamDecoder::new().push(&data).is_err());
+    }
+
+    #[test]
+    fn test_save_restore() {
+        let (gif, data) = encoded();
+
+        // Save in the middle of every block, and carry on with a restored decoder.
+        for cut in 0..data.len() {
+            let mut decoder = StreamDecoder::new();
+            let mut frames = decoder.push(&data[..cut]).unwrap().to_vec();
+            let mut decoder = StreamDecoder::restore(&decoder.save().unwrap()).unwrap();
+            frames.extend_from_slice(decoder.push(&data[cut..]).unwrap());
+
+            assert!(decoder.is_finished());
+            assert_eq!(Some(0), decoder.loop_count());
+            assert_eq!(gif.image_frames.len(), frames.len());
+            for (expected, actual) in gif.image_frames.iter().zip(&frames) {
+                assert_eq!(expected.colors, actual.colors);
+            }
+        }
+
+        assert!(StreamDecoder::restore(b"GIF89a").is_err());
+
+        // A saved frame one pixel short, with the pixel count to match.
+        let mut decoder = StreamDecoder::new();
+        decoder.push(&data[..data.len() - 1]).unwrap();
+        let mut state = decoder.save().unwrap();
+        let pixels = gif.width as usize * gif.height as usize;
+        state.truncate(state.len() - 3);
+        let len = state.len() - 3 * (pixels - 1) - 8;
+        state[len..len + 8].copy_from_slice(&(pixels as u64 - 1).to_le_bytes());
+        let error = StreamDecoder::restore(&state).unwrap_err();
+        assert_eq!("Error: saved frame does not match the screen", error);
+
+        let mut decoder = StreamDecoder::new();
+        assert!(decoder.push(b"PNG89a").is_err());
+        assert!(decoder.save().is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{Color, Gif, ImageFrame};
+
+// A GIF of `width` by `height` pixels with a frame of each of `frames`, row by row. Frame n
+// shows for 10 + n hundredths of a second, and the animation loops forever and has a comment.
+pub(crate) fn gif<I>(width: u32, height: u32, frames: I) -> Gif
+where
+    I: IntoIterator<Item = Vec<Color>>,
+{
+    Gif {
+        width,
+        height,
+        image_frames: frames
+            .into_iter()
+            .enumerate()
+            .map(|(n, colors)| ImageFrame {
+                width,
+                height,
+                colors: colors.into_boxed_slice(),
+                delay_time: 10 + n as u16,
+                placeholder: false,
+            })
+            .collect(),
+        loop_count: Some(0),
+        comments: vec!["hello".into()],
+        pixel_aspect_ratio: None,
+        transparent_color: None,
+    }
+}
+
+// `gif` and the bytes it is written as.
+pub(crate) fn encoded(gif: Gif) -> (Gif, Vec<u8>) {
+    let mut data = vec![];
+    gif.write_to(&mut data).unwrap();
+    (gif, data)
+}
@@ -0,0 +1,166 @@
+use crate::util::display_time;
+use crate::{Gif, ImageFrame};
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Hands out the frames of a GIF when they are due to show, as many times as the GIF loops,
+/// as returned by [`Gif::ticker`].
+///
+/// As an iterator, it sleeps until the next frame is due and then yields it, which is all a
+/// player has to do. To keep control of the waiting, such as in an event loop, call
+/// [`Ticker::wait`] and [`Ticker::tick`] instead.
+#[derive(Debug, Clone)]
+pub struct Ticker<'a> {
+    gif: &'a Gif,
+    // The position of the next frame, and of the play it is in.
+    next: usize,
+    play: u32,
+    // How many times the animation plays, `None` for forever.
+    plays: Option<u32>,
+    // When the next frame is due, `None` before the first frame.
+    due: Option<Instant>,
+}
+
+impl<'a> Ticker<'a> {
+    fn new(gif: &'a Gif) -> Self {
+        // The NETSCAPE2.0 loop count is the number of repeats after the first play.
+        let plays = match gif.loop_count {
+            None => Some(1),
+            Some(0) => None,
+            Some(n) => Some(n as u32 + 1),
+        };
+        Self {
+            gif,
+            next: 0,
+            play: 0,
+            plays,
+            due: None,
+        }
+    }
+
+    /// Play the animation once, however many times the GIF says it should loop.
+    pub fn once(mut self) -> Self {
+        self.plays = Some(1);
+        self
+    }
+
+    /// How long until the next frame is due, zero if it is due already, or `None` once the
+    /// animation is done playing.
+    pub fn wait(&self) -> Option<Duration> {
+        if self.is_done() {
+            return None;
+        }
+        Some(self.due.map_or(Duration::ZERO, |due| {
+            due.saturating_duration_since(Instant::now())
+        }))
+    }
+
+    /// Hand out the next frame right away, whether it is due or not, or `None` once the
+    /// animation is done playing. A frame that comes late pushes back the frames after it
+    /// instead of cutting them short.
+    pub fn tick(&mut self) -> Option<&'a ImageFrame> {
+        if self.is_done() {
+            return None;
+        }
+        let frame = &self.gif.image_frames[self.next];
+        let now = Instant::now();
+        self.due = Some(self.due.map_or(now, |due| due.max(now)) + display_time(frame.delay_time));
+
+        self.next += 1;
+        if self.next == self.gif.image_frames.len() {
+            self.next = 0;
+            self.play += 1;
+        }
+        Some(frame)
+    }
+
+    fn is_done(&self) -> bool {
+        self.gif.image_frames.is_empty() || self.plays.is_some_and(|plays| self.play >= plays)
+    }
+}
+
+impl<'a> Iterator for Ticker<'a> {
+    type Item = &'a ImageFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        thread::sleep(self.wait()?);
+        self.tick()
+    }
+}
+
+impl Gif {
+    /// Play the animation in real time: the returned [`Ticker`] yields every frame when it is
+    /// due to show, as many times as [`Gif::loop_count`] says. Frames with a delay of 0 or 10
+    /// ms show for 100 ms, like browsers do.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let gif = giffy::load_from_path("<gif path>").expect("Error loading GIF");
+    /// for frame in gif.ticker() {
+    ///     println!("showing a frame for {} ms", frame.delay_time as u32 * 10);
+    /// }
+    /// ```
+    pub fn ticker(&self) -> Ticker<'_> {
+        Ticker::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+
+    fn gif(loop_count: Option<u16>) -> Gif {
+        Gif {
+            width: 1,
+            height: 1,
+            image_frames: [2, 3]
+                .into_iter()
+                .map(|delay_time| ImageFrame {
+                    width: 1,
+                    height: 1,
+                    colors: vec![Color(delay_time, 0, 0)].into_boxed_slice(),
+                    delay_time: delay_time as u16,
+                    placeholder: false,
+                })
+                .collect(),
+            loop_count,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        }
+    }
+
+    #[test]
+    fn test_ticker() {
+        let animation = gif(Some(1));
+        let mut ticker = animation.ticker();
+        assert_eq!(Some(Duration::ZERO), ticker.wait());
+        let delays = std::iter::from_fn(|| ticker.tick())
+            .map(|frame| frame.delay_time)
+            .collect::<Vec<_>>();
+        // Played twice, without waiting.
+        assert_eq!(vec![2, 3, 2, 3], delays);
+        assert!(ticker.wait().is_none());
+
+        let mut ticker = animation.ticker().once();
+        ticker.tick();
+        assert!(ticker
+            .wait()
+            .is_some_and(|wait| wait <= Duration::from_millis(20)));
+        assert!(ticker.tick().is_some());
+        assert!(ticker.tick().is_none());
+
+        let start = Instant::now();
+        assert_eq!(2, animation.ticker().once().count());
+        // Waited for the first frame before the second.
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        let animation = gif(Some(0));
+        let mut forever = animation.ticker();
+        assert_eq!(100, std::iter::from_fn(|| forever.tick()).take(100).count());
+        assert_eq!(Duration::from_millis(100), display_time(1));
+    }
+}
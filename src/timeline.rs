@@ -0,0 +1,276 @@
+use crate::{Color, Gif, Rect};
+
+use std::fmt::Write;
+use std::time::Duration;
+
+/// When a frame of a [`Timeline`] shows, and what it changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineEntry {
+    /// The position of the frame in the animation, counting from 0.
+    pub index: usize,
+    /// When the frame starts showing, counted from the start of the first frame.
+    pub start: Duration,
+    /// How long the frame shows, as stored in the GIF. Many players show frames with a delay
+    /// of 0 or 10 ms for 100 ms instead.
+    pub duration: Duration,
+    /// The smallest rectangle around the pixels that differ from the previous frame, the whole
+    /// canvas for the first frame, or `None` if nothing changed.
+    pub dirty: Option<Rect>,
+}
+
+/// The timing of every frame of a GIF, as returned by [`Gif::timeline`], for players and
+/// editors that drive rendering from a timeline of their own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    /// The frames, in order.
+    pub entries: Vec<TimelineEntry>,
+    /// The number of times the animation should loop, as in [`Gif::loop_count`].
+    pub loop_count: Option<u16>,
+}
+
+impl Timeline {
+    /// How long one play of the animation takes.
+    pub fn duration(&self) -> Duration {
+        self.entries
+            .last()
+            .map_or(Duration::ZERO, |entry| entry.start + entry.duration)
+    }
+
+    /// Write the timeline as a JSON object, with times in milliseconds:
+    ///
+    /// ```text
+    /// {"loop_count": 0, "duration_ms": 200, "frames": [
+    ///   {"index": 0, "start_ms": 0, "duration_ms": 100, "dirty": {"left": 0, "top": 0, "width": 8, "height": 8}},
+    ///   {"index": 1, "start_ms": 100, "duration_ms": 100, "dirty": null}
+    /// ]}
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut output = format!(
+            "{{\"loop_count\": {}, \"duration_ms\": {}, \"frames\": [\n",
+            self.loop_count
+                .map_or("null".to_string(), |n| n.to_string()),
+            self.duration().as_millis()
+        );
+        for (i, entry) in self.entries.iter().enumerate() {
+            let _ = write!(
+                output,
+                "  {{\"index\": {}, \"start_ms\": {}, \"duration_ms\": {}, \"dirty\": ",
+                entry.index,
+                entry.start.as_millis(),
+                entry.duration.as_millis()
+            );
+            match entry.dirty {
+                Some(rect) => {
+                    let _ = write!(
+                        output,
+                        "{{\"left\": {}, \"top\": {}, \"width\": {}, \"height\": {}}}}}",
+                        rect.left, rect.top, rect.width, rect.height
+                    );
+                }
+                None => output.push_str("null}"),
+            }
+            output.push_str(if i + 1 < self.entries.len() {
+                ",\n"
+            } else {
+                "\n"
+            });
+        }
+        output.push_str("]}\n");
+        output
+    }
+
+    /// Write the timeline in the style of WebVTT, one cue per frame with the frame number and
+    /// its dirty rectangle as `left,top widthxheight`:
+    ///
+    /// ```text
+    /// WEBVTT
+    ///
+    /// 00:00:00.000 --> 00:00:00.100
+    /// frame 0 dirty 0,0 8x8
+    ///
+    /// 00:00:00.100 --> 00:00:00.200
+    /// frame 1 dirty none
+    /// ```
+    pub fn to_vtt(&self) -> String {
+        let mut output = String::from("WEBVTT\n");
+        for entry in self.entries.iter() {
+            let _ = write!(
+                output,
+                "\n{} --> {}\nframe {} dirty ",
+                timestamp(entry.start),
+                timestamp(entry.start + entry.duration),
+                entry.index
+            );
+            match entry.dirty {
+                Some(rect) => {
+                    let _ = writeln!(
+                        output,
+                        "{},{} {}x{}",
+                        rect.left, rect.top, rect.width, rect.height
+                    );
+                }
+                None => output.push_str("none\n"),
+            }
+        }
+        output
+    }
+}
+
+// Formats `time` as hh:mm:ss.ttt.
+fn timestamp(time: Duration) -> String {
+    let ms = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+// The smallest rectangle around the pixels that differ between two frames of `width` pixels
+// per row, or `None` if they are the same.
+fn dirty_rect(previous: &[Color], current: &[Color], width: usize) -> Option<Rect> {
+    let (mut left, mut top, mut right, mut bottom) = (usize::MAX, usize::MAX, 0, 0);
+    for (i, _) in previous
+        .iter()
+        .zip(current.iter())
+        .enumerate()
+        .filter(|(_, (a, b))| a != b)
+    {
+        let (x, y) = (i % width, i / width);
+        left = left.min(x);
+        top = top.min(y);
+        right = right.max(x + 1);
+        bottom = bottom.max(y + 1);
+    }
+
+    (left != usize::MAX).then(|| {
+        Rect::new(
+            left as u32,
+            top as u32,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        )
+    })
+}
+
+impl Gif {
+    /// List when every frame starts, how long it shows and which part of the canvas it
+    /// changes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let gif = giffy::load_from_path("<gif path>").expect("Error loading GIF");
+    /// std::fs::write("timeline.json", gif.timeline().to_json()).expect("Unable to write file");
+    /// ```
+    pub fn timeline(&self) -> Timeline {
+        let width = (self.width as usize).max(1);
+        let mut start = Duration::ZERO;
+        let entries = self
+            .image_frames
+            .iter()
+            .enumerate()
+            .map(|(index, frame)| {
+                let dirty = match index {
+                    0 => Some(Rect::new(0, 0, self.width, self.height)),
+                    _ => dirty_rect(&self.image_frames[index - 1].colors, &frame.colors, width),
+                };
+                let duration = Duration::from_millis(frame.delay_time as u64 * 10);
+                let entry = TimelineEntry {
+                    index,
+                    start,
+                    duration,
+                    dirty,
+                };
+                start += duration;
+                entry
+            })
+            .collect();
+
+        Timeline {
+            entries,
+            loop_count: self.loop_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ImageFrame;
+
+    fn gif() -> Gif {
+        let frame = |colors: Vec<Color>, delay_time| ImageFrame {
+            width: 3,
+            height: 2,
+            colors: colors.into_boxed_slice(),
+            delay_time,
+            placeholder: false,
+        };
+        let black = vec![Color(0, 0, 0); 6];
+        let mut dot = black.clone();
+        dot[5] = Color(255, 0, 0);
+        Gif {
+            width: 3,
+            height: 2,
+            image_frames: vec![frame(black, 10), frame(dot.clone(), 5), frame(dot, 6000)],
+            loop_count: Some(0),
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        }
+    }
+
+    #[test]
+    fn test_timeline() {
+        let timeline = gif().timeline();
+        let ms = Duration::from_millis;
+        assert_eq!(
+            vec![
+                TimelineEntry {
+                    index: 0,
+                    start: ms(0),
+                    duration: ms(100),
+                    dirty: Some(Rect::new(0, 0, 3, 2)),
+                },
+                TimelineEntry {
+                    index: 1,
+                    start: ms(100),
+                    duration: ms(50),
+                    dirty: Some(Rect::new(2, 1, 1, 1)),
+                },
+                TimelineEntry {
+                    index: 2,
+                    start: ms(150),
+                    duration: ms(60_000),
+                    dirty: None,
+                },
+            ],
+            timeline.entries
+        );
+        assert_eq!(ms(60_150), timeline.duration());
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            "{\"loop_count\": 0, \"duration_ms\": 60150, \"frames\": [\n\
+             \x20 {\"index\": 0, \"start_ms\": 0, \"duration_ms\": 100, \"dirty\": \
+             {\"left\": 0, \"top\": 0, \"width\": 3, \"height\": 2}},\n\
+             \x20 {\"index\": 1, \"start_ms\": 100, \"duration_ms\": 50, \"dirty\": \
+             {\"left\": 2, \"top\": 1, \"width\": 1, \"height\": 1}},\n\
+             \x20 {\"index\": 2, \"start_ms\": 150, \"duration_ms\": 60000, \"dirty\": null}\n\
+             ]}\n",
+            gif().timeline().to_json()
+        );
+    }
+
+    #[test]
+    fn test_to_vtt() {
+        let vtt = gif().timeline().to_vtt();
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:00.100\nframe 0 dirty 0,0 3x2\n"));
+        assert!(vtt.ends_with("\n00:00:00.150 --> 00:01:00.150\nframe 2 dirty none\n"));
+    }
+}
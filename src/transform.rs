@@ -0,0 +1,1245 @@
+use crate::font;
+use crate::quantizer;
+use crate::{Color, Gif, ImageFrame, Rect};
+
+use std::collections::HashMap;
+
+/// The filter used by [`Gif::resize`] to compute the resized pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResizeFilter {
+    /// Take the closest source pixel. Fast and keeps hard edges, which suits pixel art.
+    Nearest,
+    /// Blend the four closest source pixels. Smoother, but adds new colors.
+    Bilinear,
+}
+
+/// Where [`Gif::caption`] draws its bar.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaptionPosition {
+    /// Along the top edge.
+    Top,
+    /// Along the bottom edge.
+    Bottom,
+}
+
+/// Controls how [`Gif::caption`] draws text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptionOptions {
+    /// Where the caption bar goes. Defaults to [`CaptionPosition::Bottom`].
+    pub position: CaptionPosition,
+    /// How many pixels wide and tall every pixel of the built-in 5x8 font is. `None` picks the
+    /// largest scale that fits the longest line, up to a bar covering half of the GIF. Defaults
+    /// to `None`.
+    pub scale: Option<u32>,
+    /// The color of the text. Defaults to white.
+    pub text_color: Color,
+    /// The color of the bar behind the text. Defaults to black.
+    pub bar_color: Color,
+}
+
+impl Default for CaptionOptions {
+    fn default() -> Self {
+        Self {
+            position: CaptionPosition::Bottom,
+            scale: None,
+            text_color: Color(255, 255, 255),
+            bar_color: Color(0, 0, 0),
+        }
+    }
+}
+
+// Every character takes up a glyph and a column of spacing, and every line a glyph and a row
+// of padding above and below.
+const CHAR_ADVANCE: usize = font::GLYPH_WIDTH + 1;
+const LINE_HEIGHT: usize = font::GLYPH_HEIGHT + 2;
+
+impl Gif {
+    /// Re-quantize all frames to a shared palette of at most `max_colors` colors, optionally
+    /// with Floyd-Steinberg dithering. Saving the GIF afterwards gives a smaller file, since
+    /// fewer colors mean a smaller color table and shorter LZW codes.
+    ///
+    /// `max_colors` is clamped between 1 and 256.
+    pub fn reduce_colors(&mut self, max_colors: usize, dither: bool) {
+        let mut histogram = HashMap::new();
+        for frame in self.image_frames.iter() {
+            quantizer::add_to_histogram(&mut histogram, &frame.colors);
+        }
+
+        let palette = quantizer::palette(&histogram, max_colors);
+        let lookup = histogram
+            .keys()
+            .map(|c| (*c, palette[quantizer::nearest(&palette, *c) as usize]))
+            .collect::<HashMap<_, _>>();
+
+        for frame in self.image_frames.iter_mut() {
+            if dither {
+                let indices = quantizer::dither(&frame.colors, self.width as usize, &palette);
+                for (c, i) in frame.colors.iter_mut().zip(indices) {
+                    *c = palette[i as usize];
+                }
+            } else {
+                for c in frame.colors.iter_mut() {
+                    *c = lookup[c];
+                }
+            }
+        }
+    }
+
+    /// Crop every frame to `rect`, returning a new GIF the size of `rect`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `rect` is empty or does not fit inside the GIF.
+    pub fn crop(&self, rect: Rect) -> Result<Gif, String> {
+        if rect.width == 0
+            || rect.height == 0
+            || rect.left as u64 + rect.width as u64 > self.width as u64
+            || rect.top as u64 + rect.height as u64 > self.height as u64
+        {
+            return Err(format!(
+                "Error: crop rectangle {:?} does not fit in {}x{}",
+                rect, self.width, self.height
+            ));
+        }
+
+        let (left, top) = (rect.left as usize, rect.top as usize);
+        let (width, height) = (rect.width as usize, rect.height as usize);
+        let image_width = self.width as usize;
+
+        let image_frames = self
+            .image_frames
+            .iter()
+            .map(|frame| {
+                let mut colors = Vec::with_capacity(width * height);
+                for y in top..top + height {
+                    let offset = y * image_width + left;
+                    colors.extend_from_slice(&frame.colors[offset..offset + width]);
+                }
+
+                ImageFrame {
+                    width: width as u32,
+                    height: height as u32,
+                    colors: colors.into_boxed_slice(),
+                    delay_time: frame.delay_time,
+                    placeholder: frame.placeholder,
+                }
+            })
+            .collect();
+
+        Ok(Gif {
+            width: rect.width,
+            height: rect.height,
+            image_frames,
+            loop_count: self.loop_count,
+            comments: self.comments.clone(),
+            pixel_aspect_ratio: self.pixel_aspect_ratio,
+            transparent_color: self.transparent_color,
+        })
+    }
+
+    /// Add `top`, `right`, `bottom` and `left` pixels of `fill` around every frame, returning
+    /// a new GIF that much larger, such as to add margins or to reach a size a platform asks
+    /// for. Fill with the [`Gif::transparent_color`] to add transparent margins.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use giffy::Color;
+    ///
+    /// let gif = giffy::load_from_path("<gif path>").expect("Error loading GIF");
+    /// let framed = gif
+    ///     .pad(8, 8, 8, 8, Color::from([255, 255, 255]))
+    ///     .expect("Error padding GIF");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the padded GIF would be wider or taller than the
+    /// 65535 pixels a GIF can hold.
+    pub fn pad(
+        &self,
+        top: u32,
+        right: u32,
+        bottom: u32,
+        left: u32,
+        fill: Color,
+    ) -> Result<Gif, String> {
+        let width = self.width as u64 + left as u64 + right as u64;
+        let height = self.height as u64 + top as u64 + bottom as u64;
+        if width > u16::MAX as u64 || height > u16::MAX as u64 {
+            return Err(format!(
+                "Error: padded size {}x{} is larger than {}x{}",
+                width,
+                height,
+                u16::MAX,
+                u16::MAX
+            ));
+        }
+
+        let (width, height) = (width as usize, height as usize);
+        let image_width = self.width as usize;
+        let image_frames = self
+            .image_frames
+            .iter()
+            .map(|frame| {
+                let mut colors = vec![fill; width * height];
+                for (y, row) in frame.colors.chunks_exact(image_width.max(1)).enumerate() {
+                    let offset = (top as usize + y) * width + left as usize;
+                    colors[offset..offset + row.len()].copy_from_slice(row);
+                }
+
+                ImageFrame {
+                    width: width as u32,
+                    height: height as u32,
+                    colors: colors.into_boxed_slice(),
+                    delay_time: frame.delay_time,
+                    placeholder: frame.placeholder,
+                }
+            })
+            .collect();
+
+        Ok(Gif {
+            width: width as u32,
+            height: height as u32,
+            image_frames,
+            loop_count: self.loop_count,
+            comments: self.comments.clone(),
+            pixel_aspect_ratio: self.pixel_aspect_ratio,
+            transparent_color: self.transparent_color,
+        })
+    }
+
+    /// Resize every frame to `width` x `height` using `filter`, returning the resized GIF.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `width` or `height` is 0, or if this GIF is
+    /// empty.
+    pub fn resize(&self, width: u32, height: u32, filter: ResizeFilter) -> Result<Gif, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("Error: invalid size {}x{}", width, height));
+        }
+        if self.width == 0 || self.height == 0 {
+            return Err("Error: cannot resize an empty GIF".into());
+        }
+
+        let src_width = self.width as usize;
+        let src_height = self.height as usize;
+        let (dst_width, dst_height) = (width as usize, height as usize);
+
+        // Maps a destination pixel to source coordinates, lining up pixel centers.
+        let scale_x = src_width as f32 / dst_width as f32;
+        let scale_y = src_height as f32 / dst_height as f32;
+        let source = |x: usize, scale: f32, max: usize| {
+            ((x as f32 + 0.5) * scale - 0.5).clamp(0.0, (max - 1) as f32)
+        };
+
+        let image_frames = self
+            .image_frames
+            .iter()
+            .map(|frame| {
+                let mut colors = Vec::with_capacity(dst_width * dst_height);
+                for y in 0..dst_height {
+                    let sy = source(y, scale_y, src_height);
+                    for x in 0..dst_width {
+                        let sx = source(x, scale_x, src_width);
+                        colors.push(match filter {
+                            ResizeFilter::Nearest => {
+                                frame.colors[sy.round() as usize * src_width + sx.round() as usize]
+                            }
+                            ResizeFilter::Bilinear => {
+                                bilinear(&frame.colors, src_width, src_height, sx, sy)
+                            }
+                        });
+                    }
+                }
+
+                ImageFrame {
+                    width,
+                    height,
+                    colors: colors.into_boxed_slice(),
+                    delay_time: frame.delay_time,
+                    placeholder: frame.placeholder,
+                }
+            })
+            .collect();
+
+        Ok(Gif {
+            width,
+            height,
+            image_frames,
+            loop_count: self.loop_count,
+            comments: self.comments.clone(),
+            pixel_aspect_ratio: self.pixel_aspect_ratio,
+            transparent_color: self.transparent_color,
+        })
+    }
+
+    /// Resample every frame to square pixels as set by [`Gif::pixel_aspect_ratio`], keeping
+    /// the height and stretching or squashing the width, so that GIFs made for non-square
+    /// pixels do not show squashed. A GIF that already has square pixels is returned as is.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use giffy::ResizeFilter;
+    ///
+    /// let gif = giffy::load_from_path("<gif path>").expect("Error loading GIF");
+    /// let gif = gif
+    ///     .correct_aspect_ratio(ResizeFilter::Bilinear)
+    ///     .expect("Error correcting GIF");
+    /// assert_eq!(None, gif.pixel_aspect_ratio);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if this GIF is empty.
+    pub fn correct_aspect_ratio(&self, filter: ResizeFilter) -> Result<Gif, String> {
+        let ratio = match self.pixel_aspect_ratio {
+            Some(ratio) if ratio != 1.0 => ratio,
+            _ => {
+                return Ok(Gif {
+                    pixel_aspect_ratio: None,
+                    ..self.clone()
+                })
+            }
+        };
+
+        let width = ((self.width as f32 * ratio).round() as u32).max(1);
+        let mut gif = self.resize(width, self.height, filter)?;
+        gif.pixel_aspect_ratio = None;
+        Ok(gif)
+    }
+
+    /// Scale the animation with `filter` to fit inside `width` x `height` without distorting
+    /// it, and center it on `background`, returning a GIF of exactly that size, such as for an
+    /// avatar or banner slot. The animation is scaled up as well as down.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use giffy::{Color, ResizeFilter};
+    ///
+    /// let gif = giffy::load_from_path("<gif path>").expect("Error loading GIF");
+    /// let avatar = gif
+    ///     .fit(128, 128, Color::from([0, 0, 0]), ResizeFilter::Bilinear)
+    ///     .expect("Error fitting GIF");
+    /// assert_eq!((128, 128), (avatar.width, avatar.height));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `width` or `height` is 0 or more than 65535, or
+    /// if this GIF is empty.
+    pub fn fit(
+        &self,
+        width: u32,
+        height: u32,
+        background: Color,
+        filter: ResizeFilter,
+    ) -> Result<Gif, String> {
+        if width == 0 || height == 0 || width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(format!("Error: cannot fit a GIF in {}x{}", width, height));
+        }
+        if self.width == 0 || self.height == 0 {
+            return Err("Error: GIF is empty".into());
+        }
+
+        let scale = (width as f64 / self.width as f64).min(height as f64 / self.height as f64);
+        let scaled_width = ((self.width as f64 * scale).round() as u32).clamp(1, width);
+        let scaled_height = ((self.height as f64 * scale).round() as u32).clamp(1, height);
+        let scaled = if (scaled_width, scaled_height) == (self.width, self.height) {
+            self.clone()
+        } else {
+            self.resize(scaled_width, scaled_height, filter)?
+        };
+
+        let (left, top) = ((width - scaled_width) / 2, (height - scaled_height) / 2);
+        scaled.pad(
+            top,
+            width - scaled_width - left,
+            height - scaled_height - top,
+            left,
+            background,
+        )
+    }
+
+    /// Rotate every frame 90 degrees clockwise, swapping the width and height.
+    pub fn rotate90(&mut self) {
+        let height = self.height as usize;
+        self.remap(self.height, self.width, |x, y| (y, height - 1 - x));
+    }
+
+    /// Rotate every frame 180 degrees.
+    pub fn rotate180(&mut self) {
+        let (width, height) = (self.width as usize, self.height as usize);
+        self.remap(self.width, self.height, |x, y| {
+            (width - 1 - x, height - 1 - y)
+        });
+    }
+
+    /// Rotate every frame 270 degrees clockwise, swapping the width and height.
+    pub fn rotate270(&mut self) {
+        let width = self.width as usize;
+        self.remap(self.height, self.width, |x, y| (width - 1 - y, x));
+    }
+
+    /// Mirror every frame left to right.
+    pub fn flip_horizontal(&mut self) {
+        let width = self.width as usize;
+        self.remap(self.width, self.height, |x, y| (width - 1 - x, y));
+    }
+
+    /// Mirror every frame top to bottom.
+    pub fn flip_vertical(&mut self) {
+        let height = self.height as usize;
+        self.remap(self.width, self.height, |x, y| (x, height - 1 - y));
+    }
+
+    /// Alpha-blend a `width` x `height` RGBA image, such as a logo or watermark, onto every
+    /// frame with its top left corner at (`left`, `top`). Parts of the image outside of the GIF
+    /// are left out.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `rgba` does not hold `width` x `height` pixels.
+    pub fn overlay(
+        &mut self,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        left: i32,
+        top: i32,
+    ) -> Result<(), String> {
+        let (width, height) = (width as i64, height as i64);
+        if rgba.len() as i64 != width * height * 4 {
+            return Err(format!(
+                "Error: overlay has {} bytes, expected {}",
+                rgba.len(),
+                width * height * 4
+            ));
+        }
+
+        let (left, top) = (left as i64, top as i64);
+        let x_range = left.max(0)..(left + width).min(self.width as i64);
+        let y_range = top.max(0)..(top + height).min(self.height as i64);
+
+        for frame in self.image_frames.iter_mut() {
+            for y in y_range.clone() {
+                for x in x_range.clone() {
+                    let i = (((y - top) * width + (x - left)) * 4) as usize;
+                    let alpha = rgba[i + 3] as u32;
+                    if alpha == 0 {
+                        continue;
+                    }
+
+                    let c = &mut frame.colors[(y * self.width as i64 + x) as usize];
+                    let blend = |src: u8, dst: u8| {
+                        ((src as u32 * alpha + dst as u32 * (255 - alpha) + 127) / 255) as u8
+                    };
+                    *c = Color(
+                        blend(rgba[i], c.0),
+                        blend(rgba[i + 1], c.1),
+                        blend(rgba[i + 2], c.2),
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Draw `text` centered on a bar across every frame, using a built-in bitmap font. Lines
+    /// are split at `\n`, characters outside of printable ASCII are drawn as `?` and text
+    /// that does not fit is cut off.
+    pub fn caption(&mut self, text: &str, options: &CaptionOptions) {
+        let (width, height) = (self.width as usize, self.height as usize);
+        let lines = text.lines().collect::<Vec<_>>();
+        if lines.is_empty() || width == 0 || height == 0 {
+            return;
+        }
+
+        let line_width = |line: &str| (line.chars().count() * CHAR_ADVANCE).saturating_sub(1);
+        let longest = lines.iter().map(|l| line_width(l)).max().unwrap_or(0);
+        let scale = match options.scale {
+            Some(scale) => scale.max(1) as usize,
+            None => (width / longest.max(1))
+                .min(height / 2 / (lines.len() * LINE_HEIGHT))
+                .max(1),
+        };
+
+        let bar_height = (lines.len() * LINE_HEIGHT * scale).min(height);
+        let mut bar = vec![options.bar_color; width * bar_height];
+        for (row, line) in lines.iter().enumerate() {
+            let left = (width as isize - (line_width(line) * scale) as isize) / 2;
+            let top = (row * LINE_HEIGHT + 1) * scale;
+
+            for (i, c) in line.chars().enumerate() {
+                for gy in 0..font::GLYPH_HEIGHT * scale {
+                    for gx in 0..font::GLYPH_WIDTH * scale {
+                        if !font::is_set(c, gx / scale, gy / scale) {
+                            continue;
+                        }
+
+                        let x = left + (i * CHAR_ADVANCE * scale + gx) as isize;
+                        let y = top + gy;
+                        if x >= 0 && (x as usize) < width && y < bar_height {
+                            bar[y * width + x as usize] = options.text_color;
+                        }
+                    }
+                }
+            }
+        }
+
+        let offset = match options.position {
+            CaptionPosition::Top => 0,
+            CaptionPosition::Bottom => (height - bar_height) * width,
+        };
+        for frame in self.image_frames.iter_mut() {
+            frame.colors[offset..offset + bar.len()].copy_from_slice(&bar);
+        }
+    }
+
+    /// Replace the delay time of every frame with the result of `f`, which is given the frame
+    /// index and its current delay time in hundredths of a second. This can be used to ramp
+    /// the speed, hold the last frame or enforce a minimum delay.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// let mut src = File::open("<gif path>").expect("File not found");
+    /// let mut gif = giffy::load(&mut src).expect("Error loading GIF");
+    ///
+    /// // Browsers play delays below 2 as 10, so make them explicit.
+    /// gif.retime(|_, delay| if delay < 2 { 10 } else { delay });
+    /// ```
+    pub fn retime<F>(&mut self, mut f: F)
+    where
+        F: FnMut(usize, u16) -> u16,
+    {
+        for (i, frame) in self.image_frames.iter_mut().enumerate() {
+            frame.delay_time = f(i, frame.delay_time);
+        }
+    }
+
+    /// Resample the animation to `fps` frames per second: every frame of the new GIF shows
+    /// for the same time, and is the frame of this GIF that shows at its start. Frames are
+    /// repeated to fill long delays and skipped when they are shorter than a frame. As delays
+    /// are in hundredths of a second, they alternate when 100 is not a multiple of `fps`, so
+    /// that the frames do not drift. The last frame only shows until the end of the animation,
+    /// so the new GIF takes as long as this one.
+    ///
+    /// The delays are taken as stored, so [`Gif::retime`] the frames first to make the very
+    /// short delays browsers slow down explicit.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// let mut src = File::open("<gif path>").expect("File not found");
+    /// let gif = giffy::load(&mut src).expect("Error loading GIF");
+    /// let gif = gif.resample(25.0).expect("Error resampling GIF");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `fps` is not above 0 and at most 100, or if the
+    /// animation takes no time.
+    pub fn resample(&self, fps: f32) -> Result<Gif, String> {
+        if !(fps > 0.0 && fps <= 100.0) {
+            return Err(format!(
+                "Error: {} frames per second is not between 0 and 100",
+                fps
+            ));
+        }
+        let total = self
+            .image_frames
+            .iter()
+            .map(|frame| frame.delay_time as u64)
+            .sum::<u64>();
+        if total == 0 {
+            return Err("Error: the animation takes no time".into());
+        }
+
+        // When the new frame `k` starts, rounded to hundredths of a second.
+        let start = |k: u64| (k as f64 * 100.0 / fps as f64).round() as u64;
+        let mut frames = vec![];
+        let (mut source, mut end) = (0, self.image_frames[0].delay_time as u64);
+        let mut k = 0;
+        while start(k) < total {
+            while start(k) >= end {
+                source += 1;
+                end += self.image_frames[source].delay_time as u64;
+            }
+            let mut frame = self.image_frames[source].clone();
+            frame.delay_time = (start(k + 1).min(total) - start(k)) as u16;
+            frames.push(frame);
+            k += 1;
+        }
+
+        Ok(Gif {
+            width: self.width,
+            height: self.height,
+            image_frames: frames,
+            loop_count: self.loop_count,
+            comments: self.comments.clone(),
+            pixel_aspect_ratio: self.pixel_aspect_ratio,
+            transparent_color: self.transparent_color,
+        })
+    }
+
+    /// Smooth the animation by cross-fading from every frame to the next one with up to
+    /// `steps` blended frames in between. The delay of every frame is shared with the frames
+    /// blended after it, so the animation takes as long as before. The last frame fades into
+    /// the first one if the animation loops.
+    ///
+    /// No frame gets a delay below 2 hundredths of a second, which browsers would slow down, so
+    /// frames with short delays get fewer blended frames, and frames with a delay below 4 get
+    /// none.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// let mut src = File::open("<gif path>").expect("File not found");
+    /// let gif = giffy::load(&mut src).expect("Error loading GIF");
+    /// let smooth = gif.interpolate(3);
+    /// ```
+    pub fn interpolate(&self, steps: usize) -> Gif {
+        let count = self.image_frames.len();
+        let mut frames = Vec::with_capacity(count * (steps + 1));
+        for (i, frame) in self.image_frames.iter().enumerate() {
+            let next = match self.image_frames.get(i + 1) {
+                Some(next) => next,
+                None if self.loop_count.is_some() => &self.image_frames[0],
+                None => {
+                    frames.push(frame.clone());
+                    break;
+                }
+            };
+
+            let parts = (steps + 1).min(frame.delay_time as usize / 2).max(1);
+            let delay = |part: usize| {
+                let share = frame.delay_time as usize / parts;
+                (share + (part < frame.delay_time as usize % parts) as usize) as u16
+            };
+            frames.push(ImageFrame {
+                delay_time: delay(0),
+                ..frame.clone()
+            });
+            for part in 1..parts {
+                let t = part as f32 / parts as f32;
+                let blend = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+                let colors = frame
+                    .colors
+                    .iter()
+                    .zip(next.colors.iter())
+                    .map(|(a, b)| Color(blend(a.0, b.0), blend(a.1, b.1), blend(a.2, b.2)))
+                    .collect();
+                frames.push(ImageFrame {
+                    width: frame.width,
+                    height: frame.height,
+                    colors,
+                    delay_time: delay(part),
+                    placeholder: false,
+                });
+            }
+        }
+
+        Gif {
+            width: self.width,
+            height: self.height,
+            image_frames: frames,
+            loop_count: self.loop_count,
+            comments: self.comments.clone(),
+            pixel_aspect_ratio: self.pixel_aspect_ratio,
+            transparent_color: self.transparent_color,
+        }
+    }
+
+    /// Replace every pixel with the result of `f`, which is given the column and row of the
+    /// pixel, the frame index and its current color.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use giffy::Color;
+    /// use std::fs::File;
+    ///
+    /// let mut src = File::open("<gif path>").expect("File not found");
+    /// let mut gif = giffy::load(&mut src).expect("Error loading GIF");
+    ///
+    /// // Darken every other row, like an old CRT.
+    /// gif.map_pixels(|_, y, _, c| match y % 2 {
+    ///     0 => c,
+    ///     _ => Color::from([c.r() / 2, c.g() / 2, c.b() / 2]),
+    /// });
+    /// ```
+    pub fn map_pixels<F>(&mut self, mut f: F)
+    where
+        F: FnMut(u32, u32, usize, Color) -> Color,
+    {
+        let width = self.width.max(1);
+        for (i, frame) in self.image_frames.iter_mut().enumerate() {
+            for (n, c) in frame.colors.iter_mut().enumerate() {
+                let n = n as u32;
+                *c = f(n % width, n / width, i, *c);
+            }
+        }
+    }
+
+    /// Replace every color with the result of `f`. Unlike [`Gif::map_pixels`], `f` is called
+    /// once per distinct color rather than once per pixel, which is much faster for effects
+    /// that only depend on the color, as GIFs have few colors.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use giffy::Color;
+    /// use std::fs::File;
+    ///
+    /// let mut src = File::open("<gif path>").expect("File not found");
+    /// let mut gif = giffy::load(&mut src).expect("Error loading GIF");
+    ///
+    /// // Turn the animation into shades of gray.
+    /// gif.map_colors(|c| {
+    ///     let luma = (c.r() as u32 * 299 + c.g() as u32 * 587 + c.b() as u32 * 114) / 1000;
+    ///     Color::from([luma as u8; 3])
+    /// });
+    /// ```
+    pub fn map_colors<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Color) -> Color,
+    {
+        let mut mapped = HashMap::new();
+        for frame in self.image_frames.iter_mut() {
+            for c in frame.colors.iter_mut() {
+                *c = *mapped.entry(*c).or_insert_with(|| f(*c));
+            }
+        }
+    }
+
+    /// Make the pixels of every frame that are close to `key` transparent, such as the green
+    /// background of a green screen recording. A pixel is close when none of its red, green and
+    /// blue values differ from those of `key` by more than `tolerance`.
+    ///
+    /// The pixels are set to `key`, which becomes the [`Gif::transparent_color`], so they are
+    /// written as transparent when the GIF is saved. Pixels that were transparent before stay
+    /// transparent.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use giffy::Color;
+    ///
+    /// let mut gif = giffy::load_from_path("<gif path>").expect("Error loading GIF");
+    /// gif.key_out(Color::from([0, 255, 0]), 40);
+    /// gif.save("<output path>").expect("Error saving GIF");
+    /// ```
+    pub fn key_out(&mut self, key: Color, tolerance: u8) {
+        let previous = self.transparent_color.replace(key);
+        let close = |a: u8, b: u8| a.abs_diff(b) <= tolerance;
+        for frame in self.image_frames.iter_mut() {
+            for c in frame.colors.iter_mut() {
+                if Some(*c) == previous
+                    || (close(c.0, key.0) && close(c.1, key.1) && close(c.2, key.2))
+                {
+                    *c = key;
+                }
+            }
+        }
+    }
+
+    // Rebuild every frame as a `width` x `height` image, taking each pixel from the source
+    // coordinates given by `source`.
+    fn remap<F>(&mut self, width: u32, height: u32, source: F)
+    where
+        F: Fn(usize, usize) -> (usize, usize),
+    {
+        let src_width = self.width as usize;
+
+        for frame in self.image_frames.iter_mut() {
+            let mut colors = Vec::with_capacity(frame.colors.len());
+            for y in 0..height as usize {
+                for x in 0..width as usize {
+                    let (sx, sy) = source(x, y);
+                    colors.push(frame.colors[sy * src_width + sx]);
+                }
+            }
+            frame.colors = colors.into_boxed_slice();
+            frame.width = width;
+            frame.height = height;
+        }
+
+        self.width = width;
+        self.height = height;
+    }
+}
+
+fn bilinear(colors: &[Color], width: usize, height: usize, x: f32, y: f32) -> Color {
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+    let [a, b, c, d] = [
+        colors[y0 * width + x0],
+        colors[y0 * width + x1],
+        colors[y1 * width + x0],
+        colors[y1 * width + x1],
+    ];
+    let blend = |a: u8, b: u8, c: u8, d: u8| {
+        let top = a as f32 + (b as f32 - a as f32) * fx;
+        let bottom = c as f32 + (d as f32 - c as f32) * fx;
+        (top + (bottom - top) * fy).round() as u8
+    };
+
+    Color(
+        blend(a.0, b.0, c.0, d.0),
+        blend(a.1, b.1, c.1, d.1),
+        blend(a.2, b.2, c.2, d.2),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    use std::collections::HashSet;
+
+    fn gradient_gif() -> Gif {
+        let frames = (0..3u8).map(|f| (0..=255u8).map(|i| Color(i, f * 40, 255 - i)).collect());
+        testing::gif(16, 16, frames)
+    }
+
+    fn unique_colors(gif: &Gif) -> usize {
+        gif.image_frames
+            .iter()
+            .flat_map(|f| f.colors.iter())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    #[test]
+    fn test_reduce_colors() {
+        for dither in [false, true] {
+            let mut gif = gradient_gif();
+            gif.reduce_colors(8, dither);
+            assert!(unique_colors(&gif) <= 8);
+
+            let (gif, output) = testing::encoded(gif);
+            let decoded = crate::load(&mut output.as_slice()).unwrap();
+            for (expected, actual) in gif.image_frames.iter().zip(decoded.image_frames.iter()) {
+                assert_eq!(expected.colors, actual.colors);
+            }
+        }
+    }
+
+    #[test]
+    fn test_crop() {
+        let gif = gradient_gif();
+        let cropped = gif.crop(Rect::new(2, 3, 4, 5)).unwrap();
+
+        assert_eq!(4, cropped.width);
+        assert_eq!(5, cropped.height);
+        assert_eq!(gif.loop_count, cropped.loop_count);
+        for (original, frame) in gif.image_frames.iter().zip(cropped.image_frames.iter()) {
+            assert_eq!(original.delay_time, frame.delay_time);
+            assert_eq!(20, frame.colors.len());
+            for y in 0..5 {
+                for x in 0..4 {
+                    assert_eq!(
+                        original.colors[(y + 3) * 16 + x + 2],
+                        frame.colors[y * 4 + x]
+                    );
+                }
+            }
+        }
+
+        assert!(gif.crop(Rect::new(10, 0, 7, 16)).is_err());
+        assert!(gif.crop(Rect::new(0, 0, 0, 16)).is_err());
+    }
+
+    #[test]
+    fn test_pad() {
+        let gif = gradient_gif();
+        let white = Color(255, 255, 255);
+        let padded = gif.pad(1, 2, 3, 4, white).unwrap();
+        assert_eq!((22, 20), (padded.width, padded.height));
+        for (original, frame) in gif.image_frames.iter().zip(&padded.image_frames) {
+            assert_eq!((22, 20), frame.dimensions());
+            assert_eq!(white, frame.colors[0]);
+            assert_eq!(original.colors[0], frame.colors[22 + 4]);
+            assert_eq!(original.colors[255], frame.colors[16 * 22 + 19]);
+            assert_eq!(white, frame.colors[16 * 22 + 20]);
+            assert_eq!(white, frame.colors[17 * 22 + 4]);
+        }
+
+        assert!(gif.pad(0, u16::MAX as u32, 0, 0, white).is_err());
+    }
+
+    #[test]
+    fn test_fit() {
+        let gif = gradient_gif().crop(Rect::new(0, 0, 16, 8)).unwrap();
+        let black = Color(0, 0, 0);
+
+        // Scaled down to 10x5, with 2 rows above and 3 below.
+        let fitted = gif.fit(10, 10, black, ResizeFilter::Nearest).unwrap();
+        assert_eq!((10, 10), (fitted.width, fitted.height));
+        let frame = &fitted.image_frames[0];
+        assert!(frame.colors[..20].iter().all(|c| *c == black));
+        assert_eq!(gif.image_frames[0].colors[0], frame.colors[20]);
+        assert!(frame.colors[70..].iter().all(|c| *c == black));
+
+        // Scaled up, with columns on both sides.
+        let fitted = gif.fit(40, 16, black, ResizeFilter::Nearest).unwrap();
+        assert_eq!(40, fitted.width);
+        let frame = &fitted.image_frames[0];
+        assert_eq!(black, frame.colors[3]);
+        assert_eq!(gif.image_frames[0].colors[0], frame.colors[4]);
+        assert_eq!(black, frame.colors[36]);
+
+        assert!(gif.fit(0, 10, black, ResizeFilter::Nearest).is_err());
+    }
+
+    #[test]
+    fn test_resize_nearest() {
+        let gif = gradient_gif();
+
+        let doubled = gif.resize(32, 32, ResizeFilter::Nearest).unwrap();
+        assert_eq!(32, doubled.width);
+        assert_eq!(32, doubled.height);
+        for (original, frame) in gif.image_frames.iter().zip(doubled.image_frames.iter()) {
+            for y in 0..32 {
+                for x in 0..32 {
+                    assert_eq!(
+                        original.colors[y / 2 * 16 + x / 2],
+                        frame.colors[y * 32 + x]
+                    );
+                }
+            }
+        }
+
+        let back = doubled.resize(16, 16, ResizeFilter::Nearest).unwrap();
+        for (original, frame) in gif.image_frames.iter().zip(back.image_frames.iter()) {
+            assert_eq!(original.colors, frame.colors);
+        }
+
+        assert!(gif.resize(0, 16, ResizeFilter::Nearest).is_err());
+    }
+
+    #[test]
+    fn test_correct_aspect_ratio() {
+        let mut gif = testing::gif(2, 2, [(1..=4).map(|i| Color(i, 0, 0)).collect()]);
+        gif.pixel_aspect_ratio = Some(2.0);
+
+        let corrected = gif.correct_aspect_ratio(ResizeFilter::Nearest).unwrap();
+        assert_eq!((4, 2), (corrected.width, corrected.height));
+        assert_eq!((4, 2), corrected.image_frames[0].dimensions());
+        assert_eq!(None, corrected.pixel_aspect_ratio);
+        assert_eq!(
+            vec![1, 1, 2, 2, 3, 3, 4, 4],
+            corrected.image_frames[0]
+                .colors
+                .iter()
+                .map(|c| c.0)
+                .collect::<Vec<_>>()
+        );
+
+        gif.pixel_aspect_ratio = None;
+        let corrected = gif.correct_aspect_ratio(ResizeFilter::Nearest).unwrap();
+        assert_eq!((2, 2), (corrected.width, corrected.height));
+    }
+
+    #[test]
+    fn test_resize_bilinear() {
+        let gif = testing::gif(2, 1, [vec![Color(0, 0, 0), Color(200, 100, 0)]]);
+
+        let resized = gif.resize(4, 1, ResizeFilter::Bilinear).unwrap();
+        assert_eq!(
+            vec![
+                Color(0, 0, 0),
+                Color(50, 25, 0),
+                Color(150, 75, 0),
+                Color(200, 100, 0),
+            ]
+            .into_boxed_slice(),
+            resized.image_frames[0].colors
+        );
+        assert_eq!(10, resized.image_frames[0].delay_time);
+    }
+
+    #[test]
+    fn test_rotate_and_flip() {
+        let pixels = |gif: &Gif| {
+            gif.image_frames[0]
+                .colors
+                .iter()
+                .map(|c| c.0)
+                .collect::<Vec<_>>()
+        };
+        // 1 2 3
+        // 4 5 6
+        let gif = testing::gif(3, 2, [(1..=6).map(|i| Color(i, 0, 0)).collect()]);
+
+        let mut rotated = gif.clone();
+        rotated.rotate90();
+        assert_eq!((2, 3), (rotated.width, rotated.height));
+        assert_eq!((2, 3), rotated.image_frames[0].dimensions());
+        assert_eq!(vec![4, 1, 5, 2, 6, 3], pixels(&rotated));
+
+        let mut rotated = gif.clone();
+        rotated.rotate180();
+        assert_eq!((3, 2), (rotated.width, rotated.height));
+        assert_eq!(vec![6, 5, 4, 3, 2, 1], pixels(&rotated));
+
+        let mut rotated = gif.clone();
+        rotated.rotate270();
+        assert_eq!((2, 3), (rotated.width, rotated.height));
+        assert_eq!(vec![3, 6, 2, 5, 1, 4], pixels(&rotated));
+
+        let mut flipped = gif.clone();
+        flipped.flip_horizontal();
+        assert_eq!(vec![3, 2, 1, 6, 5, 4], pixels(&flipped));
+
+        let mut flipped = gif.clone();
+        flipped.flip_vertical();
+        assert_eq!(vec![4, 5, 6, 1, 2, 3], pixels(&flipped));
+
+        let mut identity = gif.clone();
+        for _ in 0..4 {
+            identity.rotate90();
+        }
+        assert_eq!(pixels(&gif), pixels(&identity));
+    }
+
+    #[test]
+    fn test_overlay() {
+        let mut gif = testing::gif(3, 3, vec![vec![Color(0, 0, 0); 9]; 2]);
+
+        // Opaque white, half transparent white, fully transparent and opaque red.
+        #[rustfmt::skip]
+        let logo = [
+            255, 255, 255, 255, 255, 255, 255, 128,
+            255, 255, 255, 0, 255, 0, 0, 255,
+        ];
+        gif.overlay(&logo, 2, 2, 1, -1).unwrap();
+
+        let black = Color(0, 0, 0);
+        for frame in gif.image_frames.iter() {
+            assert_eq!(
+                vec![
+                    black,
+                    black,
+                    Color(255, 0, 0),
+                    black,
+                    black,
+                    black,
+                    black,
+                    black,
+                    black,
+                ]
+                .into_boxed_slice(),
+                frame.colors
+            );
+        }
+
+        gif.overlay(&logo, 2, 2, 0, 0).unwrap();
+        let colors = &gif.image_frames[1].colors;
+        assert_eq!(Color(255, 255, 255), colors[0]);
+        assert_eq!(Color(128, 128, 128), colors[1]);
+        assert_eq!(black, colors[3]);
+        assert_eq!(Color(255, 0, 0), colors[4]);
+
+        assert!(gif.overlay(&logo, 3, 2, 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_caption() {
+        let black = Color(0, 0, 0);
+        let white = Color(255, 255, 255);
+        let red = Color(255, 0, 0);
+        let mut gif = testing::gif(9, 12, vec![vec![red; 9 * 12]; 2]);
+
+        gif.caption(
+            "I",
+            &CaptionOptions {
+                scale: Some(1),
+                ..CaptionOptions::default()
+            },
+        );
+
+        for frame in gif.image_frames.iter() {
+            assert!(frame.colors[..2 * 9].iter().all(|c| *c == red));
+            assert!(frame.colors[2 * 9..]
+                .iter()
+                .all(|c| *c == black || *c == white));
+
+            // The stem of the 'I' runs down the middle column, below the padding row.
+            for y in 3..10 {
+                assert_eq!(white, frame.colors[y * 9 + 4]);
+            }
+            assert_eq!(black, frame.colors[2 * 9 + 4]);
+            assert_eq!(black, frame.colors[10 * 9 + 4]);
+        }
+
+        let mut gif = gradient_gif();
+        let original = gif.clone();
+        gif.caption(
+            "HI",
+            &CaptionOptions {
+                position: CaptionPosition::Top,
+                ..CaptionOptions::default()
+            },
+        );
+        assert_eq!(
+            original.image_frames[0].colors[10 * 16..],
+            gif.image_frames[0].colors[10 * 16..]
+        );
+        assert_ne!(
+            original.image_frames[0].colors[..10 * 16],
+            gif.image_frames[0].colors[..10 * 16]
+        );
+    }
+
+    #[test]
+    fn test_retime() {
+        let mut gif = gradient_gif();
+        gif.retime(|i, delay| delay * (i as u16 + 1));
+        assert_eq!(
+            vec![10, 22, 36],
+            gif.image_frames
+                .iter()
+                .map(|f| f.delay_time)
+                .collect::<Vec<_>>()
+        );
+
+        let last = gif.image_frames.len() - 1;
+        gif.retime(|i, delay| if i == last { 500 } else { delay });
+
+        let (_, output) = testing::encoded(gif);
+        let decoded = crate::load(&mut output.as_slice()).unwrap();
+        assert_eq!(
+            vec![10, 22, 500],
+            decoded
+                .image_frames
+                .iter()
+                .map(|f| f.delay_time)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_resample() {
+        let mut gif = gradient_gif();
+        gif.retime(|i, _| [5, 20, 15][i]);
+
+        // At 10 frames per second, the long second frame shows twice.
+        let resampled = gif.resample(10.0).unwrap();
+        let sources = resampled
+            .image_frames
+            .iter()
+            .map(|f| gif.image_frames.iter().position(|g| g.colors == f.colors))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![Some(0), Some(1), Some(1), Some(2)], sources);
+        assert!(resampled.image_frames.iter().all(|f| f.delay_time == 10));
+
+        // Delays alternate to keep 3 frames per second on time, and the last frame ends with
+        // the animation.
+        let delays = |fps| {
+            gif.resample(fps)
+                .unwrap()
+                .image_frames
+                .iter()
+                .map(|f| f.delay_time)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![33, 7], delays(3.0));
+        assert_eq!(vec![14, 15, 11], delays(7.0));
+        for fps in [1.0, 3.0, 7.0, 12.5, 30.0, 100.0] {
+            assert_eq!(40, delays(fps).iter().sum::<u16>());
+        }
+
+        assert!(gif.resample(0.0).is_err());
+        assert!(gif.resample(200.0).is_err());
+        gif.retime(|_, _| 0);
+        assert!(gif.resample(10.0).is_err());
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let mut gif = gradient_gif();
+        gif.retime(|i, _| [10, 3, 6][i]);
+
+        let smooth = gif.interpolate(4);
+        let delays = smooth
+            .image_frames
+            .iter()
+            .map(|f| f.delay_time)
+            .collect::<Vec<_>>();
+        // The first frame fades over 5 frames, the second over none, and the last into the
+        // first over 3.
+        assert_eq!(vec![2, 2, 2, 2, 2, 3, 2, 2, 2], delays);
+        assert_eq!(gif.image_frames[0].colors, smooth.image_frames[0].colors);
+        assert_eq!(gif.image_frames[1].colors, smooth.image_frames[5].colors);
+        // Two fifths of the way from a green of 0 to 40, and two thirds from 80 to 0.
+        assert_eq!(16, smooth.image_frames[2].colors[0].g());
+        assert_eq!(27, smooth.image_frames[8].colors[0].g());
+
+        gif.loop_count = None;
+        assert_eq!(7, gif.interpolate(4).image_frames.len());
+        assert_eq!(3, gif.interpolate(0).image_frames.len());
+    }
+
+    #[test]
+    fn test_map_pixels() {
+        let mut gif = gradient_gif();
+        gif.map_pixels(|x, y, i, c| match (x, y) {
+            (3, 2) => Color(i as u8, 0, 0),
+            _ => c,
+        });
+        for (i, frame) in gif.image_frames.iter().enumerate() {
+            assert_eq!(Color(i as u8, 0, 0), frame.colors[2 * 16 + 3]);
+            assert_eq!(Color(0, i as u8 * 40, 255), frame.colors[0]);
+        }
+
+        let mut calls = 0;
+        gif.map_colors(|c| {
+            calls += 1;
+            Color(c.2, c.1, c.0)
+        });
+        assert_eq!(unique_colors(&gif), calls);
+        assert_eq!(Color(255, 40, 0), gif.image_frames[1].colors[0]);
+        assert_eq!(Color(0, 0, 1), gif.image_frames[1].colors[2 * 16 + 3]);
+    }
+
+    #[test]
+    fn test_key_out() {
+        let mut gif = gradient_gif();
+        gif.key_out(Color(0, 40, 255), 2);
+        assert_eq!(Some(Color(0, 40, 255)), gif.transparent_color);
+        let keyed = |frame: &ImageFrame| {
+            frame
+                .colors
+                .iter()
+                .filter(|c| **c == Color(0, 40, 255))
+                .count()
+        };
+        assert_eq!(
+            vec![0, 3, 0],
+            gif.image_frames.iter().map(keyed).collect::<Vec<_>>()
+        );
+
+        // The keyed pixels come back transparent, showing the background.
+        let (gif, output) = testing::encoded(gif);
+        let decoded = crate::load(&mut output.as_slice()).unwrap();
+        let frame = &decoded.image_frames[1];
+        assert_eq!(&gif.image_frames[1].colors[3..], &frame.colors[3..]);
+        assert!(frame.colors[..3].iter().all(|c| *c == frame.colors[0]));
+        assert_ne!(Color(0, 40, 255), frame.colors[0]);
+    }
+}
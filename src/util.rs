@@ -1,7 +1,14 @@
 use std::convert::From;
+use std::time::Duration;
 
 /// Color stores Red, Green, Blue values in that order.
 #[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+// Laid out as three bytes, so a slice of colors can be handed out as RGB bytes without copying.
+#[repr(C)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)
+)]
 pub struct Color(pub(crate) u8, pub(crate) u8, pub(crate) u8);
 
 impl Color {
@@ -47,3 +54,60 @@ impl From<&[u8]> for Color {
         Color(array[0], array[1], array[2])
     }
 }
+
+/// A rectangle of pixels, with its top left corner at (`left`, `top`).
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
+pub struct Rect {
+    /// The column of the left edge.
+    pub left: u32,
+    /// The row of the top edge.
+    pub top: u32,
+    /// The width in pixels.
+    pub width: u32,
+    /// The height in pixels.
+    pub height: u32,
+}
+
+impl Rect {
+    /// Create a new rectangle.
+    pub fn new(left: u32, top: u32, width: u32, height: u32) -> Self {
+        Self {
+            left,
+            top,
+            width,
+            height,
+        }
+    }
+}
+
+// How long a frame with `delay_time` shows. Like browsers do, very short delays are slowed
+// down, as many GIFs rely on it.
+pub(crate) fn display_time(delay_time: u16) -> Duration {
+    match delay_time {
+        0 | 1 => Duration::from_millis(100),
+        n => Duration::from_millis(n as u64 * 10),
+    }
+}
+
+// Measures how long something takes. `Instant::now` panics on wasm32-unknown-unknown, which has
+// no clock, so there it always reads zero.
+pub(crate) struct Stopwatch {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    start: std::time::Instant,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> std::time::Duration {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        return self.start.elapsed();
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        return std::time::Duration::ZERO;
+    }
+}
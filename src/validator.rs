@@ -0,0 +1,559 @@
+use crate::cursor::Cursor;
+use crate::decompressor::Decompressor;
+
+use std::fmt;
+use std::io::Read;
+
+/// How serious a [`Finding`] is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The file breaks the spec in a way most decoders tolerate.
+    Warning,
+    /// The file is broken and will not decode, or will decode differently between decoders.
+    Error,
+}
+
+/// A problem found by [`validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// The byte offset in the file of the block the problem was found in.
+    pub offset: usize,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?} at {:#x}: {}",
+            self.severity, self.offset, self.message
+        )
+    }
+}
+
+/// Check the GIF read from `src` against the GIF89a spec and list every problem found, in file
+/// order. An empty list means the file is valid.
+///
+/// Unlike [`load`](crate::load), this keeps going after most problems, and checks things a
+/// decoder would silently accept, such as out of range color indices or data after the
+/// trailer.
+///
+/// # Example
+///
+/// ```no_run
+/// use giffy::Severity;
+/// use std::fs::File;
+///
+/// let mut src = File::open("<gif path>").expect("File not found");
+/// let findings = giffy::validate(&mut src).expect("Error reading GIF");
+/// for finding in findings.iter().filter(|f| f.severity == Severity::Error) {
+///     println!("{}", finding);
+/// }
+/// ```
+///
+/// # Errors
+///
+/// This function will return an error if reading from `src` fails.
+pub fn validate<R>(src: &mut R) -> Result<Vec<Finding>, String>
+where
+    R: Read,
+{
+    let mut data = vec![];
+    src.read_to_end(&mut data)
+        .map_err(|e| format!("Error: {}", e))?;
+
+    let mut validator = Validator::new(&data);
+    validator.validate();
+    Ok(validator.findings)
+}
+
+struct Validator<'a> {
+    cursor: Cursor<'a>,
+    findings: Vec<Finding>,
+    version: [u8; 3],
+    width: u16,
+    height: u16,
+    global_color_table_len: Option<usize>,
+}
+
+// Information from a Graphic Control Extension needed to check the block it applies to.
+struct PendingControl {
+    offset: usize,
+    transparent_color_index: Option<u8>,
+}
+
+impl<'a> Validator<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            cursor: Cursor::new(data),
+            findings: vec![],
+            version: [0; 3],
+            width: 0,
+            height: 0,
+            global_color_table_len: None,
+        }
+    }
+
+    fn report<S: Into<String>>(&mut self, severity: Severity, offset: usize, message: S) {
+        self.findings.push(Finding {
+            severity,
+            offset,
+            message: message.into(),
+        });
+    }
+
+    // Reads a fixed size block whose size byte should be `expected`. Returns `None` if the
+    // file ends inside of it.
+    fn read_sized_block(&mut self, offset: usize, name: &str, expected: u8) -> Option<&'a [u8]> {
+        let size = self.cursor.read_u8()?;
+        if size != expected {
+            self.report(
+                Severity::Error,
+                offset,
+                format!("{} block size is {}, expected {}", name, size, expected),
+            );
+        }
+        self.cursor.read_bytes(size as usize)
+    }
+
+    fn validate(&mut self) {
+        if !self.validate_header() || !self.validate_logical_screen_descriptor() {
+            return;
+        }
+
+        let mut pending_control: Option<PendingControl> = None;
+        let mut image_count = 0;
+
+        loop {
+            let offset = self.cursor.pos();
+            let introducer = match self.cursor.read_u8() {
+                Some(introducer) => introducer,
+                None => {
+                    self.report(Severity::Error, offset, "trailer is missing");
+                    break;
+                }
+            };
+
+            let complete = match introducer {
+                0x2c => {
+                    image_count += 1;
+                    let control = pending_control.take();
+                    self.validate_image(offset, control.and_then(|c| c.transparent_color_index))
+                }
+                0x21 => self.validate_extension(offset, &mut pending_control),
+                0x3b => {
+                    if let Some(control) = pending_control.take() {
+                        self.report(
+                            Severity::Warning,
+                            control.offset,
+                            "Graphic Control Extension is not followed by an image",
+                        );
+                    }
+
+                    let trailing = self.cursor.remaining();
+                    if trailing > 0 {
+                        self.report(
+                            Severity::Warning,
+                            self.cursor.pos(),
+                            format!("{} bytes of data after the trailer", trailing),
+                        );
+                    }
+                    break;
+                }
+                x => {
+                    self.report(
+                        Severity::Error,
+                        offset,
+                        format!("unknown block type: {:#x}", x),
+                    );
+                    break;
+                }
+            };
+
+            if !complete {
+                self.report(Severity::Error, offset, "file ends inside of this block");
+                break;
+            }
+        }
+
+        if image_count == 0 {
+            self.report(Severity::Warning, 0, "GIF has no images");
+        }
+    }
+
+    fn validate_header(&mut self) -> bool {
+        let header = match self.cursor.read_bytes(6) {
+            Some(header) => header,
+            None => {
+                self.report(Severity::Error, 0, "header is cut off");
+                return false;
+            }
+        };
+
+        if &header[..3] != b"GIF" {
+            self.report(Severity::Error, 0, "file is not a GIF");
+            return false;
+        }
+
+        self.version.copy_from_slice(&header[3..]);
+        if &self.version != b"87a" && &self.version != b"89a" {
+            self.report(
+                Severity::Warning,
+                3,
+                format!(
+                    "unknown version: {}",
+                    String::from_utf8_lossy(&self.version)
+                ),
+            );
+        }
+
+        true
+    }
+
+    fn validate_logical_screen_descriptor(&mut self) -> bool {
+        let offset = self.cursor.pos();
+        let descriptor = match self.cursor.read_bytes(7) {
+            Some(descriptor) => descriptor,
+            None => {
+                self.report(
+                    Severity::Error,
+                    offset,
+                    "Logical Screen Descriptor is cut off",
+                );
+                return false;
+            }
+        };
+
+        let width = u16::from_le_bytes([descriptor[0], descriptor[1]]);
+        let height = u16::from_le_bytes([descriptor[2], descriptor[3]]);
+        let packed_fields = descriptor[4];
+        let background_color_index = descriptor[5];
+
+        self.width = width;
+        self.height = height;
+        if width == 0 || height == 0 {
+            self.report(
+                Severity::Warning,
+                offset,
+                format!("logical screen is {}x{}", width, height),
+            );
+        }
+
+        if packed_fields >> 7 == 1 {
+            let len = 1 << ((packed_fields & 0b0000_0111) + 1);
+            if self.cursor.read_bytes(3 * len).is_none() {
+                self.report(Severity::Error, offset, "global color table is cut off");
+                return false;
+            }
+            self.global_color_table_len = Some(len);
+
+            if background_color_index as usize >= len {
+                self.report(
+                    Severity::Warning,
+                    offset,
+                    format!(
+                        "background color index {} is outside of the global color table",
+                        background_color_index
+                    ),
+                );
+            }
+        }
+
+        true
+    }
+
+    // Returns `false` if the file ends inside of the extension.
+    fn validate_extension(
+        &mut self,
+        offset: usize,
+        pending_control: &mut Option<PendingControl>,
+    ) -> bool {
+        if &self.version == b"87a" {
+            self.report(
+                Severity::Warning,
+                offset,
+                "extensions are not part of GIF87a",
+            );
+        }
+
+        let label = match self.cursor.read_u8() {
+            Some(label) => label,
+            None => return false,
+        };
+
+        match label {
+            0xf9 => {
+                let fields = match self.read_sized_block(offset, "Graphic Control Extension", 4) {
+                    Some(fields) => fields,
+                    None => return false,
+                };
+                match self.cursor.read_u8() {
+                    Some(0) => {}
+                    Some(_) => {
+                        // Carry on as if the terminator was left out.
+                        self.cursor.rewind(1);
+                        self.report(
+                            Severity::Error,
+                            offset,
+                            "Graphic Control Extension block terminator is missing",
+                        );
+                    }
+                    None => return false,
+                }
+
+                if let Some(previous) = pending_control.take() {
+                    self.report(
+                        Severity::Warning,
+                        previous.offset,
+                        "Graphic Control Extension is replaced by another one before its image",
+                    );
+                }
+
+                let transparent_color_index = match fields {
+                    [packed_fields, _, _, index, ..] => {
+                        if (packed_fields & 0b0001_1100) >> 2 > 3 {
+                            self.report(Severity::Warning, offset, "disposal method is reserved");
+                        }
+                        if packed_fields & 1 == 1 {
+                            Some(*index)
+                        } else {
+                            None
+                        }
+                    }
+                    _ => None,
+                };
+
+                *pending_control = Some(PendingControl {
+                    offset,
+                    transparent_color_index,
+                });
+                true
+            }
+            0x01 => {
+                // The Graphic Control Extension applies to this block instead of an image.
+                *pending_control = None;
+                self.read_sized_block(offset, "Plain Text Extension", 12)
+                    .is_some()
+                    && self.cursor.read_data_sub_blocks().is_some()
+            }
+            0xff => {
+                self.read_sized_block(offset, "Application Extension", 11)
+                    .is_some()
+                    && self.cursor.read_data_sub_blocks().is_some()
+            }
+            0xfe => self.cursor.read_data_sub_blocks().is_some(),
+            x => {
+                self.report(
+                    Severity::Warning,
+                    offset,
+                    format!("unknown extension type: {:#x}", x),
+                );
+                self.cursor.read_data_sub_blocks().is_some()
+            }
+        }
+    }
+
+    // Returns `false` if the file ends inside of the image.
+    fn validate_image(&mut self, offset: usize, transparent_color_index: Option<u8>) -> bool {
+        let (left, top, width, height, packed_fields) = match (
+            self.cursor.read_u16(),
+            self.cursor.read_u16(),
+            self.cursor.read_u16(),
+            self.cursor.read_u16(),
+            self.cursor.read_u8(),
+        ) {
+            (Some(l), Some(t), Some(w), Some(h), Some(p)) => (l, t, w, h, p),
+            _ => return false,
+        };
+
+        if width == 0 || height == 0 {
+            self.report(
+                Severity::Warning,
+                offset,
+                format!("image is {}x{}", width, height),
+            );
+        }
+        if left as u32 + width as u32 > self.width as u32
+            || top as u32 + height as u32 > self.height as u32
+        {
+            self.report(
+                Severity::Warning,
+                offset,
+                format!(
+                    "image at ({}, {}) of size {}x{} does not fit in the logical screen",
+                    left, top, width, height
+                ),
+            );
+        }
+
+        let color_table_len = if packed_fields >> 7 == 1 {
+            let len = 1 << ((packed_fields & 0b0000_0111) + 1);
+            if self.cursor.read_bytes(3 * len).is_none() {
+                return false;
+            }
+            Some(len)
+        } else {
+            self.global_color_table_len
+        };
+
+        let lzw_min_code_size = match self.cursor.read_u8() {
+            Some(size) => size,
+            None => return false,
+        };
+        let data = match self.cursor.read_data_sub_blocks() {
+            Some(data) => data,
+            None => return false,
+        };
+
+        let color_table_len = match color_table_len {
+            Some(len) => len,
+            None => {
+                self.report(Severity::Error, offset, "image has no color table");
+                return true;
+            }
+        };
+
+        if let Some(index) = transparent_color_index {
+            if index as usize >= color_table_len {
+                self.report(
+                    Severity::Warning,
+                    offset,
+                    format!(
+                        "transparent color index {} is outside of the color table",
+                        index
+                    ),
+                );
+            }
+        }
+
+        if !(2..=8).contains(&lzw_min_code_size) {
+            self.report(
+                Severity::Error,
+                offset,
+                format!("invalid LZW minimum code size: {}", lzw_min_code_size),
+            );
+            return true;
+        }
+
+        // Decode one extra pixel to find out whether there are too many.
+        let expected = width as usize * height as usize;
+        let mut decompressor = Decompressor::new(&data, lzw_min_code_size);
+        decompressor.set_max_len(expected + 1);
+        let (indices, error) = decompressor.decompress_partial();
+        if let Some(e) = error {
+            self.report(Severity::Error, offset, format!("invalid LZW data: {}", e));
+        }
+
+        if indices.len() < expected {
+            self.report(
+                Severity::Error,
+                offset,
+                format!(
+                    "image data has {} pixels, expected {}",
+                    indices.len(),
+                    expected
+                ),
+            );
+        } else if indices.len() > expected {
+            self.report(
+                Severity::Warning,
+                offset,
+                format!("image data has more than the expected {} pixels", expected),
+            );
+        }
+
+        let out_of_range = indices.iter().filter(|i| **i >= color_table_len).count();
+        if out_of_range > 0 {
+            self.report(
+                Severity::Error,
+                offset,
+                format!(
+                    "{} pixels refer to colors outside of the color table",
+                    out_of_range
+                ),
+            );
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Encoder, ImageFrame};
+
+    fn sample() -> Vec<u8> {
+        let frames = (0..2)
+            .map(|i| ImageFrame {
+                width: 4,
+                height: 4,
+                colors: (0..16).map(|p| Color(p * 16, i * 100, 0)).collect(),
+                delay_time: 10,
+                placeholder: false,
+            })
+            .collect::<Vec<_>>();
+
+        let mut output = vec![];
+        let mut encoder = Encoder::new(&mut output, 4, 4);
+        encoder.set_loop_count(Some(0));
+        encoder.write_frames(&frames).unwrap();
+        encoder.finish().unwrap();
+        output
+    }
+
+    fn findings(data: &[u8]) -> Vec<(Severity, usize, String)> {
+        validate(&mut &data[..])
+            .unwrap()
+            .into_iter()
+            .map(|f| (f.severity, f.offset, f.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_valid() {
+        assert!(findings(&sample()).is_empty());
+    }
+
+    #[test]
+    fn test_validate_trailing_data_and_truncation() {
+        let mut data = sample();
+        let len = data.len();
+        data.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(
+            vec![(
+                Severity::Warning,
+                len,
+                "3 bytes of data after the trailer".to_string()
+            )],
+            findings(&data)
+        );
+
+        let data = sample();
+        let image = data.iter().rposition(|b| *b == 0x2c).unwrap();
+        let actual = findings(&data[..data.len() - 4]);
+        assert_eq!(1, actual.len());
+        assert_eq!(Severity::Error, actual[0].0);
+        assert_eq!(image, actual[0].1);
+    }
+
+    #[test]
+    fn test_validate_block_size() {
+        let mut data = sample();
+        let gce = data.windows(3).position(|w| w == [0x21, 0xf9, 4]).unwrap();
+        data[gce + 7] = 1;
+
+        let actual = findings(&data);
+        assert_eq!(
+            vec![(
+                Severity::Error,
+                gce,
+                "Graphic Control Extension block terminator is missing".to_string()
+            )],
+            actual[..1]
+        );
+    }
+}
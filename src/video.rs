@@ -0,0 +1,265 @@
+use crate::{Color, Gif};
+
+use std::io::Write;
+
+/// The stream format written by [`Gif::write_video`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VideoFormat {
+    /// A YUV4MPEG2 stream in 4:4:4 BT.601, which carries its size and frame rate in a header.
+    /// Read it with `ffmpeg -i -`.
+    Y4m,
+    /// Headerless RGBA frames, one after the other. Read it with
+    /// `ffmpeg -f rawvideo -pix_fmt rgba -s <width>x<height> -framerate <rate> -i -`.
+    RawRgba,
+}
+
+/// Controls how [`Gif::write_video`] writes frames.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VideoOptions {
+    /// The stream format. Defaults to [`VideoFormat::Y4m`].
+    pub format: VideoFormat,
+    /// The constant frame rate of the stream. Frames are repeated or dropped to keep their
+    /// timing. `None` picks the lowest rate at which every frame delay is a whole number of
+    /// video frames, so no frame is dropped. Defaults to `None`.
+    pub fps: Option<u32>,
+}
+
+impl Default for VideoOptions {
+    fn default() -> Self {
+        Self {
+            format: VideoFormat::Y4m,
+            fps: None,
+        }
+    }
+}
+
+impl Gif {
+    /// Write the frames of this GIF to `dst` as a constant frame rate video stream, such as to
+    /// pipe them into ffmpeg. Returns the frame rate of the stream as a numerator and
+    /// denominator, which a [`VideoFormat::RawRgba`] consumer needs to be told.
+    ///
+    /// Frames with a delay of 0 are skipped, unless every delay is 0, in which case every frame
+    /// is written once at 10 frames per second.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use giffy::VideoOptions;
+    /// use std::fs::File;
+    /// use std::io;
+    ///
+    /// // cargo run | ffmpeg -i - -pix_fmt yuv420p output.mp4
+    /// let mut src = File::open("<gif path>").expect("File not found");
+    /// let gif = giffy::load(&mut src).expect("Error loading GIF");
+    /// gif.write_video(&mut io::stdout().lock(), &VideoOptions::default())
+    ///     .expect("Error writing video");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `fps` is 0, if a frame does not have
+    /// `width * height` pixels or if writing to `dst` fails.
+    pub fn write_video<W>(&self, dst: &mut W, options: &VideoOptions) -> Result<(u32, u32), String>
+    where
+        W: Write,
+    {
+        let pixel_count = self.width as usize * self.height as usize;
+        for (i, frame) in self.image_frames.iter().enumerate() {
+            if frame.colors.len() != pixel_count {
+                return Err(format!(
+                    "Error: frame {} has {} pixels, expected {}",
+                    i,
+                    frame.colors.len(),
+                    pixel_count
+                ));
+            }
+        }
+
+        let (numerator, denominator) = match options.fps {
+            Some(0) => return Err("Error: fps must be greater than 0".into()),
+            Some(fps) => (fps, 1),
+            None => self.video_frame_rate(),
+        };
+
+        if options.format == VideoFormat::Y4m {
+            writeln!(
+                dst,
+                "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C444",
+                self.width, self.height, numerator, denominator
+            )
+            .map_err(|e| format!("Error: {}", e))?;
+        }
+
+        let mut buffer = Vec::with_capacity(pixel_count * 4);
+        for index in self.video_frames(numerator, denominator) {
+            let colors = &self.image_frames[index].colors;
+            buffer.clear();
+            match options.format {
+                VideoFormat::Y4m => {
+                    buffer.extend_from_slice(b"FRAME\n");
+                    let yuv = colors.iter().map(yuv).collect::<Vec<_>>();
+                    for plane in 0..3 {
+                        buffer.extend(yuv.iter().map(|c| c[plane]));
+                    }
+                }
+                VideoFormat::RawRgba => {
+                    for c in colors.iter() {
+                        buffer.extend_from_slice(&[c.0, c.1, c.2, 255]);
+                    }
+                }
+            }
+            dst.write_all(&buffer)
+                .map_err(|e| format!("Error: {}", e))?;
+        }
+
+        Ok((numerator, denominator))
+    }
+
+    // The lowest frame rate at which every non-zero delay lasts a whole number of video frames.
+    fn video_frame_rate(&self) -> (u32, u32) {
+        let step = self
+            .image_frames
+            .iter()
+            .map(|frame| frame.delay_time as u32)
+            .filter(|&delay| delay > 0)
+            .fold(0, gcd);
+        if step == 0 {
+            return (10, 1);
+        }
+
+        // Delays are in hundredths of a second.
+        let divisor = gcd(100, step);
+        (100 / divisor, step / divisor)
+    }
+
+    // The index of the GIF frame shown at every video frame.
+    fn video_frames(&self, numerator: u32, denominator: u32) -> Vec<usize> {
+        let ends = self
+            .image_frames
+            .iter()
+            .scan(0u64, |end, frame| {
+                *end += frame.delay_time as u64;
+                Some(*end)
+            })
+            .collect::<Vec<_>>();
+        let total = match ends.last() {
+            Some(&total) if total > 0 => total,
+            _ => return (0..self.image_frames.len()).collect(),
+        };
+
+        // Video frame k starts at k * 100 * denominator / numerator hundredths of a second.
+        let (numerator, denominator) = (numerator as u64, denominator as u64 * 100);
+        let count = (total * numerator).div_ceil(denominator);
+        let mut index = 0;
+        (0..count)
+            .map(|k| {
+                let time = k * denominator;
+                while ends[index] * numerator <= time {
+                    index += 1;
+                }
+                index
+            })
+            .collect()
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// Limited range BT.601, which is what ffmpeg assumes for Y4M.
+fn yuv(c: &Color) -> [u8; 3] {
+    let (r, g, b) = (c.0 as i32, c.1 as i32, c.2 as i32);
+    let y = ((66 * r + 129 * g + 25 * b + 128) >> 8) + 16;
+    let u = ((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128;
+    let v = ((112 * r - 94 * g - 18 * b + 128) >> 8) + 128;
+    [y as u8, u as u8, v as u8]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ImageFrame;
+
+    fn gif(delays: &[u16]) -> Gif {
+        Gif {
+            width: 2,
+            height: 1,
+            image_frames: delays
+                .iter()
+                .enumerate()
+                .map(|(i, &delay_time)| ImageFrame {
+                    width: 2,
+                    height: 1,
+                    colors: vec![Color(i as u8, 0, 0), Color(255, 255, 255)].into_boxed_slice(),
+                    delay_time,
+                    placeholder: false,
+                })
+                .collect(),
+            loop_count: None,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        }
+    }
+
+    #[test]
+    fn test_video_frame_rate() {
+        assert_eq!((10, 1), gif(&[10, 20]).video_frame_rate());
+        assert_eq!((100, 3), gif(&[3, 0, 6]).video_frame_rate());
+        assert_eq!((10, 1), gif(&[0, 0]).video_frame_rate());
+    }
+
+    #[test]
+    fn test_video_frames() {
+        assert_eq!(vec![0, 1, 1], gif(&[10, 20]).video_frames(10, 1));
+        assert_eq!(vec![0, 2, 2], gif(&[3, 0, 6]).video_frames(100, 3));
+        assert_eq!(vec![0, 1], gif(&[0, 0]).video_frames(10, 1));
+        // 25 fps is 4 hundredths of a second per video frame.
+        assert_eq!(vec![0, 0, 0, 1, 1], gif(&[10, 10]).video_frames(25, 1));
+    }
+
+    #[test]
+    fn test_write_y4m() {
+        let mut output = vec![];
+        let rate = gif(&[10, 20])
+            .write_video(&mut output, &VideoOptions::default())
+            .unwrap();
+        assert_eq!((10, 1), rate);
+
+        let header = b"YUV4MPEG2 W2 H1 F10:1 Ip A1:1 C444\n";
+        assert!(output.starts_with(header));
+        let frames = output[header.len()..].chunks(12).collect::<Vec<_>>();
+        assert_eq!(3, frames.len());
+        // Black and white in limited range.
+        assert_eq!(b"FRAME\n\x10\xeb\x80\x80\x80\x80", frames[0]);
+    }
+
+    #[test]
+    fn test_write_raw_rgba() {
+        let options = VideoOptions {
+            format: VideoFormat::RawRgba,
+            fps: Some(20),
+        };
+        let mut output = vec![];
+        let rate = gif(&[5, 10]).write_video(&mut output, &options).unwrap();
+        assert_eq!((20, 1), rate);
+        assert_eq!(
+            vec![
+                0, 0, 0, 255, 255, 255, 255, 255, 1, 0, 0, 255, 255, 255, 255, 255, 1, 0, 0, 255,
+                255, 255, 255, 255
+            ],
+            output
+        );
+
+        let options = VideoOptions {
+            fps: Some(0),
+            ..options
+        };
+        assert!(gif(&[5]).write_video(&mut vec![], &options).is_err());
+    }
+}
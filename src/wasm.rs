@@ -0,0 +1,173 @@
+//! JavaScript bindings for running giffy in the browser, built with `wasm-bindgen`.
+//!
+//! This module is only available with the `wasm` feature. Build the module as a `cdylib` and
+//! generate the bindings with
+//! `cargo rustc --release --lib --crate-type cdylib --target wasm32-unknown-unknown --features wasm`
+//! and `wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/giffy.wasm`,
+//! then use it from JavaScript:
+//!
+//! ```js
+//! import init, { decode } from "./pkg/giffy.js";
+//!
+//! await init();
+//! const bytes = new Uint8Array(await (await fetch("animation.gif")).arrayBuffer());
+//! const gif = decode(bytes);
+//! const frame = gif.frames[0];
+//! const image = new ImageData(new Uint8ClampedArray(frame.pixels.buffer), gif.width, gif.height);
+//! ```
+//!
+//! To show frames while a large GIF is still downloading, feed the chunks to a
+//! [`StreamDecoder`] instead:
+//!
+//! ```js
+//! const decoder = new StreamDecoder();
+//! const reader = (await fetch("animation.gif")).body.getReader();
+//! for (let chunk = await reader.read(); !chunk.done; chunk = await reader.read()) {
+//!     for (const frame of decoder.push(chunk.value)) {
+//!         show(frame, decoder.width, decoder.height);
+//!     }
+//! }
+//! ```
+
+use crate::{Gif, ImageFrame};
+
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+/// Decode the GIF in `bytes`. Returns an object with the `width` and `height` of the GIF, its
+/// `loopCount` (`null` if it does not loop), its `comments` and its `frames`. Every frame has a
+/// `delay` in milliseconds and its `pixels`, a `Uint8Array` of `width * height` RGBA pixels
+/// that can be wrapped in an `ImageData`.
+///
+/// # Errors
+///
+/// This function will throw an `Error` if `bytes` is not in a valid GIF format.
+#[wasm_bindgen]
+pub fn decode(bytes: &[u8]) -> Result<JsValue, JsError> {
+    let gif = crate::load(&mut &bytes[..]).map_err(|e| JsError::new(&e))?;
+    Ok(gif_object(&gif).into())
+}
+
+/// Decodes a GIF from chunks of bytes as they are downloaded. Wraps
+/// [`giffy::StreamDecoder`](crate::StreamDecoder), handing every frame over to JavaScript as
+/// soon as it is complete, so only the canvas the next frame is drawn over is kept.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct StreamDecoder {
+    decoder: crate::StreamDecoder,
+}
+
+#[wasm_bindgen]
+impl StreamDecoder {
+    /// Create a decoder that has not been given any data yet.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `chunk` to the data received so far and return an array of the frames it
+    /// completed, in the same shape as the frames returned by [`decode`].
+    ///
+    /// # Errors
+    ///
+    /// This function will throw an `Error` if the data received so far is not in a valid GIF
+    /// format.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Array, JsError> {
+        self.decoder.push(chunk).map_err(|e| JsError::new(&e))?;
+        Ok(self
+            .decoder
+            .take_frames()
+            .iter()
+            .map(frame_object)
+            .collect())
+    }
+
+    /// The width of the GIF, or `undefined` until enough data has been pushed to know it.
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> Option<u32> {
+        self.decoder.width()
+    }
+
+    /// The height of the GIF, or `undefined` until enough data has been pushed to know it.
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> Option<u32> {
+        self.decoder.height()
+    }
+
+    /// The loop count received so far, `undefined` if the GIF does not loop.
+    #[wasm_bindgen(getter, js_name = loopCount)]
+    pub fn loop_count(&self) -> Option<u16> {
+        self.decoder.loop_count()
+    }
+
+    /// Whether the whole GIF has been received.
+    #[wasm_bindgen(getter)]
+    pub fn finished(&self) -> bool {
+        self.decoder.is_finished()
+    }
+}
+
+fn gif_object(gif: &Gif) -> Object {
+    let object = Object::new();
+    set(&object, "width", gif.width.into());
+    set(&object, "height", gif.height.into());
+    set(
+        &object,
+        "loopCount",
+        gif.loop_count.map_or(JsValue::NULL, JsValue::from),
+    );
+
+    let comments = gif
+        .comments
+        .iter()
+        .map(|comment| JsValue::from_str(comment))
+        .collect::<Array>();
+    set(&object, "comments", comments.into());
+
+    let frames = gif.image_frames.iter().map(frame_object).collect::<Array>();
+    set(&object, "frames", frames.into());
+    object
+}
+
+fn frame_object(frame: &ImageFrame) -> JsValue {
+    let object = Object::new();
+    set(&object, "delay", (frame.delay_time as u32 * 10).into());
+    set(
+        &object,
+        "pixels",
+        Uint8Array::from(rgba(frame).as_slice()).into(),
+    );
+    object.into()
+}
+
+// Setting a property only fails on frozen objects and proxies, and these are neither.
+fn set(object: &Object, key: &str, value: JsValue) {
+    let _ = Reflect::set(object, &JsValue::from_str(key), &value);
+}
+
+// The pixels of a frame as RGBA, the layout of `ImageData`.
+fn rgba(frame: &ImageFrame) -> Vec<u8> {
+    frame
+        .colors
+        .iter()
+        .flat_map(|c| [c.0, c.1, c.2, 255])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+
+    #[test]
+    fn test_rgba() {
+        let frame = ImageFrame {
+            width: 2,
+            height: 1,
+            colors: vec![Color(1, 2, 3), Color(4, 5, 6)].into_boxed_slice(),
+            delay_time: 0,
+            placeholder: false,
+        };
+        assert_eq!(vec![1, 2, 3, 255, 4, 5, 6, 255], rgba(&frame));
+    }
+}
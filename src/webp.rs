@@ -0,0 +1,289 @@
+use crate::{Color, Gif};
+
+use image_webp::{ColorType, WebPEncoder};
+use std::io::Write;
+
+// The largest canvas size and frame duration that fit in the 24 bit fields of the format.
+const MAX_SIZE: u32 = 1 << 24;
+const MAX_DURATION: u32 = (1 << 24) - 1;
+
+// A frame of the animation: the changed rectangle of a GIF frame, and how long it shows.
+struct WebPFrame {
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+    duration: u32,
+    data: Vec<u8>,
+}
+
+impl Gif {
+    /// Encode this GIF as a lossless animated WebP and write it to `dst`.
+    ///
+    /// Frame delays and the loop count are carried over. Since the frames of a [`Gif`] are
+    /// already composited, every frame only stores the rectangle that changed since the previous
+    /// one and replaces it without blending, which reproduces every disposal method exactly.
+    /// Frames identical to the previous one are merged into it by adding up their delays.
+    ///
+    /// This is only available with the `webp` feature.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::io::BufWriter;
+    ///
+    /// let mut src = File::open("<gif path>").expect("File not found");
+    /// let gif = giffy::load(&mut src).expect("Error loading GIF");
+    /// let mut dst = BufWriter::new(File::create("<output path>").expect("Unable to create file"));
+    /// gif.write_webp(&mut dst).expect("Error writing WebP");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the GIF has no frames, if its size does not fit in
+    /// a WebP, if a frame does not have `width * height` pixels or if writing to `dst` fails.
+    pub fn write_webp<W>(&self, dst: &mut W) -> Result<(), String>
+    where
+        W: Write,
+    {
+        if self.image_frames.is_empty() {
+            return Err("Error: no frames to encode".into());
+        }
+        if self.width == 0 || self.height == 0 || self.width > MAX_SIZE || self.height > MAX_SIZE {
+            return Err(format!(
+                "Error: size {}x{} does not fit in a WebP",
+                self.width, self.height
+            ));
+        }
+
+        let pixel_count = self.width as usize * self.height as usize;
+        let mut frames: Vec<WebPFrame> = vec![];
+        let mut previous: Option<&[Color]> = None;
+        for (i, frame) in self.image_frames.iter().enumerate() {
+            if frame.colors.len() != pixel_count {
+                return Err(format!(
+                    "Error: frame {} has {} pixels, expected {}",
+                    i,
+                    frame.colors.len(),
+                    pixel_count
+                ));
+            }
+
+            let duration = frame.delay_time as u32 * 10;
+            let rect = match previous {
+                None => Some((0, 0, self.width, self.height)),
+                Some(previous) => self.changed_rect(previous, &frame.colors),
+            };
+            previous = Some(&frame.colors);
+
+            let (left, top, width, height) = match (rect, frames.last_mut()) {
+                (Some(rect), _) => rect,
+                (None, Some(last)) => {
+                    last.duration = (last.duration + duration).min(MAX_DURATION);
+                    continue;
+                }
+                (None, None) => unreachable!(),
+            };
+
+            let mut pixels = Vec::with_capacity(width as usize * height as usize * 3);
+            for y in top..top + height {
+                let offset = (y * self.width + left) as usize;
+                for c in frame.colors[offset..offset + width as usize].iter() {
+                    pixels.extend_from_slice(&<[u8; 3]>::from(c));
+                }
+            }
+
+            frames.push(WebPFrame {
+                left,
+                top,
+                width,
+                height,
+                duration: duration.min(MAX_DURATION),
+                data: encode_lossless(&pixels, width, height)?,
+            });
+        }
+
+        // The NETSCAPE2.0 loop count is the number of repeats after the first play, while WebP
+        // counts plays.
+        let loop_count = match self.loop_count {
+            None => 1,
+            Some(0) => 0,
+            Some(n) => n.saturating_add(1),
+        };
+
+        let mut body = vec![];
+        body.extend_from_slice(b"WEBP");
+
+        let mut vp8x = vec![0b0000_0010, 0, 0, 0];
+        vp8x.extend_from_slice(&u24(self.width - 1));
+        vp8x.extend_from_slice(&u24(self.height - 1));
+        write_chunk(&mut body, b"VP8X", &vp8x);
+
+        let mut anim = vec![0; 4];
+        anim.extend_from_slice(&loop_count.to_le_bytes());
+        write_chunk(&mut body, b"ANIM", &anim);
+
+        for frame in frames.iter() {
+            let mut anmf = vec![];
+            anmf.extend_from_slice(&u24(frame.left / 2));
+            anmf.extend_from_slice(&u24(frame.top / 2));
+            anmf.extend_from_slice(&u24(frame.width - 1));
+            anmf.extend_from_slice(&u24(frame.height - 1));
+            anmf.extend_from_slice(&u24(frame.duration));
+            // Do not blend, do not dispose.
+            anmf.push(0b0000_0010);
+            write_chunk(&mut anmf, b"VP8L", &frame.data);
+            write_chunk(&mut body, b"ANMF", &anmf);
+        }
+
+        let size = u32::try_from(body.len()).map_err(|_| "Error: WebP is too large")?;
+        dst.write_all(b"RIFF")
+            .and_then(|_| dst.write_all(&size.to_le_bytes()))
+            .and_then(|_| dst.write_all(&body))
+            .map_err(|e| format!("Error: {}", e))
+    }
+
+    // The smallest rectangle around the pixels that differ between two frames, or `None` if
+    // they are the same. Frame offsets are stored halved, so the left and top are even.
+    fn changed_rect(&self, previous: &[Color], current: &[Color]) -> Option<(u32, u32, u32, u32)> {
+        let width = self.width as usize;
+        let (mut left, mut top, mut right, mut bottom) = (usize::MAX, usize::MAX, 0, 0);
+        for (i, (a, b)) in previous.iter().zip(current.iter()).enumerate() {
+            if a != b {
+                let (x, y) = (i % width, i / width);
+                left = left.min(x);
+                top = top.min(y);
+                right = right.max(x + 1);
+                bottom = bottom.max(y + 1);
+            }
+        }
+
+        if left == usize::MAX {
+            return None;
+        }
+        let (left, top) = (left & !1, top & !1);
+        Some((
+            left as u32,
+            top as u32,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        ))
+    }
+}
+
+// Returns the VP8L bitstream of an RGB image.
+fn encode_lossless(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let mut output = vec![];
+    WebPEncoder::new(&mut output)
+        .encode(pixels, width, height, ColorType::Rgb8)
+        .map_err(|e| format!("Error: {}", e))?;
+
+    // Without metadata, the encoder writes a RIFF header followed by a single VP8L chunk.
+    match output.get(12..20) {
+        Some(header) if &header[..4] == b"VP8L" => {
+            let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+            output
+                .get(20..20 + len)
+                .map(|data| data.to_vec())
+                .ok_or_else(|| "Error: VP8L chunk is cut off".into())
+        }
+        _ => Err("Error: VP8L chunk is missing".into()),
+    }
+}
+
+fn u24(value: u32) -> [u8; 3] {
+    let bytes = value.to_le_bytes();
+    [bytes[0], bytes[1], bytes[2]]
+}
+
+// Chunks are padded to an even size.
+fn write_chunk(dst: &mut Vec<u8>, fourcc: &[u8; 4], data: &[u8]) {
+    dst.extend_from_slice(fourcc);
+    dst.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    dst.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        dst.push(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ImageFrame;
+    use image_webp::{LoopCount, WebPDecoder};
+    use std::io::Cursor;
+
+    fn frame(colors: Vec<Color>, delay_time: u16) -> ImageFrame {
+        ImageFrame {
+            width: 4,
+            height: 3,
+            colors: colors.into_boxed_slice(),
+            delay_time,
+            placeholder: false,
+        }
+    }
+
+    #[test]
+    fn test_write_webp() {
+        let first = (0..12).map(|i| Color(i * 20, 0, 0)).collect::<Vec<_>>();
+        let mut second = first.clone();
+        second[7] = Color(0, 255, 0);
+
+        let gif = Gif {
+            width: 4,
+            height: 3,
+            image_frames: vec![
+                frame(first.clone(), 10),
+                frame(first.clone(), 5),
+                frame(second.clone(), 20),
+            ],
+            loop_count: Some(0),
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        };
+
+        let mut output = vec![];
+        gif.write_webp(&mut output).unwrap();
+
+        let mut decoder = WebPDecoder::new(Cursor::new(output)).unwrap();
+        assert!(decoder.is_animated());
+        assert_eq!((4, 3), decoder.dimensions());
+        assert_eq!(2, decoder.num_frames());
+        assert!(matches!(decoder.loop_count(), LoopCount::Forever));
+
+        let mut buf = vec![0; decoder.output_buffer_size().unwrap()];
+        let bytes_per_pixel = buf.len() / 12;
+        for (expected, duration) in [(first, 150), (second, 200)] {
+            assert_eq!(duration, decoder.read_frame(&mut buf).unwrap());
+            let actual = buf
+                .chunks_exact(bytes_per_pixel)
+                .map(|p| Color(p[0], p[1], p[2]))
+                .collect::<Vec<_>>();
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn test_changed_rect() {
+        let gif = Gif {
+            width: 4,
+            height: 3,
+            image_frames: vec![],
+            loop_count: None,
+            comments: vec![],
+            pixel_aspect_ratio: None,
+            transparent_color: None,
+        };
+        let a = vec![Color(0, 0, 0); 12];
+        let mut b = a.clone();
+        assert_eq!(None, gif.changed_rect(&a, &b));
+
+        b[7] = Color(1, 1, 1);
+        assert_eq!(Some((2, 0, 2, 2)), gif.changed_rect(&a, &b));
+
+        let mut output = vec![];
+        assert!(gif.write_webp(&mut output).is_err());
+    }
+}
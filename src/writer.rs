@@ -0,0 +1,213 @@
+use crate::parser::*;
+use crate::util::Color;
+
+use std::io::Write;
+
+#[derive(Debug)]
+pub(crate) struct Writer<'a, T: Write> {
+    dst: &'a mut T,
+}
+
+impl<'a, T: Write> Writer<'a, T> {
+    pub(crate) fn new(dst: &'a mut T) -> Self {
+        Self { dst }
+    }
+
+    #[inline(always)]
+    fn write_bytes(&mut self, buffer: &[u8]) -> Result<(), String> {
+        self.dst
+            .write_all(buffer)
+            .map_err(|e| format!("Error: {}", e))
+    }
+
+    #[inline(always)]
+    fn write_u8(&mut self, value: u8) -> Result<(), String> {
+        self.write_bytes(&[value])
+    }
+
+    #[inline(always)]
+    fn write_u16(&mut self, value: u16) -> Result<(), String> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    pub(crate) fn write(&mut self, result: &ParseResult) -> Result<(), String> {
+        self.write_header(&result.header)?;
+        self.write_logical_screen_descriptor(&result.logical_screen_descriptor)?;
+
+        for block in result.data_blocks.iter() {
+            match block {
+                DataType::ApplicationExtensionType(ext) => self.write_application_extension(ext)?,
+                DataType::CommentExtensionType(ext) => self.write_comment_extension(ext)?,
+                DataType::PlainTextExtensionType(ext) => self.write_plain_text_extension(ext)?,
+                DataType::TableBasedImageType(image) => self.write_table_based_image(image)?,
+            }
+        }
+
+        self.write_trailer()
+    }
+
+    pub(crate) fn write_header(&mut self, header: &Header) -> Result<(), String> {
+        if header.sig.len() != 3 || header.version.len() != 3 {
+            return Err(format!(
+                "Error: invalid header: {}{}",
+                header.sig, header.version
+            ));
+        }
+
+        self.write_bytes(header.sig.as_bytes())?;
+        self.write_bytes(header.version.as_bytes())
+    }
+
+    pub(crate) fn write_logical_screen_descriptor(
+        &mut self,
+        lsd: &LogicalScreenDescriptor,
+    ) -> Result<(), String> {
+        self.write_u16(lsd.width)?;
+        self.write_u16(lsd.height)?;
+
+        let packed_fields = ((lsd.global_color_table_flag as u8) << 7)
+            | ((lsd.color_resolution & 0b0000_0111) << 4)
+            | ((lsd.sort_flag as u8) << 3)
+            | (lsd.global_color_table_size & 0b0000_0111);
+        self.write_u8(packed_fields)?;
+
+        self.write_u8(lsd.background_color_index)?;
+        self.write_u8(if lsd.pixel_aspect_ratio == 0f32 {
+            0
+        } else {
+            (lsd.pixel_aspect_ratio * 64.0f32 - 15.0f32).round() as u8
+        })?;
+
+        if let Some(ref table) = lsd.global_color_table {
+            self.write_color_table(table, lsd.global_color_table_size)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_color_table(&mut self, table: &[Color], table_size: u8) -> Result<(), String> {
+        let len = 1 << (table_size + 1);
+        if table.len() > len {
+            return Err(format!(
+                "Error: color table has {} entries, expected at most {}",
+                table.len(),
+                len
+            ));
+        }
+
+        let mut buffer = Vec::with_capacity(3 * len);
+        for c in table {
+            let values: [u8; 3] = c.into();
+            buffer.extend_from_slice(&values);
+        }
+        buffer.resize(3 * len, 0);
+
+        self.write_bytes(&buffer)
+    }
+
+    fn write_data_sub_blocks(&mut self, data: &[u8]) -> Result<(), String> {
+        for chunk in data.chunks(255) {
+            self.write_u8(chunk.len() as u8)?;
+            self.write_bytes(chunk)?;
+        }
+
+        // Block terminator value is 0x00
+        self.write_u8(0)
+    }
+
+    pub(crate) fn write_application_extension(
+        &mut self,
+        ext: &ApplicationExtension,
+    ) -> Result<(), String> {
+        if ext.id.len() != 8 || ext.auth_code.len() != 3 {
+            return Err(format!(
+                "Error: invalid Application Extension identifier: {}{}",
+                ext.id, ext.auth_code
+            ));
+        }
+
+        self.write_bytes(&[0x21, 0xff, 11])?;
+        self.write_bytes(ext.id.as_bytes())?;
+        self.write_bytes(ext.auth_code.as_bytes())?;
+        // Written as they were split, which extensions such as XMP depend on.
+        for block in ext.sub_blocks().into_iter().filter(|b| !b.is_empty()) {
+            self.write_u8(block.len() as u8)?;
+            self.write_bytes(block)?;
+        }
+        self.write_u8(0)
+    }
+
+    pub(crate) fn write_comment_extension(&mut self, ext: &CommentExtension) -> Result<(), String> {
+        self.write_bytes(&[0x21, 0xfe])?;
+        self.write_data_sub_blocks(ext.text.as_bytes())
+    }
+
+    pub(crate) fn write_plain_text_extension(
+        &mut self,
+        ext: &PlainTextExtension,
+    ) -> Result<(), String> {
+        if let Some(ref gce) = ext.graphic_control_extension {
+            self.write_graphic_control_extension(gce)?;
+        }
+
+        self.write_bytes(&[0x21, 0x01, 12])?;
+        self.write_u16(ext.text_grid_left_pos)?;
+        self.write_u16(ext.text_grid_top_pos)?;
+        self.write_u16(ext.text_grid_width)?;
+        self.write_u16(ext.text_grid_height)?;
+        self.write_bytes(&[
+            ext.char_cell_width,
+            ext.char_cell_height,
+            ext.text_fg_color_index,
+            ext.text_bg_color_index,
+        ])?;
+        self.write_data_sub_blocks(ext.plain_text_data.as_bytes())
+    }
+
+    pub(crate) fn write_graphic_control_extension(
+        &mut self,
+        ext: &GraphicControlExtension,
+    ) -> Result<(), String> {
+        let packed_fields = (ext.disposal_method.value() << 2)
+            | ((ext.user_input_expected as u8) << 1)
+            | (ext.transparent_color_index_available as u8);
+
+        self.write_bytes(&[0x21, 0xf9, 4, packed_fields])?;
+        self.write_u16(ext.delay_time)?;
+        self.write_u8(ext.transparent_color_index)?;
+        self.write_u8(0)
+    }
+
+    pub(crate) fn write_table_based_image(
+        &mut self,
+        image: &TableBasedImage,
+    ) -> Result<(), String> {
+        if let Some(ref ext) = image.graphic_control_extension {
+            self.write_graphic_control_extension(ext)?;
+        }
+
+        let desc = &image.image_descriptor;
+        self.write_u8(0x2c)?;
+        self.write_u16(desc.left)?;
+        self.write_u16(desc.top)?;
+        self.write_u16(desc.width)?;
+        self.write_u16(desc.height)?;
+
+        let packed_fields = ((desc.local_color_table_flag as u8) << 7)
+            | ((desc.interlace_flag as u8) << 6)
+            | ((desc.sort_flag as u8) << 5)
+            | (desc.local_color_table_size & 0b0000_0111);
+        self.write_u8(packed_fields)?;
+
+        if let Some(ref table) = image.local_color_table {
+            self.write_color_table(table, desc.local_color_table_size)?;
+        }
+
+        self.write_u8(image.image_data.lzw_min_code_size)?;
+        self.write_data_sub_blocks(&image.image_data.data_sub_blocks)
+    }
+
+    pub(crate) fn write_trailer(&mut self) -> Result<(), String> {
+        self.write_u8(0x3b)
+    }
+}
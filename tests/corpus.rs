@@ -0,0 +1,33 @@
+//! Checks the decoder against the reference GIFs in `tests/corpus`, or the directory given by
+//! the `GIFFY_CORPUS` environment variable. Set `GIFFY_CORPUS_BLESS=1` to write the manifests
+//! of new or changed files.
+//!
+//! Run with `cargo test --features corpus --test corpus`.
+
+#![cfg(feature = "corpus")]
+
+use std::env;
+use std::path::PathBuf;
+
+#[test]
+fn test_corpus() {
+    let dir = env::var_os("GIFFY_CORPUS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/corpus"));
+    let bless = env::var_os("GIFFY_CORPUS_BLESS").is_some();
+
+    let report = giffy::corpus::check_dir(&dir, bless).unwrap();
+    for path in report.blessed.iter() {
+        println!("blessed {}", path.display());
+    }
+    for (path, difference) in report.failures.iter() {
+        println!("{}: {}", path.display(), difference);
+    }
+
+    assert!(report.checked > 0, "no GIFs found in {}", dir.display());
+    assert!(
+        report.failures.is_empty(),
+        "{} GIFs do not match their manifest",
+        report.failures.len()
+    );
+}